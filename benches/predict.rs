@@ -0,0 +1,88 @@
+//! Compares the prediction input paths across batch sizes:
+//!
+//! - `dmatrix`: build a [`DMatrix`] from dense rows, then predict it
+//! - `dense`: in-place dense prediction with a fresh output buffer
+//! - `dense_into`: in-place dense prediction into a reused buffer
+//! - `csr`: in-place prediction from CSR arrays
+//!
+//! Run with `cargo bench --bench predict`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rand::Rng;
+use xgb_rs::booster::Booster;
+use xgb_rs::dmatrix::{Csr, DMatrix};
+use xgb_rs::predict::PredictOptions;
+
+const N_COLS: usize = 30;
+const TRAIN_ROWS: usize = 5000;
+const ROUNDS: usize = 100;
+const BATCH_SIZES: [usize; 4] = [1, 64, 1024, 16384];
+/// Share of missing values in the CSR input.
+const SPARSITY: f64 = 0.7;
+
+fn random_rows(rows: usize) -> Vec<f32> {
+    let mut rng = rand::thread_rng();
+    (0..rows * N_COLS).map(|_| rng.gen()).collect()
+}
+
+fn trained_booster() -> Booster {
+    let data = random_rows(TRAIN_ROWS);
+    let labels: Vec<f32> = data.chunks(N_COLS).map(|row| row[0] + row[1]).collect();
+    let dtrain = DMatrix::try_from_data(&data, TRAIN_ROWS as u64, N_COLS as u64).unwrap();
+    dtrain.try_add_label(&labels).unwrap();
+    Booster::train(&dtrain, &dtrain, ROUNDS).unwrap()
+}
+
+fn sparse_rows(rows: usize) -> Csr {
+    let mut rng = rand::thread_rng();
+    let mut csr = Csr {
+        indptr: vec![0],
+        indices: Vec::new(),
+        values: Vec::new(),
+    };
+    for _ in 0..rows {
+        for col in 0..N_COLS {
+            if !rng.gen_bool(SPARSITY) {
+                csr.indices.push(col as u32);
+                csr.values.push(rng.gen());
+            }
+        }
+        csr.indptr.push(csr.values.len() as u64);
+    }
+    csr
+}
+
+fn bench_predict(c: &mut Criterion) {
+    let booster = trained_booster();
+    let options = PredictOptions::default();
+    let mut group = c.benchmark_group("predict");
+    for rows in BATCH_SIZES {
+        let data = random_rows(rows);
+        let csr = sparse_rows(rows);
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::new("dmatrix", rows), &data, |b, data| {
+            b.iter(|| {
+                let dmat = DMatrix::try_from_data(data, rows as u64, N_COLS as u64).unwrap();
+                booster.predict_with(&dmat, &options).unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("dense", rows), &data, |b, data| {
+            b.iter(|| booster.predict_dense(data, rows, N_COLS, &options).unwrap())
+        });
+        group.bench_with_input(BenchmarkId::new("dense_into", rows), &data, |b, data| {
+            let mut out = Vec::new();
+            b.iter(|| {
+                booster
+                    .predict_dense_into(data, rows, N_COLS, &options, &mut out)
+                    .unwrap()
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("csr", rows), &csr, |b, csr| {
+            b.iter(|| booster.predict_csr(csr, N_COLS as u64, &options).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_predict);
+criterion_main!(benches);
@@ -0,0 +1,247 @@
+//! Atomic result bundles. [`write_bundle`] writes the model, its parameters, metrics and
+//! feature names into a temporary directory next to the target and renames it into place
+//! once every file is synced, so the target directory either holds a complete bundle or
+//! does not exist. Leftover `.tmp-*` directories are from runs that crashed mid-write.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::booster::{Booster, ModelFormat};
+use crate::experiment::{ExperimentError, RoundMetrics};
+use crate::params::TrainParams;
+
+pub const PARAMS_FILE: &str = "params.json";
+pub const METRICS_FILE: &str = "metrics.json";
+pub const FEATURE_NAMES_FILE: &str = "feature_names.json";
+
+/// Everything a bundle holds besides the model.
+pub struct Bundle<'a> {
+    /// Base name of the model file, `<name>.json` or `<name>.ubj`.
+    pub name: &'a str,
+    pub booster: &'a Booster,
+    pub format: ModelFormat,
+    pub params: &'a TrainParams,
+    /// Metrics of every evaluation set, one entry per round.
+    pub history: &'a [RoundMetrics],
+}
+
+/// Writes `bundle` to `dir`, replacing a previous bundle there. Returns the paths of the
+/// files in their final location.
+pub fn write_bundle(dir: &Path, bundle: &Bundle) -> Result<Vec<PathBuf>, ExperimentError> {
+    let staging = sibling(dir, "tmp")?;
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    fs::create_dir_all(&staging)?;
+    let files = match write_files(&staging, bundle) {
+        Ok(files) => files,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&staging);
+            return Err(e);
+        }
+    };
+    if let Err(e) = install(&staging, dir, &sibling(dir, "old")?) {
+        let _ = fs::remove_dir_all(&staging);
+        return Err(e.into());
+    }
+    if let Some(parent) = dir.parent().filter(|p| !p.as_os_str().is_empty()) {
+        // Persist the rename itself; not every platform can open directories
+        if let Ok(parent) = File::open(parent) {
+            let _ = parent.sync_all();
+        }
+    }
+    Ok(files.iter().map(|file| dir.join(file)).collect())
+}
+
+/// Renames `staging` to `dir`. A directory can only be renamed over an empty one, so an
+/// existing `dir` is moved aside to `previous` first and deleted afterwards. The target is
+/// briefly absent, but never incomplete; if `staging` cannot be moved in, the previous
+/// bundle is put back.
+fn install(staging: &Path, dir: &Path, previous: &Path) -> io::Result<()> {
+    let replacing = dir.exists();
+    if replacing {
+        if previous.exists() {
+            fs::remove_dir_all(previous)?;
+        }
+        fs::rename(dir, previous)?;
+    }
+    if let Err(e) = fs::rename(staging, dir) {
+        if replacing {
+            fs::rename(previous, dir)?;
+        }
+        return Err(e);
+    }
+    if replacing {
+        fs::remove_dir_all(previous)?;
+    }
+    Ok(())
+}
+
+/// `<dir>.<tag>-<pid>`, in the same parent so renames stay on one file system.
+fn sibling(dir: &Path, tag: &str) -> Result<PathBuf, ExperimentError> {
+    let name = dir
+        .file_name()
+        .ok_or_else(|| ExperimentError::Argument(format!("invalid output dir {:?}", dir)))?;
+    let mut name = name.to_os_string();
+    name.push(format!(".{}-{}", tag, std::process::id()));
+    Ok(dir.with_file_name(name))
+}
+
+fn write_files(dir: &Path, bundle: &Bundle) -> Result<Vec<String>, ExperimentError> {
+    let extension = match bundle.format {
+        ModelFormat::Json => "json",
+        ModelFormat::Ubj => "ubj",
+    };
+    let model = format!("{}.{}", bundle.name, extension);
+    write_synced(
+        &dir.join(&model),
+        &bundle.booster.save_model_to_buffer(bundle.format)?,
+    )?;
+
+    let params: serde_json::Map<String, serde_json::Value> = bundle
+        .params
+        .to_pairs()
+        .into_iter()
+        .map(|(key, value)| (key, value.into()))
+        .collect();
+    write_json(&dir.join(PARAMS_FILE), &params)?;
+    write_json(&dir.join(METRICS_FILE), &metrics_json(bundle.history))?;
+
+    write_json(
+        &dir.join(FEATURE_NAMES_FILE),
+        &bundle.booster.feature_names()?,
+    )?;
+    Ok(vec![
+        model,
+        PARAMS_FILE.to_string(),
+        METRICS_FILE.to_string(),
+        FEATURE_NAMES_FILE.to_string(),
+    ])
+}
+
+/// One object per round, e.g. `{"round": 0, "valid": {"rmse": 0.5}}`, with sorted keys.
+fn metrics_json(history: &[RoundMetrics]) -> serde_json::Value {
+    history
+        .iter()
+        .enumerate()
+        .map(|(round, sets)| {
+            let mut entry = serde_json::Map::new();
+            entry.insert("round".to_string(), round.into());
+            for (name, values) in sets {
+                let values: BTreeMap<_, _> = values.iter().collect();
+                entry.insert(name.clone(), serde_json::json!(values));
+            }
+            serde_json::Value::Object(entry)
+        })
+        .collect()
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> io::Result<()> {
+    write_synced(path, &serde_json::to_vec_pretty(value)?)
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_sibling() {
+        let tmp = sibling(Path::new("runs/churn"), "tmp").unwrap();
+        assert_eq!(
+            tmp,
+            PathBuf::from(format!("runs/churn.tmp-{}", std::process::id()))
+        );
+        assert!(sibling(Path::new("/"), "tmp").is_err());
+    }
+
+    /// A fresh directory for one test, removed on drop.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(test: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("xgb-rs-{}-{}", test, std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn bundle_dir(path: &Path, file: &str) -> PathBuf {
+        fs::create_dir_all(path).unwrap();
+        fs::write(path.join(file), file).unwrap();
+        path.to_path_buf()
+    }
+
+    #[test]
+    fn test_install_replaces() {
+        let tmp = TempDir::new("install-replace");
+        let (dir, previous) = (tmp.0.join("run"), tmp.0.join("run.old"));
+        install(&bundle_dir(&tmp.0.join("first"), "a"), &dir, &previous).unwrap();
+        assert!(dir.join("a").exists());
+        install(&bundle_dir(&tmp.0.join("second"), "b"), &dir, &previous).unwrap();
+        assert!(dir.join("b").exists());
+        assert!(!dir.join("a").exists());
+        assert!(!previous.exists());
+        assert!(!tmp.0.join("second").exists());
+    }
+
+    #[test]
+    fn test_install_failure_restores_previous() {
+        let tmp = TempDir::new("install-failure");
+        let (dir, previous) = (bundle_dir(&tmp.0.join("run"), "a"), tmp.0.join("run.old"));
+        // Moving the missing staging directory in fails after the old bundle moved aside
+        assert!(install(&tmp.0.join("missing"), &dir, &previous).is_err());
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "a");
+        assert!(!previous.exists());
+    }
+
+    #[test]
+    fn test_write_bundle_replaces() {
+        let dtrain = crate::dmatrix::DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[0.0, 1.0]).unwrap();
+        let booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let tmp = TempDir::new("write-bundle");
+        let dir = bundle_dir(&tmp.0.join("run"), "stale");
+        let params = TrainParams::new();
+        let bundle = Bundle {
+            name: "model",
+            booster: &booster,
+            format: ModelFormat::Ubj,
+            params: &params,
+            history: &[],
+        };
+        let files = write_bundle(&dir, &bundle).unwrap();
+        assert_eq!(files[0], dir.join("model.ubj"));
+        assert!(files.iter().all(|f| f.exists()));
+        assert!(!dir.join("stale").exists());
+        let leftovers = fs::read_dir(&tmp.0).unwrap().count();
+        assert_eq!(leftovers, 1);
+    }
+
+    #[test]
+    fn test_metrics_json() {
+        let history = vec![vec![(
+            "valid".to_string(),
+            HashMap::from([("rmse".to_string(), 0.5), ("mae".to_string(), 0.25)]),
+        )]];
+        assert_eq!(
+            metrics_json(&history).to_string(),
+            r#"[{"round":0,"valid":{"mae":0.25,"rmse":0.5}}]"#
+        );
+    }
+}
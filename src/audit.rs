@@ -0,0 +1,230 @@
+//! Row-level audit trail of predictions. An [`AuditLog`] attached to a booster with
+//! [`Booster::set_audit_log`](crate::booster::Booster::set_audit_log) receives a copy of
+//! every batch scored from in-memory rows; a background thread splits it into
+//! [`AuditRecord`]s and hands them to a sink, so the prediction path only pays for the
+//! copy and a non-blocking send. Auditing never fails a prediction.
+
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::cache;
+use crate::dmatrix::Csr;
+use crate::predict::Prediction;
+
+/// What is kept about one scored row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditRecord {
+    /// Position of the row among all rows audited by the log, from 0.
+    pub row_id: u64,
+    /// The caller's id for the row, from
+    /// [`PredictOptions::audit_ids`](crate::predict::PredictOptions::audit_ids).
+    pub id: Option<String>,
+    /// [`features_hash`] of the row's inputs, to match it to the request without
+    /// storing the features.
+    pub features_hash: u64,
+    pub prediction: Vec<f32>,
+    pub model_version: String,
+}
+
+/// 64-bit FNV-1a over the bit patterns of `features`. Stable across platforms and
+/// releases, and equal for rows with the same values, NaN payloads included. It is the
+/// key under which [`CachedPredictor`](crate::cache::CachedPredictor) caches the row.
+pub fn features_hash(features: &[f32]) -> u64 {
+    cache::row_key(features)
+}
+
+/// Inputs of a batch, expanded row by row on the worker.
+enum Rows {
+    Dense(Vec<f32>),
+    Sparse(Csr),
+}
+
+struct Batch {
+    first_row: u64,
+    rows: Rows,
+    cols: usize,
+    ids: Option<Arc<[String]>>,
+    prediction: Prediction,
+}
+
+/// Background audit sink. When the bounded queue is full, batches are not audited and
+/// counted in [`AuditLog::dropped`] rather than slowing predictions down; panics in the
+/// sink are caught on the worker thread. Dropping the log flushes the queue.
+pub struct AuditLog {
+    sender: Option<SyncSender<Batch>>,
+    worker: Option<JoinHandle<()>>,
+    next_row: AtomicU64,
+    dropped: AtomicU64,
+}
+
+impl AuditLog {
+    /// Starts the worker. `queue_size` is in batches; `model_version` is copied into
+    /// every record.
+    pub fn new<F>(model_version: &str, queue_size: usize, sink: F) -> Self
+    where
+        F: FnMut(AuditRecord) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel::<Batch>(queue_size);
+        let model_version = model_version.to_string();
+        let mut sink = sink;
+        let worker = thread::spawn(move || {
+            let mut dense_row = Vec::new();
+            for batch in receiver {
+                let row_len = batch.prediction.shape.row_len();
+                let rows = batch.prediction.shape.rows() as usize;
+                for row in 0..rows {
+                    let features = match &batch.rows {
+                        Rows::Dense(data) => &data[row * batch.cols..(row + 1) * batch.cols],
+                        Rows::Sparse(csr) => {
+                            csr.dense_row(row, batch.cols, &mut dense_row);
+                            &dense_row
+                        }
+                    };
+                    let record = AuditRecord {
+                        row_id: batch.first_row + row as u64,
+                        id: batch.ids.as_ref().map(|ids| ids[row].clone()),
+                        features_hash: features_hash(features),
+                        prediction: batch.prediction.values[row * row_len..(row + 1) * row_len]
+                            .to_vec(),
+                        model_version: model_version.clone(),
+                    };
+                    let _ = panic::catch_unwind(AssertUnwindSafe(|| sink(record)));
+                }
+            }
+        });
+        AuditLog {
+            sender: Some(sender),
+            worker: Some(worker),
+            next_row: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queues the rows of a scored batch, row-major `data` with `cols` columns. `ids`
+    /// are ignored unless there is one per row.
+    pub(crate) fn submit(
+        &self,
+        data: &[f32],
+        cols: usize,
+        ids: Option<&Arc<[String]>>,
+        prediction: &Prediction,
+    ) {
+        if data.len() as u64 != prediction.shape.rows() * cols as u64 {
+            return;
+        }
+        self.send(Rows::Dense(data.to_vec()), cols, ids, prediction);
+    }
+
+    /// Like [`AuditLog::submit`] for sparse rows, which are only expanded on the worker.
+    pub(crate) fn submit_csr(
+        &self,
+        csr: &Csr,
+        cols: usize,
+        ids: Option<&Arc<[String]>>,
+        prediction: &Prediction,
+    ) {
+        if csr.num_rows() as u64 != prediction.shape.rows() {
+            return;
+        }
+        self.send(Rows::Sparse(csr.clone()), cols, ids, prediction);
+    }
+
+    fn send(&self, rows: Rows, cols: usize, ids: Option<&Arc<[String]>>, prediction: &Prediction) {
+        let num_rows = prediction.shape.rows();
+        if num_rows == 0 {
+            return;
+        }
+        let first_row = self.next_row.fetch_add(num_rows, Ordering::Relaxed);
+        let batch = Batch {
+            first_row,
+            rows,
+            cols,
+            ids: ids.filter(|ids| ids.len() as u64 == num_rows).cloned(),
+            prediction: prediction.clone(),
+        };
+        if let Some(sender) = &self.sender {
+            if sender.try_send(batch).is_err() {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Batches that were not audited because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl fmt::Debug for AuditLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuditLog")
+            .field("next_row", &self.next_row)
+            .field("dropped", &self.dropped)
+            .finish()
+    }
+}
+
+impl Drop for AuditLog {
+    /// Waits for the queued batches to reach the sink.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::predict::Shape;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_features_hash() {
+        assert_eq!(features_hash(&[]), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(features_hash(&[1.0, 2.0]), features_hash(&[1.0, 2.0]));
+        assert_ne!(features_hash(&[1.0, 2.0]), features_hash(&[2.0, 1.0]));
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let log = AuditLog::new("v3", 8, move |r| sink.lock().unwrap().push(r));
+        let prediction = |values: Vec<f32>| Prediction {
+            shape: Shape::from_dims(&[values.len() as u64, 1]),
+            values,
+        };
+        let ids: Arc<[String]> = vec!["a".to_string(), "b".to_string()].into();
+        log.submit(
+            &[1.0, 2.0, 3.0, 4.0],
+            2,
+            Some(&ids),
+            &prediction(vec![0.1, 0.2]),
+        );
+        log.submit(&[5.0, 6.0], 2, Some(&ids), &prediction(vec![0.3]));
+        log.submit(&[5.0], 2, None, &prediction(vec![0.3]));
+        let csr = Csr {
+            indptr: vec![0, 1],
+            indices: vec![1],
+            values: vec![6.0],
+        };
+        log.submit_csr(&csr, 2, None, &prediction(vec![0.4]));
+        drop(log);
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[3].features_hash, features_hash(&[f32::NAN, 6.0]));
+        assert_eq!(records[2].row_id, 2);
+        assert_eq!(records[2].prediction, vec![0.3]);
+        assert_eq!(records[2].features_hash, features_hash(&[5.0, 6.0]));
+        assert_eq!(records[0].model_version, "v3");
+        assert_eq!(records[1].id.as_deref(), Some("b"));
+        // Ids not matching the rows are left out
+        assert_eq!(records[2].id, None);
+    }
+}
@@ -1,12 +1,43 @@
-use std::ffi::CString;
-use std::os::raw::c_float;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::fmt;
+use std::os::raw::{c_char, c_float, c_int, c_void};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard};
+use std::time::Instant;
 use thiserror::Error;
 use xgb_sys::{
-    BoosterHandle, XGBoosterCreate, XGBoosterFree, XGBoosterGetNumFeature, XGBoosterLoadModel,
-    XGBoosterPredictFromDMatrix, XGBoosterSaveModel, XGBoosterSetParam, XGBoosterUpdateOneIter,
+    BoosterHandle, XGBoosterBoostedRounds, XGBoosterCreate, XGBoosterEvalOneIter,
+    XGBoosterFeatureScore, XGBoosterFree, XGBoosterGetAttr, XGBoosterGetNumFeature,
+    XGBoosterGetStrFeatureInfo, XGBoosterLoadModel, XGBoosterLoadModelFromBuffer,
+    XGBoosterPredictFromCSR, XGBoosterPredictFromDMatrix, XGBoosterPredictFromDense,
+    XGBoosterSaveJsonConfig, XGBoosterSaveModel, XGBoosterSaveModelToBuffer, XGBoosterSetAttr,
+    XGBoosterSetParam, XGBoosterSetStrFeatureInfo, XGBoosterSlice, XGBoosterTrainOneIter,
+    XGBoosterUpdateOneIter,
 };
 
-use crate::dmatrix::DMatrix;
+use crate::audit::AuditLog;
+use crate::compat;
+use crate::dmatrix::{self, Csr, DMatrix, DMatrixError};
+use crate::encoding::{Encoders, ENCODERS_ATTR};
+#[cfg(feature = "encryption")]
+use crate::encryption::{self, EncryptionError};
+use crate::handles::{self, HandleKind};
+use crate::importance::{self, ImportanceType};
+use crate::instrument;
+use crate::interchange::SourceFormat;
+use crate::memory;
+use crate::model::{
+    self, Complexity, LinearWeights, ModelSummary, RoundContributions, TreeEnsemble,
+};
+use crate::model_card::{ModelCard, MODEL_CARD_ATTR};
+use crate::params::{ParamError, TrainParams};
+use crate::predict::{Device, PredictOptions, Prediction, PredictionType, Shape};
+use crate::schema::{FeatureSchema, SchemaError, FEATURE_SCHEMA_ATTR};
+#[cfg(feature = "object-store")]
+use crate::storage::{self, StorageError};
+use crate::training::{BestRound, StopReason, TrainControl, TrainOutcome, TrainingCallback};
 
 #[derive(Error, Debug)]
 pub enum XGBoostError {
@@ -20,15 +51,82 @@ pub enum XGBoostError {
     Train(usize),
     #[error("Cannot set {0} to {1}")]
     Config(String, String),
+    #[error("Cannot evaluate on {0}")]
+    Eval(String),
     #[error("Cannot save model")]
     Save,
     #[error("Cannot get booster info: {0}")]
     GetInfo(String),
+    #[error("Invalid model card: {0}")]
+    ModelCard(String),
+    #[error("Invalid encoders: {0}")]
+    Encoders(String),
+    #[error(transparent)]
+    Schema(#[from] SchemaError),
+    #[error(transparent)]
+    Param(#[from] ParamError),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+    #[error("Unsupported model: {0}")]
+    Unsupported(String),
+    #[error("Callback panicked: {0}")]
+    CallbackPanic(String),
+    #[error("Prediction thread panicked: {0}")]
+    Panic(String),
+    #[error("Training callback failed: {0}")]
+    Callback(String),
+    #[error("Cannot free Booster")]
+    Free,
+    #[error("No model: train the booster or load a model first")]
+    ModelNotLoaded,
+    #[cfg(feature = "object-store")]
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[cfg(feature = "encryption")]
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+}
+
+/// Serialization formats accepted by XGBoost.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFormat {
+    Json,
+    Ubj,
+}
+
+impl ModelFormat {
+    /// The format XGBoost picks from a file name: UBJSON for `.ubj`, JSON otherwise.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".ubj") {
+            ModelFormat::Ubj
+        } else {
+            ModelFormat::Json
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            ModelFormat::Json => "json",
+            ModelFormat::Ubj => "ubj",
+        }
+    }
 }
 
-#[derive(Debug)]
 pub struct Booster {
     handle: BoosterHandle,
+    schema: Option<FeatureSchema>,
+    audit: Option<Arc<AuditLog>>,
+    /// Computed on first use, reset by the methods changing the model.
+    summary: OnceLock<ModelSummary>,
+    /// Format of the last loaded model, if any.
+    format: Option<ModelFormat>,
+    /// Release of XGBoost that wrote the last loaded model, if known.
+    source_version: Option<[u32; 3]>,
+    /// UBJSON size of the model as of its last change, see [`memory::memory_stats`].
+    model_bytes: AtomicU64,
+    /// Held for reading by every `&self` call into the C API and for writing while a
+    /// prediction temporarily changes `device` or `nthread`.
+    config_lock: RwLock<()>,
 }
 
 impl Booster {
@@ -36,14 +134,107 @@ impl Booster {
         let mut handle = std::ptr::null_mut();
         unsafe {
             if XGBoosterCreate(std::ptr::null_mut(), 0, &mut handle) == 0 {
-                Ok(Booster { handle })
+                Ok(Booster::from_handle(handle))
             } else {
                 Err(XGBoostError::Create)
             }
         }
     }
 
+    /// Takes ownership of a booster created elsewhere through the C API, e.g. by code
+    /// linking `xgb-sys` directly. The returned `Booster` frees it when dropped.
+    ///
+    /// # Safety
+    /// `handle` must be a live handle from `XGBoosterCreate` that nothing else frees or
+    /// uses concurrently from now on. Passing the same handle twice leads to a double free.
+    pub unsafe fn from_raw_handle(handle: BoosterHandle) -> Self {
+        let booster = Booster::from_handle(handle);
+        booster.resize();
+        booster
+    }
+
+    fn from_handle(handle: BoosterHandle) -> Self {
+        handles::register(HandleKind::Booster, handle);
+        memory::track_booster();
+        Booster {
+            handle,
+            schema: None,
+            audit: None,
+            summary: OnceLock::new(),
+            format: None,
+            source_version: None,
+            model_bytes: AtomicU64::new(0),
+            config_lock: RwLock::new(()),
+        }
+    }
+
+    /// Releases ownership of the handle without freeing it. The caller becomes
+    /// responsible for calling `XGBoosterFree` on it exactly once.
+    pub fn into_raw_handle(mut self) -> BoosterHandle {
+        let handle = std::mem::replace(&mut self.handle, std::ptr::null_mut());
+        handles::unregister(handle);
+        memory::untrack_booster(*self.model_bytes.get_mut());
+        handle
+    }
+
+    /// The handle, still owned by this booster, for C API calls this crate does not wrap.
+    /// It must not be freed and is only valid while the booster is alive.
+    pub fn as_raw_handle(&self) -> BoosterHandle {
+        self.handle
+    }
+
+    /// Frees the booster, reporting a failure that dropping it would only log.
+    pub fn close(mut self) -> Result<(), XGBoostError> {
+        let handle = std::mem::replace(&mut self.handle, std::ptr::null_mut());
+        handles::unregister(handle);
+        memory::untrack_booster(*self.model_bytes.get_mut());
+        unsafe {
+            if XGBoosterFree(handle) == 0 {
+                Ok(())
+            } else {
+                Err(XGBoostError::Free)
+            }
+        }
+    }
+
+    /// Re-sizes the model for [`memory::memory_stats`] after it changed. The size is always
+    /// taken in UBJSON, whatever format the model is loaded from or saved to, so it does
+    /// not depend on which one was used last. A model that cannot be serialized, e.g. an
+    /// untrained one, counts as 0.
+    fn resize(&self) {
+        let config =
+            CString::new(format!("{{\"format\": \"{}\"}}", ModelFormat::Ubj.as_str())).unwrap();
+        let mut out_len: u64 = 0;
+        let mut out_dptr: *const c_char = std::ptr::null();
+        let bytes = unsafe {
+            if XGBoosterSaveModelToBuffer(self.handle, config.as_ptr(), &mut out_len, &mut out_dptr)
+                == 0
+            {
+                out_len
+            } else {
+                0
+            }
+        };
+        let old = self.model_bytes.swap(bytes, Ordering::Relaxed);
+        memory::resize_model(old, bytes);
+    }
+
     pub fn set_conf(&mut self, key: &str, value: &str) -> Result<(), XGBoostError> {
+        self.summary.take();
+        let c_key = CString::new(key).unwrap();
+        let c_value = CString::new(value).unwrap();
+        unsafe {
+            if XGBoosterSetParam(self.handle, c_key.as_ptr(), c_value.as_ptr()) == 0 {
+                Ok(())
+            } else {
+                Err(XGBoostError::Config(key.to_string(), value.to_string()))
+            }
+        }
+    }
+
+    /// Sets a parameter through a shared reference. Only for
+    /// [`Booster::with_prediction_options`], which holds `config_lock` for writing.
+    fn set_param_exclusive(&self, key: &str, value: &str) -> Result<(), XGBoostError> {
         let c_key = CString::new(key).unwrap();
         let c_value = CString::new(value).unwrap();
         unsafe {
@@ -55,7 +246,53 @@ impl Booster {
         }
     }
 
+    fn shared(&self) -> RwLockReadGuard<'_, ()> {
+        self.config_lock.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs `predict` with the device and thread count of `options`, if set. XGBoost has
+    /// no per-call setting for either, so they are set on the booster while no other call
+    /// uses it and restored afterwards. If CUDA is requested but the library was built
+    /// without it, or the prediction fails on the device, it runs on the CPU instead.
+    fn with_prediction_options<T>(
+        &self,
+        options: &PredictOptions,
+        predict: impl Fn() -> Result<T, XGBoostError>,
+    ) -> Result<T, XGBoostError> {
+        if options.device.is_none() && options.nthread.is_none() {
+            let _shared = self.shared();
+            return predict();
+        }
+        let _exclusive = self.config_lock.write().unwrap_or_else(|e| e.into_inner());
+        let config = self.json_config()?;
+        let generic = &config["learner"]["generic_param"];
+        let previous_device = generic["device"].as_str().unwrap_or("cpu").to_string();
+        let previous_nthread = generic["nthread"].as_str().unwrap_or("0").to_string();
+        if let Some(nthread) = options.nthread {
+            self.set_param_exclusive("nthread", &nthread.to_string())?;
+        }
+        let result = match options.device.map(Device::or_cpu) {
+            Some(device) => self
+                .set_param_exclusive("device", &device.as_param())
+                .and_then(|_| match predict() {
+                    Err(_) if device != Device::Cpu => {
+                        self.set_param_exclusive("device", &Device::Cpu.as_param())?;
+                        predict()
+                    }
+                    result => result,
+                }),
+            None => predict(),
+        };
+        let restored = self
+            .set_param_exclusive("device", &previous_device)
+            .and_then(|_| self.set_param_exclusive("nthread", &previous_nthread));
+        let value = result?;
+        restored?;
+        Ok(value)
+    }
+
     pub fn get_number_of_features(&self) -> Result<usize, XGBoostError> {
+        let _shared = self.shared();
         let mut num_feats: u64 = u64::default();
         unsafe {
             if XGBoosterGetNumFeature(self.handle, &mut num_feats as *mut u64) == 0 {
@@ -68,29 +305,338 @@ impl Booster {
 
     pub fn train(
         dtrain: &DMatrix,
-        _dtest: &DMatrix,
+        dtest: &DMatrix,
         num_boost: usize,
     ) -> Result<Self, XGBoostError> {
+        let mut booster = Booster::with_cache(&[dtrain, dtest])?;
+        for i in 0..num_boost {
+            booster.update(dtrain, i)?;
+        }
+        Ok(booster)
+    }
+
+    /// Applies validated typed parameters.
+    pub fn set_params(&mut self, params: &TrainParams) -> Result<(), XGBoostError> {
+        params.validate()?;
+        for (key, value) in params.to_pairs() {
+            self.set_conf(&key, &value)?;
+        }
+        Ok(())
+    }
+
+    /// Like [`Booster::train`], configuring the booster with `params` first. For `multi:*`
+    /// objectives `num_class` is checked against, or inferred from, the labels of `dtrain`.
+    pub fn train_with_params(
+        dtrain: &DMatrix,
+        params: &TrainParams,
+        num_boost: usize,
+    ) -> Result<Self, XGBoostError> {
+        let mut booster = Booster::configured(dtrain, &[], params)?;
+        for i in 0..num_boost {
+            booster.update(dtrain, i)?;
+        }
+        Ok(booster)
+    }
+
+    /// Like [`Booster::train_with_params`], but stops early when `control` says so and
+    /// returns the rounds trained up to that point. Without evaluation sets there is no
+    /// score to pick the best round by, see [`Booster::train_with_evals`].
+    pub fn train_with_control(
+        dtrain: &DMatrix,
+        params: &TrainParams,
+        num_boost: usize,
+        control: &TrainControl,
+    ) -> Result<TrainOutcome, XGBoostError> {
+        Booster::train_with_evals(dtrain, &[], params, num_boost, control, &mut ())
+    }
+
+    /// Like [`Booster::train_with_control`], scoring every named set in `evals` after each
+    /// round and handing the metrics to `callback`. The sets are added to the prediction
+    /// cache, so each evaluation only runs the new trees.
+    ///
+    /// The best round is picked as XGBoost's early stopping does, by the last `eval_metric`
+    /// of `params` (or the only metric reported) on the last set of `evals`. If `control`
+    /// stops training, the returned booster is truncated to that round.
+    pub fn train_with_evals(
+        dtrain: &DMatrix,
+        evals: &[(&DMatrix, &str)],
+        params: &TrainParams,
+        num_boost: usize,
+        control: &TrainControl,
+        callback: &mut dyn TrainingCallback,
+    ) -> Result<TrainOutcome, XGBoostError> {
+        let started = Instant::now();
+        let cached: Vec<&DMatrix> = evals.iter().map(|(data, _)| *data).collect();
+        let mut booster = Booster::configured(dtrain, &cached, params)?;
+        let mut best = BestRound::new(params.get("eval_metric"));
+        for i in 0..num_boost {
+            if let Some(stop) = control.check(started) {
+                let rounds = best.round().map_or(i, |round| round + 1);
+                if rounds < i {
+                    booster = booster.slice(0, rounds)?;
+                }
+                return Ok(TrainOutcome {
+                    booster,
+                    rounds,
+                    stop,
+                    best_score: best.score(),
+                });
+            }
+            booster.update(dtrain, i)?;
+            if !evals.is_empty() {
+                let metrics = evals
+                    .iter()
+                    .map(|(data, name)| Ok((name.to_string(), booster.evaluate(data, name)?)))
+                    .collect::<Result<Vec<_>, XGBoostError>>()?;
+                best.observe(i, &metrics);
+                callback
+                    .after_round(i, &metrics)
+                    .map_err(|e| XGBoostError::Callback(e.to_string()))?;
+            }
+        }
+        Ok(TrainOutcome {
+            booster,
+            rounds: num_boost,
+            stop: StopReason::Completed,
+            best_score: best.score(),
+        })
+    }
+
+    /// A new booster holding rounds `begin..end` of this one.
+    pub fn slice(&self, begin: usize, end: usize) -> Result<Booster, XGBoostError> {
+        let _shared = self.shared();
         let mut handle = std::ptr::null_mut();
-        let booster = unsafe {
-            if XGBoosterCreate([dtrain.handle].as_ptr(), 1, &mut handle) == 0 {
-                Ok(Booster { handle })
+        unsafe {
+            if XGBoosterSlice(self.handle, begin as c_int, end as c_int, 1, &mut handle) == 0 {
+                let booster = Booster::from_handle(handle);
+                booster.resize();
+                Ok(booster)
             } else {
-                Err(XGBoostError::Create)
+                Err(XGBoostError::Unsupported(format!(
+                    "cannot slice rounds {}..{}",
+                    begin, end
+                )))
             }
-        }?;
+        }
+    }
+
+    /// Boosts the residuals of `base`: its margins become the `base_margin` of `dtrain` and
+    /// of every matrix in `evals`, and the new trees are trained on top of them.
+    ///
+    /// `dtrain` and `evals` are modified: they keep `base`'s margins as base margin
+    /// afterwards, replacing any they had, which is what evaluating the returned booster
+    /// on them needs. The returned booster only holds the new trees. To score new data,
+    /// set its base margin with [`Booster::set_base_margin_of`] on `base` before
+    /// predicting.
+    pub fn train_from(
+        base: &Booster,
+        dtrain: &DMatrix,
+        evals: &[&DMatrix],
+        params: &TrainParams,
+        num_boost: usize,
+    ) -> Result<Self, XGBoostError> {
+        for data in std::iter::once(dtrain).chain(evals.iter().copied()) {
+            base.set_base_margin_of(data)?;
+        }
+        let mut booster = Booster::configured(dtrain, evals, params)?;
         for i in 0..num_boost {
-            unsafe {
-                if XGBoosterUpdateOneIter(booster.handle, i as i32, dtrain.handle) != 0 {
-                    return Err(XGBoostError::Train(i));
+            booster.update(dtrain, i)?;
+        }
+        Ok(booster)
+    }
+
+    /// Sets the `base_margin` of `data` to this model's untransformed predictions on it, so
+    /// another model can start from them. A base margin already set on `data` is ignored
+    /// while predicting and replaced, so calling this twice does not add the margins up.
+    /// If the prediction fails, `data` keeps its previous base margin.
+    pub fn set_base_margin_of(&self, data: &DMatrix) -> Result<(), XGBoostError> {
+        let previous = data.get_base_margin()?;
+        if !previous.is_empty() {
+            data.clear_base_margin()?;
+        }
+        match self.predict_with(data, &PredictOptions::margin()) {
+            Ok(margin) => Ok(data.set_base_margin(&margin)?),
+            Err(e) => {
+                if !previous.is_empty() {
+                    data.set_base_margin(&previous)?;
                 }
+                Err(e)
+            }
+        }
+    }
+
+    /// A booster caching `dtrain` and `evals` with `params` applied, `num_class` resolved
+    /// for `multi:*` objectives.
+    fn configured(
+        dtrain: &DMatrix,
+        evals: &[&DMatrix],
+        params: &TrainParams,
+    ) -> Result<Self, XGBoostError> {
+        let params = if params.is_multiclass() {
+            params.resolve_num_class(&dtrain.get_labels()?)?
+        } else {
+            params.clone()
+        };
+        if params
+            .get_objective()
+            .is_some_and(|o| o.starts_with("rank:"))
+        {
+            // Catch missing or inconsistent groups before XGBoost fails mid-training
+            let group_ptr = dtrain.get_group_ptr()?;
+            if group_ptr.len() < 2 || group_ptr.last() != Some(&(dtrain.num_rows() as u32)) {
+                return Err(XGBoostError::Config(
+                    "objective".to_string(),
+                    format!(
+                        "{} without query groups covering all rows",
+                        params.get_objective().unwrap_or_default()
+                    ),
+                ));
             }
         }
+        let cached: Vec<&DMatrix> = std::iter::once(dtrain)
+            .chain(evals.iter().copied())
+            .collect();
+        let mut booster = Booster::with_cache(&cached)?;
+        booster.set_params(&params)?;
         Ok(booster)
     }
 
+    /// Creates a booster whose prediction cache covers `dmats`.
+    ///
+    /// XGBoost keeps the predictions of cached matrices between rounds, so evaluating or
+    /// predicting on them after each [`Booster::update`] only runs the newly added trees
+    /// instead of the whole model. Register the training matrix and any validation sets
+    /// that are scored repeatedly. The matrices should outlive the booster's training loop;
+    /// a freed matrix simply drops out of the cache.
+    pub fn with_cache(dmats: &[&DMatrix]) -> Result<Self, XGBoostError> {
+        let handles: Vec<_> = dmats.iter().map(|d| d.handle).collect();
+        let mut handle = std::ptr::null_mut();
+        unsafe {
+            if XGBoosterCreate(handles.as_ptr(), handles.len() as u64, &mut handle) == 0 {
+                Ok(Booster::from_handle(handle))
+            } else {
+                Err(XGBoostError::Create)
+            }
+        }
+    }
+
+    /// Runs one boosting round on `dtrain`.
+    pub fn update(&mut self, dtrain: &DMatrix, iteration: usize) -> Result<(), XGBoostError> {
+        self.summary.take();
+        instrument::traced("update", dtrain.num_rows(), || unsafe {
+            if XGBoosterUpdateOneIter(self.handle, iteration as i32, dtrain.handle) == 0 {
+                Ok(())
+            } else {
+                Err(XGBoostError::Train(iteration))
+            }
+        })?;
+        self.resize();
+        Ok(())
+    }
+
+    /// Number of boosting rounds in the model.
+    pub fn boosted_rounds(&self) -> Result<usize, XGBoostError> {
+        let _shared = self.shared();
+        let mut rounds: c_int = 0;
+        unsafe {
+            if XGBoosterBoostedRounds(self.handle, &mut rounds) == 0 {
+                Ok(rounds as usize)
+            } else {
+                Err(XGBoostError::GetInfo("boosted rounds".to_string()))
+            }
+        }
+    }
+
+    /// Fails with [`XGBoostError::ModelNotLoaded`] if the booster was neither trained nor
+    /// loaded, instead of letting XGBoost predict from an unconfigured model.
+    fn ensure_loaded(&self) -> Result<(), XGBoostError> {
+        if self.boosted_rounds()? == 0 {
+            return Err(XGBoostError::ModelNotLoaded);
+        }
+        Ok(())
+    }
+
+    /// Runs one boosting round from externally computed gradients and hessians, bypassing
+    /// the objective. Both hold one value per row, or `rows * groups` values laid out row by
+    /// row for multi-output models.
+    pub fn boost(
+        &mut self,
+        dtrain: &DMatrix,
+        grad: &[f32],
+        hess: &[f32],
+    ) -> Result<(), XGBoostError> {
+        let rows = dtrain.num_rows() as usize;
+        if rows == 0 || grad.len() != hess.len() || !grad.len().is_multiple_of(rows) {
+            return Err(XGBoostError::Unsupported(format!(
+                "{} gradients and {} hessians for {} rows",
+                grad.len(),
+                hess.len(),
+                rows
+            )));
+        }
+        let iteration = self.boosted_rounds()?;
+        self.summary.take();
+        let shape = [rows, grad.len() / rows];
+        let c_grad = array_interface(grad, &shape);
+        let c_hess = array_interface(hess, &shape);
+        instrument::traced("boost", rows as u64, || unsafe {
+            if XGBoosterTrainOneIter(
+                self.handle,
+                dtrain.handle,
+                iteration as c_int,
+                c_grad.as_ptr(),
+                c_hess.as_ptr(),
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(XGBoostError::Train(iteration))
+            }
+        })?;
+        self.resize();
+        Ok(())
+    }
+
+    /// Scores `data` with the metrics configured on the booster (`eval_metric`, or the
+    /// objective's default), keyed by metric name.
+    pub fn evaluate(
+        &self,
+        data: &DMatrix,
+        name: &str,
+    ) -> Result<HashMap<String, f64>, XGBoostError> {
+        let c_name = CString::new(name).unwrap();
+        let mut dmats = [data.handle];
+        let mut names = [c_name.as_ptr()];
+        let mut out: *const c_char = std::ptr::null();
+        let _shared = self.shared();
+        let result = unsafe {
+            if XGBoosterEvalOneIter(
+                self.handle,
+                0,
+                dmats.as_mut_ptr(),
+                names.as_mut_ptr(),
+                1,
+                &mut out,
+            ) != 0
+            {
+                return Err(XGBoostError::Eval(name.to_string()));
+            }
+            CStr::from_ptr(out).to_string_lossy().into_owned()
+        };
+        parse_eval(&result, name).ok_or(XGBoostError::Eval(name.to_string()))
+    }
+
+    /// Saves the model to a file, or to object storage for `s3://` and `gs://` URIs with
+    /// the `object-store` feature. The format follows the extension, see
+    /// [`ModelFormat::from_path`].
     pub fn save_model(&self, fname: &str) -> Result<(), XGBoostError> {
+        #[cfg(feature = "object-store")]
+        if storage::is_remote(fname) {
+            let buf = self.save_model_to_buffer(ModelFormat::from_path(fname))?;
+            return Ok(storage::put(fname, buf)?);
+        }
         let fname = CString::new(fname).unwrap();
+        let _shared = self.shared();
         unsafe {
             if XGBoosterSaveModel(self.handle, fname.as_ptr()) == 0 {
                 Ok(())
@@ -100,51 +646,865 @@ impl Booster {
         }
     }
 
+    pub fn save_model_to_buffer(&self, format: ModelFormat) -> Result<Vec<u8>, XGBoostError> {
+        let config = CString::new(format!("{{\"format\": \"{}\"}}", format.as_str())).unwrap();
+        let mut out_len: u64 = 0;
+        let mut out_dptr: *const c_char = std::ptr::null();
+        let _shared = self.shared();
+        unsafe {
+            if XGBoosterSaveModelToBuffer(self.handle, config.as_ptr(), &mut out_len, &mut out_dptr)
+                == 0
+            {
+                Ok(std::slice::from_raw_parts(out_dptr as *const u8, out_len as usize).to_vec())
+            } else {
+                Err(XGBoostError::Save)
+            }
+        }
+    }
+
+    /// Loads a model from memory, validating its [`ModelCard`] and [`FeatureSchema`] like
+    /// [`Booster::load_model`].
+    pub fn load_model_from_buffer(&mut self, buf: &[u8]) -> Result<(), XGBoostError> {
+        self.summary.take();
+        self.schema = None;
+        unsafe {
+            if XGBoosterLoadModelFromBuffer(
+                self.handle,
+                buf.as_ptr() as *const c_void,
+                buf.len() as u64,
+            ) == 0
+            {
+                self.format = match SourceFormat::detect(buf) {
+                    SourceFormat::Json => Some(ModelFormat::Json),
+                    SourceFormat::Ubj => Some(ModelFormat::Ubj),
+                    _ => None,
+                };
+                self.source_version = compat::sniff_version(buf);
+                self.resize();
+            } else {
+                return Err(XGBoostError::Load);
+            }
+        }
+        self.check_loaded_metadata()
+    }
+
+    /// The booster's internal configuration, including every training parameter as
+    /// XGBoost parsed it.
+    pub fn config(&self) -> Result<serde_json::Value, XGBoostError> {
+        let _shared = self.shared();
+        self.json_config()
+    }
+
+    /// [`Booster::config`], for callers already holding `config_lock`.
+    fn json_config(&self) -> Result<serde_json::Value, XGBoostError> {
+        let mut out_len: u64 = 0;
+        let mut out: *const c_char = std::ptr::null();
+        let config = unsafe {
+            if XGBoosterSaveJsonConfig(self.handle, &mut out_len, &mut out) == 0 {
+                std::slice::from_raw_parts(out as *const u8, out_len as usize)
+            } else {
+                return Err(XGBoostError::GetInfo("config".to_string()));
+            }
+        };
+        serde_json::from_slice(config).map_err(|e| XGBoostError::GetInfo(e.to_string()))
+    }
+
+    /// Booster type, objective and sizes of the model. Computed from its JSON once and
+    /// cached until the model changes.
+    pub fn summary(&self) -> Result<ModelSummary, XGBoostError> {
+        if let Some(summary) = self.summary.get() {
+            return Ok(summary.clone());
+        }
+        let summary = ModelSummary::from_model_json(&self.model_json()?)
+            .map_err(XGBoostError::Unsupported)?;
+        Ok(self.summary.get_or_init(|| summary).clone())
+    }
+
+    /// Format of the last model loaded from a file or buffer, `None` if the booster was
+    /// trained in this process.
+    pub fn model_format(&self) -> Option<ModelFormat> {
+        self.format
+    }
+
+    /// Release of XGBoost that wrote the last loaded model, as `[major, minor, patch]`.
+    /// `None` if the booster was trained in this process or the file does not say. The
+    /// learner type is in [`Booster::summary`]; see [`compat::check`] for a full report.
+    pub fn source_version(&self) -> Option<[u32; 3]> {
+        self.source_version
+    }
+
+    /// The model in XGBoost's JSON schema.
+    pub fn model_json(&self) -> Result<serde_json::Value, XGBoostError> {
+        let buf = self.save_model_to_buffer(ModelFormat::Json)?;
+        serde_json::from_slice(&buf).map_err(|e| XGBoostError::GetInfo(e.to_string()))
+    }
+
+    /// Per-feature coefficients and bias of a `booster=gblinear` model.
+    pub fn linear_weights(&self) -> Result<LinearWeights, XGBoostError> {
+        LinearWeights::from_model_json(&self.model_json()?).map_err(XGBoostError::Unsupported)
+    }
+
+    /// Trees of a `gbtree` or `dart` model, parsed from its JSON representation.
+    pub fn trees(&self) -> Result<TreeEnsemble, XGBoostError> {
+        TreeEnsemble::from_model_json(&self.model_json()?).map_err(XGBoostError::Unsupported)
+    }
+
+    /// Number of trees, leaves, depths and serialized size of a tree model.
+    pub fn complexity(&self) -> Result<Complexity, XGBoostError> {
+        let size = self.save_model_to_buffer(ModelFormat::Ubj)?.len();
+        Ok(Complexity::new(&self.trees()?, size))
+    }
+
+    /// Returns a copy of the model where splits with a loss change below `min_gain` are
+    /// collapsed into leaves, bottom-up. Useful to shrink ensembles for latency budgets.
+    pub fn prune(&self, min_gain: f32) -> Result<Booster, XGBoostError> {
+        let mut json = self.model_json()?;
+        model::prune_model_json(&mut json, min_gain).map_err(XGBoostError::Unsupported)?;
+        let buf =
+            serde_json::to_vec(&json).map_err(|e| XGBoostError::Unsupported(e.to_string()))?;
+        let mut pruned = Booster::new()?;
+        pruned.load_model_from_buffer(&buf)?;
+        pruned.schema = self.schema.clone();
+        Ok(pruned)
+    }
+
+    /// Recomputes the node statistics of every tree from `new_data` while keeping the tree
+    /// structure, and also the leaf values unless `refresh_stats_only` is set. This runs
+    /// XGBoost's `refresh` updater with `process_type=update` once per boosting round, then
+    /// restores `process_type`, `updater` and `refresh_leaf`, also if refreshing failed, so
+    /// further rounds grow new trees again.
+    pub fn refresh_leaves(
+        &mut self,
+        new_data: &DMatrix,
+        refresh_stats_only: bool,
+    ) -> Result<(), XGBoostError> {
+        self.ensure_loaded()?;
+        let rounds = self.boosted_rounds()?;
+        let previous = training_updaters(&self.config()?);
+        let refreshed = self
+            .set_conf("process_type", "update")
+            .and_then(|_| self.set_conf("updater", "refresh"))
+            .and_then(|_| self.set_conf("refresh_leaf", if refresh_stats_only { "0" } else { "1" }))
+            .and_then(|_| (0..rounds).try_for_each(|i| self.update(new_data, i)));
+        // Restored even if refreshing failed, attempting every setting
+        let restored = previous
+            .iter()
+            .map(|(key, value)| self.set_conf(key, value))
+            .fold(Ok(()), Result::and);
+        refreshed.and(restored)
+    }
+
+    /// Margins of a tree model computed in double precision, laid out as `(rows, groups)`.
+    ///
+    /// The C API only returns single precision, so this predicts leaf indices and sums the
+    /// leaf values itself, in `f64` and always in the same tree order. Sums are therefore
+    /// reproducible, and may differ from [`Booster::predict_with`] margins in the last
+    /// float digits. Leaf values and the intercept are stored by XGBoost in single
+    /// precision and are widened, not recovered. The `base_margin` of `data` is honored.
+    pub fn predict_margin_f64(&self, data: &DMatrix) -> Result<Vec<f64>, XGBoostError> {
+        let json = self.model_json()?;
+        let ensemble = TreeEnsemble::from_model_json(&json).map_err(XGBoostError::Unsupported)?;
+        let intercept = model::margin_intercept(&json).map_err(XGBoostError::Unsupported)?;
+        let leaves = self.predict_with(data, &PredictOptions::new().kind(PredictionType::Leaf))?;
+        Ok(ensemble.margins_f64(&leaves, &intercept, &data.get_base_margin()?))
+    }
+
+    /// Decomposes each row's margin into the output of every boosting round, from leaf
+    /// predictions and the leaf values of the trees. Summing a row's rounds and adding
+    /// the base score gives its margin.
+    pub fn predict_per_tree(&self, data: &DMatrix) -> Result<RoundContributions, XGBoostError> {
+        let ensemble = self.trees()?;
+        let leaves = self.predict_with(data, &PredictOptions::new().kind(PredictionType::Leaf))?;
+        Ok(RoundContributions::from_leaves(&ensemble, &leaves))
+    }
+
+    /// Sparse one-hot encoding of the leaf each row reaches in each tree, one column per
+    /// leaf, for stacking a linear model on top of the trees.
+    pub fn leaf_features(&self, data: &DMatrix) -> Result<DMatrix, XGBoostError> {
+        let ensemble = self.trees()?;
+        let leaves = self.predict_with(data, &PredictOptions::new().kind(PredictionType::Leaf))?;
+        Ok(DMatrix::try_from_csr(
+            &ensemble.leaf_one_hot(&leaves),
+            ensemble.num_leaves() as u64,
+        )?)
+    }
+
+    /// Loads a model file, or an object for `s3://` and `gs://` URIs with the
+    /// `object-store` feature. If it carries a [`ModelCard`] or a [`FeatureSchema`], they
+    /// are validated against the loaded model.
     pub fn load_model(&mut self, fname: &str) -> Result<(), XGBoostError> {
+        #[cfg(feature = "object-store")]
+        if storage::is_remote(fname) {
+            return self.load_model_from_buffer(&storage::get(fname)?);
+        }
+        self.summary.take();
+        // The previous model's schema must not outlive it, even if this load fails
+        self.schema = None;
         let c_fname = CString::new(fname).unwrap();
         unsafe {
-            if XGBoosterLoadModel(self.handle, c_fname.as_ptr()) == 0 {
+            if XGBoosterLoadModel(self.handle, c_fname.as_ptr()) != 0 {
+                return Err(XGBoostError::Load);
+            }
+        }
+        self.format = Some(ModelFormat::from_path(fname));
+        self.source_version = compat::file_version(fname);
+        self.resize();
+        self.check_loaded_metadata()
+    }
+
+    /// Saves the model as UBJSON encrypted with AES-256-GCM under `key`. See
+    /// [`encryption`] for the file layout.
+    #[cfg(feature = "encryption")]
+    pub fn save_model_encrypted(&self, path: &str, key: &[u8; 32]) -> Result<(), XGBoostError> {
+        let sealed = encryption::seal(key, &self.save_model_to_buffer(ModelFormat::Ubj)?)?;
+        std::fs::write(path, sealed).map_err(EncryptionError::from)?;
+        Ok(())
+    }
+
+    /// Loads a model written by [`Booster::save_model_encrypted`], failing if `key` is
+    /// wrong or the file was modified.
+    #[cfg(feature = "encryption")]
+    pub fn load_model_encrypted(&mut self, path: &str, key: &[u8; 32]) -> Result<(), XGBoostError> {
+        let sealed = std::fs::read(path).map_err(EncryptionError::from)?;
+        self.load_model_from_buffer(&encryption::open(key, &sealed)?)
+    }
+
+    /// Validates the [`ModelCard`] and [`FeatureSchema`] of a freshly loaded model. Without
+    /// a schema of its own, the model is checked against the card's.
+    fn check_loaded_metadata(&mut self) -> Result<(), XGBoostError> {
+        let card = self.model_card()?;
+        if let Some(card) = &card {
+            card.validate(self.get_number_of_features()?)
+                .map_err(XGBoostError::ModelCard)?;
+        }
+        self.schema = match self.get_attr(FEATURE_SCHEMA_ATTR)? {
+            Some(json) => Some(FeatureSchema::from_json(&json)?),
+            None => card
+                .map(|card| card.feature_schema)
+                .filter(|schema| !schema.is_empty()),
+        };
+        if let Some(schema) = &self.schema {
+            let num_features = self.get_number_of_features()?;
+            if schema.len() != num_features {
+                return Err(SchemaError::ColumnCount {
+                    expected: num_features,
+                    got: schema.len(),
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Sends the inputs and outputs of every later prediction from dense or CSR rows to
+    /// `log`, with the row ids given by [`PredictOptions::audit_ids`]. Predictions from a
+    /// [`DMatrix`] are not audited, since that would copy its data out on every call.
+    pub fn set_audit_log(&mut self, log: Arc<AuditLog>) {
+        self.audit = Some(log);
+    }
+
+    pub fn clear_audit_log(&mut self) {
+        self.audit = None;
+    }
+
+    pub fn set_attr(&mut self, key: &str, value: &str) -> Result<(), XGBoostError> {
+        let c_key = CString::new(key).unwrap();
+        let c_value = CString::new(value).unwrap();
+        unsafe {
+            if XGBoosterSetAttr(self.handle, c_key.as_ptr(), c_value.as_ptr()) == 0 {
                 Ok(())
             } else {
-                Err(XGBoostError::Load)
+                Err(XGBoostError::Config(key.to_string(), value.to_string()))
             }
         }
     }
 
-    pub fn predict(&self, data: &DMatrix) -> Result<Vec<f32>, XGBoostError> {
-        let mut out_result: *const c_float = std::ptr::null();
-        let mut out_shape: u64 = 0;
+    pub fn get_attr(&self, key: &str) -> Result<Option<String>, XGBoostError> {
+        let c_key = CString::new(key).unwrap();
+        let mut out: *const c_char = std::ptr::null();
+        let mut success: i32 = 0;
+        let _shared = self.shared();
+        unsafe {
+            if XGBoosterGetAttr(self.handle, c_key.as_ptr(), &mut out, &mut success) != 0 {
+                return Err(XGBoostError::GetInfo(key.to_string()));
+            }
+            if success == 0 || out.is_null() {
+                Ok(None)
+            } else {
+                Ok(Some(CStr::from_ptr(out).to_string_lossy().into_owned()))
+            }
+        }
+    }
+
+    /// Stores the card as a booster attribute, so it is written by the next `save_model`.
+    pub fn set_model_card(&mut self, card: &ModelCard) -> Result<(), XGBoostError> {
+        card.validate(self.get_number_of_features()?)
+            .map_err(XGBoostError::ModelCard)?;
+        let json = card.to_json().map_err(XGBoostError::ModelCard)?;
+        self.set_attr(MODEL_CARD_ATTR, &json)
+    }
+
+    pub fn model_card(&self) -> Result<Option<ModelCard>, XGBoostError> {
+        match self.get_attr(MODEL_CARD_ATTR)? {
+            Some(json) => Ok(Some(
+                ModelCard::from_json(&json).map_err(XGBoostError::ModelCard)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Stores fitted categorical encoders with the model, to be applied at inference.
+    pub fn set_encoders(&mut self, encoders: &Encoders) -> Result<(), XGBoostError> {
+        let json = encoders.to_json().map_err(XGBoostError::Encoders)?;
+        self.set_attr(ENCODERS_ATTR, &json)
+    }
+
+    pub fn encoders(&self) -> Result<Option<Encoders>, XGBoostError> {
+        match self.get_attr(ENCODERS_ATTR)? {
+            Some(json) => Ok(Some(
+                Encoders::from_json(&json).map_err(XGBoostError::Encoders)?,
+            )),
+            None => Ok(None),
+        }
+    }
 
-        // Run the prediction
-        let conf = CString::new("{\"training\": false, \"type\": 0, \"iteration_begin\": 0, \"iteration_end\": 0, \"strict_shape\": false}").expect("Cannot create pred config");
+    /// Attaches a schema to the model. It is stored as an attribute, so it is saved with
+    /// the model, and its names and types become the booster feature names and types.
+    pub fn set_feature_schema(&mut self, schema: FeatureSchema) -> Result<(), XGBoostError> {
+        let num_features = self.get_number_of_features()?;
+        if schema.len() != num_features {
+            return Err(SchemaError::ColumnCount {
+                expected: num_features,
+                got: schema.len(),
+            }
+            .into());
+        }
+        self.set_str_feature_info("feature_name", &schema.names())?;
+        self.set_str_feature_info("feature_type", &schema.types())?;
+        self.set_attr(FEATURE_SCHEMA_ATTR, &schema.to_json()?)?;
+        self.schema = Some(schema);
+        Ok(())
+    }
+
+    pub fn feature_schema(&self) -> Option<&FeatureSchema> {
+        self.schema.as_ref()
+    }
+
+    /// Feature names stored in the model, empty if it was trained without names.
+    pub fn feature_names(&self) -> Result<Vec<String>, XGBoostError> {
+        self.get_str_feature_info("feature_name")
+    }
+
+    pub fn set_feature_names(&mut self, names: &[&str]) -> Result<(), XGBoostError> {
+        self.set_str_feature_info("feature_name", names)
+    }
+
+    fn set_str_feature_info(&mut self, field: &str, values: &[&str]) -> Result<(), XGBoostError> {
+        self.summary.take();
+        let c_field = CString::new(field).unwrap();
+        let c_values = values
+            .iter()
+            .map(|v| CString::new(*v))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| XGBoostError::Config(field.to_string(), values.join(",")))?;
+        let mut ptrs: Vec<*const c_char> = c_values.iter().map(|v| v.as_ptr()).collect();
         unsafe {
-            let predict_result = XGBoosterPredictFromDMatrix(
+            if XGBoosterSetStrFeatureInfo(
                 self.handle,
-                data.handle,
-                conf.as_ptr(),
-                &mut (&data.rows as *const u64) as *mut *const u64,
+                c_field.as_ptr(),
+                ptrs.as_mut_ptr(),
+                ptrs.len() as u64,
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(XGBoostError::Config(field.to_string(), values.join(",")))
+            }
+        }
+    }
+
+    fn get_str_feature_info(&self, field: &str) -> Result<Vec<String>, XGBoostError> {
+        let c_field = CString::new(field).unwrap();
+        let mut len: u64 = 0;
+        let mut out: *mut *const c_char = std::ptr::null_mut();
+        let _shared = self.shared();
+        unsafe {
+            if XGBoosterGetStrFeatureInfo(self.handle, c_field.as_ptr(), &mut len, &mut out) != 0 {
+                return Err(XGBoostError::GetInfo(field.to_string()));
+            }
+            if len == 0 {
+                return Ok(Vec::new());
+            }
+            Ok(std::slice::from_raw_parts(out, len as usize)
+                .iter()
+                .map(|p| CStr::from_ptr(*p).to_string_lossy().into_owned())
+                .collect())
+        }
+    }
+
+    /// Raw importance scores keyed by feature name, or `f{index}` for unnamed features.
+    /// Features never used in a split are absent. For multi-output linear models the
+    /// scores of all outputs are summed.
+    pub fn feature_importance(
+        &self,
+        kind: ImportanceType,
+    ) -> Result<HashMap<String, f64>, XGBoostError> {
+        let config =
+            CString::new(format!("{{\"importance_type\": \"{}\"}}", kind.as_str())).unwrap();
+        let mut n_features: u64 = 0;
+        let mut features: *mut *const c_char = std::ptr::null_mut();
+        let mut out_dim: u64 = 0;
+        let mut out_shape: *const u64 = std::ptr::null();
+        let mut out_scores: *const f32 = std::ptr::null();
+        let _shared = self.shared();
+        unsafe {
+            if XGBoosterFeatureScore(
+                self.handle,
+                config.as_ptr(),
+                &mut n_features,
+                &mut features,
+                &mut out_dim,
                 &mut out_shape,
-                &mut out_result,
-            );
+                &mut out_scores,
+            ) != 0
+            {
+                return Err(XGBoostError::GetInfo("feature importance".to_string()));
+            }
+            if n_features == 0 {
+                return Ok(HashMap::new());
+            }
+            let shape = std::slice::from_raw_parts(out_shape, out_dim as usize);
+            let per_feature = shape[1..].iter().product::<u64>() as usize;
+            let names = std::slice::from_raw_parts(features, n_features as usize);
+            let scores = std::slice::from_raw_parts(out_scores, n_features as usize * per_feature);
+            Ok(names
+                .iter()
+                .zip(scores.chunks(per_feature))
+                .map(|(name, s)| {
+                    let name = CStr::from_ptr(*name).to_string_lossy().into_owned();
+                    (name, s.iter().map(|v| *v as f64).sum())
+                })
+                .collect())
+        }
+    }
 
-            if predict_result == 0 {
-                // Convert the raw pointer to a slice and return the prediction result
-                let slice = std::slice::from_raw_parts(out_result, data.rows as usize);
-                Ok(slice.to_vec())
-            } else {
-                Err(XGBoostError::Predict)
+    /// Importance sorted in decreasing order, joined against `feature_names` when given
+    /// (otherwise against the booster feature names, if any). See [`importance::rank`].
+    pub fn ranked_importance(
+        &self,
+        kind: ImportanceType,
+        feature_names: Option<&[String]>,
+        normalize: bool,
+    ) -> Result<Vec<(String, f64)>, XGBoostError> {
+        let scores = self.feature_importance(kind)?;
+        let names = match feature_names {
+            Some(names) => names.to_vec(),
+            None => self.feature_names()?,
+        };
+        Ok(importance::rank(&scores, &names, normalize))
+    }
+
+    pub fn predict(&self, data: &DMatrix) -> Result<Vec<f32>, XGBoostError> {
+        self.predict_with(data, &PredictOptions::default())
+    }
+
+    /// Like [`Booster::predict`], but first checks `data` against the attached
+    /// [`FeatureSchema`], if any.
+    pub fn predict_checked(&self, data: &DMatrix) -> Result<Vec<f32>, XGBoostError> {
+        if let Some(schema) = &self.schema {
+            schema.validate_matrix(data)?;
+        }
+        self.predict(data)
+    }
+
+    pub fn predict_with(
+        &self,
+        data: &DMatrix,
+        options: &PredictOptions,
+    ) -> Result<Vec<f32>, XGBoostError> {
+        Ok(self.predict_with_shape(data, options)?.values)
+    }
+
+    /// Returns predictions together with their [`Shape`], most useful with
+    /// [`PredictOptions::strict_shape`].
+    pub fn predict_with_shape(
+        &self,
+        data: &DMatrix,
+        options: &PredictOptions,
+    ) -> Result<Prediction, XGBoostError> {
+        self.ensure_loaded()?;
+        let prediction = instrument::observe_prediction("dmatrix", data.num_rows(), || {
+            let conf = CString::new(options.to_json()).expect("Cannot create pred config");
+            self.with_prediction_options(options, || {
+                let mut out_result: *const c_float = std::ptr::null();
+                let mut out_shape: *const u64 = std::ptr::null();
+                let mut out_dim: u64 = 0;
+                unsafe {
+                    let predict_result = XGBoosterPredictFromDMatrix(
+                        self.handle,
+                        data.handle,
+                        conf.as_ptr(),
+                        &mut out_shape,
+                        &mut out_dim,
+                        &mut out_result,
+                    );
+
+                    if predict_result == 0 {
+                        Ok(collect_prediction(out_shape, out_dim, out_result))
+                    } else {
+                        Err(XGBoostError::Predict)
+                    }
+                }
+            })
+        })?;
+        Ok(prediction)
+    }
+
+    /// Predicts `data` in slices of at most `chunk_rows` rows, to bound peak memory on very
+    /// large matrices.
+    pub fn predict_chunked(
+        &self,
+        data: &DMatrix,
+        chunk_rows: usize,
+    ) -> Result<Vec<f32>, XGBoostError> {
+        let mut values = Vec::new();
+        self.predict_chunked_with(data, chunk_rows, &PredictOptions::default(), |_, chunk| {
+            values.extend_from_slice(&chunk.values);
+            Ok(())
+        })?;
+        Ok(values)
+    }
+
+    /// Streams the predictions of each chunk of at most `chunk_rows` rows to `on_chunk`,
+    /// together with the index of its first row, so they never all sit in memory at once.
+    pub fn predict_chunked_with<F>(
+        &self,
+        data: &DMatrix,
+        chunk_rows: usize,
+        options: &PredictOptions,
+        mut on_chunk: F,
+    ) -> Result<(), XGBoostError>
+    where
+        F: FnMut(usize, &Prediction) -> Result<(), XGBoostError>,
+    {
+        if chunk_rows == 0 {
+            return Err(XGBoostError::Unsupported(
+                "chunk_rows must be positive".to_string(),
+            ));
+        }
+        let num_rows = data.num_rows() as usize;
+        for start in (0..num_rows).step_by(chunk_rows) {
+            let rows: Vec<i32> = (start..num_rows.min(start + chunk_rows))
+                .map(|r| r as i32)
+                .collect();
+            let chunk = data.slice(&rows)?;
+            on_chunk(start, &self.predict_with_shape(&chunk, options)?)?;
+        }
+        Ok(())
+    }
+
+    /// Predicts row-major dense `data` in place, without building a [`DMatrix`]. NaN marks
+    /// missing values.
+    pub fn predict_dense(
+        &self,
+        data: &[f32],
+        rows: usize,
+        cols: usize,
+        options: &PredictOptions,
+    ) -> Result<Prediction, XGBoostError> {
+        let mut values = Vec::new();
+        let shape = self.predict_dense_into(data, rows, cols, options, &mut values)?;
+        Ok(Prediction { values, shape })
+    }
+
+    /// Like [`Booster::predict_dense`], but writes the predictions to `out`, replacing its
+    /// contents. Reusing `out` across calls saves an allocation per batch on hot paths.
+    pub fn predict_dense_into(
+        &self,
+        data: &[f32],
+        rows: usize,
+        cols: usize,
+        options: &PredictOptions,
+        out: &mut Vec<f32>,
+    ) -> Result<Shape, XGBoostError> {
+        if data.len() != rows * cols {
+            return Err(DMatrixError::Length {
+                field: "data".to_string(),
+                expected: (rows * cols) as u64,
+                got: data.len(),
+            }
+            .into());
+        }
+        self.ensure_loaded()?;
+        let data = match options.invalid_values {
+            Some(policy) => dmatrix::sanitize(data, cols, policy)?,
+            None => Cow::Borrowed(data),
+        };
+        let shape = instrument::observe_prediction("dense", rows as u64, || {
+            let array = array_interface(&data, &[rows, cols]);
+            let conf = CString::new(options.to_inplace_json()).unwrap();
+            let (out_shape, out_dim, out_result) = self.with_prediction_options(options, || {
+                let mut out_result: *const c_float = std::ptr::null();
+                let mut out_shape: *const u64 = std::ptr::null();
+                let mut out_dim: u64 = 0;
+                unsafe {
+                    if XGBoosterPredictFromDense(
+                        self.handle,
+                        array.as_ptr(),
+                        conf.as_ptr(),
+                        std::ptr::null_mut(),
+                        &mut out_shape,
+                        &mut out_dim,
+                        &mut out_result,
+                    ) == 0
+                    {
+                        Ok((out_shape, out_dim, out_result))
+                    } else {
+                        Err(XGBoostError::Predict)
+                    }
+                }
+            })?;
+            Ok::<_, XGBoostError>(unsafe {
+                collect_prediction_into(out_shape, out_dim, out_result, out)
+            })
+        })?;
+        if let Some(audit) = &self.audit {
+            let prediction = Prediction {
+                values: out.clone(),
+                shape,
+            };
+            audit.submit(&data, cols, options.audit_ids.as_ref(), &prediction);
+        }
+        Ok(shape)
+    }
+
+    /// Predicts sparse `csr` data with `cols` columns in place, without building a
+    /// [`DMatrix`]. Absent entries are missing. The arrays are checked with
+    /// [`Csr::validate`] first.
+    pub fn predict_csr(
+        &self,
+        csr: &Csr,
+        cols: u64,
+        options: &PredictOptions,
+    ) -> Result<Prediction, XGBoostError> {
+        csr.validate(cols)?;
+        self.ensure_loaded()?;
+        let csr = match options.invalid_values {
+            Some(policy) => csr.sanitize(policy)?,
+            None => Cow::Borrowed(csr),
+        };
+        let nnz = csr.values.len();
+        let rows = csr.num_rows() as u64;
+        let prediction = instrument::observe_prediction("csr", rows, || {
+            let indptr =
+                dmatrix::array_interface(csr.indptr.as_ptr() as usize, "<u8", &[csr.indptr.len()]);
+            let indices = dmatrix::array_interface(csr.indices.as_ptr() as usize, "<u4", &[nnz]);
+            let values = dmatrix::array_interface(csr.values.as_ptr() as usize, "<f4", &[nnz]);
+            let conf = CString::new(options.to_inplace_json()).unwrap();
+            self.with_prediction_options(options, || {
+                let mut out_result: *const c_float = std::ptr::null();
+                let mut out_shape: *const u64 = std::ptr::null();
+                let mut out_dim: u64 = 0;
+                unsafe {
+                    if XGBoosterPredictFromCSR(
+                        self.handle,
+                        indptr.as_ptr(),
+                        indices.as_ptr(),
+                        values.as_ptr(),
+                        cols,
+                        conf.as_ptr(),
+                        std::ptr::null_mut(),
+                        &mut out_shape,
+                        &mut out_dim,
+                        &mut out_result,
+                    ) == 0
+                    {
+                        Ok(collect_prediction(out_shape, out_dim, out_result))
+                    } else {
+                        Err(XGBoostError::Predict)
+                    }
+                }
+            })
+        })?;
+        if let Some(audit) = &self.audit {
+            audit.submit_csr(&csr, cols as usize, options.audit_ids.as_ref(), &prediction);
+        }
+        Ok(prediction)
+    }
+
+    /// Predicts a single row of features, for request/response serving. Fails if the model
+    /// has several outputs per row, use [`Booster::predict_row_multi`] then.
+    pub fn predict_row(&self, features: &[f32]) -> Result<f32, XGBoostError> {
+        match self.predict_row_multi(features)?.as_slice() {
+            [value] => Ok(*value),
+            values => Err(XGBoostError::Unsupported(format!(
+                "model has {} outputs per row",
+                values.len()
+            ))),
+        }
+    }
+
+    /// All outputs for a single row, e.g. class probabilities for `multi:softprob`.
+    pub fn predict_row_multi(&self, features: &[f32]) -> Result<Vec<f32>, XGBoostError> {
+        Ok(self
+            .predict_dense(features, 1, features.len(), &PredictOptions::default())?
+            .values)
+    }
+}
+
+/// `__array_interface__` JSON describing a borrowed read-only `f32` buffer.
+fn array_interface(data: &[f32], shape: &[usize]) -> CString {
+    dmatrix::array_interface(data.as_ptr() as usize, "<f4", shape)
+}
+
+/// Copies a prediction result owned by XGBoost.
+unsafe fn collect_prediction(
+    out_shape: *const u64,
+    out_dim: u64,
+    out_result: *const c_float,
+) -> Prediction {
+    let mut values = Vec::new();
+    let shape = collect_prediction_into(out_shape, out_dim, out_result, &mut values);
+    Prediction { values, shape }
+}
+
+/// Copies a prediction result owned by XGBoost into `values`, replacing its contents.
+unsafe fn collect_prediction_into(
+    out_shape: *const u64,
+    out_dim: u64,
+    out_result: *const c_float,
+    values: &mut Vec<f32>,
+) -> Shape {
+    let dims = std::slice::from_raw_parts(out_shape, out_dim as usize);
+    let shape = Shape::from_dims(dims);
+    values.clear();
+    if !shape.is_empty() {
+        values.extend_from_slice(std::slice::from_raw_parts(out_result, shape.len()));
+    }
+    shape
+}
+
+/// Parses `[0]\tname-metric:value\t...` as returned by `XGBoosterEvalOneIter`.
+fn parse_eval(result: &str, name: &str) -> Option<HashMap<String, f64>> {
+    let prefix = format!("{}-", name);
+    result
+        .split('\t')
+        .skip(1)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let (key, value) = entry.rsplit_once(':')?;
+            let metric = key.strip_prefix(&prefix)?;
+            Some((metric.to_string(), value.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// The settings [`Booster::refresh_leaves`] changes, with their values in `config`. A
+/// booster that never set `updater` gets the one XGBoost picks for its `tree_method`, since
+/// once set, the parameter cannot be unset again.
+fn training_updaters(config: &serde_json::Value) -> Vec<(&'static str, String)> {
+    let booster = &config["learner"]["gradient_booster"];
+    let param = |key: &str| {
+        booster["gbtree_train_param"][key]
+            .as_str()
+            .filter(|v| !v.is_empty())
+    };
+    let gpu = config["learner"]["generic_param"]["device"]
+        .as_str()
+        .is_some_and(|d| d.starts_with("cuda") || d.starts_with("gpu"));
+    let updater = param("updater").unwrap_or(match (param("tree_method"), gpu) {
+        (Some("exact"), _) => "grow_colmaker,prune",
+        (Some("approx"), false) => "grow_histmaker",
+        (Some("approx"), true) => "grow_gpu_approx",
+        (_, false) => "grow_quantile_histmaker",
+        (_, true) => "grow_gpu_hist",
+    });
+    // Reported by each configured updater, "1" unless it was changed
+    let refresh_leaf = booster["updater"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|u| u["train_param"]["refresh_leaf"].as_str())
+        .unwrap_or("1");
+    vec![
+        (
+            "process_type",
+            param("process_type").unwrap_or("default").to_string(),
+        ),
+        ("updater", updater.to_string()),
+        ("refresh_leaf", refresh_leaf.to_string()),
+    ]
+}
+
+impl fmt::Debug for Booster {
+    /// Only reads counters and the learner configuration, never the model itself, so it
+    /// stays cheap on large models. The tree count is shown once [`Booster::summary`] has
+    /// been computed.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("Booster");
+        match self.boosted_rounds() {
+            Ok(rounds) => debug.field("rounds", &rounds),
+            Err(_) => debug.field("rounds", &"<unavailable>"),
+        };
+        match self.get_number_of_features() {
+            Ok(features) => debug.field("num_features", &features),
+            Err(_) => debug.field("num_features", &"<unavailable>"),
+        };
+        match self.config() {
+            Ok(config) => {
+                let learner = &config["learner"];
+                debug
+                    .field("booster", &learner["learner_train_param"]["booster"])
+                    .field("objective", &learner["learner_train_param"]["objective"])
+                    .field("num_class", &learner["learner_model_param"]["num_class"])
             }
+            Err(_) => debug.field("config", &"<unavailable>"),
+        };
+        if let Some(summary) = self.summary.get() {
+            debug.field("num_trees", &summary.num_trees);
+        }
+        debug
+            .field("format", &self.format)
+            .field("source_version", &self.source_version)
+            .field("schema", &self.schema.is_some())
+            .field("audit", &self.audit)
+            .finish()
+    }
+}
+
+impl fmt::Display for Booster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.summary() {
+            Ok(summary) => write!(f, "Booster({}", summary)?,
+            Err(_) => write!(f, "Booster(<unavailable>")?,
+        }
+        if let Some(format) = self.format {
+            write!(f, ", loaded from {}", format.as_str())?;
         }
+        if let Some([major, minor, patch]) = self.source_version {
+            write!(f, " written by XGBoost {}.{}.{}", major, minor, patch)?;
+        }
+        write!(f, ")")
     }
 }
 
-unsafe impl Sync for Booster { }
-unsafe impl Send for Booster { }
+// Predictions may run concurrently, which XGBoost supports. Every other `&self` call holds
+// `config_lock` for reading, and the learner is only reconfigured through `&mut self` or
+// with the lock held for writing.
+unsafe impl Sync for Booster {}
+unsafe impl Send for Booster {}
 
 impl Drop for Booster {
     fn drop(&mut self) {
+        if self.handle.is_null() {
+            return;
+        }
+        handles::unregister(self.handle);
+        memory::untrack_booster(*self.model_bytes.get_mut());
         unsafe {
-            XGBoosterFree(self.handle);
+            if XGBoosterFree(self.handle) != 0 {
+                instrument::warn("XGBoosterFree failed while dropping a Booster");
+            }
         }
     }
 }
@@ -152,6 +1512,18 @@ impl Drop for Booster {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::params::{ColumnSampling, GrowPolicy};
+    use crate::schema::FeatureType;
+
+    fn find_param<'a>(config: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+        match config {
+            serde_json::Value::Object(map) => map
+                .get(key)
+                .and_then(|v| v.as_str())
+                .or_else(|| map.values().find_map(|v| find_param(v, key))),
+            _ => None,
+        }
+    }
 
     #[test]
     fn test_booster_creation() {
@@ -159,6 +1531,16 @@ mod tests {
         assert!(booster.is_ok(), "Failed to create Booster");
     }
 
+    #[test]
+    fn test_raw_handle_roundtrip() {
+        let booster = Booster::new().unwrap();
+        let handle = booster.into_raw_handle();
+        assert!(!handle.is_null());
+        let booster = unsafe { Booster::from_raw_handle(handle) };
+        assert_eq!(booster.as_raw_handle(), handle);
+        assert!(booster.close().is_ok());
+    }
+
     #[test]
     fn test_booster_config() {
         let mut booster = Booster::new().unwrap();
@@ -181,6 +1563,557 @@ mod tests {
         assert_eq!(num_feats, 2, "Wrong number of features");
     }
 
+    #[test]
+    fn test_model_sized_in_ubj() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::with_cache(&[&dtrain]).unwrap();
+        booster.update(&dtrain, 0).unwrap();
+        // Trained in-process, so sized before anything was saved
+        let ubj = booster.save_model_to_buffer(ModelFormat::Ubj).unwrap();
+        assert_eq!(
+            booster.model_bytes.load(Ordering::Relaxed),
+            ubj.len() as u64
+        );
+        // Saving as JSON must not change the reported size
+        booster.save_model_to_buffer(ModelFormat::Json).unwrap();
+        assert_eq!(
+            booster.model_bytes.load(Ordering::Relaxed),
+            ubj.len() as u64
+        );
+        let json = booster.save_model_to_buffer(ModelFormat::Json).unwrap();
+        let mut loaded = Booster::new().unwrap();
+        loaded.load_model_from_buffer(&json).unwrap();
+        assert_eq!(loaded.model_bytes.load(Ordering::Relaxed), ubj.len() as u64);
+    }
+
+    #[test]
+    fn test_cached_evaluation() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let dvalid = DMatrix::try_from_data(&[0.2, 0.1, 0.4, 0.3], 2, 2).unwrap();
+        dvalid.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::with_cache(&[&dtrain, &dvalid]).unwrap();
+        for i in 0..3 {
+            booster.update(&dtrain, i).unwrap();
+            assert!(booster.evaluate(&dvalid, "valid").unwrap()["rmse"] >= 0.0);
+        }
+        assert_eq!(booster.predict(&dvalid).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_resume_with_column_sampling() {
+        let data: Vec<f32> = (0..64).map(|i| ((i * 7) % 13) as f32).collect();
+        let dtrain = DMatrix::try_from_data(&data, 16, 4).unwrap();
+        let labels: Vec<f32> = (0..16).map(|i| (i % 3) as f32).collect();
+        dtrain.try_add_label(&labels).unwrap();
+        let params = TrainParams::new()
+            .colsample(ColumnSampling {
+                bytree: Some(0.5),
+                bynode: Some(0.5),
+                ..Default::default()
+            })
+            .subsample(0.7)
+            .seed(3)
+            .seed_per_iteration(true);
+
+        let mut full = Booster::with_cache(&[&dtrain]).unwrap();
+        full.set_params(&params).unwrap();
+        for i in 0..4 {
+            full.update(&dtrain, i).unwrap();
+        }
+
+        let mut first = Booster::with_cache(&[&dtrain]).unwrap();
+        first.set_params(&params).unwrap();
+        for i in 0..2 {
+            first.update(&dtrain, i).unwrap();
+        }
+        let checkpoint = first.save_model_to_buffer(ModelFormat::Ubj).unwrap();
+        let mut resumed = Booster::with_cache(&[&dtrain]).unwrap();
+        resumed.load_model_from_buffer(&checkpoint).unwrap();
+        resumed.set_params(&params).unwrap();
+        for i in 2..4 {
+            resumed.update(&dtrain, i).unwrap();
+        }
+
+        assert_eq!(
+            full.predict(&dtrain).unwrap(),
+            resumed.predict(&dtrain).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_tree_params_in_config() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let params = TrainParams::new()
+            .max_bin(32)
+            .grow_policy(GrowPolicy::LossGuide)
+            .max_leaves(4)
+            .min_child_weight(0.5)
+            .set("tree_method", "hist");
+        let booster = Booster::train_with_params(&dtrain, &params, 1).unwrap();
+        let config = booster.config().unwrap();
+        assert_eq!(find_param(&config, "max_bin"), Some("32"));
+        assert_eq!(find_param(&config, "grow_policy"), Some("lossguide"));
+        assert_eq!(find_param(&config, "max_leaves"), Some("4"));
+        assert_eq!(find_param(&config, "min_child_weight"), Some("0.5"));
+    }
+
+    #[test]
+    fn test_display_and_summary_cache() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::train_with_params(&dtrain, &TrainParams::new(), 2).unwrap();
+        assert_eq!(
+            booster.to_string(),
+            "Booster(gbtree reg:squarederror, 2 trees, 2 features)"
+        );
+        booster.update(&dtrain, 2).unwrap();
+        assert_eq!(booster.summary().unwrap().num_trees, Some(3));
+        let debug = format!("{:?}", booster);
+        assert!(debug.contains("rounds: 3"));
+        assert!(debug.contains("objective: String(\"reg:squarederror\")"));
+        assert_eq!(dtrain.to_string(), "DMatrix(2 rows x 2 cols)");
+
+        // UBJSON buffers start with '{' too, so the format comes from the content
+        for format in [ModelFormat::Json, ModelFormat::Ubj] {
+            let buf = booster.save_model_to_buffer(format).unwrap();
+            let mut loaded = Booster::new().unwrap();
+            loaded.load_model_from_buffer(&buf).unwrap();
+            assert_eq!(loaded.model_format(), Some(format));
+            assert!(loaded
+                .to_string()
+                .contains(&format!("loaded from {}", format.as_str())));
+        }
+    }
+
+    #[test]
+    fn test_train_cancelled() {
+        use crate::training::CancellationToken;
+
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let token = CancellationToken::new();
+        let control = TrainControl::new().cancel_token(token.clone());
+        let outcome =
+            Booster::train_with_control(&dtrain, &TrainParams::new(), 3, &control).unwrap();
+        assert_eq!((outcome.rounds, outcome.stop), (3, StopReason::Completed));
+        token.cancel();
+        let outcome =
+            Booster::train_with_control(&dtrain, &TrainParams::new(), 3, &control).unwrap();
+        assert_eq!((outcome.rounds, outcome.stop), (0, StopReason::Cancelled));
+    }
+
+    #[test]
+    fn test_stopped_training_keeps_best_round() {
+        use crate::training::CancellationToken;
+
+        struct CancelAt(usize, CancellationToken);
+        impl TrainingCallback for CancelAt {
+            fn after_round(
+                &mut self,
+                round: usize,
+                _: &[(String, HashMap<String, f64>)],
+            ) -> std::io::Result<()> {
+                if round == self.0 {
+                    self.1.cancel();
+                }
+                Ok(())
+            }
+        }
+
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        // Labels the model moves away from, so the first round scores best
+        let dvalid = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dvalid.try_add_label(&[2., 1.]).unwrap();
+        let token = CancellationToken::new();
+        let control = TrainControl::new().cancel_token(token.clone());
+        let params = TrainParams::new().eval_metric("rmse");
+        let outcome = Booster::train_with_evals(
+            &dtrain,
+            &[(&dvalid, "valid")],
+            &params,
+            10,
+            &control,
+            &mut CancelAt(4, token),
+        )
+        .unwrap();
+        assert_eq!(outcome.stop, StopReason::Cancelled);
+        assert!(outcome.rounds < 5);
+        assert_eq!(outcome.booster.boosted_rounds().unwrap(), outcome.rounds);
+        assert!(outcome.best_score.is_some());
+    }
+
+    #[test]
+    fn test_train_with_metrics_logger() {
+        use crate::training::{LogFormat, MetricsLogger};
+
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut logger = MetricsLogger::new(Vec::new(), LogFormat::JsonLines);
+        Booster::train_with_evals(
+            &dtrain,
+            &[(&dtrain, "train")],
+            &TrainParams::new(),
+            2,
+            &TrainControl::new(),
+            &mut logger,
+        )
+        .unwrap();
+        let out = String::from_utf8(logger.into_inner()).unwrap();
+        assert_eq!(out.lines().count(), 2);
+        assert!(out.starts_with("{\"round\":0,\"train\":{\"rmse\":"));
+    }
+
+    #[test]
+    fn test_train_from_linear_base() {
+        let data: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        let dtrain = DMatrix::try_from_data(&data, 10, 2).unwrap();
+        let labels: Vec<f32> = (0..10).map(|i| (i * i) as f32).collect();
+        dtrain.try_add_label(&labels).unwrap();
+        let linear = TrainParams::new().booster(crate::params::BoosterType::GbLinear);
+        let base = Booster::train_with_params(&dtrain, &linear, 5).unwrap();
+        let margin = base
+            .predict_with(&dtrain, &PredictOptions::margin())
+            .unwrap();
+
+        let trees = Booster::train_from(&base, &dtrain, &[], &TrainParams::new(), 3).unwrap();
+        assert_eq!(dtrain.get_base_margin().unwrap(), margin);
+        let stacked = trees.predict(&dtrain).unwrap();
+        let alone = DMatrix::try_from_data(&data, 10, 2).unwrap();
+        assert_ne!(trees.predict(&alone).unwrap(), stacked);
+    }
+
+    #[test]
+    fn test_set_base_margin_of_replaces_margin() {
+        let data = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        data.try_add_label(&[0., 1.]).unwrap();
+        let params = TrainParams::new().objective("binary:logistic");
+        let booster = Booster::train_with_params(&data, &params, 3).unwrap();
+        let expected = booster
+            .predict_with(&data, &PredictOptions::margin())
+            .unwrap();
+        data.set_base_margin(&[5.0, -5.0]).unwrap();
+        booster.set_base_margin_of(&data).unwrap();
+        booster.set_base_margin_of(&data).unwrap();
+        assert_eq!(data.get_base_margin().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_refresh_leaves() {
+        let data: Vec<f32> = (0..20).map(|i| i as f32).collect();
+        let dtrain = DMatrix::try_from_data(&data, 10, 2).unwrap();
+        dtrain
+            .try_add_label(&(0..10).map(|i| i as f32).collect::<Vec<_>>())
+            .unwrap();
+        let mut booster = Booster::train_with_params(&dtrain, &TrainParams::new(), 3).unwrap();
+        let before = booster.trees().unwrap();
+        let predictions = booster.predict(&dtrain).unwrap();
+
+        let dnew = DMatrix::try_from_data(&data, 10, 2).unwrap();
+        dnew.try_add_label(&(0..10).map(|i| (10 - i) as f32).collect::<Vec<_>>())
+            .unwrap();
+        booster.refresh_leaves(&dnew, true).unwrap();
+        assert_eq!(booster.predict(&dtrain).unwrap(), predictions);
+        booster.refresh_leaves(&dnew, false).unwrap();
+        let after = booster.trees().unwrap();
+        assert_eq!(after.num_leaves(), before.num_leaves());
+        assert_eq!(booster.boosted_rounds().unwrap(), 3);
+        assert_ne!(booster.predict(&dtrain).unwrap(), predictions);
+        // The training configuration is back, so the next round grows a new tree
+        let config = booster.config().unwrap();
+        let param = &config["learner"]["gradient_booster"]["gbtree_train_param"];
+        assert_eq!(param["process_type"], "default");
+        assert_ne!(param["updater"], "refresh");
+        booster.update(&dtrain, 3).unwrap();
+        assert_eq!(booster.boosted_rounds().unwrap(), 4);
+        assert_eq!(booster.trees().unwrap().trees.len(), after.trees.len() + 1);
+    }
+
+    #[test]
+    fn test_training_updaters() {
+        let config = serde_json::json!({"learner": {
+            "generic_param": {"device": "cpu"},
+            "gradient_booster": {
+                "gbtree_train_param": {"process_type": "default", "tree_method": "approx"},
+                "updater": [{"name": "grow_histmaker", "train_param": {"refresh_leaf": "0"}}]
+            }
+        }});
+        assert_eq!(
+            training_updaters(&config),
+            vec![
+                ("process_type", "default".to_string()),
+                ("updater", "grow_histmaker".to_string()),
+                ("refresh_leaf", "0".to_string()),
+            ]
+        );
+        let config = serde_json::json!({"learner": {
+            "generic_param": {"device": "cuda:0"},
+            "gradient_booster": {"gbtree_train_param": {"updater": "grow_colmaker,prune"}}
+        }});
+        assert_eq!(training_updaters(&config)[1].1, "grow_colmaker,prune");
+        let config = serde_json::json!({"learner": {"generic_param": {"device": "cuda:0"}}});
+        assert_eq!(
+            training_updaters(&config),
+            vec![
+                ("process_type", "default".to_string()),
+                ("updater", "grow_gpu_hist".to_string()),
+                ("refresh_leaf", "1".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_predict_chunked() {
+        let data: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        let dtrain = DMatrix::try_from_data(&data, 5, 2).unwrap();
+        dtrain.try_add_label(&[0., 1., 2., 3., 4.]).unwrap();
+        let booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        assert_eq!(
+            booster.predict_chunked(&dtrain, 2).unwrap(),
+            booster.predict(&dtrain).unwrap()
+        );
+        assert!(matches!(
+            booster.predict_chunked(&dtrain, 0),
+            Err(XGBoostError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_predict_margin_f64() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[0., 1.]).unwrap();
+        let params = TrainParams::new().objective("binary:logistic");
+        let booster = Booster::train_with_params(&dtrain, &params, 3).unwrap();
+        let margins = booster.predict_margin_f64(&dtrain).unwrap();
+        let expected = booster
+            .predict_with(&dtrain, &PredictOptions::margin())
+            .unwrap();
+        for (m, e) in margins.iter().zip(expected) {
+            assert!((m - e as f64).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_prediction_options_restored() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[0., 1.]).unwrap();
+        let params = TrainParams::new().objective("binary:logistic");
+        let booster = Booster::train_with_params(&dtrain, &params, 3).unwrap();
+        let generic = |b: &Booster| b.config().unwrap()["learner"]["generic_param"].clone();
+        let before = generic(&booster);
+        let expected = booster.predict(&dtrain).unwrap();
+        let options = PredictOptions::new().device(Device::Cuda(0)).nthread(1);
+        let data = [0.1, 0.2, 0.3, 0.4];
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    let with = booster.predict_dense(&data, 2, 2, &options).unwrap();
+                    let without = booster
+                        .predict_dense(&data, 2, 2, &PredictOptions::new())
+                        .unwrap();
+                    assert_eq!(with.values, expected);
+                    assert_eq!(without.values, expected);
+                });
+            }
+        });
+        let after = generic(&booster);
+        assert_eq!(after["device"], before["device"]);
+        assert_eq!(after["nthread"], before["nthread"]);
+    }
+
+    #[test]
+    fn test_inplace_predictions_match_dmatrix() {
+        let data = [0.1, f32::NAN, 0.3, 0.4, 0.5, 0.2];
+        let dtrain = DMatrix::try_from_data(&data, 3, 2).unwrap();
+        dtrain.try_add_label(&[0., 1., 1.]).unwrap();
+        let params = TrainParams::new().objective("binary:logistic");
+        let booster = Booster::train_with_params(&dtrain, &params, 3).unwrap();
+        let options = PredictOptions::margin();
+        let expected = booster.predict_with(&dtrain, &options).unwrap();
+
+        let mut out = vec![1.0; 10];
+        let shape = booster
+            .predict_dense_into(&data, 3, 2, &options, &mut out)
+            .unwrap();
+        assert_eq!(shape.rows(), 3);
+        assert_eq!(out, expected);
+
+        let csr = Csr {
+            indptr: vec![0, 1, 3, 5],
+            indices: vec![0, 0, 1, 0, 1],
+            values: vec![0.1, 0.3, 0.4, 0.5, 0.2],
+        };
+        assert_eq!(
+            booster.predict_csr(&csr, 2, &options).unwrap().values,
+            expected
+        );
+        let mut invalid = csr.clone();
+        invalid.indices[0] = 2;
+        assert!(booster.predict_csr(&invalid, 2, &options).is_err());
+        invalid = csr.clone();
+        invalid.values[0] = f32::INFINITY;
+        let strict = options
+            .clone()
+            .invalid_values(dmatrix::InvalidValuePolicy::Error);
+        assert!(booster.predict_csr(&invalid, 2, &strict).is_err());
+    }
+
+    #[test]
+    fn test_predict_without_model() {
+        let booster = Booster::new().unwrap();
+        let data = DMatrix::try_from_data(&[0.5, 1.2], 1, 2).unwrap();
+        assert!(matches!(
+            booster.predict(&data),
+            Err(XGBoostError::ModelNotLoaded)
+        ));
+        assert!(matches!(
+            booster.predict_dense(&[0.5, 1.2], 1, 2, &PredictOptions::new()),
+            Err(XGBoostError::ModelNotLoaded)
+        ));
+        // The input is checked before the model
+        assert!(matches!(
+            booster.predict_dense(&[0.5, 1.2, 3.0], 2, 2, &PredictOptions::new()),
+            Err(XGBoostError::DMatrix(DMatrixError::Length {
+                expected: 4,
+                got: 3,
+                ..
+            }))
+        ));
+    }
+
+    #[cfg(feature = "encryption")]
+    #[test]
+    fn test_encrypted_model_roundtrip() {
+        let data: Vec<f32> = (0..40).map(|i| i as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, 20, 2).unwrap();
+        dmat.try_add_label(&(0..20).map(|i| i as f32).collect::<Vec<_>>())
+            .unwrap();
+        let booster = Booster::train(&dmat, &dmat, 3).unwrap();
+        let path = std::env::temp_dir().join("xgb-rs-encrypted.bin");
+        let path = path.to_str().unwrap();
+        booster.save_model_encrypted(path, &[1u8; 32]).unwrap();
+        assert!(!std::fs::read(path).unwrap().starts_with(b"{"));
+
+        let mut loaded = Booster::new().unwrap();
+        assert!(matches!(
+            loaded.load_model_encrypted(path, &[2u8; 32]),
+            Err(XGBoostError::Encryption(EncryptionError::Decrypt))
+        ));
+        loaded.load_model_encrypted(path, &[1u8; 32]).unwrap();
+        assert_eq!(
+            loaded.predict(&dmat).unwrap(),
+            booster.predict(&dmat).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_audit_log_on_predict() {
+        let data: Vec<f32> = (0..40).map(|i| i as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, 20, 2).unwrap();
+        dmat.try_add_label(&(0..20).map(|i| i as f32).collect::<Vec<_>>())
+            .unwrap();
+        let mut booster = Booster::train(&dmat, &dmat, 3).unwrap();
+        let records = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = records.clone();
+        let log = Arc::new(AuditLog::new("1", 4, move |r| sink.lock().unwrap().push(r)));
+        booster.set_audit_log(log.clone());
+        let options = PredictOptions::new().audit_ids(vec!["r1".to_string(), "r2".to_string()]);
+        let preds = booster.predict_dense(&data[..4], 2, 2, &options).unwrap();
+        // DMatrix inputs are not audited
+        booster.predict(&dmat).unwrap();
+        booster.clear_audit_log();
+        drop(log);
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].prediction, preds.row(1));
+        assert_eq!(records[1].id.as_deref(), Some("r2"));
+        assert_eq!(
+            records[1].features_hash,
+            crate::audit::features_hash(&data[2..4])
+        );
+    }
+
+    #[test]
+    fn test_array_interface() {
+        let data = [1.0f32, 2.0];
+        let json = array_interface(&data, &[2, 1]).into_string().unwrap();
+        assert!(json.contains(&format!("\"data\": [{}, true]", data.as_ptr() as usize)));
+        assert!(json.contains("\"shape\": [2, 1]"));
+    }
+
+    #[test]
+    fn test_parse_eval() {
+        let metrics = parse_eval("[0]\ttest-rmse:0.25\ttest-ndcg@5:0.9", "test").unwrap();
+        assert_eq!(metrics["rmse"], 0.25);
+        assert_eq!(metrics["ndcg@5"], 0.9);
+        assert!(parse_eval("[0]\ttrain-rmse:0.25", "test").is_none());
+    }
+
+    #[test]
+    fn test_feature_schema_cleared_on_load() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let bad = FeatureSchema::new()
+            .feature("a\0b", FeatureType::Float)
+            .feature("c", FeatureType::Float);
+        assert!(matches!(
+            booster.set_feature_schema(bad),
+            Err(XGBoostError::Config(..))
+        ));
+        let buf = booster.save_model_to_buffer(ModelFormat::Json).unwrap();
+        let schema = FeatureSchema::new()
+            .feature("a", FeatureType::Float)
+            .feature("b", FeatureType::Float);
+        booster.set_feature_schema(schema).unwrap();
+        assert!(booster.feature_schema().is_some());
+        booster.load_model_from_buffer(&buf).unwrap();
+        assert!(booster.feature_schema().is_none());
+        assert!(booster.load_model_from_buffer(b"not a model").is_err());
+        assert!(booster.feature_schema().is_none());
+    }
+
+    #[test]
+    fn test_feature_schema_loaded_from_buffer() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let schema = FeatureSchema::new()
+            .feature("a", FeatureType::Float)
+            .bounded_feature("b", FeatureType::Float, 0.0, 1.0);
+        booster.set_feature_schema(schema.clone()).unwrap();
+        for format in [ModelFormat::Json, ModelFormat::Ubj] {
+            let buf = booster.save_model_to_buffer(format).unwrap();
+            let mut loaded = Booster::new().unwrap();
+            loaded.load_model_from_buffer(&buf).unwrap();
+            assert_eq!(loaded.feature_schema(), Some(&schema));
+        }
+    }
+
+    #[test]
+    fn test_model_card_schema_on_load() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let schema = FeatureSchema::new()
+            .bounded_feature("a", FeatureType::Float, 0.0, 1.0)
+            .feature("b", FeatureType::Float);
+        let card = ModelCard::new("card", "1").feature_schema(schema.clone());
+        booster.set_model_card(&card).unwrap();
+        let path = std::env::temp_dir().join(format!("xgb-rs-card-{}.json", std::process::id()));
+        let path = path.to_str().unwrap();
+        booster.save_model(path).unwrap();
+        let mut loaded = Booster::new().unwrap();
+        let result = loaded.load_model(path);
+        std::fs::remove_file(path).unwrap();
+        result.unwrap();
+        assert_eq!(loaded.model_card().unwrap(), Some(card));
+        assert_eq!(loaded.feature_schema(), Some(&schema));
+    }
+
     #[test]
     fn test_load_model() {
         let mut booster = Booster::new().expect("Failed to create Booster");
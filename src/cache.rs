@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::DMatrix;
+
+/// Storage for predictions keyed by a row hash. Implement it to plug in another cache.
+pub trait PredictionCache: Send {
+    fn get(&mut self, key: u64) -> Option<Vec<f32>>;
+    fn insert(&mut self, key: u64, value: Vec<f32>);
+    fn clear(&mut self);
+}
+
+/// Least-recently-used cache with an optional time to live per entry.
+pub struct LruCache {
+    capacity: usize,
+    ttl: Option<Duration>,
+    entries: HashMap<u64, (Vec<f32>, Instant, u64)>,
+    recency: BTreeMap<u64, u64>,
+    tick: u64,
+}
+
+impl LruCache {
+    pub fn new(capacity: usize, ttl: Option<Duration>) -> Self {
+        LruCache {
+            capacity,
+            ttl,
+            entries: HashMap::with_capacity(capacity),
+            recency: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn remove(&mut self, key: u64) {
+        if let Some((_, _, tick)) = self.entries.remove(&key) {
+            self.recency.remove(&tick);
+        }
+    }
+}
+
+impl PredictionCache for LruCache {
+    fn get(&mut self, key: u64) -> Option<Vec<f32>> {
+        let (inserted, old_tick) = match self.entries.get(&key) {
+            Some((_, inserted, tick)) => (*inserted, *tick),
+            None => return None,
+        };
+        if self.ttl.is_some_and(|ttl| inserted.elapsed() > ttl) {
+            self.remove(key);
+            return None;
+        }
+        self.tick += 1;
+        self.recency.remove(&old_tick);
+        self.recency.insert(self.tick, key);
+        let entry = self.entries.get_mut(&key)?;
+        entry.2 = self.tick;
+        Some(entry.0.clone())
+    }
+
+    fn insert(&mut self, key: u64, value: Vec<f32>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(key);
+        while self.entries.len() >= self.capacity {
+            match self.recency.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+        self.tick += 1;
+        self.recency.insert(self.tick, key);
+        self.entries.insert(key, (value, Instant::now(), self.tick));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+}
+
+/// Wraps a booster and memoizes per-row predictions, for workloads scoring the same
+/// entities repeatedly. Rows are keyed by a hash of their raw values.
+pub struct CachedPredictor<C: PredictionCache = LruCache> {
+    booster: Booster,
+    cache: Mutex<C>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl CachedPredictor<LruCache> {
+    pub fn new(booster: Booster, capacity: usize, ttl: Option<Duration>) -> Self {
+        Self::with_cache(booster, LruCache::new(capacity, ttl))
+    }
+}
+
+impl<C: PredictionCache> CachedPredictor<C> {
+    pub fn with_cache(booster: Booster, cache: C) -> Self {
+        CachedPredictor {
+            booster,
+            cache: Mutex::new(cache),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Predicts row-major `data` with `cols` columns. Only rows missing from the cache
+    /// reach the booster, in a single batch.
+    pub fn predict(&self, data: &[f32], cols: usize) -> Result<Vec<f32>, XGBoostError> {
+        if cols == 0 || !data.len().is_multiple_of(cols) {
+            return Err(XGBoostError::Unsupported(format!(
+                "{} values do not make rows of {} columns",
+                data.len(),
+                cols
+            )));
+        }
+        let rows: Vec<&[f32]> = data.chunks(cols).collect();
+        let keys: Vec<u64> = rows.iter().map(|r| row_key(r)).collect();
+        let mut results: Vec<Option<Vec<f32>>> = {
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            keys.iter().map(|k| cache.get(*k)).collect()
+        };
+
+        let missing: Vec<usize> = (0..rows.len()).filter(|i| results[*i].is_none()).collect();
+        self.hits
+            .fetch_add((rows.len() - missing.len()) as u64, Ordering::Relaxed);
+        self.misses
+            .fetch_add(missing.len() as u64, Ordering::Relaxed);
+
+        if !missing.is_empty() {
+            let batch: Vec<f32> = missing
+                .iter()
+                .flat_map(|i| rows[*i].iter().copied())
+                .collect();
+            let dmat = DMatrix::try_from_data(&batch, missing.len() as u64, cols as u64)?;
+            let preds = self.booster.predict(&dmat)?;
+            let per_row = preds.len() / missing.len();
+            let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            for (i, chunk) in missing.iter().zip(preds.chunks(per_row)) {
+                cache.insert(keys[*i], chunk.to_vec());
+                results[*i] = Some(chunk.to_vec());
+            }
+        }
+        Ok(results.into_iter().flatten().flatten().collect())
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of rows served from the cache, 0 before any prediction.
+    pub fn hit_rate(&self) -> f64 {
+        let (hits, misses) = (self.hits(), self.misses());
+        if hits + misses == 0 {
+            0.0
+        } else {
+            hits as f64 / (hits + misses) as f64
+        }
+    }
+
+    pub fn reset_stats(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    pub fn booster(&self) -> &Booster {
+        &self.booster
+    }
+}
+
+/// 64-bit FNV-1a over the bit patterns of the row values. Stable across platforms and
+/// releases, and equal for rows with the same values, NaN payloads included. Also the
+/// [`features_hash`](crate::audit::features_hash) of audit records.
+pub(crate) fn row_key(row: &[f32]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in row.iter().flat_map(|v| v.to_bits().to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lru_eviction() {
+        let mut cache = LruCache::new(2, None);
+        cache.insert(1, vec![1.0]);
+        cache.insert(2, vec![2.0]);
+        assert_eq!(cache.get(1), Some(vec![1.0]));
+        cache.insert(3, vec![3.0]);
+        assert_eq!(cache.get(2), None);
+        assert_eq!(cache.get(1), Some(vec![1.0]));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_lru_ttl() {
+        let mut cache = LruCache::new(2, Some(Duration::ZERO));
+        cache.insert(1, vec![1.0]);
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(cache.get(1), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_predict_rejects_ragged_rows() {
+        let predictor = CachedPredictor::new(Booster::new().unwrap(), 4, None);
+        for cols in [0, 2] {
+            assert!(matches!(
+                predictor.predict(&[1.0, 2.0, 3.0], cols),
+                Err(XGBoostError::Unsupported(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_row_key() {
+        assert_eq!(row_key(&[1.0, 2.0]), row_key(&[1.0, 2.0]));
+        assert_ne!(row_key(&[1.0, 2.0]), row_key(&[2.0, 1.0]));
+    }
+}
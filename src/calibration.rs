@@ -0,0 +1,251 @@
+use std::cmp::Ordering;
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::{DMatrix, DMatrixError};
+use crate::predict::PredictOptions;
+
+#[derive(Error, Debug)]
+pub enum CalibrationError {
+    #[error("Got {0} scores for {1} labels")]
+    Length(usize, usize),
+    #[error("Calibration needs both positive and negative labels")]
+    SingleClass,
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CalibrationMethod {
+    Platt,
+    Isotonic,
+}
+
+/// Maps raw margins to calibrated probabilities.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Calibrator {
+    /// `p = 1 / (1 + exp(a * margin + b))`
+    Platt { a: f64, b: f64 },
+    /// Piecewise linear, non-decreasing interpolation between fitted points.
+    Isotonic { margins: Vec<f64>, probs: Vec<f64> },
+}
+
+impl Calibrator {
+    pub fn fit(
+        method: CalibrationMethod,
+        margins: &[f32],
+        labels: &[f32],
+    ) -> Result<Self, CalibrationError> {
+        match method {
+            CalibrationMethod::Platt => Self::fit_platt(margins, labels),
+            CalibrationMethod::Isotonic => Self::fit_isotonic(margins, labels),
+        }
+    }
+
+    /// Platt scaling, fitted with the Newton method and target smoothing of
+    /// Lin, Lin and Weng (2007).
+    pub fn fit_platt(margins: &[f32], labels: &[f32]) -> Result<Self, CalibrationError> {
+        check(margins, labels)?;
+        let n_pos = labels.iter().filter(|y| **y > 0.5).count() as f64;
+        let n_neg = labels.len() as f64 - n_pos;
+        let hi = (n_pos + 1.0) / (n_pos + 2.0);
+        let lo = 1.0 / (n_neg + 2.0);
+        let data: Vec<(f64, f64)> = margins
+            .iter()
+            .zip(labels)
+            .map(|(f, y)| (*f as f64, if *y > 0.5 { hi } else { lo }))
+            .collect();
+
+        let objective = |a: f64, b: f64| -> f64 {
+            data.iter()
+                .map(|(f, t)| {
+                    let z = f * a + b;
+                    if z >= 0.0 {
+                        t * z + (-z).exp().ln_1p()
+                    } else {
+                        (t - 1.0) * z + z.exp().ln_1p()
+                    }
+                })
+                .sum()
+        };
+
+        let (mut a, mut b) = (0.0, ((n_neg + 1.0) / (n_pos + 1.0)).ln());
+        let mut fval = objective(a, b);
+        for _ in 0..100 {
+            let (mut h11, mut h22, mut h21, mut g1, mut g2) = (1e-12, 1e-12, 0.0, 0.0, 0.0);
+            for (f, t) in &data {
+                let z = f * a + b;
+                let (p, q) = if z >= 0.0 {
+                    let e = (-z).exp();
+                    (e / (1.0 + e), 1.0 / (1.0 + e))
+                } else {
+                    let e = z.exp();
+                    (1.0 / (1.0 + e), e / (1.0 + e))
+                };
+                let d2 = p * q;
+                h11 += f * f * d2;
+                h22 += d2;
+                h21 += f * d2;
+                let d1 = t - p;
+                g1 += f * d1;
+                g2 += d1;
+            }
+            if g1.abs() < 1e-5 && g2.abs() < 1e-5 {
+                break;
+            }
+            let det = h11 * h22 - h21 * h21;
+            let da = -(h22 * g1 - h21 * g2) / det;
+            let db = -(-h21 * g1 + h11 * g2) / det;
+            let gd = g1 * da + g2 * db;
+            let mut step = 1.0;
+            while step >= 1e-10 {
+                let (new_a, new_b) = (a + step * da, b + step * db);
+                let new_f = objective(new_a, new_b);
+                if new_f < fval + 1e-4 * step * gd {
+                    (a, b, fval) = (new_a, new_b, new_f);
+                    break;
+                }
+                step /= 2.0;
+            }
+            if step < 1e-10 {
+                break;
+            }
+        }
+        Ok(Calibrator::Platt { a, b })
+    }
+
+    /// Isotonic regression fitted with pool-adjacent-violators.
+    pub fn fit_isotonic(margins: &[f32], labels: &[f32]) -> Result<Self, CalibrationError> {
+        check(margins, labels)?;
+        let mut order: Vec<usize> = (0..margins.len()).collect();
+        order.sort_by(|a, b| {
+            margins[*a]
+                .partial_cmp(&margins[*b])
+                .unwrap_or(Ordering::Equal)
+        });
+        // Blocks of (sum of margins, sum of labels, count)
+        let mut blocks: Vec<(f64, f64, f64)> = Vec::with_capacity(order.len());
+        for i in order {
+            blocks.push((margins[i] as f64, labels[i] as f64, 1.0));
+            while blocks.len() > 1 {
+                let (m2, y2, n2) = blocks[blocks.len() - 1];
+                let (m1, y1, n1) = blocks[blocks.len() - 2];
+                if y1 / n1 < y2 / n2 {
+                    break;
+                }
+                blocks.pop();
+                *blocks.last_mut().unwrap() = (m1 + m2, y1 + y2, n1 + n2);
+            }
+        }
+        let (margins, probs) = blocks.iter().map(|(m, y, n)| (m / n, y / n)).unzip();
+        Ok(Calibrator::Isotonic { margins, probs })
+    }
+
+    pub fn transform(&self, margin: f32) -> f32 {
+        let margin = margin as f64;
+        let p = match self {
+            Calibrator::Platt { a, b } => 1.0 / (1.0 + (a * margin + b).exp()),
+            Calibrator::Isotonic { margins, probs } => {
+                let idx = margins.partition_point(|m| *m < margin);
+                if idx == 0 {
+                    probs[0]
+                } else if idx == margins.len() {
+                    probs[idx - 1]
+                } else {
+                    let (m0, m1) = (margins[idx - 1], margins[idx]);
+                    let (p0, p1) = (probs[idx - 1], probs[idx]);
+                    p0 + (p1 - p0) * (margin - m0) / (m1 - m0)
+                }
+            }
+        };
+        p as f32
+    }
+}
+
+fn check(margins: &[f32], labels: &[f32]) -> Result<(), CalibrationError> {
+    if margins.len() != labels.len() {
+        return Err(CalibrationError::Length(margins.len(), labels.len()));
+    }
+    let n_pos = labels.iter().filter(|y| **y > 0.5).count();
+    if n_pos == 0 || n_pos == labels.len() {
+        return Err(CalibrationError::SingleClass);
+    }
+    Ok(())
+}
+
+/// A binary classifier whose probabilities are post-processed by a [`Calibrator`].
+pub struct CalibratedModel {
+    booster: Booster,
+    calibrator: Calibrator,
+}
+
+impl CalibratedModel {
+    pub fn new(booster: Booster, calibrator: Calibrator) -> Self {
+        CalibratedModel {
+            booster,
+            calibrator,
+        }
+    }
+
+    /// Fits a calibrator on the margins the booster produces for a held-out `validation`
+    /// set, using its labels as targets.
+    pub fn fit(
+        booster: Booster,
+        validation: &DMatrix,
+        method: CalibrationMethod,
+    ) -> Result<Self, CalibrationError> {
+        let margins = booster.predict_with(validation, &PredictOptions::margin())?;
+        let labels = validation.get_labels()?;
+        let calibrator = Calibrator::fit(method, &margins, &labels)?;
+        Ok(CalibratedModel::new(booster, calibrator))
+    }
+
+    pub fn predict_proba(&self, data: &DMatrix) -> Result<Vec<f32>, CalibrationError> {
+        let margins = self.booster.predict_with(data, &PredictOptions::margin())?;
+        Ok(margins
+            .into_iter()
+            .map(|m| self.calibrator.transform(m))
+            .collect())
+    }
+
+    pub fn booster(&self) -> &Booster {
+        &self.booster
+    }
+
+    pub fn calibrator(&self) -> &Calibrator {
+        &self.calibrator
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARGINS: [f32; 8] = [-3.0, -2.0, -1.5, -0.5, 0.5, 1.0, 2.0, 3.0];
+    const LABELS: [f32; 8] = [0., 0., 1., 0., 1., 0., 1., 1.];
+
+    #[test]
+    fn test_platt_is_increasing() {
+        let calibrator = Calibrator::fit_platt(&MARGINS, &LABELS).unwrap();
+        let probs: Vec<f32> = MARGINS.iter().map(|m| calibrator.transform(*m)).collect();
+        assert!(probs.windows(2).all(|w| w[0] < w[1]));
+        assert!(probs.iter().all(|p| *p > 0.0 && *p < 1.0));
+    }
+
+    #[test]
+    fn test_isotonic_is_monotone() {
+        let calibrator = Calibrator::fit_isotonic(&MARGINS, &LABELS).unwrap();
+        let probs: Vec<f32> = MARGINS.iter().map(|m| calibrator.transform(*m)).collect();
+        assert!(probs.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(calibrator.transform(-10.0), 0.0);
+        assert_eq!(calibrator.transform(10.0), 1.0);
+    }
+
+    #[test]
+    fn test_single_class_rejected() {
+        let r = Calibrator::fit_platt(&[0.1, 0.2], &[1., 1.]);
+        assert!(matches!(r, Err(CalibrationError::SingleClass)));
+    }
+}
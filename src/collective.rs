@@ -0,0 +1,871 @@
+//! Distributed training through XGBoost's collective communicator: a [`Tracker`]
+//! coordinates the workers, each of which joins with a [`Communicator`] before training
+//! on its own shard of the data. Boosters trained under an active communicator
+//! synchronize histograms every round and end up identical on all workers.
+//!
+//! The communicator is per thread, so several workers can also live in one process;
+//! [`train_multi_gpu`] uses this to train on all GPUs of a machine.
+
+use std::ffi::{CStr, CString};
+use std::ops::Range;
+use std::os::raw::{c_char, c_void};
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use thiserror::Error;
+use xgb_sys::{
+    XGBGetLastError, XGCommunicatorFinalize, XGCommunicatorGetRank, XGCommunicatorGetWorldSize,
+    XGCommunicatorInit, XGTrackerCreate, XGTrackerFree, XGTrackerRun, XGTrackerWaitFor,
+    XGTrackerWorkerArgs,
+};
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::{DMatrix, DMatrixError};
+use crate::params::TrainParams;
+use crate::predict::Device;
+
+#[derive(Error, Debug)]
+pub enum CollectiveError {
+    #[error("Invalid argument: {0}")]
+    Argument(String),
+    #[error("Tracker failed to {0}")]
+    Tracker(String),
+    #[error("Cannot {0} communicator")]
+    Communicator(String),
+    #[error("Worker {rank} failed: {message}")]
+    Worker { rank: u32, message: String },
+    #[error("Gave up after {attempts} attempts: {last}")]
+    RetriesExhausted {
+        attempts: u32,
+        last: Box<CollectiveError>,
+    },
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+}
+
+/// The rendezvous point of the workers, running in the background of this process.
+pub struct Tracker {
+    handle: *mut c_void,
+}
+
+impl Tracker {
+    /// Starts a tracker for `n_workers` on `host`; port 0 picks a free port.
+    pub fn start(n_workers: u32, host: &str, port: u16) -> Result<Self, CollectiveError> {
+        if n_workers == 0 {
+            return Err(CollectiveError::Argument(
+                "at least one worker is needed".to_string(),
+            ));
+        }
+        let config = json!({
+            "dmlc_communicator": "rabit",
+            "n_workers": n_workers,
+            "host_ip": host,
+            "port": port,
+            "sortby": "task",
+        });
+        let config = CString::new(config.to_string()).unwrap();
+        let run = CString::new("{}").unwrap();
+        let mut handle: *mut c_void = std::ptr::null_mut();
+        unsafe {
+            if XGTrackerCreate(config.as_ptr(), &mut handle) != 0 {
+                return Err(CollectiveError::Tracker(format!("start: {}", last_error())));
+            }
+            let tracker = Tracker { handle };
+            if XGTrackerRun(tracker.handle, run.as_ptr()) == 0 {
+                Ok(tracker)
+            } else {
+                Err(CollectiveError::Tracker(format!("run: {}", last_error())))
+            }
+        }
+    }
+
+    /// Configuration workers pass to [`Communicator::init`] to reach this tracker.
+    pub fn worker_args(&self) -> Result<Value, CollectiveError> {
+        let mut out: *const c_char = std::ptr::null();
+        unsafe {
+            if XGTrackerWorkerArgs(self.handle, &mut out) != 0 || out.is_null() {
+                return Err(CollectiveError::Tracker("report worker args".to_string()));
+            }
+            serde_json::from_str(&CStr::from_ptr(out).to_string_lossy())
+                .map_err(|e| CollectiveError::Tracker(format!("report worker args: {}", e)))
+        }
+    }
+
+    /// Blocks until every worker has finalized its communicator.
+    pub fn wait(self) -> Result<(), CollectiveError> {
+        let config = CString::new("{}").unwrap();
+        unsafe {
+            if XGTrackerWaitFor(self.handle, config.as_ptr()) == 0 {
+                Ok(())
+            } else {
+                Err(CollectiveError::Tracker(format!(
+                    "wait for workers: {}",
+                    last_error()
+                )))
+            }
+        }
+    }
+}
+
+impl Drop for Tracker {
+    fn drop(&mut self) {
+        unsafe {
+            XGTrackerFree(self.handle);
+        }
+    }
+}
+
+unsafe impl Send for Tracker {}
+unsafe impl Sync for Tracker {}
+
+/// Membership of the calling thread in the collective, finalized on drop.
+pub struct Communicator {
+    // The communicator is thread-local in XGBoost, so the guard must stay on its thread.
+    _not_send: std::marker::PhantomData<*const ()>,
+}
+
+impl Communicator {
+    /// Joins the collective described by `args`, usually [`Tracker::worker_args`], as
+    /// the worker with the given `task_id`. Ranks follow the order of task ids.
+    pub fn init(args: &Value, task_id: &str) -> Result<Self, CollectiveError> {
+        let mut config = args.clone();
+        let Some(fields) = config.as_object_mut() else {
+            return Err(CollectiveError::Argument(
+                "worker args must be a JSON object".to_string(),
+            ));
+        };
+        fields.insert("dmlc_task_id".to_string(), json!(task_id));
+        fields
+            .entry("dmlc_communicator")
+            .or_insert_with(|| json!("rabit"));
+        let config = CString::new(config.to_string()).unwrap();
+        unsafe {
+            if XGCommunicatorInit(config.as_ptr()) == 0 {
+                Ok(Communicator {
+                    _not_send: std::marker::PhantomData,
+                })
+            } else {
+                Err(CollectiveError::Communicator(format!(
+                    "initialize ({})",
+                    last_error()
+                )))
+            }
+        }
+    }
+
+    pub fn rank(&self) -> u32 {
+        unsafe { XGCommunicatorGetRank().max(0) as u32 }
+    }
+
+    pub fn world_size(&self) -> u32 {
+        unsafe { XGCommunicatorGetWorldSize().max(1) as u32 }
+    }
+
+    /// Leaves the collective, reporting failures that dropping would ignore.
+    pub fn finalize(self) -> Result<(), CollectiveError> {
+        std::mem::forget(self);
+        unsafe {
+            if XGCommunicatorFinalize() == 0 {
+                Ok(())
+            } else {
+                Err(CollectiveError::Communicator(format!(
+                    "finalize ({})",
+                    last_error()
+                )))
+            }
+        }
+    }
+}
+
+impl Drop for Communicator {
+    fn drop(&mut self) {
+        unsafe {
+            XGCommunicatorFinalize();
+        }
+    }
+}
+
+/// A worker's place in the collective, used to pick its share of the data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WorkerId {
+    pub rank: u32,
+    pub world_size: u32,
+}
+
+impl WorkerId {
+    pub fn new(rank: u32, world_size: u32) -> Result<Self, CollectiveError> {
+        if rank >= world_size {
+            return Err(CollectiveError::Argument(format!(
+                "rank {} out of range for {} workers",
+                rank, world_size
+            )));
+        }
+        Ok(WorkerId { rank, world_size })
+    }
+
+    /// The calling thread's rank and world size in the active collective.
+    pub fn of(communicator: &Communicator) -> Self {
+        WorkerId {
+            rank: communicator.rank(),
+            world_size: communicator.world_size(),
+        }
+    }
+
+    /// This worker's contiguous share of `0..n`. Shares cover the range in rank order and
+    /// differ in size by at most one.
+    pub fn range(&self, n: u64) -> Range<u64> {
+        let (rank, world_size) = (self.rank as u64, self.world_size as u64);
+        let start = |r: u64| r * (n / world_size) + r.min(n % world_size);
+        start(rank)..start(rank + 1)
+    }
+
+    /// This worker's files: every `world_size`-th one, in path order, starting at `rank`,
+    /// so the assignment does not depend on how each worker listed them.
+    pub fn files<P: AsRef<Path> + Clone>(&self, files: &[P]) -> Vec<P> {
+        let mut sorted = files.to_vec();
+        sorted.sort_by(|a, b| a.as_ref().cmp(b.as_ref()));
+        sorted
+            .into_iter()
+            .skip(self.rank as usize)
+            .step_by(self.world_size as usize)
+            .collect()
+    }
+
+    /// A seed for this worker's own randomness, e.g. shuffling or subsampling its shard,
+    /// derived from the run's `base` seed. Each rank gets a different seed, and the same
+    /// one whenever the run is repeated or resumed, whatever the world size.
+    ///
+    /// XGBoost's [`seed`](crate::params::TrainParams::seed) parameter is not per worker: it
+    /// must be the same on all of them.
+    pub fn seed(&self, base: u64) -> u64 {
+        // SplitMix64 finalizer, so neighbouring ranks and bases give unrelated seeds
+        let mut z = base.wrapping_add((self.rank as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+/// This worker's rows of `data`, per [`WorkerId::range`]. With query groups, whole groups
+/// are distributed instead of rows and kept on the shard.
+pub fn shard_dmatrix(data: &DMatrix, worker: WorkerId) -> Result<DMatrix, CollectiveError> {
+    let group_ptr = data.get_group_ptr()?;
+    if group_ptr.len() < 2 {
+        let rows = worker.range(data.num_rows());
+        let rows: Vec<i32> = (rows.start as i32..rows.end as i32).collect();
+        return Ok(data.slice(&rows)?);
+    }
+    let groups = worker.range(group_ptr.len() as u64 - 1);
+    let (start, end) = (
+        group_ptr[groups.start as usize],
+        group_ptr[groups.end as usize],
+    );
+    let shard = data.slice(&(start as i32..end as i32).collect::<Vec<_>>())?;
+    let sizes: Vec<u32> = group_ptr[groups.start as usize..=groups.end as usize]
+        .windows(2)
+        .map(|w| w[1] - w[0])
+        .collect();
+    if !sizes.is_empty() {
+        shard.set_group(&sizes)?;
+    }
+    Ok(shard)
+}
+
+/// Loads this worker's share of `files`, per [`WorkerId::files`], with `load` and stacks
+/// them. Fails when there are fewer files than workers, as a worker would have no data.
+pub fn shard_files<P, F>(files: &[P], worker: WorkerId, load: F) -> Result<DMatrix, CollectiveError>
+where
+    P: AsRef<Path> + Clone,
+    F: Fn(&Path) -> Result<DMatrix, DMatrixError>,
+{
+    if files.len() < worker.world_size as usize {
+        return Err(CollectiveError::Argument(format!(
+            "{} files cannot be shared by {} workers",
+            files.len(),
+            worker.world_size
+        )));
+    }
+    let mut parts = worker
+        .files(files)
+        .iter()
+        .map(|f| load(f.as_ref()))
+        .collect::<Result<Vec<_>, _>>()?;
+    if parts.len() == 1 {
+        return Ok(parts.remove(0));
+    }
+    Ok(DMatrix::concat(&parts.iter().collect::<Vec<_>>())?)
+}
+
+/// Environment variables holding the rank, in order of precedence: torchrun-style
+/// launchers, Kubernetes indexed Jobs, Open MPI, and MPICH/Intel MPI.
+pub const RANK_VARS: [&str; 4] = [
+    "RANK",
+    "JOB_COMPLETION_INDEX",
+    "OMPI_COMM_WORLD_RANK",
+    "PMI_RANK",
+];
+/// Environment variables holding the number of workers, in order of precedence.
+pub const WORLD_SIZE_VARS: [&str; 4] = [
+    "WORLD_SIZE",
+    "DMLC_NUM_WORKER",
+    "OMPI_COMM_WORLD_SIZE",
+    "PMI_SIZE",
+];
+/// Environment variables holding the tracker's host and port, in order of precedence.
+pub const TRACKER_HOST_VARS: [&str; 2] = ["DMLC_TRACKER_URI", "MASTER_ADDR"];
+pub const TRACKER_PORT_VARS: [&str; 2] = ["DMLC_TRACKER_PORT", "MASTER_PORT"];
+/// Port of the tracker when no port variable is set, as in XGBoost's launchers.
+pub const DEFAULT_TRACKER_PORT: u16 = 9091;
+
+/// Where this worker stands and where to meet the others, usually read from the
+/// environment set by the launcher. In Kubernetes, an indexed Job with a headless
+/// service provides `JOB_COMPLETION_INDEX`; set `WORLD_SIZE` to the completions and
+/// `MASTER_ADDR` to the DNS name of the pod with index 0, which hosts the tracker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rendezvous {
+    pub worker: WorkerId,
+    pub tracker_host: String,
+    pub tracker_port: u16,
+    /// Whether this worker starts the tracker; rank 0 does by default.
+    pub host_tracker: bool,
+}
+
+impl Rendezvous {
+    pub fn from_env() -> Result<Self, CollectiveError> {
+        Rendezvous::from_vars(|name| std::env::var(name).ok())
+    }
+
+    /// Like [`Rendezvous::from_env`], reading variables through `var`.
+    pub fn from_vars<F>(var: F) -> Result<Self, CollectiveError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let first = |names: &[&'static str]| {
+            names
+                .iter()
+                .find_map(|name| var(name).map(|value| (*name, value.trim().to_string())))
+        };
+        let number = |names: &[&'static str]| -> Result<Option<u32>, CollectiveError> {
+            first(names)
+                .map(|(name, value)| {
+                    value.parse().map_err(|_| {
+                        CollectiveError::Argument(format!("{}={} is not a number", name, value))
+                    })
+                })
+                .transpose()
+        };
+        let world_size = number(&WORLD_SIZE_VARS)?.ok_or_else(|| {
+            CollectiveError::Argument(format!("none of {} is set", WORLD_SIZE_VARS.join(", ")))
+        })?;
+        let rank = number(&RANK_VARS)?.ok_or_else(|| {
+            CollectiveError::Argument(format!("none of {} is set", RANK_VARS.join(", ")))
+        })?;
+        let worker = WorkerId::new(rank, world_size)?;
+        let tracker_host = first(&TRACKER_HOST_VARS)
+            .map(|(_, host)| host)
+            .ok_or_else(|| {
+                CollectiveError::Argument(format!(
+                    "none of {} is set",
+                    TRACKER_HOST_VARS.join(", ")
+                ))
+            })?;
+        let tracker_port = match number(&TRACKER_PORT_VARS)? {
+            Some(port) => u16::try_from(port).map_err(|_| {
+                CollectiveError::Argument(format!("tracker port {} out of range", port))
+            })?,
+            None => DEFAULT_TRACKER_PORT,
+        };
+        Ok(Rendezvous {
+            worker,
+            tracker_host,
+            tracker_port,
+            host_tracker: rank == 0,
+        })
+    }
+
+    /// Starts the tracker if this worker hosts it, listening on all interfaces, then
+    /// joins the collective.
+    pub fn join(&self) -> Result<Member, CollectiveError> {
+        let tracker = if self.host_tracker {
+            Some(Tracker::start(
+                self.worker.world_size,
+                "0.0.0.0",
+                self.tracker_port,
+            )?)
+        } else {
+            None
+        };
+        let args = json!({
+            "dmlc_tracker_uri": self.tracker_host,
+            "dmlc_tracker_port": self.tracker_port,
+        });
+        let communicator = Communicator::init(&args, &format!("{:06}", self.worker.rank))?;
+        Ok(Member {
+            communicator,
+            tracker,
+        })
+    }
+}
+
+/// A worker that has joined the collective, possibly hosting its tracker.
+pub struct Member {
+    pub communicator: Communicator,
+    tracker: Option<Tracker>,
+}
+
+impl Member {
+    /// Leaves the collective; the tracker's host then waits for the other workers.
+    pub fn finish(self) -> Result<(), CollectiveError> {
+        self.communicator.finalize()?;
+        match self.tracker {
+            Some(tracker) => tracker.wait(),
+            None => Ok(()),
+        }
+    }
+}
+
+fn check_devices(devices: &[u32]) -> Result<(), CollectiveError> {
+    if devices.is_empty() {
+        return Err(CollectiveError::Argument("no devices given".to_string()));
+    }
+    let mut sorted = devices.to_vec();
+    sorted.sort_unstable();
+    if sorted.windows(2).any(|w| w[0] == w[1]) {
+        return Err(CollectiveError::Argument(
+            "each device can only be used once".to_string(),
+        ));
+    }
+    if !Device::cuda_available() {
+        return Err(CollectiveError::Argument(
+            "the linked XGBoost library was built without CUDA".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Trains one model on several GPUs of this machine. XGBoost runs one worker per device,
+/// so a worker thread is started for each of `devices`, joined through a local tracker,
+/// and trains with `device=cuda:<ordinal>` and the `hist` tree method on the shard
+/// returned by `load_shard(rank, world_size)`. Shards should be disjoint and of similar
+/// size. The workers' models are identical; rank 0's is returned.
+pub fn train_multi_gpu<F>(
+    devices: &[u32],
+    params: &TrainParams,
+    num_boost: usize,
+    load_shard: F,
+) -> Result<Booster, CollectiveError>
+where
+    F: Fn(u32, u32) -> Result<DMatrix, DMatrixError> + Sync,
+{
+    check_devices(devices)?;
+    let world_size = devices.len() as u32;
+    let tracker = Tracker::start(world_size, "127.0.0.1", 0)?;
+    let args = tracker.worker_args()?;
+    let results: Vec<Result<Booster, CollectiveError>> = thread::scope(|scope| {
+        let workers: Vec<_> = devices
+            .iter()
+            .enumerate()
+            .map(|(rank, ordinal)| {
+                let (args, load_shard) = (&args, &load_shard);
+                scope.spawn(move || {
+                    let work = || {
+                        let communicator = Communicator::init(args, &format!("{:06}", rank))?;
+                        let shard = load_shard(communicator.rank(), world_size)?;
+                        let params = params
+                            .clone()
+                            .set("device", &Device::Cuda(*ordinal).as_param())
+                            .set("tree_method", "hist");
+                        let booster = Booster::train_with_params(&shard, &params, num_boost)
+                            .map_err(|e| {
+                                // The native message is thread-local, so it is read on the
+                                // worker, and only after a failed C API call
+                                let message = if failed_native_call(&e) {
+                                    format!("{} ({})", e, last_error())
+                                } else {
+                                    e.to_string()
+                                };
+                                CollectiveError::Worker {
+                                    rank: rank as u32,
+                                    message,
+                                }
+                            })?;
+                        communicator.finalize()?;
+                        Ok(booster)
+                    };
+                    work().map_err(|e: CollectiveError| match e {
+                        CollectiveError::Worker { .. } => e,
+                        e => CollectiveError::Worker {
+                            rank: rank as u32,
+                            message: e.to_string(),
+                        },
+                    })
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .enumerate()
+            .map(|(rank, worker)| {
+                worker.join().unwrap_or_else(|_| {
+                    Err(CollectiveError::Worker {
+                        rank: rank as u32,
+                        message: "panicked".to_string(),
+                    })
+                })
+            })
+            .collect()
+    });
+    tracker.wait()?;
+    let mut boosters = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+    Ok(boosters.swap_remove(0))
+}
+
+/// Whether `error` reports a failed C API call, which left its message for
+/// [`last_error`]. Errors raised by the wrappers before calling into XGBoost did not.
+fn failed_native_call(error: &XGBoostError) -> bool {
+    matches!(
+        error,
+        XGBoostError::Create | XGBoostError::Train(_) | XGBoostError::Config(..)
+    )
+}
+
+/// The message of the last failed C API call on this thread.
+fn last_error() -> String {
+    unsafe {
+        let message = XGBGetLastError();
+        if message.is_null() {
+            String::new()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        }
+    }
+}
+
+/// Whether retrying can help with a collective failure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Network blips, restarting peers, rendezvous timeouts.
+    Transient,
+    /// Bad arguments, bad data, or bugs: retrying would fail the same way.
+    Fatal,
+}
+
+/// Substrings of socket and rendezvous failures, as reported by XGBoost and the OS.
+const TRANSIENT_MARKERS: [&str; 9] = [
+    "connection refused",
+    "connection reset",
+    "connection closed",
+    "broken pipe",
+    "timed out",
+    "timeout",
+    "socket",
+    "peer",
+    "unreachable",
+];
+
+impl CollectiveError {
+    /// Classifies the error from its kind and message. Rendezvous and communicator
+    /// failures mentioning the network are transient, as is any worker failure that
+    /// does; everything else is fatal.
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            CollectiveError::Tracker(message)
+            | CollectiveError::Communicator(message)
+            | CollectiveError::Worker { message, .. } => classify_message(message),
+            CollectiveError::RetriesExhausted { last, .. } => last.class(),
+            _ => ErrorClass::Fatal,
+        }
+    }
+}
+
+fn classify_message(message: &str) -> ErrorClass {
+    let message = message.to_lowercase();
+    if TRANSIENT_MARKERS.iter().any(|m| message.contains(m)) {
+        ErrorClass::Transient
+    } else {
+        ErrorClass::Fatal
+    }
+}
+
+/// How often and how patiently [`retry`] repeats transient failures.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Total attempts, including the first; 1 disables retries.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Waits `initial` before the first retry, growing by `multiplier` up to `max`.
+    pub fn backoff(mut self, initial: Duration, max: Duration, multiplier: f64) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max.max(initial);
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    /// Wait before retry number `retry`, counting from 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.saturating_sub(1) as i32);
+        self.initial_backoff
+            .mul_f64(factor.min(u32::MAX as f64))
+            .min(self.max_backoff)
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails with a fatal error, or `policy` runs out of
+/// attempts, sleeping between tries. `attempt` receives the attempt number from 0 and
+/// must redo the whole collective step, rendezvous included: after a peer failure every
+/// worker has to start over, so it usually wraps a complete training run such as
+/// [`train_multi_gpu`].
+pub fn retry<T, F>(policy: &RetryPolicy, mut attempt: F) -> Result<T, CollectiveError>
+where
+    F: FnMut(u32) -> Result<T, CollectiveError>,
+{
+    let mut n = 0;
+    loop {
+        let error = match attempt(n) {
+            Ok(value) => return Ok(value),
+            Err(e) => e,
+        };
+        n += 1;
+        if error.class() == ErrorClass::Fatal {
+            return Err(error);
+        }
+        if n >= policy.max_attempts {
+            return Err(CollectiveError::RetriesExhausted {
+                attempts: n,
+                last: Box::new(error),
+            });
+        }
+        thread::sleep(policy.delay(n));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_devices() {
+        assert!(matches!(
+            check_devices(&[]),
+            Err(CollectiveError::Argument(_))
+        ));
+        assert!(matches!(
+            check_devices(&[0, 1, 0]),
+            Err(CollectiveError::Argument(_))
+        ));
+    }
+
+    #[test]
+    fn test_worker_range() {
+        let ranges: Vec<Range<u64>> = (0..3)
+            .map(|r| WorkerId::new(r, 3).unwrap().range(10))
+            .collect();
+        assert_eq!(ranges, vec![0..4, 4..7, 7..10]);
+        assert_eq!(WorkerId::new(4, 5).unwrap().range(2), 2..2);
+        assert!(WorkerId::new(2, 2).is_err());
+    }
+
+    #[test]
+    fn test_worker_files() {
+        let files = ["c.csv", "a.csv", "d.csv", "b.csv", "e.csv"];
+        assert_eq!(
+            WorkerId::new(0, 2).unwrap().files(&files),
+            vec!["a.csv", "c.csv", "e.csv"]
+        );
+        assert_eq!(
+            WorkerId::new(1, 2).unwrap().files(&files),
+            vec!["b.csv", "d.csv"]
+        );
+    }
+
+    #[test]
+    fn test_worker_seed() {
+        let seeds: Vec<u64> = (0..4)
+            .map(|r| WorkerId::new(r, 4).unwrap().seed(42))
+            .collect();
+        let mut distinct = seeds.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 4);
+        // Same rank and base, same seed, even with another world size
+        assert_eq!(WorkerId::new(2, 8).unwrap().seed(42), seeds[2]);
+        assert_ne!(WorkerId::new(2, 4).unwrap().seed(43), seeds[2]);
+    }
+
+    #[test]
+    fn test_shard_dmatrix_groups() {
+        let data: Vec<f32> = (0..10).map(|i| i as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, 10, 1).unwrap();
+        dmat.set_group(&[3, 3, 4]).unwrap();
+        let shard = shard_dmatrix(&dmat, WorkerId::new(1, 2).unwrap()).unwrap();
+        assert_eq!(shard.num_rows(), 4);
+        assert_eq!(shard.get_group_ptr().unwrap(), vec![0, 4]);
+    }
+
+    #[test]
+    fn test_rendezvous_from_vars() {
+        let vars = |pairs: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                pairs
+                    .iter()
+                    .find(|(k, _)| *k == name)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        let rendezvous = Rendezvous::from_vars(vars(&[
+            ("JOB_COMPLETION_INDEX", "2"),
+            ("WORLD_SIZE", "4"),
+            ("MASTER_ADDR", "train-0.train"),
+        ]))
+        .unwrap();
+        assert_eq!(rendezvous.worker, WorkerId::new(2, 4).unwrap());
+        assert_eq!(rendezvous.tracker_host, "train-0.train");
+        assert_eq!(rendezvous.tracker_port, DEFAULT_TRACKER_PORT);
+        assert!(!rendezvous.host_tracker);
+
+        let mpi = Rendezvous::from_vars(vars(&[
+            ("OMPI_COMM_WORLD_RANK", "0"),
+            ("OMPI_COMM_WORLD_SIZE", "2"),
+            ("DMLC_TRACKER_URI", "10.0.0.1"),
+            ("DMLC_TRACKER_PORT", "9000"),
+        ]))
+        .unwrap();
+        assert!(mpi.host_tracker);
+        assert_eq!(mpi.tracker_port, 9000);
+
+        assert!(Rendezvous::from_vars(vars(&[("RANK", "0"), ("WORLD_SIZE", "2")])).is_err());
+        assert!(Rendezvous::from_vars(vars(&[
+            ("RANK", "x"),
+            ("WORLD_SIZE", "2"),
+            ("MASTER_ADDR", "h")
+        ]))
+        .is_err());
+        assert!(Rendezvous::from_vars(vars(&[
+            ("RANK", "2"),
+            ("WORLD_SIZE", "2"),
+            ("MASTER_ADDR", "h")
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_failed_native_call() {
+        assert!(failed_native_call(&XGBoostError::Train(3)));
+        assert!(!failed_native_call(&XGBoostError::Unsupported(
+            "gblinear".to_string()
+        )));
+        assert!(!failed_native_call(&XGBoostError::CallbackPanic(
+            "boom".to_string()
+        )));
+    }
+
+    #[test]
+    fn test_error_class() {
+        let reset = CollectiveError::Communicator("initialize (Connection reset by peer)".into());
+        assert_eq!(reset.class(), ErrorClass::Transient);
+        let bad = CollectiveError::Worker {
+            rank: 1,
+            message: "Invalid argument: no devices given".to_string(),
+        };
+        assert_eq!(bad.class(), ErrorClass::Fatal);
+        assert_eq!(
+            CollectiveError::Argument("timeout".to_string()).class(),
+            ErrorClass::Fatal
+        );
+    }
+
+    #[test]
+    fn test_retry() {
+        let policy =
+            RetryPolicy::new()
+                .max_attempts(3)
+                .backoff(Duration::ZERO, Duration::ZERO, 2.0);
+        let mut calls = 0;
+        let value = retry(&policy, |n| {
+            calls += 1;
+            if n < 2 {
+                Err(CollectiveError::Tracker("start: timed out".to_string()))
+            } else {
+                Ok(n)
+            }
+        })
+        .unwrap();
+        assert_eq!((value, calls), (2, 3));
+
+        let result: Result<(), _> = retry(&policy, |_| {
+            Err(CollectiveError::Tracker("start: timed out".to_string()))
+        });
+        assert!(matches!(
+            result,
+            Err(CollectiveError::RetriesExhausted { attempts: 3, .. })
+        ));
+
+        let mut calls = 0;
+        let result: Result<(), _> = retry(&policy, |_| {
+            calls += 1;
+            Err(CollectiveError::Argument("bad".to_string()))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_retry_delay() {
+        let policy =
+            RetryPolicy::new().backoff(Duration::from_millis(100), Duration::from_millis(350), 2.0);
+        assert_eq!(policy.delay(1), Duration::from_millis(100));
+        assert_eq!(policy.delay(2), Duration::from_millis(200));
+        assert_eq!(policy.delay(3), Duration::from_millis(350));
+    }
+
+    #[test]
+    fn test_local_tracker() {
+        let tracker = Tracker::start(2, "127.0.0.1", 0).unwrap();
+        let args = tracker.worker_args().unwrap();
+        let ranks: Vec<(u32, u32)> = thread::scope(|scope| {
+            let workers: Vec<_> = (0..2)
+                .map(|i| {
+                    let args = &args;
+                    scope.spawn(move || {
+                        let communicator = Communicator::init(args, &i.to_string()).unwrap();
+                        let rank = (communicator.rank(), communicator.world_size());
+                        communicator.finalize().unwrap();
+                        rank
+                    })
+                })
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).collect()
+        });
+        tracker.wait().unwrap();
+        assert_eq!(ranks, vec![(0, 2), (1, 2)]);
+    }
+}
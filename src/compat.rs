@@ -0,0 +1,235 @@
+//! Compatibility of model files with the linked libxgboost. [`check`] reads a model saved
+//! by any XGBoost package, including scikit-learn estimators, and reports which release
+//! wrote it, its learner type, the features it relies on and whether this library can
+//! load it, without loading it.
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::interchange::{self, InterchangeError, ModelHeader, SourceFormat, VersionPolicy};
+
+/// First release reading categorical splits from JSON models.
+const CATEGORICAL_SINCE: [u32; 3] = [1, 6, 0];
+/// First release with vector-leaf trees (`multi_strategy=multi_output_tree`).
+const VECTOR_LEAF_SINCE: [u32; 3] = [2, 0, 0];
+/// Bytes at the end of a model file searched for its version.
+const VERSION_TAIL: u64 = 256;
+
+/// Model features that need a recent enough library.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelFeatures {
+    /// Trees split on category sets.
+    pub categorical_splits: bool,
+    /// Several targets, e.g. multi-output regression.
+    pub multi_output: bool,
+    /// Trees with one leaf vector for all targets rather than one tree per target.
+    pub vector_leaf: bool,
+    /// 0 unless the objective is multi-class.
+    pub num_class: usize,
+    /// The estimator class recorded by the scikit-learn wrapper, e.g. `XGBClassifier`'s
+    /// `classifier`.
+    pub sklearn_estimator: Option<String>,
+}
+
+/// Outcome of [`check`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompatReport {
+    pub format: SourceFormat,
+    /// Version and learner type, if the file is a valid model document.
+    pub header: Option<ModelHeader>,
+    pub features: ModelFeatures,
+    /// Version of the linked library.
+    pub library: [u32; 3],
+    /// Why the library cannot load the model, empty if it can.
+    pub problems: Vec<String>,
+}
+
+impl CompatReport {
+    pub fn loadable(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Reports whether the linked libxgboost can load the model at `path`. Only failing to
+/// read the file is an error; problems with its contents end up in the report.
+pub fn check<P: AsRef<Path>>(path: P) -> io::Result<CompatReport> {
+    Ok(check_bytes(
+        &std::fs::read(path)?,
+        interchange::library_version(),
+    ))
+}
+
+fn check_bytes(bytes: &[u8], library: [u32; 3]) -> CompatReport {
+    let mut report = CompatReport {
+        format: SourceFormat::detect(bytes),
+        header: None,
+        features: ModelFeatures::default(),
+        library,
+        problems: Vec::new(),
+    };
+    let model = match interchange::parse_model(bytes) {
+        Ok((_, model)) => model,
+        Err(e) => {
+            report.problems.push(e.to_string());
+            return report;
+        }
+    };
+    report.features = features(&model);
+    let header = match interchange::validate(&model) {
+        Ok(header) => header,
+        Err(e) => {
+            report.problems.push(e.to_string());
+            return report;
+        }
+    };
+    if let Err(InterchangeError::Version { reason, .. }) =
+        VersionPolicy::UpToLibrary.check(header.version, library)
+    {
+        report.problems.push(format!("the model {}", reason));
+    }
+    if report.features.categorical_splits && library < CATEGORICAL_SINCE {
+        report
+            .problems
+            .push("categorical splits need XGBoost 1.6 or later".to_string());
+    }
+    if report.features.vector_leaf && library < VECTOR_LEAF_SINCE {
+        report
+            .problems
+            .push("vector-leaf trees need XGBoost 2.0 or later".to_string());
+    }
+    report.header = Some(header);
+    report
+}
+
+fn features(model: &Value) -> ModelFeatures {
+    let learner = &model["learner"];
+    let booster = &learner["gradient_booster"];
+    let trees = match booster["name"].as_str() {
+        Some("dart") => &booster["gbtree"]["model"]["trees"],
+        _ => &booster["model"]["trees"],
+    };
+    let trees = trees.as_array().map(Vec::as_slice).unwrap_or_default();
+    let param = |name: &str| {
+        let value = &learner["learner_model_param"][name];
+        value
+            .as_str()
+            .and_then(|v| v.parse().ok())
+            .or(value.as_u64().map(|v| v as usize))
+            .unwrap_or(0)
+    };
+    let vector_leaf = trees.iter().any(|tree| {
+        tree["tree_param"]["size_leaf_vector"]
+            .as_str()
+            .and_then(|v| v.parse::<usize>().ok())
+            .is_some_and(|size| size > 1)
+    });
+    let sklearn_estimator = learner["attributes"]["scikit_learn"]
+        .as_str()
+        .and_then(|attr| serde_json::from_str::<Value>(attr).ok())
+        .and_then(|attr| attr["_estimator_type"].as_str().map(str::to_string));
+    ModelFeatures {
+        categorical_splits: trees.iter().any(|tree| {
+            tree["split_type"]
+                .as_array()
+                .is_some_and(|types| types.iter().any(|t| t.as_u64() == Some(1)))
+        }),
+        multi_output: param("num_target") > 1 || vector_leaf,
+        vector_leaf,
+        num_class: param("num_class"),
+        sklearn_estimator,
+    }
+}
+
+/// Version of the release that wrote a model, read from the tail of the file, where
+/// XGBoost puts the `version` key. `None` if it is not there.
+pub(crate) fn file_version(path: &str) -> Option<[u32; 3]> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(VERSION_TAIL)))
+        .ok()?;
+    let mut tail = Vec::with_capacity(VERSION_TAIL as usize);
+    file.read_to_end(&mut tail).ok()?;
+    sniff_version(&tail)
+}
+
+/// [`file_version`] for a model held in memory.
+pub(crate) fn sniff_version(bytes: &[u8]) -> Option<[u32; 3]> {
+    let tail = &bytes[bytes.len().saturating_sub(VERSION_TAIL as usize)..];
+    let key = b"version";
+    let at = tail.windows(key.len()).rposition(|w| w == key)? + key.len();
+    let value = match tail.get(at) {
+        // JSON: `"version": [3, 0, 2]`
+        Some(b'"') => {
+            let rest = &tail[at + 1..];
+            let end = rest.iter().position(|b| *b == b']')?;
+            let text = std::str::from_utf8(&rest[..=end]).ok()?;
+            serde_json::from_str(text.trim_start_matches([':', ' '])).ok()?
+        }
+        // UBJSON: the key is followed by the array's marker
+        Some(_) => interchange::ubjson_to_json(&tail[at..]).ok()?,
+        None => return None,
+    };
+    let version: Vec<u32> = value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_u64().map(|v| v as u32))
+        .collect::<Option<_>>()?;
+    version.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn model(version: [u32; 3], trees: Value) -> Value {
+        json!({
+            "learner": {
+                "attributes": {"scikit_learn": "{\"_estimator_type\": \"classifier\"}"},
+                "gradient_booster": {"name": "gbtree", "model": {"trees": trees}},
+                "learner_model_param": {"num_class": "0", "num_target": "1"},
+                "objective": {"name": "binary:logistic"}
+            },
+            "version": version
+        })
+    }
+
+    #[test]
+    fn test_check_bytes() {
+        let trees = json!([{"split_type": [1, 0, 0], "tree_param": {"size_leaf_vector": "1"}}]);
+        let bytes = serde_json::to_vec(&model([2, 1, 0], trees)).unwrap();
+        let report = check_bytes(&bytes, [3, 0, 2]);
+        assert!(report.loadable(), "{:?}", report.problems);
+        assert_eq!(report.header.unwrap().version, [2, 1, 0]);
+        assert!(report.features.categorical_splits);
+        assert!(!report.features.multi_output);
+        assert_eq!(
+            report.features.sklearn_estimator.as_deref(),
+            Some("classifier")
+        );
+
+        let trees = json!([{"split_type": [0], "tree_param": {"size_leaf_vector": "3"}}]);
+        let bytes = serde_json::to_vec(&model([3, 1, 0], trees)).unwrap();
+        let report = check_bytes(&bytes, [3, 0, 2]);
+        assert!(report.features.vector_leaf && report.features.multi_output);
+        assert_eq!(report.problems.len(), 1);
+
+        let report = check_bytes(&[0x80, 4, 0x95], [3, 0, 2]);
+        assert_eq!(report.format, SourceFormat::Pickle);
+        assert!(!report.loadable());
+    }
+
+    #[test]
+    fn test_sniff_version() {
+        let json = serde_json::to_vec(&model([2, 1, 4], json!([]))).unwrap();
+        assert_eq!(sniff_version(&json), Some([2, 1, 4]));
+        let ubj = b"{i\x07learner{}i\x07version[$L#i\x03\
+            \x00\x00\x00\x00\x00\x00\x00\x03\x00\x00\x00\x00\x00\x00\x00\x00\
+            \x00\x00\x00\x00\x00\x00\x00\x02}";
+        assert_eq!(sniff_version(ubj), Some([3, 0, 2]));
+        assert_eq!(sniff_version(b"{}"), None);
+    }
+}
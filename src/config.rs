@@ -0,0 +1,333 @@
+//! Process-wide XGBoost settings, through `XGBSetGlobalConfig`/`XGBGetGlobalConfig`.
+
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::sync::{LazyLock, RwLock};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use xgb_sys::{XGBGetGlobalConfig, XGBRegisterLogCallback, XGBSetGlobalConfig};
+
+use crate::ffi_guard::PanicSlot;
+use crate::memory;
+use crate::predict::Device;
+
+type LogHandler = Box<dyn Fn(&str) + Send + Sync>;
+
+static LOG_HANDLER: RwLock<Option<LogHandler>> = RwLock::new(None);
+static LOG_PANIC: LazyLock<PanicSlot> = LazyLock::new(PanicSlot::new);
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Cannot set global config {0}")]
+    Set(String),
+    #[error("Cannot get global config")]
+    Get,
+    #[error("Unsupported setting: {0}")]
+    Unsupported(String),
+    #[error("Cannot register log handler")]
+    Log,
+}
+
+/// Global settings. Unset fields are left untouched by [`set_global_config`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GlobalConfig {
+    /// 0 silent, 1 warning, 2 info, 3 debug.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verbosity: Option<u32>,
+    /// Allocate GPU memory through RMM instead of XGBoost's caching allocator, so it is
+    /// drawn from the process's RMM memory resource. XGBoost's C API cannot configure
+    /// that resource: install the pool, with its initial and maximum size, through RMM
+    /// before the first GPU call. Nothing in this crate caps device memory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub use_rmm: Option<bool>,
+    /// Limit, in bytes, on the estimate of [`memory_stats`](crate::memory::memory_stats),
+    /// 0 for none. This is bookkeeping on this side of the C API, not a cap on any
+    /// allocator: XGBoost never sees it. After a `DMatrix` is created, the matrix is freed
+    /// again and
+    /// [`DMatrixError::TrackedMemoryLimit`](crate::dmatrix::DMatrixError::TrackedMemoryLimit)
+    /// returned if the estimate went above the limit, so the memory was allocated for a
+    /// moment regardless. It bounds neither device memory nor an RMM pool.
+    #[serde(skip)]
+    pub tracked_memory_limit: Option<u64>,
+    /// Upper bound on the threads of every XGBoost operation in the process, 0 for all
+    /// cores. Per-call settings like [`PredictOptions::nthread`](crate::predict::PredictOptions::nthread)
+    /// cannot exceed it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nthread: Option<u32>,
+}
+
+impl GlobalConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn verbosity(mut self, verbosity: u32) -> Self {
+        self.verbosity = Some(verbosity);
+        self
+    }
+
+    pub fn use_rmm(mut self, use_rmm: bool) -> Self {
+        self.use_rmm = Some(use_rmm);
+        self
+    }
+
+    pub fn nthread(mut self, nthread: u32) -> Self {
+        self.nthread = Some(nthread);
+        self
+    }
+
+    pub fn tracked_memory_limit(mut self, bytes: u64) -> Self {
+        self.tracked_memory_limit = Some(bytes);
+        self
+    }
+
+    fn validate(&self) -> Result<(), ConfigError> {
+        if self.verbosity.is_some_and(|v| v > 3) {
+            return Err(ConfigError::Unsupported(format!(
+                "verbosity {} is above 3",
+                self.verbosity.unwrap_or_default()
+            )));
+        }
+        if self.use_rmm == Some(true) && !Device::cuda_available() {
+            return Err(ConfigError::Unsupported(
+                "use_rmm needs XGBoost built with CUDA".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Applies the set fields of `config` to the whole process.
+pub fn set_global_config(config: &GlobalConfig) -> Result<(), ConfigError> {
+    config.validate()?;
+    let json = serde_json::to_string(config).map_err(|e| ConfigError::Set(e.to_string()))?;
+    let c_json = CString::new(json.as_str()).unwrap();
+    unsafe {
+        if XGBSetGlobalConfig(c_json.as_ptr()) != 0 {
+            return Err(ConfigError::Set(json));
+        }
+    }
+    if let Some(limit) = config.tracked_memory_limit {
+        memory::set_limit(limit);
+    }
+    Ok(())
+}
+
+/// Current global settings.
+pub fn global_config() -> Result<GlobalConfig, ConfigError> {
+    let mut out: *const c_char = std::ptr::null();
+    unsafe {
+        if XGBGetGlobalConfig(&mut out) != 0 || out.is_null() {
+            return Err(ConfigError::Get);
+        }
+        let json = CStr::from_ptr(out).to_string_lossy();
+        let config: GlobalConfig = serde_json::from_str(&json).map_err(|_| ConfigError::Get)?;
+        Ok(GlobalConfig {
+            tracked_memory_limit: Some(memory::limit().unwrap_or(0)),
+            ..config
+        })
+    }
+}
+
+/// How OpenMP binds its threads to processors, as `OMP_PROC_BIND`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcBind {
+    /// Threads may migrate between processors.
+    False,
+    /// Threads are pinned close to the parent thread's place.
+    Close,
+    /// Threads are pinned spread over the places.
+    Spread,
+}
+
+impl ProcBind {
+    fn as_env(&self) -> &'static str {
+        match self {
+            ProcBind::False => "false",
+            ProcBind::Close => "close",
+            ProcBind::Spread => "spread",
+        }
+    }
+}
+
+/// OpenMP runtime settings. XGBoost parallelizes with OpenMP, whose idle threads spin by
+/// default and, with one thread per core, oversubscribe a process that also runs e.g.
+/// tokio workers. Capping the threads and letting idle ones sleep avoids that; pinning
+/// keeps a dedicated scoring process on its cores.
+///
+/// Only the thread count can be changed from a running process, see
+/// [`configure_openmp`]. The OpenMP runtime reads the other settings from the environment
+/// when it is loaded, before `main`, so they have to be set where the process is launched,
+/// e.g. in a container spec, using [`OpenMpSettings::env_vars`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OpenMpSettings {
+    pub num_threads: Option<u32>,
+    /// Let idle threads sleep instead of spinning (`OMP_WAIT_POLICY=passive`).
+    pub passive_wait: Option<bool>,
+    pub proc_bind: Option<ProcBind>,
+    /// Processors to run on, as `OMP_PLACES`, e.g. `cores` or `{0}:4:2`.
+    pub places: Option<String>,
+}
+
+impl OpenMpSettings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn num_threads(mut self, num_threads: u32) -> Self {
+        self.num_threads = Some(num_threads);
+        self
+    }
+
+    pub fn passive_wait(mut self, passive: bool) -> Self {
+        self.passive_wait = Some(passive);
+        self
+    }
+
+    pub fn proc_bind(mut self, proc_bind: ProcBind) -> Self {
+        self.proc_bind = Some(proc_bind);
+        self
+    }
+
+    pub fn places(mut self, places: &str) -> Self {
+        self.places = Some(places.to_string());
+        self
+    }
+
+    /// The environment variables these settings translate to, for the launch environment
+    /// of the process.
+    pub fn env_vars(&self) -> Vec<(&'static str, String)> {
+        let mut vars = Vec::new();
+        if let Some(n) = self.num_threads {
+            vars.push(("OMP_NUM_THREADS", n.to_string()));
+        }
+        if let Some(passive) = self.passive_wait {
+            let policy = if passive { "passive" } else { "active" };
+            vars.push(("OMP_WAIT_POLICY", policy.to_string()));
+        }
+        if let Some(bind) = self.proc_bind {
+            vars.push(("OMP_PROC_BIND", bind.as_env().to_string()));
+        }
+        if let Some(places) = &self.places {
+            vars.push(("OMP_PLACES", places.clone()));
+        }
+        vars
+    }
+}
+
+/// Applies the thread count of `settings` as XGBoost's global `nthread`, which works at
+/// any time. The wait policy, binding and places cannot be changed once the process runs
+/// and are ignored here; see [`OpenMpSettings`].
+pub fn configure_openmp(settings: &OpenMpSettings) -> Result<(), ConfigError> {
+    if settings.num_threads == Some(0) {
+        return Err(ConfigError::Unsupported(
+            "OpenMP needs at least one thread".to_string(),
+        ));
+    }
+    match settings.num_threads {
+        Some(n) => set_global_config(&GlobalConfig::new().nthread(n)),
+        None => Ok(()),
+    }
+}
+
+/// Routes XGBoost's log messages, filtered by `verbosity`, to `handler` instead of stderr.
+/// Without the `tracing` feature, failures this crate cannot return, e.g. from `Drop`, are
+/// passed to it too.
+///
+/// A panicking handler is contained at the C boundary: the message is dropped and the
+/// handler is not called again until [`log_handler_panic`] has been checked.
+pub fn set_log_handler<F>(handler: F) -> Result<(), ConfigError>
+where
+    F: Fn(&str) + Send + Sync + 'static,
+{
+    *LOG_HANDLER.write().unwrap_or_else(|e| e.into_inner()) = Some(Box::new(handler));
+    unsafe {
+        if XGBRegisterLogCallback(Some(log_callback)) == 0 {
+            Ok(())
+        } else {
+            Err(ConfigError::Log)
+        }
+    }
+}
+
+/// Message of a panic caught in the log handler, if any, re-enabling the handler.
+pub fn log_handler_panic() -> Option<String> {
+    LOG_PANIC.take()
+}
+
+unsafe extern "C" fn log_callback(message: *const c_char) {
+    if message.is_null() {
+        return;
+    }
+    log(&CStr::from_ptr(message).to_string_lossy());
+}
+
+/// Passes `message` to the handler set with [`set_log_handler`], if any.
+pub(crate) fn log(message: &str) {
+    LOG_PANIC.catch((), || {
+        if let Some(handler) = LOG_HANDLER
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            handler(message);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_json() {
+        let config = GlobalConfig::new().verbosity(1);
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"verbosity":1}"#
+        );
+        let parsed: GlobalConfig =
+            serde_json::from_str(r#"{"verbosity": 2, "use_rmm": false, "nthread": 0}"#).unwrap();
+        assert_eq!(
+            parsed,
+            GlobalConfig::new().verbosity(2).use_rmm(false).nthread(0)
+        );
+        assert!(GlobalConfig::new().verbosity(4).validate().is_err());
+        // The tracked memory limit stays on this side of the C API
+        let config = GlobalConfig::new().tracked_memory_limit(1 << 30);
+        assert_eq!(serde_json::to_string(&config).unwrap(), "{}");
+    }
+
+    #[test]
+    fn test_openmp_env_vars() {
+        let settings = OpenMpSettings::new()
+            .num_threads(2)
+            .passive_wait(true)
+            .proc_bind(ProcBind::Close)
+            .places("cores");
+        assert_eq!(
+            settings.env_vars(),
+            vec![
+                ("OMP_NUM_THREADS", "2".to_string()),
+                ("OMP_WAIT_POLICY", "passive".to_string()),
+                ("OMP_PROC_BIND", "close".to_string()),
+                ("OMP_PLACES", "cores".to_string()),
+            ]
+        );
+        assert!(OpenMpSettings::new().env_vars().is_empty());
+        assert!(configure_openmp(&OpenMpSettings::new().num_threads(0)).is_err());
+    }
+
+    #[test]
+    fn test_log_callback_contains_panic() {
+        *LOG_HANDLER.write().unwrap() = Some(Box::new(|m: &str| panic!("handler got {}", m)));
+        let hook = std::panic::take_hook();
+        std::panic::set_hook(Box::new(|_| {}));
+        let message = CString::new("hello").unwrap();
+        unsafe { log_callback(message.as_ptr()) };
+        std::panic::set_hook(hook);
+        assert_eq!(log_handler_panic().as_deref(), Some("handler got hello"));
+        *LOG_HANDLER.write().unwrap() = None;
+    }
+}
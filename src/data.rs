@@ -0,0 +1,406 @@
+//! Dataset preparation helpers working on [`DMatrix`] and its metadata.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+use crate::dmatrix::{DMatrix, DMatrixError};
+use crate::params::TrainParams;
+
+/// Rows of one side of a split, with the sizes of its query groups if any.
+#[derive(Debug, Default, PartialEq)]
+struct Part {
+    rows: Vec<i32>,
+    groups: Vec<u32>,
+}
+
+/// Randomly splits `data` into `(train, test)` with about `test_fraction` of the rows in
+/// `test`. Labels, weights and base margins follow their rows. With query groups whole
+/// groups are assigned to either side, so no query leaks between them, and the group
+/// sizes are set again on both matrices. Rows keep their original order.
+pub fn train_test_split(
+    data: &DMatrix,
+    test_fraction: f32,
+    seed: u64,
+) -> Result<(DMatrix, DMatrix), DMatrixError> {
+    if !(test_fraction > 0.0 && test_fraction < 1.0) {
+        return Err(DMatrixError::Argument(format!(
+            "test_fraction must be in (0, 1), got {}",
+            test_fraction
+        )));
+    }
+    let group_ptr = data.get_group_ptr()?;
+    let (train, test) = split_rows(data.num_rows() as usize, &group_ptr, test_fraction, seed);
+    if train.rows.is_empty() || test.rows.is_empty() {
+        return Err(DMatrixError::Argument(format!(
+            "test_fraction {} leaves one side of the split empty",
+            test_fraction
+        )));
+    }
+    let mut out = Vec::with_capacity(2);
+    for part in [train, test] {
+        let dmat = data.slice(&part.rows)?;
+        if !part.groups.is_empty() {
+            dmat.set_group(&part.groups)?;
+        }
+        out.push(dmat);
+    }
+    let test = out.pop().unwrap();
+    Ok((out.pop().unwrap(), test))
+}
+
+/// How [`rebalance`] equalizes the two classes of a binary problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resampling {
+    /// Randomly drop rows of the majority class.
+    Undersample,
+    /// Randomly repeat rows of the minority class.
+    Oversample,
+}
+
+/// Builds a matrix with as many positive as negative rows from a binary problem with 0/1
+/// labels. Weights and base margins follow their rows. Matrices with query groups are
+/// rejected, as resampling would break them.
+pub fn rebalance(
+    data: &DMatrix,
+    resampling: Resampling,
+    seed: u64,
+) -> Result<DMatrix, DMatrixError> {
+    if data.get_group_ptr()?.len() > 1 {
+        return Err(DMatrixError::Argument(
+            "cannot rebalance a matrix with query groups".to_string(),
+        ));
+    }
+    let rows = rebalance_rows(&data.get_labels()?, resampling, seed)?;
+    data.slice(&rows)
+}
+
+/// `scale_pos_weight` balancing a binary problem: the number of negative rows divided by
+/// the number of positive ones.
+pub fn scale_pos_weight(labels: &[f32]) -> Result<f32, DMatrixError> {
+    let (negatives, positives) = split_classes(labels)?;
+    Ok(negatives.len() as f32 / positives.len() as f32)
+}
+
+fn split_classes(labels: &[f32]) -> Result<(Vec<i32>, Vec<i32>), DMatrixError> {
+    let (mut negatives, mut positives) = (Vec::new(), Vec::new());
+    for (row, label) in labels.iter().enumerate() {
+        match *label {
+            0.0 => negatives.push(row as i32),
+            1.0 => positives.push(row as i32),
+            l => {
+                return Err(DMatrixError::Argument(format!(
+                    "expected 0/1 labels, got {} at row {}",
+                    l, row
+                )))
+            }
+        }
+    }
+    if negatives.is_empty() || positives.is_empty() {
+        return Err(DMatrixError::Argument(
+            "both classes need at least one row".to_string(),
+        ));
+    }
+    Ok((negatives, positives))
+}
+
+fn rebalance_rows(
+    labels: &[f32],
+    resampling: Resampling,
+    seed: u64,
+) -> Result<Vec<i32>, DMatrixError> {
+    let (negatives, positives) = split_classes(labels)?;
+    let (mut minority, mut majority) = if negatives.len() < positives.len() {
+        (negatives, positives)
+    } else {
+        (positives, negatives)
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    match resampling {
+        Resampling::Undersample => {
+            majority.shuffle(&mut rng);
+            majority.truncate(minority.len());
+        }
+        Resampling::Oversample => {
+            let extra: Vec<i32> = (minority.len()..majority.len())
+                .filter_map(|_| minority.choose(&mut rng).copied())
+                .collect();
+            minority.extend(extra);
+        }
+    }
+    let mut rows = minority;
+    rows.extend(majority);
+    rows.sort();
+    Ok(rows)
+}
+
+/// Row indices of one cross-validation fold.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fold {
+    pub train: Vec<i32>,
+    pub test: Vec<i32>,
+}
+
+/// K-fold splitting where all rows sharing an entity id land in the same fold, so no
+/// entity is seen both in training and validation. Like scikit-learn's `GroupKFold`,
+/// entities are assigned largest first to the currently smallest fold, which keeps fold
+/// sizes balanced; the assignment is deterministic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GroupKFold {
+    pub n_splits: usize,
+}
+
+impl GroupKFold {
+    pub fn new(n_splits: usize) -> Self {
+        GroupKFold { n_splits }
+    }
+
+    /// Splits rows given one id per row.
+    pub fn split<T: Eq + Hash>(&self, ids: &[T]) -> Result<Vec<Fold>, DMatrixError> {
+        let mut first_seen: HashMap<&T, usize> = HashMap::new();
+        let mut entities: Vec<Vec<i32>> = Vec::new();
+        for (row, id) in ids.iter().enumerate() {
+            let entity = *first_seen.entry(id).or_insert_with(|| {
+                entities.push(Vec::new());
+                entities.len() - 1
+            });
+            entities[entity].push(row as i32);
+        }
+        if self.n_splits < 2 || entities.len() < self.n_splits {
+            return Err(DMatrixError::Argument(format!(
+                "cannot make {} folds from {} distinct ids",
+                self.n_splits,
+                entities.len()
+            )));
+        }
+        // Stable sort keeps first-seen order among entities of equal size
+        entities.sort_by_key(|rows| std::cmp::Reverse(rows.len()));
+        let mut tests: Vec<Vec<i32>> = vec![Vec::new(); self.n_splits];
+        for rows in entities {
+            let smallest = (0..self.n_splits)
+                .min_by_key(|f| tests[*f].len())
+                .unwrap_or(0);
+            tests[smallest].extend(rows);
+        }
+        Ok(tests
+            .into_iter()
+            .map(|mut test| {
+                test.sort();
+                let train = (0..ids.len() as i32)
+                    .filter(|r| test.binary_search(r).is_err())
+                    .collect();
+                Fold { train, test }
+            })
+            .collect())
+    }
+
+    /// `(train, test)` matrices for each fold of `data`, given one id per row. Matrices
+    /// with query groups are rejected; their groups already define the entities.
+    pub fn folds<T: Eq + Hash>(
+        &self,
+        data: &DMatrix,
+        ids: &[T],
+    ) -> Result<Vec<(DMatrix, DMatrix)>, DMatrixError> {
+        if ids.len() as u64 != data.num_rows() {
+            return Err(DMatrixError::Length {
+                field: "ids".to_string(),
+                expected: data.num_rows(),
+                got: ids.len(),
+            });
+        }
+        if data.get_group_ptr()?.len() > 1 {
+            return Err(DMatrixError::Argument(
+                "cannot split a matrix with query groups by id".to_string(),
+            ));
+        }
+        self.split(ids)?
+            .into_iter()
+            .map(|fold| Ok((data.slice(&fold.train)?, data.slice(&fold.test)?)))
+            .collect()
+    }
+}
+
+/// Learning task, to pick an objective in [`suggest_params`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Task {
+    Regression,
+    BinaryClassification,
+    MulticlassClassification,
+    Ranking,
+}
+
+/// Starting parameters derived from the shape, sparsity and labels of `data`: objective,
+/// `tree_method`, `max_bin`, learning rate, depth, and `scale_pos_weight` or `num_class`
+/// where relevant. Meant as a baseline to tune from, not a replacement for tuning.
+pub fn suggest_params(data: &DMatrix, task: Task) -> Result<TrainParams, DMatrixError> {
+    let cells = data.num_rows() * data.num_cols();
+    let density = if cells == 0 {
+        1.0
+    } else {
+        data.num_non_missing()? as f64 / cells as f64
+    };
+    let params = suggest_from_shape(data.num_rows(), data.num_cols(), density, task);
+    let labels = data.get_labels()?;
+    match task {
+        Task::BinaryClassification => {
+            let ratio = scale_pos_weight(&labels)?;
+            // Only reweight clearly imbalanced problems
+            if !(0.5..=2.0).contains(&ratio) {
+                return Ok(params.set("scale_pos_weight", &format!("{:.4}", ratio)));
+            }
+            Ok(params)
+        }
+        Task::MulticlassClassification => params
+            .resolve_num_class(&labels)
+            .map_err(|e| DMatrixError::Argument(e.to_string())),
+        Task::Regression | Task::Ranking => Ok(params),
+    }
+}
+
+fn suggest_from_shape(rows: u64, cols: u64, density: f64, task: Task) -> TrainParams {
+    let objective = match task {
+        Task::Regression => "reg:squarederror",
+        Task::BinaryClassification => "binary:logistic",
+        Task::MulticlassClassification => "multi:softprob",
+        Task::Ranking => "rank:ndcg",
+    };
+    // Small data overfits quickly: shallower trees and coarser bins
+    let (eta, max_depth, max_bin) = match rows {
+        0..10_000 => (0.1, 4, 64),
+        10_000..1_000_000 => (0.1, 6, 256),
+        _ => (0.05, 8, 256),
+    };
+    let mut params = TrainParams::new()
+        .objective(objective)
+        .eta(eta)
+        .max_depth(max_depth)
+        .set("tree_method", "hist")
+        .set("max_bin", &max_bin.to_string());
+    if cols > 100 {
+        params = params.set("colsample_bytree", "0.8");
+    }
+    if rows >= 10_000 {
+        params = params.set("subsample", "0.8");
+    }
+    if density < 0.1 {
+        // Very sparse data rarely benefits from deep interactions
+        params = params.max_depth(max_depth.min(6));
+    }
+    params
+}
+
+/// Shuffles rows, or whole groups when `group_ptr` is not empty, and sends the first
+/// `test_fraction` of the rows to the test side.
+fn split_rows(num_rows: usize, group_ptr: &[u32], test_fraction: f32, seed: u64) -> (Part, Part) {
+    let bounds: Vec<(usize, usize)> = if group_ptr.len() > 1 {
+        group_ptr
+            .windows(2)
+            .map(|w| (w[0] as usize, w[1] as usize))
+            .collect()
+    } else {
+        (0..num_rows).map(|r| (r, r + 1)).collect()
+    };
+    let mut order: Vec<usize> = (0..bounds.len()).collect();
+    order.shuffle(&mut StdRng::seed_from_u64(seed));
+
+    let target = (num_rows as f64 * test_fraction as f64).round() as usize;
+    let mut in_test = vec![false; bounds.len()];
+    let mut test_rows = 0;
+    for unit in order {
+        if test_rows >= target {
+            break;
+        }
+        in_test[unit] = true;
+        test_rows += bounds[unit].1 - bounds[unit].0;
+    }
+
+    let (mut train, mut test) = (Part::default(), Part::default());
+    for (unit, (begin, end)) in bounds.into_iter().enumerate() {
+        let part = if in_test[unit] { &mut test } else { &mut train };
+        part.rows.extend(begin as i32..end as i32);
+        if group_ptr.len() > 1 {
+            part.groups.push((end - begin) as u32);
+        }
+    }
+    (train, test)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_rows() {
+        let (train, test) = split_rows(10, &[], 0.3, 42);
+        assert_eq!(test.rows.len(), 3);
+        assert_eq!(train.rows.len(), 7);
+        assert!(train.groups.is_empty());
+        let mut all: Vec<i32> = train.rows.iter().chain(&test.rows).copied().collect();
+        all.sort();
+        assert_eq!(all, (0..10).collect::<Vec<_>>());
+        assert_eq!(split_rows(10, &[], 0.3, 42), (train, test));
+    }
+
+    #[test]
+    fn test_split_keeps_groups_whole() {
+        let (train, test) = split_rows(9, &[0, 2, 5, 6, 9], 0.4, 7);
+        for part in [&train, &test] {
+            assert_eq!(part.groups.iter().sum::<u32>() as usize, part.rows.len());
+        }
+        let sizes: Vec<u32> = train.groups.iter().chain(&test.groups).copied().collect();
+        assert_eq!(sizes.len(), 4);
+        assert!(test.rows.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_rebalance_rows() {
+        let labels = [0.0, 0.0, 1.0, 0.0, 0.0, 1.0];
+        let under = rebalance_rows(&labels, Resampling::Undersample, 1).unwrap();
+        assert_eq!(under.len(), 4);
+        assert!(under.contains(&2) && under.contains(&5));
+        let over = rebalance_rows(&labels, Resampling::Oversample, 1).unwrap();
+        assert_eq!(over.len(), 8);
+        assert_eq!(
+            over.iter().filter(|r| labels[**r as usize] == 1.0).count(),
+            4
+        );
+        assert!(rebalance_rows(&[0.0, 2.0], Resampling::Oversample, 1).is_err());
+    }
+
+    #[test]
+    fn test_suggest_from_shape() {
+        let pairs = suggest_from_shape(1_000, 10, 1.0, Task::BinaryClassification).to_pairs();
+        assert!(pairs.contains(&("objective".to_string(), "binary:logistic".to_string())));
+        assert!(pairs.contains(&("max_bin".to_string(), "64".to_string())));
+        assert!(pairs.contains(&("max_depth".to_string(), "4".to_string())));
+        let pairs = suggest_from_shape(5_000_000, 500, 0.01, Task::Regression).to_pairs();
+        assert!(pairs.contains(&("eta".to_string(), "0.05".to_string())));
+        assert!(pairs.contains(&("max_depth".to_string(), "6".to_string())));
+        assert!(pairs.contains(&("colsample_bytree".to_string(), "0.8".to_string())));
+    }
+
+    #[test]
+    fn test_group_k_fold() {
+        let ids = ["a", "b", "a", "c", "d", "b", "a", "e"];
+        let folds = GroupKFold::new(3).split(&ids).unwrap();
+        assert_eq!(folds.len(), 3);
+        assert_eq!(folds[0].test, vec![0, 2, 6]);
+        for fold in &folds {
+            assert_eq!(fold.train.len() + fold.test.len(), ids.len());
+            for test in &fold.test {
+                let id = ids[*test as usize];
+                assert!(fold.train.iter().all(|r| ids[*r as usize] != id));
+            }
+        }
+        assert!(GroupKFold::new(6).split(&ids).is_err());
+    }
+
+    #[test]
+    fn test_scale_pos_weight() {
+        assert_eq!(scale_pos_weight(&[0.0, 0.0, 0.0, 1.0]).unwrap(), 3.0);
+        assert!(scale_pos_weight(&[0.0, 0.0]).is_err());
+    }
+}
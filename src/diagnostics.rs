@@ -0,0 +1,298 @@
+//! Training diagnostics: leakage checks and curves of model quality.
+
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::data::{train_test_split, GroupKFold};
+use crate::dmatrix::{DMatrix, DMatrixError};
+use crate::feature_selection::Table;
+use crate::metrics::{self, Metric, MetricError};
+use crate::params::TrainParams;
+use crate::predict::PredictOptions;
+
+#[derive(Error, Debug)]
+pub enum DiagnosticsError {
+    #[error("Invalid argument: {0}")]
+    Argument(String),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+    #[error(transparent)]
+    Metric(#[from] MetricError),
+}
+
+/// Settings of [`leakage_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeakageOptions {
+    /// Score from which a single feature is suspicious.
+    pub threshold: f64,
+    pub max_depth: u32,
+    pub num_boost: usize,
+}
+
+impl Default for LeakageOptions {
+    fn default() -> Self {
+        LeakageOptions {
+            threshold: 0.9,
+            max_depth: 2,
+            num_boost: 10,
+        }
+    }
+}
+
+/// How well one feature predicts the label on its own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureLeakage {
+    pub feature: String,
+    /// Holdout AUC for binary labels, R² otherwise, of a shallow model using only this
+    /// feature. AUCs below 0.5 are mirrored, as an inverted feature leaks as much.
+    pub score: f64,
+    pub flagged: bool,
+}
+
+/// Trains a shallow model on each feature alone, on half of the rows, and scores it on
+/// the other half. Features that nearly determine the label by themselves are flagged:
+/// they are often computed from the target or only known after it. Sorted by decreasing
+/// score. Each model trains on the column as stored, with its missing values and feature
+/// type, and on the rows' weights and base margins. Matrices with query groups are
+/// rejected.
+pub fn leakage_report(
+    data: &DMatrix,
+    options: &LeakageOptions,
+) -> Result<Vec<FeatureLeakage>, DiagnosticsError> {
+    if data.get_group_ptr()?.len() > 1 {
+        return Err(DiagnosticsError::Argument(
+            "cannot check leakage on a matrix with query groups".to_string(),
+        ));
+    }
+    let table = Table::new(data)?;
+    let binary = table.labels.iter().all(|y| *y == 0.0 || *y == 1.0);
+    let params = TrainParams::new()
+        .objective(if binary {
+            "binary:logistic"
+        } else {
+            "reg:squarederror"
+        })
+        .max_depth(options.max_depth);
+    let row_ids: Vec<usize> = (0..table.rows).collect();
+    let fold = GroupKFold::new(2).split(&row_ids)?.remove(0);
+
+    let mut report = Vec::with_capacity(table.cols);
+    for (f, feature) in table.names.iter().enumerate() {
+        let dmat = table.select(&[f])?;
+        let (train, test) = (dmat.slice(&fold.train)?, dmat.slice(&fold.test)?);
+        let booster = Booster::train_with_params(&train, &params, options.num_boost)?;
+        let (preds, labels) = (booster.predict(&test)?, test.get_labels()?);
+        let score = if binary {
+            let auc = metrics::auc(&preds, &labels)?;
+            if auc.is_nan() {
+                0.5
+            } else {
+                auc.max(1.0 - auc)
+            }
+        } else {
+            let r2 = metrics::regression_report(&preds, &labels)?.r2;
+            if r2.is_nan() {
+                0.0
+            } else {
+                r2
+            }
+        };
+        report.push(FeatureLeakage {
+            feature: feature.clone(),
+            score,
+            flagged: score >= options.threshold,
+        });
+    }
+    report.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(report)
+}
+
+/// Share of the rows held out for validation by [`learning_curve`].
+pub const LEARNING_CURVE_HOLDOUT: f32 = 0.2;
+
+/// Metrics of a model trained on a fraction of the training rows, keyed by the names of
+/// the booster's evaluation metrics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LearningCurvePoint {
+    pub fraction: f64,
+    pub train_rows: u64,
+    pub train: HashMap<String, f64>,
+    pub validation: HashMap<String, f64>,
+}
+
+/// Holds out [`LEARNING_CURVE_HOLDOUT`] of `data` for validation and trains on growing
+/// `fractions` of the remaining rows. Subsets are nested, each one containing the smaller
+/// ones, and query groups are kept whole.
+///
+/// Validation metrics that keep improving with more rows mean more data would help; a
+/// large, stable gap to the training metrics points at variance, and both being poor at
+/// capacity.
+pub fn learning_curve(
+    params: &TrainParams,
+    num_boost: usize,
+    data: &DMatrix,
+    fractions: &[f64],
+    seed: u64,
+) -> Result<Vec<LearningCurvePoint>, DiagnosticsError> {
+    if let Some(fraction) = fractions.iter().find(|f| !(**f > 0.0 && **f <= 1.0)) {
+        return Err(DiagnosticsError::Argument(format!(
+            "fractions must be in (0, 1], got {}",
+            fraction
+        )));
+    }
+    let (train, validation) = train_test_split(data, LEARNING_CURVE_HOLDOUT, seed)?;
+    fractions
+        .iter()
+        .map(|fraction| {
+            let subset = if *fraction < 1.0 {
+                Some(train_test_split(&train, (1.0 - fraction) as f32, seed)?.0)
+            } else {
+                None
+            };
+            let subset = subset.as_ref().unwrap_or(&train);
+            let booster = Booster::train_with_params(subset, params, num_boost)?;
+            Ok(LearningCurvePoint {
+                fraction: *fraction,
+                train_rows: subset.num_rows(),
+                train: booster.evaluate(subset, "train")?,
+                validation: booster.evaluate(&validation, "validation")?,
+            })
+        })
+        .collect()
+}
+
+/// Validation metric as a function of the number of boosting rounds used for prediction.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundsSweep {
+    /// `scores[i]` is the metric when predicting with the first `i + 1` rounds.
+    pub scores: Vec<f64>,
+    /// Number of rounds with the best score; the earliest one on ties.
+    pub best_rounds: usize,
+    pub best_score: f64,
+}
+
+/// Scores `data` with every prefix of the boosted rounds, via iteration-ranged
+/// predictions, to find where the model could have been truncated. Much cheaper than
+/// retraining with each `num_boost`, though unlike early stopping the later trees were
+/// still fitted on the earlier ones.
+pub fn rounds_sweep(
+    booster: &Booster,
+    data: &DMatrix,
+    metric: Metric,
+) -> Result<RoundsSweep, DiagnosticsError> {
+    let rounds = booster.boosted_rounds()?;
+    let scores = (1..=rounds)
+        .map(|end| {
+            let options = PredictOptions::new().iteration_range(0, end as u32);
+            let preds = booster.predict_with(data, &options)?;
+            Ok(metric.evaluate(&preds, data)?)
+        })
+        .collect::<Result<Vec<f64>, DiagnosticsError>>()?;
+    let (best, best_score) = best_score(&scores, metric.higher_is_better())
+        .ok_or_else(|| DiagnosticsError::Argument("booster has no boosted rounds".to_string()))?;
+    Ok(RoundsSweep {
+        scores,
+        best_rounds: best + 1,
+        best_score,
+    })
+}
+
+fn best_score(scores: &[f64], higher_is_better: bool) -> Option<(usize, f64)> {
+    scores
+        .iter()
+        .copied()
+        .enumerate()
+        .reduce(|best, (i, score)| {
+            let better = if higher_is_better {
+                score > best.1
+            } else {
+                score < best.1
+            };
+            if better {
+                (i, score)
+            } else {
+                best
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leakage_report() {
+        // The label is a threshold on f0, f1 is unrelated
+        let rows = 40;
+        let data: Vec<f32> = (0..rows)
+            .flat_map(|i| [i as f32, ((i * 7) % 5) as f32])
+            .collect();
+        let labels: Vec<f32> = (0..rows).map(|i| (i >= rows / 2) as u8 as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, rows as u64, 2).unwrap();
+        dmat.try_add_label(&labels).unwrap();
+        let report = leakage_report(&dmat, &LeakageOptions::default()).unwrap();
+        assert_eq!(report[0].feature, "f0");
+        assert!(report[0].flagged);
+        assert!(!report[1].flagged);
+    }
+
+    #[test]
+    fn test_leakage_report_keeps_feature_info() {
+        // f0 is a categorical copy of the label, f1 is mostly missing noise
+        let rows = 40;
+        let data: Vec<f32> = (0..rows)
+            .flat_map(|i| {
+                let noise = if i % 3 == 0 { (i % 4) as f32 } else { f32::NAN };
+                [(i % 2) as f32, noise]
+            })
+            .collect();
+        let labels: Vec<f32> = (0..rows).map(|i| (i % 2) as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, rows as u64, 2).unwrap();
+        dmat.try_add_label(&labels).unwrap();
+        dmat.set_weights(&vec![2.0; rows]).unwrap();
+        dmat.set_feature_names(&["segment", "noise"]).unwrap();
+        dmat.set_feature_types(&["c", "q"]).unwrap();
+        let report = leakage_report(&dmat, &LeakageOptions::default()).unwrap();
+        assert_eq!(report[0].feature, "segment");
+        assert!(report[0].flagged);
+        assert_eq!(report[1].feature, "noise");
+        assert!(!report[1].flagged);
+    }
+
+    #[test]
+    fn test_best_score() {
+        assert_eq!(best_score(&[3.0, 1.0, 2.0, 1.0], false), Some((1, 1.0)));
+        assert_eq!(best_score(&[0.5, 0.7, 0.7], true), Some((1, 0.7)));
+        assert_eq!(best_score(&[], true), None);
+    }
+
+    #[test]
+    fn test_rounds_sweep() {
+        let data: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, 50, 2).unwrap();
+        dmat.try_add_label(&(0..50).map(|i| i as f32).collect::<Vec<_>>())
+            .unwrap();
+        let booster = Booster::train_with_params(&dmat, &TrainParams::new(), 8).unwrap();
+        let sweep = rounds_sweep(&booster, &dmat, Metric::Rmse).unwrap();
+        assert_eq!(sweep.scores.len(), 8);
+        assert_eq!(sweep.best_rounds, 8);
+        assert!(sweep.scores[0] > sweep.scores[7]);
+    }
+
+    #[test]
+    fn test_learning_curve() {
+        let data: Vec<f32> = (0..100).map(|i| i as f32).collect();
+        let dmat = DMatrix::try_from_data(&data, 50, 2).unwrap();
+        dmat.try_add_label(&(0..50).map(|i| i as f32).collect::<Vec<_>>())
+            .unwrap();
+        let curve = learning_curve(&TrainParams::new(), 5, &dmat, &[0.5, 1.0], 3).unwrap();
+        assert_eq!(curve.len(), 2);
+        assert!(curve[0].train_rows < curve[1].train_rows);
+        assert!(curve[1].validation.contains_key("rmse"));
+        assert!(learning_curve(&TrainParams::new(), 5, &dmat, &[0.0], 3).is_err());
+    }
+}
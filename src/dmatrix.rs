@@ -1,29 +1,340 @@
-use std::{ffi::CString, os::raw::c_void};
+use std::{
+    borrow::Cow,
+    ffi::{CStr, CString},
+    fmt, io,
+    os::raw::{c_char, c_int, c_void},
+};
 use thiserror::Error;
-use xgb_sys::{XGDMatrixCreateFromMat, XGDMatrixFree, XGDMatrixSetFloatInfo};
+
+use crate::handles::{self, HandleKind};
+use crate::instrument;
+use crate::memory;
+use crate::ranking::group_sizes_from_qid;
+use crate::stats::DataStats;
+use crate::weights::balanced_from_labels;
+use xgb_sys::{
+    DMatrixHandle, XGDMatrixCreateFromCSR, XGDMatrixCreateFromMat, XGDMatrixCreateFromMat_omp,
+    XGDMatrixFree, XGDMatrixGetDataAsCSR, XGDMatrixGetFloatInfo, XGDMatrixGetQuantileCut,
+    XGDMatrixGetStrFeatureInfo, XGDMatrixGetUIntInfo, XGDMatrixNumCol, XGDMatrixNumNonMissing,
+    XGDMatrixNumRow, XGDMatrixSetFloatInfo, XGDMatrixSetStrFeatureInfo, XGDMatrixSetUIntInfo,
+    XGDMatrixSliceDMatrixEx,
+};
 
 #[derive(Error, Debug)]
 pub enum DMatrixError {
     #[error("Cannot create DMatrix")]
     Create,
+    #[error("Cannot set {0}")]
+    SetInfo(String),
+    #[error("Cannot get {0}")]
+    GetInfo(String),
+    #[error("Cannot convert input: {0}")]
+    Convert(String),
+    #[error("Invalid argument: {0}")]
+    Argument(String),
+    #[error("Callback panicked: {0}")]
+    CallbackPanic(String),
+    #[error("Cannot free DMatrix")]
+    Free,
+    #[error("Batch source failed: {0}")]
+    Source(#[from] io::Error),
+    #[error("Invalid value {value} at row {row}, column {col}")]
+    InvalidValue { row: usize, col: usize, value: f32 },
+    #[error("Invalid label {value} at row {row}: {reason}")]
+    InvalidLabel {
+        row: usize,
+        value: i64,
+        reason: String,
+    },
+    #[error("Tracked memory of {used} bytes is above the limit of {limit} bytes")]
+    TrackedMemoryLimit { limit: u64, used: u64 },
+    #[error("Expected {expected} values for {field}, got {got}")]
+    Length {
+        field: String,
+        expected: u64,
+        got: usize,
+    },
+}
+
+/// What to do with infinite and subnormal inputs. NaN always means missing and is kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidValuePolicy {
+    /// Fail with [`DMatrixError::InvalidValue`] at the first offending cell.
+    #[default]
+    Error,
+    /// Infinities become the largest finite value of their sign, subnormals become zero.
+    Clamp,
+    /// Replace them with NaN.
+    TreatAsMissing,
+}
+
+/// Which integer labels [`DMatrix::try_add_int_labels`] accepts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelCheck {
+    /// Any integer an `f32` holds exactly, i.e. of magnitude at most 2^24.
+    Exact,
+    /// Class indices in `0..num_class`, as for `multi:softmax`, `multi:softprob` and,
+    /// with 2, `binary:logistic`.
+    Classes(u32),
+}
+
+/// Largest magnitude below which every integer is an exact `f32`.
+const MAX_EXACT_F32_INT: i64 = 1 << 24;
+
+/// Converts integer labels to `f32`, failing on the first one `check` rejects.
+fn int_labels_to_f32<T>(labels: &[T], check: LabelCheck) -> Result<Vec<f32>, DMatrixError>
+where
+    T: Copy + Into<i64>,
+{
+    labels
+        .iter()
+        .enumerate()
+        .map(|(row, label)| {
+            let value: i64 = (*label).into();
+            let reason = match check {
+                LabelCheck::Exact if value.unsigned_abs() > MAX_EXACT_F32_INT as u64 => {
+                    Some("not exactly representable as f32".to_string())
+                }
+                LabelCheck::Classes(num_class) if !(0..num_class as i64).contains(&value) => {
+                    Some(format!("not a class index below {}", num_class))
+                }
+                _ => None,
+            };
+            match reason {
+                Some(reason) => Err(DMatrixError::InvalidLabel { row, value, reason }),
+                None => Ok(value as f32),
+            }
+        })
+        .collect()
+}
+
+/// Applies `policy` to row-major `data` with `cols` columns, copying only if a value has
+/// to change.
+pub fn sanitize(
+    data: &[f32],
+    cols: usize,
+    policy: InvalidValuePolicy,
+) -> Result<Cow<'_, [f32]>, DMatrixError> {
+    let invalid = |v: &f32| v.is_infinite() || v.is_subnormal();
+    let Some(first) = data.iter().position(invalid) else {
+        return Ok(Cow::Borrowed(data));
+    };
+    if policy == InvalidValuePolicy::Error {
+        return Err(DMatrixError::InvalidValue {
+            row: first / cols.max(1),
+            col: first % cols.max(1),
+            value: data[first],
+        });
+    }
+    let mut owned = data.to_vec();
+    for value in owned[first..].iter_mut().filter(|v| invalid(v)) {
+        *value = match policy {
+            InvalidValuePolicy::Clamp if value.is_infinite() => f32::MAX.copysign(*value),
+            InvalidValuePolicy::Clamp => 0.0,
+            _ => f32::NAN,
+        };
+    }
+    Ok(Cow::Owned(owned))
+}
+
+/// Fails if `field` is set on some parts of a concatenation but not on others.
+fn check_all_or_none(field: &str, set: impl Iterator<Item = bool>) -> Result<(), DMatrixError> {
+    let (with, without): (Vec<bool>, Vec<bool>) = set.partition(|set| *set);
+    if with.is_empty() || without.is_empty() {
+        Ok(())
+    } else {
+        Err(DMatrixError::Argument(format!(
+            "{} is set on {} of {} parts",
+            field,
+            with.len(),
+            with.len() + without.len()
+        )))
+    }
+}
+
+/// Natural log of positive, finite exposures.
+fn log_exposure(exposure: &[f32]) -> Result<Vec<f32>, DMatrixError> {
+    exposure
+        .iter()
+        .map(|e| {
+            if *e > 0.0 && e.is_finite() {
+                Ok(e.ln())
+            } else {
+                Err(DMatrixError::Argument(format!(
+                    "exposure must be positive and finite, got {}",
+                    e
+                )))
+            }
+        })
+        .collect()
+}
+
+/// Compressed sparse row data: row `i` holds `values[indptr[i]..indptr[i + 1]]` at columns
+/// `indices[indptr[i]..indptr[i + 1]]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Csr {
+    pub indptr: Vec<u64>,
+    pub indices: Vec<u32>,
+    pub values: Vec<f32>,
+}
+
+impl Csr {
+    pub fn num_rows(&self) -> usize {
+        self.indptr.len().saturating_sub(1)
+    }
+
+    /// Checks that the arrays describe a matrix with `cols` columns: one value per index,
+    /// row offsets starting at 0, never decreasing and ending at the number of values,
+    /// and column indices below `cols`. XGBoost reads the arrays without these checks.
+    pub fn validate(&self, cols: u64) -> Result<(), DMatrixError> {
+        let invalid = |reason: &str| Err(DMatrixError::Convert(format!("invalid CSR: {}", reason)));
+        let nnz = self.values.len();
+        if self.indices.len() != nnz {
+            return invalid("indices and values differ in length");
+        }
+        if self.indptr.first().is_some_and(|p| *p != 0) {
+            return invalid("indptr does not start at 0");
+        }
+        if self.indptr.windows(2).any(|w| w[0] > w[1]) {
+            return invalid("indptr decreases");
+        }
+        if self.indptr.last().map_or(nnz != 0, |p| *p as usize != nnz) {
+            return invalid("indptr does not end at the number of values");
+        }
+        if let Some(col) = self.indices.iter().find(|c| **c as u64 >= cols) {
+            return invalid(&format!(
+                "column index {} out of range for {} columns",
+                col, cols
+            ));
+        }
+        Ok(())
+    }
+
+    /// [`sanitize`] for the stored values, copying only if a value has to change.
+    pub fn sanitize(&self, policy: InvalidValuePolicy) -> Result<Cow<'_, Csr>, DMatrixError> {
+        match sanitize(&self.values, 1, policy) {
+            Ok(Cow::Borrowed(_)) => Ok(Cow::Borrowed(self)),
+            Ok(Cow::Owned(values)) => Ok(Cow::Owned(Csr {
+                indptr: self.indptr.clone(),
+                indices: self.indices.clone(),
+                values,
+            })),
+            Err(DMatrixError::InvalidValue {
+                row: position,
+                value,
+                ..
+            }) => Err(DMatrixError::InvalidValue {
+                row: self.indptr.partition_point(|p| *p as usize <= position) - 1,
+                col: self.indices[position] as usize,
+                value,
+            }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// The given columns, in that order, renumbered from 0. Columns may repeat.
+    pub fn select_columns(&self, features: &[usize]) -> Csr {
+        let mut targets: Vec<Vec<u32>> = Vec::new();
+        for (new, old) in features.iter().enumerate() {
+            if targets.len() <= *old {
+                targets.resize(old + 1, Vec::new());
+            }
+            targets[*old].push(new as u32);
+        }
+        let mut out = Csr {
+            indptr: Vec::with_capacity(self.indptr.len()),
+            ..Default::default()
+        };
+        out.indptr.push(0);
+        let mut row: Vec<(u32, f32)> = Vec::new();
+        for w in self.indptr.windows(2) {
+            row.clear();
+            for i in w[0] as usize..w[1] as usize {
+                if let Some(news) = targets.get(self.indices[i] as usize) {
+                    row.extend(news.iter().map(|new| (*new, self.values[i])));
+                }
+            }
+            row.sort_unstable_by_key(|(new, _)| *new);
+            out.indices.extend(row.iter().map(|(new, _)| *new));
+            out.values.extend(row.iter().map(|(_, value)| *value));
+            out.indptr.push(out.values.len() as u64);
+        }
+        out
+    }
+
+    /// Row `row` with missing values as NaN, written to `out`.
+    pub(crate) fn dense_row(&self, row: usize, cols: usize, out: &mut Vec<f32>) {
+        out.clear();
+        out.resize(cols, f32::NAN);
+        let (begin, end) = (self.indptr[row] as usize, self.indptr[row + 1] as usize);
+        for (col, value) in self.indices[begin..end]
+            .iter()
+            .zip(&self.values[begin..end])
+        {
+            out[*col as usize] = *value;
+        }
+    }
+}
+
+/// Histogram bin boundaries per feature: feature `f` has upper bounds
+/// `values[indptr[f]..indptr[f + 1]]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuantileCuts {
+    pub indptr: Vec<u64>,
+    pub values: Vec<f32>,
+}
+
+impl QuantileCuts {
+    pub fn feature(&self, feature: usize) -> &[f32] {
+        &self.values[self.indptr[feature] as usize..self.indptr[feature + 1] as usize]
+    }
 }
 
 pub struct DMatrix {
     pub(crate) handle: *mut c_void,
     pub(crate) rows: u64,
-    _cols: u64,
+    pub(crate) cols: u64,
+    stats: Option<DataStats>,
+    /// Size reported to [`memory::memory_stats`].
+    bytes: u64,
 }
 
 impl DMatrix {
+    /// Takes ownership of a handle created by the C API.
+    pub(crate) fn from_handle(handle: *mut c_void, rows: u64, cols: u64) -> Self {
+        handles::register(HandleKind::DMatrix, handle);
+        let mut nnz: u64 = 0;
+        if unsafe { XGDMatrixNumNonMissing(handle, &mut nnz) } != 0 {
+            nnz = rows * cols;
+        }
+        let bytes = memory::dmatrix_bytes(rows, nnz);
+        memory::track_dmatrix(bytes);
+        DMatrix {
+            handle,
+            rows,
+            cols,
+            stats: None,
+            bytes,
+        }
+    }
+
+    /// Frees the matrix again if it took the tracked memory above
+    /// [`GlobalConfig::tracked_memory_limit`](crate::config::GlobalConfig::tracked_memory_limit).
+    pub(crate) fn within_tracked_limit(self) -> Result<Self, DMatrixError> {
+        match memory::over_limit() {
+            Some((limit, used)) => {
+                drop(self);
+                Err(DMatrixError::TrackedMemoryLimit { limit, used })
+            }
+            None => Ok(self),
+        }
+    }
+
     pub fn try_new() -> Result<Self, DMatrixError> {
         let mut handle: *mut c_void = std::ptr::null_mut();
         unsafe {
             if XGDMatrixCreateFromMat(std::ptr::null(), 0, 0, f32::NAN, &mut handle) == 0 {
-                Ok(DMatrix {
-                    handle,
-                    rows: 0,
-                    _cols: 0,
-                })
+                Ok(DMatrix::from_handle(handle, 0, 0))
             } else {
                 Err(DMatrixError::Create)
             }
@@ -32,32 +343,970 @@ impl DMatrix {
 
     pub fn try_from_data(data: &[f32], rows: u64, cols: u64) -> Result<Self, DMatrixError> {
         let mut handle: *mut c_void = std::ptr::null_mut();
-        unsafe {
+        instrument::traced("dmatrix", rows, || unsafe {
             if XGDMatrixCreateFromMat(data.as_ptr(), rows, cols, f32::NAN, &mut handle) == 0 {
-                Ok(DMatrix { handle, rows, _cols: cols })
+                DMatrix::from_handle(handle, rows, cols).within_tracked_limit()
             } else {
                 Err(DMatrixError::Create)
             }
+        })
+    }
+
+    /// Like [`DMatrix::try_from_data`], first applying `policy` to infinite and subnormal
+    /// values. See [`sanitize`].
+    pub fn try_from_data_checked(
+        data: &[f32],
+        rows: u64,
+        cols: u64,
+        policy: InvalidValuePolicy,
+    ) -> Result<Self, DMatrixError> {
+        let data = sanitize(data, cols as usize, policy)?;
+        DMatrix::try_from_data(&data, rows, cols)
+    }
+
+    /// Builds a sparse matrix with `cols` columns from CSR data. Absent entries are missing.
+    pub fn try_from_csr(csr: &Csr, cols: u64) -> Result<Self, DMatrixError> {
+        csr.validate(cols)?;
+        let rows = csr.num_rows();
+        let nnz = csr.values.len();
+        let indptr = array_interface(csr.indptr.as_ptr() as usize, "<u8", &[csr.indptr.len()]);
+        let indices = array_interface(csr.indices.as_ptr() as usize, "<u4", &[nnz]);
+        let values = array_interface(csr.values.as_ptr() as usize, "<f4", &[nnz]);
+        let config = CString::new("{\"missing\": NaN, \"nthread\": 0}").unwrap();
+        let mut handle: *mut c_void = std::ptr::null_mut();
+        instrument::traced("dmatrix", rows as u64, || unsafe {
+            if XGDMatrixCreateFromCSR(
+                indptr.as_ptr(),
+                indices.as_ptr(),
+                values.as_ptr(),
+                cols,
+                config.as_ptr(),
+                &mut handle,
+            ) == 0
+            {
+                DMatrix::from_handle(handle, rows as u64, cols).within_tracked_limit()
+            } else {
+                Err(DMatrixError::Create)
+            }
+        })
+    }
+
+    /// Stacks matrices with the same columns row-wise. Each part is copied out as CSR in
+    /// turn, so the data never needs to be concatenated as dense vectors. Labels, weights,
+    /// base margins and query groups are concatenated when every part has them, and fail
+    /// the call when only some do; feature names and types come from the first part.
+    pub fn concat(parts: &[&DMatrix]) -> Result<DMatrix, DMatrixError> {
+        let first = parts
+            .first()
+            .ok_or_else(|| DMatrixError::Argument("nothing to concatenate".to_string()))?;
+        if let Some(part) = parts.iter().find(|p| p.cols != first.cols) {
+            return Err(DMatrixError::Argument(format!(
+                "cannot concatenate {} and {} columns",
+                first.cols, part.cols
+            )));
+        }
+        let mut csr = Csr {
+            indptr: vec![0],
+            ..Default::default()
+        };
+        for part in parts {
+            let part_csr = part.to_csr()?;
+            let offset = csr.values.len() as u64;
+            csr.indptr
+                .extend(part_csr.indptr.iter().skip(1).map(|p| p + offset));
+            csr.indices.extend(part_csr.indices);
+            csr.values.extend(part_csr.values);
         }
+        let out = DMatrix::try_from_csr(&csr, first.cols)?;
+
+        for field in ["label", "weight", "base_margin"] {
+            let infos = parts
+                .iter()
+                .map(|p| p.get_float_info(field))
+                .collect::<Result<Vec<_>, _>>()?;
+            check_all_or_none(field, infos.iter().map(|i| !i.is_empty()))?;
+            if infos.iter().all(|i| !i.is_empty()) {
+                let joined = infos.concat();
+                out.set_float_info(field, &joined, joined.len() as u64)?;
+            }
+        }
+        let group_ptrs = parts
+            .iter()
+            .map(|p| p.get_group_ptr())
+            .collect::<Result<Vec<_>, _>>()?;
+        check_all_or_none("group", group_ptrs.iter().map(|g| g.len() > 1))?;
+        if group_ptrs.iter().all(|g| g.len() > 1) {
+            let sizes: Vec<u32> = group_ptrs
+                .iter()
+                .flat_map(|g| g.windows(2).map(|w| w[1] - w[0]))
+                .collect();
+            out.set_group(&sizes)?;
+        }
+        let names = first.feature_names()?;
+        if !names.is_empty() {
+            out.set_feature_names(&names.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        let types = first.feature_types()?;
+        if !types.is_empty() {
+            out.set_feature_types(&types.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        Ok(out)
     }
 
-    pub fn try_add_label(&self, data: &[f32]) -> Result<(), DMatrixError> {
-        let lab = CString::new("label").map_err(|_| DMatrixError::Create)?;
+    /// Like [`DMatrix::try_from_data`], using at most `nthread` threads to build the
+    /// matrix, `0` for all cores.
+    pub fn try_from_data_with_nthread(
+        data: &[f32],
+        rows: u64,
+        cols: u64,
+        nthread: u32,
+    ) -> Result<Self, DMatrixError> {
+        let mut handle: *mut c_void = std::ptr::null_mut();
+        instrument::traced("dmatrix", rows, || unsafe {
+            if XGDMatrixCreateFromMat_omp(
+                data.as_ptr(),
+                rows,
+                cols,
+                f32::NAN,
+                &mut handle,
+                nthread as c_int,
+            ) == 0
+            {
+                DMatrix::from_handle(handle, rows, cols).within_tracked_limit()
+            } else {
+                Err(DMatrixError::Create)
+            }
+        })
+    }
+
+    /// Like [`DMatrix::try_from_data`], also computing per-column [`DataStats`] while the
+    /// dense data is at hand.
+    pub fn try_from_data_with_stats(
+        data: &[f32],
+        rows: u64,
+        cols: u64,
+    ) -> Result<Self, DMatrixError> {
+        let mut dmat = Self::try_from_data(data, rows, cols)?;
+        dmat.stats = Some(DataStats::compute(data, rows, cols));
+        Ok(dmat)
+    }
+
+    /// Takes ownership of a matrix created elsewhere through the C API. The returned
+    /// `DMatrix` frees it when dropped. If reading its shape fails, the handle is not
+    /// freed and stays with the caller.
+    ///
+    /// # Safety
+    /// `handle` must be a live `DMatrixHandle` that nothing else frees from now on.
+    /// Passing the same handle twice leads to a double free.
+    pub unsafe fn from_raw_handle(handle: DMatrixHandle) -> Result<Self, DMatrixError> {
+        let (mut rows, mut cols) = (0, 0);
+        if XGDMatrixNumRow(handle, &mut rows) != 0 || XGDMatrixNumCol(handle, &mut cols) != 0 {
+            return Err(DMatrixError::GetInfo("shape".to_string()));
+        }
+        Ok(DMatrix::from_handle(handle, rows, cols))
+    }
+
+    /// Releases ownership of the handle without freeing it. The caller becomes
+    /// responsible for calling `XGDMatrixFree` on it exactly once.
+    pub fn into_raw_handle(mut self) -> DMatrixHandle {
+        let handle = std::mem::replace(&mut self.handle, std::ptr::null_mut());
+        handles::unregister(handle);
+        memory::untrack_dmatrix(self.bytes);
+        handle
+    }
+
+    /// The handle, still owned by this matrix, for C API calls this crate does not wrap.
+    /// It must not be freed and is only valid while the matrix is alive.
+    pub fn as_raw_handle(&self) -> DMatrixHandle {
+        self.handle
+    }
+
+    /// Frees the matrix, reporting a failure that dropping it would only log.
+    pub fn close(mut self) -> Result<(), DMatrixError> {
+        let handle = std::mem::replace(&mut self.handle, std::ptr::null_mut());
+        handles::unregister(handle);
+        memory::untrack_dmatrix(self.bytes);
         unsafe {
-            if XGDMatrixSetFloatInfo(self.handle, lab.as_ptr(), data.as_ptr(), self.rows) == 0 {
+            if XGDMatrixFree(handle) == 0 {
                 Ok(())
+            } else {
+                Err(DMatrixError::Free)
             }
-            else {
+        }
+    }
+
+    /// Column statistics, if the matrix was built with `try_from_data_with_stats`.
+    pub fn stats(&self) -> Option<&DataStats> {
+        self.stats.as_ref()
+    }
+
+    pub fn num_rows(&self) -> u64 {
+        self.rows
+    }
+
+    pub fn num_cols(&self) -> u64 {
+        self.cols
+    }
+
+    /// New matrix with the given rows, in that order. Labels, weights and base margins
+    /// follow the rows; query groups do not and must be set again on the result.
+    pub fn slice(&self, rows: &[i32]) -> Result<DMatrix, DMatrixError> {
+        if let Some(row) = rows.iter().find(|r| **r < 0 || **r as u64 >= self.rows) {
+            return Err(DMatrixError::Argument(format!(
+                "row {} out of range for {} rows",
+                row, self.rows
+            )));
+        }
+        let mut handle: *mut c_void = std::ptr::null_mut();
+        unsafe {
+            if XGDMatrixSliceDMatrixEx(
+                self.handle,
+                rows.as_ptr() as *const c_int,
+                rows.len() as u64,
+                &mut handle,
+                1,
+            ) == 0
+            {
+                DMatrix::from_handle(handle, rows.len() as u64, self.cols).within_tracked_limit()
+            } else {
                 Err(DMatrixError::Create)
             }
         }
     }
+
+    /// Dense matrix of the same shape holding `values`, with this matrix's labels,
+    /// weights, base margin, query groups and feature names and types. For re-predicting
+    /// on modified data, e.g. a shuffled or swept column, without losing what the model
+    /// and the metric need besides the features.
+    pub fn with_values(&self, values: &[f32]) -> Result<DMatrix, DMatrixError> {
+        if values.len() as u64 != self.rows * self.cols {
+            return Err(DMatrixError::Length {
+                field: "data".to_string(),
+                expected: self.rows * self.cols,
+                got: values.len(),
+            });
+        }
+        let out = DMatrix::try_from_data(values, self.rows, self.cols)?;
+        self.copy_row_info(&out)?;
+        let names = self.feature_names()?;
+        if !names.is_empty() {
+            out.set_feature_names(&names.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        let types = self.feature_types()?;
+        if !types.is_empty() {
+            out.set_feature_types(&types.iter().map(String::as_str).collect::<Vec<_>>())?;
+        }
+        Ok(out)
+    }
+
+    /// Matrix of the given columns, in that order, with this matrix's labels, weights,
+    /// base margin, query groups and the selected features' names and types. Missing
+    /// values stay missing.
+    pub fn select_columns(&self, features: &[usize]) -> Result<DMatrix, DMatrixError> {
+        self.select_columns_of(&self.to_csr()?, features)
+    }
+
+    /// [`DMatrix::select_columns`] from `csr`, this matrix's data already copied out, for
+    /// callers selecting repeatedly.
+    pub(crate) fn select_columns_of(
+        &self,
+        csr: &Csr,
+        features: &[usize],
+    ) -> Result<DMatrix, DMatrixError> {
+        if let Some(f) = features.iter().find(|f| **f as u64 >= self.cols) {
+            return Err(DMatrixError::Argument(format!(
+                "column {} out of range for {} columns",
+                f, self.cols
+            )));
+        }
+        let out = DMatrix::try_from_csr(&csr.select_columns(features), features.len() as u64)?;
+        self.copy_row_info(&out)?;
+        for (field, values) in [
+            ("feature_name", self.feature_names()?),
+            ("feature_type", self.feature_types()?),
+        ] {
+            if !values.is_empty() {
+                let selected: Vec<&str> = features.iter().map(|f| values[*f].as_str()).collect();
+                out.set_str_info(field, &selected)?;
+            }
+        }
+        Ok(out)
+    }
+
+    /// Copies labels, weights, base margin and query groups to `out`, which has the same
+    /// rows.
+    fn copy_row_info(&self, out: &DMatrix) -> Result<(), DMatrixError> {
+        for field in ["label", "weight", "base_margin"] {
+            let info = self.get_float_info(field)?;
+            if !info.is_empty() {
+                out.set_float_info(field, &info, info.len() as u64)?;
+            }
+        }
+        let group_ptr = self.get_group_ptr()?;
+        if group_ptr.len() > 1 {
+            let sizes: Vec<u32> = group_ptr.windows(2).map(|w| w[1] - w[0]).collect();
+            out.set_group(&sizes)?;
+        }
+        Ok(())
+    }
+
+    /// Number of stored, non-missing values.
+    pub fn num_non_missing(&self) -> Result<u64, DMatrixError> {
+        let mut nnz: u64 = 0;
+        unsafe {
+            if XGDMatrixNumNonMissing(self.handle, &mut nnz) == 0 {
+                Ok(nnz)
+            } else {
+                Err(DMatrixError::GetInfo("num_non_missing".to_string()))
+            }
+        }
+    }
+
+    /// Quantile cuts computed by XGBoost, available on quantile matrices and on matrices
+    /// already used for `hist` training.
+    pub fn quantile_cuts(&self) -> Result<QuantileCuts, DMatrixError> {
+        let config = CString::new("{}").unwrap();
+        let mut out_indptr: *const c_char = std::ptr::null();
+        let mut out_data: *const c_char = std::ptr::null();
+        unsafe {
+            if XGDMatrixGetQuantileCut(self.handle, config.as_ptr(), &mut out_indptr, &mut out_data)
+                != 0
+            {
+                return Err(DMatrixError::GetInfo("quantile cuts".to_string()));
+            }
+            let indptr = CStr::from_ptr(out_indptr).to_string_lossy();
+            let data = CStr::from_ptr(out_data).to_string_lossy();
+            Ok(QuantileCuts {
+                indptr: read_array_interface(&indptr, "<u8")?,
+                values: read_array_interface(&data, "<f4")?,
+            })
+        }
+    }
+
+    /// Copies the stored values out as CSR.
+    pub fn to_csr(&self) -> Result<Csr, DMatrixError> {
+        let nnz = self.num_non_missing()?;
+        let config = CString::new("{}").unwrap();
+        unsafe {
+            let mut indptr = vec![0u64; self.rows as usize + 1];
+            let mut indices = vec![0u32; nnz as usize];
+            let mut values = vec![0f32; nnz as usize];
+            if XGDMatrixGetDataAsCSR(
+                self.handle,
+                config.as_ptr(),
+                indptr.as_mut_ptr(),
+                indices.as_mut_ptr(),
+                values.as_mut_ptr(),
+            ) != 0
+            {
+                return Err(DMatrixError::GetInfo("data".to_string()));
+            }
+            Ok(Csr {
+                indptr,
+                indices,
+                values,
+            })
+        }
+    }
+
+    /// Copies the stored values out as a row-major dense vector, with NaN for missing.
+    pub fn to_dense(&self) -> Result<Vec<f32>, DMatrixError> {
+        let csr = self.to_csr()?;
+        let cols = self.cols as usize;
+        let mut dense = vec![f32::NAN; self.rows as usize * cols];
+        for (row, bounds) in csr.indptr.windows(2).enumerate() {
+            for k in bounds[0] as usize..bounds[1] as usize {
+                dense[row * cols + csr.indices[k] as usize] = csr.values[k];
+            }
+        }
+        Ok(dense)
+    }
+
+    pub fn try_add_label(&self, data: &[f32]) -> Result<(), DMatrixError> {
+        self.set_float_info("label", data, self.rows)
+    }
+
+    /// Sets integer labels, e.g. `&[u32]` or `&[i64]` class indices, after checking each
+    /// against `check`, so out-of-range classes fail here rather than during training.
+    pub fn try_add_int_labels<T>(&self, labels: &[T], check: LabelCheck) -> Result<(), DMatrixError>
+    where
+        T: Copy + Into<i64>,
+    {
+        self.set_float_info("label", &int_labels_to_f32(labels, check)?, self.rows)
+    }
+
+    /// Sets the labels from an iterator, e.g. a database cursor, without collecting them
+    /// first. XGBoost keeps its own copy, so they are written once into a buffer of the
+    /// announced length; a wrong length fails before the iterator is consumed.
+    pub fn try_add_label_iter<I>(&self, labels: I) -> Result<(), DMatrixError>
+    where
+        I: IntoIterator<Item = f32>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let labels = collect_exact("label", labels, self.rows)?;
+        self.set_float_info("label", &labels, self.rows)
+    }
+
+    pub fn get_labels(&self) -> Result<Vec<f32>, DMatrixError> {
+        self.get_float_info("label")
+    }
+
+    /// Sets the starting margin of every row, one value per row and output group in
+    /// row-major order. Boosting then starts from these instead of `base_score`.
+    pub fn set_base_margin(&self, margin: &[f32]) -> Result<(), DMatrixError> {
+        if self.rows == 0 || !(margin.len() as u64).is_multiple_of(self.rows) {
+            return Err(DMatrixError::Length {
+                field: "base_margin".to_string(),
+                expected: self.rows,
+                got: margin.len(),
+            });
+        }
+        self.set_float_info("base_margin", margin, margin.len() as u64)
+    }
+
+    /// Removes the base margin, so predictions start from the model's `base_score` again.
+    pub fn clear_base_margin(&self) -> Result<(), DMatrixError> {
+        self.set_float_info("base_margin", &[], 0)
+    }
+
+    pub fn get_base_margin(&self) -> Result<Vec<f32>, DMatrixError> {
+        self.get_float_info("base_margin")
+    }
+
+    /// Sets `ln(exposure)` as the base margin, the usual offset of log-link objectives
+    /// such as `count:poisson`, `reg:gamma` and `reg:tweedie`: the model then predicts
+    /// `exposure * rate`. Data scored later needs its exposure set the same way.
+    pub fn set_exposure(&self, exposure: &[f32]) -> Result<(), DMatrixError> {
+        if exposure.len() as u64 != self.rows {
+            return Err(DMatrixError::Length {
+                field: "exposure".to_string(),
+                expected: self.rows,
+                got: exposure.len(),
+            });
+        }
+        self.set_base_margin(&log_exposure(exposure)?)
+    }
+
+    /// Sets one weight per row. For ranking, XGBoost expects one weight per group instead.
+    pub fn set_weights(&self, weights: &[f32]) -> Result<(), DMatrixError> {
+        let expected = match self.get_group_ptr()?.len() {
+            0 | 1 => self.rows,
+            n => n as u64 - 1,
+        };
+        self.set_float_info("weight", weights, expected)
+    }
+
+    /// [`DMatrix::set_weights`] from an iterator, see [`DMatrix::try_add_label_iter`].
+    pub fn set_weights_iter<I>(&self, weights: I) -> Result<(), DMatrixError>
+    where
+        I: IntoIterator<Item = f32>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let expected = match self.get_group_ptr()?.len() {
+            0 | 1 => self.rows,
+            n => n as u64 - 1,
+        };
+        self.set_float_info(
+            "weight",
+            &collect_exact("weight", weights, expected)?,
+            expected,
+        )
+    }
+
+    pub fn get_weights(&self) -> Result<Vec<f32>, DMatrixError> {
+        self.get_float_info("weight")
+    }
+
+    /// Sets weights balancing the classes of the labels, see
+    /// [`weights::balanced_from_labels`](crate::weights::balanced_from_labels).
+    pub fn set_balanced_weights(&self) -> Result<(), DMatrixError> {
+        self.set_weights(&balanced_from_labels(&self.get_labels()?))
+    }
+
+    /// Sets query groups for ranking, given as the size of each consecutive group of rows.
+    pub fn set_group(&self, group_sizes: &[u32]) -> Result<(), DMatrixError> {
+        let total: u64 = group_sizes.iter().map(|s| *s as u64).sum();
+        if total != self.rows {
+            return Err(DMatrixError::Length {
+                field: "group".to_string(),
+                expected: self.rows,
+                got: total as usize,
+            });
+        }
+        if group_sizes.contains(&0) {
+            return Err(DMatrixError::Argument("empty query group".to_string()));
+        }
+        let c_field =
+            CString::new("group").map_err(|_| DMatrixError::SetInfo("group".to_string()))?;
+        unsafe {
+            if XGDMatrixSetUIntInfo(
+                self.handle,
+                c_field.as_ptr(),
+                group_sizes.as_ptr(),
+                group_sizes.len() as u64,
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(DMatrixError::SetInfo("group".to_string()))
+            }
+        }
+    }
+
+    /// Returns group boundaries as offsets into the rows, `[0, end_0, end_1, ...]`.
+    /// Empty if no groups were set.
+    pub fn get_group_ptr(&self) -> Result<Vec<u32>, DMatrixError> {
+        let c_field = CString::new("group_ptr")
+            .map_err(|_| DMatrixError::GetInfo("group_ptr".to_string()))?;
+        let mut out_len: u64 = 0;
+        let mut out_ptr: *const u32 = std::ptr::null();
+        unsafe {
+            if XGDMatrixGetUIntInfo(self.handle, c_field.as_ptr(), &mut out_len, &mut out_ptr) == 0
+            {
+                if out_len == 0 {
+                    Ok(Vec::new())
+                } else {
+                    Ok(std::slice::from_raw_parts(out_ptr, out_len as usize).to_vec())
+                }
+            } else {
+                Err(DMatrixError::GetInfo("group_ptr".to_string()))
+            }
+        }
+    }
+
+    /// Sets query groups from a per-row query id, whose rows must be contiguous. See
+    /// [`ranking::sort_by_qid`](crate::ranking::sort_by_qid) to reorder rows first.
+    pub fn set_group_from_qid(&self, qids: &[u32]) -> Result<(), DMatrixError> {
+        if qids.len() as u64 != self.rows {
+            return Err(DMatrixError::Length {
+                field: "qid".to_string(),
+                expected: self.rows,
+                got: qids.len(),
+            });
+        }
+        let sizes =
+            group_sizes_from_qid(qids).map_err(|e| DMatrixError::Argument(e.to_string()))?;
+        self.set_group(&sizes)
+    }
+
+    /// Sets per-feature sampling weights used by `colsample_bytree`, `colsample_bylevel`
+    /// and `colsample_bynode`. Needs one non-negative weight per column.
+    pub fn set_feature_weights(&self, weights: &[f32]) -> Result<(), DMatrixError> {
+        if let Some((index, weight)) = weights
+            .iter()
+            .enumerate()
+            .find(|(_, w)| **w < 0.0 || w.is_nan())
+        {
+            return Err(DMatrixError::Argument(format!(
+                "feature weight {} is {}, expected a non-negative number",
+                index, weight
+            )));
+        }
+        self.set_float_info("feature_weights", weights, self.cols)
+    }
+
+    /// Per-feature sampling weights, empty if none were set.
+    pub fn get_feature_weights(&self) -> Result<Vec<f32>, DMatrixError> {
+        self.get_float_info("feature_weights")
+    }
+
+    pub fn set_feature_names(&self, names: &[&str]) -> Result<(), DMatrixError> {
+        self.set_str_info("feature_name", names)
+    }
+
+    /// Feature names, empty if none were set.
+    pub fn feature_names(&self) -> Result<Vec<String>, DMatrixError> {
+        self.get_str_info("feature_name")
+    }
+
+    /// Sets XGBoost feature types: `"q"` or `"float"` for numerical, `"int"`, `"i"` for
+    /// indicators and `"c"` for categorical features.
+    pub fn set_feature_types(&self, types: &[&str]) -> Result<(), DMatrixError> {
+        self.set_str_info("feature_type", types)
+    }
+
+    pub fn feature_types(&self) -> Result<Vec<String>, DMatrixError> {
+        self.get_str_info("feature_type")
+    }
+
+    fn set_str_info(&self, field: &str, values: &[&str]) -> Result<(), DMatrixError> {
+        if values.len() as u64 != self.cols {
+            return Err(DMatrixError::Length {
+                field: field.to_string(),
+                expected: self.cols,
+                got: values.len(),
+            });
+        }
+        let c_field = CString::new(field).map_err(|_| DMatrixError::SetInfo(field.to_string()))?;
+        let c_values = values
+            .iter()
+            .map(|v| CString::new(*v))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| DMatrixError::SetInfo(field.to_string()))?;
+        let mut ptrs: Vec<*const c_char> = c_values.iter().map(|v| v.as_ptr()).collect();
+        unsafe {
+            if XGDMatrixSetStrFeatureInfo(
+                self.handle,
+                c_field.as_ptr(),
+                ptrs.as_mut_ptr(),
+                ptrs.len() as u64,
+            ) == 0
+            {
+                Ok(())
+            } else {
+                Err(DMatrixError::SetInfo(field.to_string()))
+            }
+        }
+    }
+
+    fn get_str_info(&self, field: &str) -> Result<Vec<String>, DMatrixError> {
+        let c_field = CString::new(field).map_err(|_| DMatrixError::GetInfo(field.to_string()))?;
+        let mut size: u64 = 0;
+        let mut out: *mut *const c_char = std::ptr::null_mut();
+        unsafe {
+            if XGDMatrixGetStrFeatureInfo(self.handle, c_field.as_ptr(), &mut size, &mut out) != 0 {
+                return Err(DMatrixError::GetInfo(field.to_string()));
+            }
+            if size == 0 {
+                return Ok(Vec::new());
+            }
+            Ok(std::slice::from_raw_parts(out, size as usize)
+                .iter()
+                .map(|p| CStr::from_ptr(*p).to_string_lossy().into_owned())
+                .collect())
+        }
+    }
+
+    fn get_float_info(&self, field: &str) -> Result<Vec<f32>, DMatrixError> {
+        let c_field = CString::new(field).map_err(|_| DMatrixError::GetInfo(field.to_string()))?;
+        let mut out_len: u64 = 0;
+        let mut out_ptr: *const f32 = std::ptr::null();
+        unsafe {
+            if XGDMatrixGetFloatInfo(self.handle, c_field.as_ptr(), &mut out_len, &mut out_ptr) == 0
+            {
+                if out_len == 0 {
+                    Ok(Vec::new())
+                } else {
+                    Ok(std::slice::from_raw_parts(out_ptr, out_len as usize).to_vec())
+                }
+            } else {
+                Err(DMatrixError::GetInfo(field.to_string()))
+            }
+        }
+    }
+
+    fn set_float_info(&self, field: &str, data: &[f32], expected: u64) -> Result<(), DMatrixError> {
+        if data.len() as u64 != expected {
+            return Err(DMatrixError::Length {
+                field: field.to_string(),
+                expected,
+                got: data.len(),
+            });
+        }
+        let c_field = CString::new(field).map_err(|_| DMatrixError::SetInfo(field.to_string()))?;
+        unsafe {
+            if XGDMatrixSetFloatInfo(self.handle, c_field.as_ptr(), data.as_ptr(), expected) == 0 {
+                Ok(())
+            } else {
+                Err(DMatrixError::SetInfo(field.to_string()))
+            }
+        }
+    }
+}
+
+/// `__array_interface__` JSON for a borrowed read-only buffer at `ptr`, as taken by the
+/// C API functions accepting array interfaces.
+pub(crate) fn array_interface(ptr: usize, typestr: &str, shape: &[usize]) -> CString {
+    let shape: Vec<String> = shape.iter().map(|d| d.to_string()).collect();
+    CString::new(format!(
+        "{{\"data\": [{}, true], \"shape\": [{}], \"typestr\": \"{}\", \"version\": 3}}",
+        ptr,
+        shape.join(", "),
+        typestr
+    ))
+    .unwrap()
+}
+
+/// Collects exactly `expected` values of `field`, checking the announced length before
+/// consuming anything and the actual one after.
+fn collect_exact<I>(field: &str, values: I, expected: u64) -> Result<Vec<f32>, DMatrixError>
+where
+    I: IntoIterator<Item = f32>,
+    I::IntoIter: ExactSizeIterator,
+{
+    let length_error = |got| DMatrixError::Length {
+        field: field.to_string(),
+        expected,
+        got,
+    };
+    let values = values.into_iter();
+    if values.len() as u64 != expected {
+        return Err(length_error(values.len()));
+    }
+    let mut buffer = Vec::with_capacity(expected as usize);
+    buffer.extend(values);
+    if buffer.len() as u64 != expected {
+        return Err(length_error(buffer.len()));
+    }
+    Ok(buffer)
+}
+
+/// Copies the 1-D array described by an `__array_interface__` JSON returned by XGBoost.
+///
+/// # Safety
+/// The described buffer must be alive and hold elements of type `T` matching `typestr`.
+unsafe fn read_array_interface<T: Copy>(json: &str, typestr: &str) -> Result<Vec<T>, DMatrixError> {
+    let invalid = || DMatrixError::Convert(format!("invalid array interface {}", json));
+    let value: serde_json::Value = serde_json::from_str(json).map_err(|_| invalid())?;
+    if value["typestr"].as_str() != Some(typestr) {
+        return Err(invalid());
+    }
+    let ptr = value["data"][0].as_u64().ok_or_else(invalid)? as usize as *const T;
+    let len = value["shape"][0].as_u64().ok_or_else(invalid)? as usize;
+    if len == 0 {
+        return Ok(Vec::new());
+    }
+    Ok(std::slice::from_raw_parts(ptr, len).to_vec())
+}
+
+impl fmt::Debug for DMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("DMatrix");
+        debug.field("rows", &self.rows).field("cols", &self.cols);
+        match self.feature_names() {
+            Ok(names) => debug.field("num_feature_names", &names.len()),
+            Err(_) => debug.field("num_feature_names", &"<unavailable>"),
+        };
+        debug.field("stats", &self.stats.is_some()).finish()
+    }
+}
+
+impl fmt::Display for DMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DMatrix({} rows x {} cols", self.rows, self.cols)?;
+        match self.feature_names() {
+            Ok(names) if !names.is_empty() => write!(f, ", {} named)", names.len()),
+            _ => write!(f, ")"),
+        }
+    }
 }
 
 impl Drop for DMatrix {
     fn drop(&mut self) {
+        if self.handle.is_null() {
+            return;
+        }
+        handles::unregister(self.handle);
+        memory::untrack_dmatrix(self.bytes);
         unsafe {
-            XGDMatrixFree(self.handle);
+            if XGDMatrixFree(self.handle) != 0 {
+                instrument::warn("XGDMatrixFree failed while dropping a DMatrix");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_handle_roundtrip() {
+        let dmat = DMatrix::try_from_data(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3, 2).unwrap();
+        let handle = dmat.into_raw_handle();
+        let dmat = unsafe { DMatrix::from_raw_handle(handle) }.unwrap();
+        assert_eq!(dmat.as_raw_handle(), handle);
+        assert_eq!((dmat.rows, dmat.cols), (3, 2));
+        assert!(dmat.close().is_ok());
+    }
+
+    #[test]
+    fn test_no_leaked_handles() {
+        crate::handles::assert_no_leaks(|| {
+            let dmat = DMatrix::try_from_data(&[1.0, 2.0, 3.0, 4.0], 2, 2).unwrap();
+            let sliced = dmat.slice(&[1]).unwrap();
+            drop(dmat);
+            assert!(sliced.close().is_ok());
+        });
+    }
+
+    #[test]
+    fn test_with_values_keeps_info() {
+        let dmat = DMatrix::try_from_data(&[1.0, 2.0, 3.0, 4.0], 2, 2).unwrap();
+        dmat.try_add_label(&[0.0, 1.0]).unwrap();
+        dmat.set_weights(&[0.5, 2.0]).unwrap();
+        dmat.set_base_margin(&[0.1, 0.2]).unwrap();
+        dmat.set_feature_names(&["a", "b"]).unwrap();
+        dmat.set_feature_types(&["q", "c"]).unwrap();
+        let copy = dmat.with_values(&[4.0, 3.0, 2.0, 1.0]).unwrap();
+        assert_eq!(copy.to_dense().unwrap(), vec![4.0, 3.0, 2.0, 1.0]);
+        assert_eq!(copy.get_labels().unwrap(), vec![0.0, 1.0]);
+        assert_eq!(copy.get_weights().unwrap(), vec![0.5, 2.0]);
+        assert_eq!(copy.get_base_margin().unwrap(), vec![0.1, 0.2]);
+        assert_eq!(copy.feature_names().unwrap(), vec!["a", "b"]);
+        assert_eq!(copy.feature_types().unwrap(), vec!["q", "c"]);
+        assert!(matches!(
+            dmat.with_values(&[1.0]),
+            Err(DMatrixError::Length { .. })
+        ));
+    }
+
+    #[test]
+    fn test_csr_validate_and_sanitize() {
+        let csr = Csr {
+            indptr: vec![0, 2, 3],
+            indices: vec![0, 2, 1],
+            values: vec![1.0, f32::INFINITY, 3.0],
+        };
+        assert!(csr.validate(3).is_ok());
+        assert!(csr.validate(2).is_err());
+        let mut bad = csr.clone();
+        bad.indptr = vec![1, 2, 3];
+        assert!(bad.validate(3).is_err());
+        bad.indptr = vec![0, 3, 2];
+        assert!(bad.validate(3).is_err());
+        bad.indptr = vec![0, 2, 4];
+        assert!(bad.validate(3).is_err());
+
+        match csr.sanitize(InvalidValuePolicy::Error) {
+            Err(DMatrixError::InvalidValue { row, col, .. }) => assert_eq!((row, col), (0, 2)),
+            other => panic!("unexpected {:?}", other),
+        }
+        let clamped = csr.sanitize(InvalidValuePolicy::Clamp).unwrap();
+        assert_eq!(clamped.values, vec![1.0, f32::MAX, 3.0]);
+        let mut row = Vec::new();
+        clamped.dense_row(1, 3, &mut row);
+        assert!(row[0].is_nan() && row[2].is_nan());
+        assert_eq!(row[1], 3.0);
+    }
+
+    #[test]
+    fn test_csr_select_columns() {
+        // [1, _, 2]
+        // [_, 3, _]
+        let csr = Csr {
+            indptr: vec![0, 2, 3],
+            indices: vec![0, 2, 1],
+            values: vec![1.0, 2.0, 3.0],
+        };
+        let selected = csr.select_columns(&[2, 0, 2]);
+        assert_eq!(selected.indptr, vec![0, 3, 3]);
+        assert_eq!(selected.indices, vec![0, 1, 2]);
+        assert_eq!(selected.values, vec![2.0, 1.0, 2.0]);
+        assert_eq!(csr.select_columns(&[1]).values, vec![3.0]);
+    }
+
+    #[test]
+    fn test_concat() {
+        let a = DMatrix::try_from_data(&[1.0, 2.0, 3.0, 4.0], 2, 2).unwrap();
+        let b = DMatrix::try_from_data(&[5.0, 6.0], 1, 2).unwrap();
+        a.try_add_label(&[0.0, 1.0]).unwrap();
+        b.try_add_label(&[1.0]).unwrap();
+        let joined = DMatrix::concat(&[&a, &b]).unwrap();
+        assert_eq!(joined.num_rows(), 3);
+        assert_eq!(joined.get_labels().unwrap(), vec![0.0, 1.0, 1.0]);
+        assert!(joined.get_weights().unwrap().is_empty());
+
+        // Weights on only one part would silently be lost
+        a.set_weights(&[1.0, 2.0]).unwrap();
+        assert!(matches!(
+            DMatrix::concat(&[&a, &b]),
+            Err(DMatrixError::Argument(_))
+        ));
+        let narrow = DMatrix::try_from_data(&[1.0, 2.0, 3.0], 3, 1).unwrap();
+        assert!(matches!(
+            DMatrix::concat(&[&b, &narrow]),
+            Err(DMatrixError::Argument(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_all_or_none() {
+        assert!(check_all_or_none("label", [true, true].into_iter()).is_ok());
+        assert!(check_all_or_none("label", [false, false].into_iter()).is_ok());
+        assert!(check_all_or_none("label", [true, false].into_iter()).is_err());
+    }
+
+    #[test]
+    fn test_log_exposure() {
+        let margin = log_exposure(&[1.0, std::f32::consts::E]).unwrap();
+        assert_eq!(margin[0], 0.0);
+        assert!((margin[1] - 1.0).abs() < 1e-6);
+        assert!(log_exposure(&[0.0]).is_err());
+        assert!(log_exposure(&[f32::NAN]).is_err());
+    }
+
+    #[test]
+    fn test_feature_weights() {
+        let dmat = DMatrix::try_from_data(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2, 3).unwrap();
+        assert!(dmat.get_feature_weights().unwrap().is_empty());
+        dmat.set_feature_weights(&[0.5, 1.0, 2.0]).unwrap();
+        assert_eq!(dmat.get_feature_weights().unwrap(), vec![0.5, 1.0, 2.0]);
+        assert!(matches!(
+            dmat.set_feature_weights(&[1.0, 1.0]),
+            Err(DMatrixError::Length {
+                expected: 3,
+                got: 2,
+                ..
+            })
+        ));
+        for bad in [-1.0, f32::NAN] {
+            match dmat.set_feature_weights(&[1.0, bad, 1.0]) {
+                Err(DMatrixError::Argument(message)) => {
+                    assert!(message.starts_with(&format!("feature weight 1 is {}", bad)))
+                }
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        // Rejected weights leave the previous ones in place
+        assert_eq!(dmat.get_feature_weights().unwrap(), vec![0.5, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_int_labels() {
+        let labels = int_labels_to_f32(&[0u32, 2, 1], LabelCheck::Classes(3)).unwrap();
+        assert_eq!(labels, vec![0.0, 2.0, 1.0]);
+        match int_labels_to_f32(&[0i64, -1], LabelCheck::Classes(3)) {
+            Err(DMatrixError::InvalidLabel { row, value, .. }) => assert_eq!((row, value), (1, -1)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(int_labels_to_f32(&[3u32], LabelCheck::Classes(3)).is_err());
+        assert_eq!(
+            int_labels_to_f32(&[-(1i64 << 24)], LabelCheck::Exact).unwrap(),
+            vec![-16777216.0]
+        );
+        assert!(int_labels_to_f32(&[(1i64 << 24) + 1], LabelCheck::Exact).is_err());
+        assert!(int_labels_to_f32(&[i64::MIN], LabelCheck::Exact).is_err());
+    }
+
+    #[test]
+    fn test_collect_exact() {
+        let labels = collect_exact("label", (0..3).map(|i| i as f32), 3).unwrap();
+        assert_eq!(labels, vec![0.0, 1.0, 2.0]);
+        match collect_exact("label", vec![1.0; 2], 3) {
+            Err(DMatrixError::Length { expected, got, .. }) => assert_eq!((expected, got), (3, 2)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_sanitize() {
+        let data = [1.0, f32::INFINITY, f32::NAN, -f32::INFINITY, 1e-40, 2.0];
+        match sanitize(&data, 3, InvalidValuePolicy::Error) {
+            Err(DMatrixError::InvalidValue { row, col, value }) => {
+                assert_eq!((row, col, value), (0, 1, f32::INFINITY))
+            }
+            other => panic!("unexpected {:?}", other),
         }
+        let clamped = sanitize(&data, 3, InvalidValuePolicy::Clamp).unwrap();
+        assert_eq!(clamped[1], f32::MAX);
+        assert!(clamped[2].is_nan());
+        assert_eq!(&clamped[3..], &[f32::MIN, 0.0, 2.0]);
+        let missing = sanitize(&data, 3, InvalidValuePolicy::TreatAsMissing).unwrap();
+        assert_eq!(missing.iter().filter(|v| v.is_nan()).count(), 4);
+        assert!(matches!(
+            sanitize(&[1.0, 2.0], 2, InvalidValuePolicy::Error).unwrap(),
+            Cow::Borrowed(_)
+        ));
     }
 }
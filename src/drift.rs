@@ -0,0 +1,162 @@
+//! Distribution drift between a reference (training) matrix and current (serving) data.
+
+use crate::dmatrix::{DMatrix, DMatrixError};
+
+/// Smoothing for empty bins, so PSI stays finite.
+const PSI_EPS: f64 = 1e-4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureDrift {
+    pub feature: usize,
+    pub name: String,
+    /// Population stability index over the reference bins plus a missing-value bin.
+    /// Common rules of thumb: below 0.1 stable, above 0.25 significant drift.
+    pub psi: f64,
+    /// Two-sample Kolmogorov-Smirnov statistic over non-missing values.
+    pub ks: f64,
+    pub reference_missing: f64,
+    pub current_missing: f64,
+}
+
+/// Per-feature drift of `current` against `reference`. Bins are XGBoost's quantile cuts
+/// of `reference` when available (quantile matrices, or matrices used for `hist`
+/// training), otherwise deciles of the reference values.
+pub fn compare(reference: &DMatrix, current: &DMatrix) -> Result<Vec<FeatureDrift>, DMatrixError> {
+    let cols = reference.num_cols() as usize;
+    if current.num_cols() as usize != cols {
+        return Err(DMatrixError::Argument(format!(
+            "reference has {} columns, current has {}",
+            cols,
+            current.num_cols()
+        )));
+    }
+    let ref_dense = reference.to_dense()?;
+    let cur_dense = current.to_dense()?;
+    let cuts = reference.quantile_cuts().ok();
+    let names = reference.feature_names()?;
+    Ok((0..cols)
+        .map(|feature| {
+            let r: Vec<f32> = ref_dense
+                .iter()
+                .skip(feature)
+                .step_by(cols)
+                .copied()
+                .collect();
+            let c: Vec<f32> = cur_dense
+                .iter()
+                .skip(feature)
+                .step_by(cols)
+                .copied()
+                .collect();
+            let bounds = match &cuts {
+                Some(cuts) => cuts.feature(feature).to_vec(),
+                None => decile_bounds(&r),
+            };
+            FeatureDrift {
+                feature,
+                name: names
+                    .get(feature)
+                    .cloned()
+                    .unwrap_or_else(|| format!("f{}", feature)),
+                psi: psi(&histogram(&r, &bounds), &histogram(&c, &bounds)),
+                ks: ks(&r, &c),
+                reference_missing: missing_ratio(&r),
+                current_missing: missing_ratio(&c),
+            }
+        })
+        .collect())
+}
+
+/// Interior decile boundaries of the non-missing values, deduplicated.
+fn decile_bounds(values: &[f32]) -> Vec<f32> {
+    let mut sorted: Vec<f32> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+    if sorted.is_empty() {
+        return Vec::new();
+    }
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mut bounds: Vec<f32> = (1..10)
+        .map(|i| sorted[(sorted.len() - 1) * i / 10])
+        .collect();
+    bounds.dedup();
+    bounds
+}
+
+/// Fraction of values per bin: bin `i` holds values below `bounds[i]`, then one bin for
+/// values above all bounds and a last one for missing values.
+fn histogram(values: &[f32], bounds: &[f32]) -> Vec<f64> {
+    let mut counts = vec![0usize; bounds.len() + 2];
+    for v in values {
+        let bin = if v.is_nan() {
+            bounds.len() + 1
+        } else {
+            bounds.partition_point(|b| b <= v)
+        };
+        counts[bin] += 1;
+    }
+    let n = values.len().max(1) as f64;
+    counts.into_iter().map(|c| c as f64 / n).collect()
+}
+
+fn psi(reference: &[f64], current: &[f64]) -> f64 {
+    reference
+        .iter()
+        .zip(current)
+        .map(|(r, c)| {
+            let (r, c) = (r.max(PSI_EPS), c.max(PSI_EPS));
+            (c - r) * (c / r).ln()
+        })
+        .sum()
+}
+
+fn ks(reference: &[f32], current: &[f32]) -> f64 {
+    let sorted = |values: &[f32]| {
+        let mut v: Vec<f32> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        v.sort_by(|a, b| a.total_cmp(b));
+        v
+    };
+    let (a, b) = (sorted(reference), sorted(current));
+    if a.is_empty() || b.is_empty() {
+        return f64::NAN;
+    }
+    let (mut i, mut j, mut d) = (0, 0, 0.0f64);
+    while i < a.len() && j < b.len() {
+        let x = a[i].min(b[j]);
+        while i < a.len() && a[i] <= x {
+            i += 1;
+        }
+        while j < b.len() && b[j] <= x {
+            j += 1;
+        }
+        d = d.max((i as f64 / a.len() as f64 - j as f64 / b.len() as f64).abs());
+    }
+    d
+}
+
+fn missing_ratio(values: &[f32]) -> f64 {
+    values.iter().filter(|v| v.is_nan()).count() as f64 / values.len().max(1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_histogram() {
+        let hist = histogram(&[0.0, 1.0, 2.0, f32::NAN], &[1.0, 2.0]);
+        assert_eq!(hist, vec![0.25, 0.25, 0.25, 0.25]);
+    }
+
+    #[test]
+    fn test_psi_and_ks() {
+        let reference: Vec<f32> = (0..100).map(|v| v as f32).collect();
+        let bounds = decile_bounds(&reference);
+        assert_eq!(bounds.len(), 9);
+        let same = histogram(&reference, &bounds);
+        assert!(psi(&same, &same).abs() < 1e-12);
+        assert_eq!(ks(&reference, &reference), 0.0);
+
+        let shifted: Vec<f32> = reference.iter().map(|v| v + 50.0).collect();
+        assert!(psi(&same, &histogram(&shifted, &bounds)) > 0.25);
+        assert!((ks(&reference, &shifted) - 0.5).abs() < 1e-9);
+    }
+}
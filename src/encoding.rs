@@ -0,0 +1,304 @@
+//! Categorical encoders whose fitted mappings are saved with the model, so inference
+//! applies exactly the training-time encoding.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::dmatrix::Csr;
+
+/// Booster attribute under which fitted encoders are stored.
+pub const ENCODERS_ATTR: &str = "encoders";
+
+/// Maps each category seen during fitting to a code, in sorted order. Unknown categories
+/// become missing values, which XGBoost routes along the default direction.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrdinalEncoder {
+    pub categories: BTreeMap<String, u32>,
+}
+
+impl OrdinalEncoder {
+    pub fn fit(values: &[&str]) -> Self {
+        let mut categories: Vec<&str> = values.to_vec();
+        categories.sort_unstable();
+        categories.dedup();
+        OrdinalEncoder {
+            categories: categories
+                .into_iter()
+                .enumerate()
+                .map(|(i, c)| (c.to_string(), i as u32))
+                .collect(),
+        }
+    }
+
+    pub fn transform(&self, values: &[&str]) -> Vec<f32> {
+        values
+            .iter()
+            .map(|v| self.categories.get(*v).map_or(f32::NAN, |c| *c as f32))
+            .collect()
+    }
+}
+
+/// Replaces each category by the mean target of its rows, shrunk towards the global mean:
+/// `(sum + smoothing * prior) / (count + smoothing)`. Unknown categories get the prior.
+///
+/// Fitting and transforming the same rows leaks the target; fit on out-of-fold data when
+/// encoding training rows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TargetEncoder {
+    pub means: BTreeMap<String, f64>,
+    pub prior: f64,
+    pub smoothing: f64,
+}
+
+impl TargetEncoder {
+    pub fn fit(values: &[&str], targets: &[f32], smoothing: f64) -> Result<Self, String> {
+        if values.len() != targets.len() || values.is_empty() {
+            return Err(format!(
+                "{} values for {} targets",
+                values.len(),
+                targets.len()
+            ));
+        }
+        let prior = targets.iter().map(|t| *t as f64).sum::<f64>() / targets.len() as f64;
+        let mut stats: BTreeMap<&str, (f64, f64)> = BTreeMap::new();
+        for (value, target) in values.iter().zip(targets) {
+            let entry = stats.entry(value).or_default();
+            entry.0 += *target as f64;
+            entry.1 += 1.0;
+        }
+        let means = stats
+            .into_iter()
+            .map(|(c, (sum, count))| {
+                (
+                    c.to_string(),
+                    (sum + smoothing * prior) / (count + smoothing),
+                )
+            })
+            .collect();
+        Ok(TargetEncoder {
+            means,
+            prior,
+            smoothing,
+        })
+    }
+
+    pub fn transform(&self, values: &[&str]) -> Vec<f32> {
+        values
+            .iter()
+            .map(|v| *self.means.get(*v).unwrap_or(&self.prior) as f32)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Encoder {
+    Ordinal(OrdinalEncoder),
+    Target(TargetEncoder),
+}
+
+impl Encoder {
+    pub fn transform(&self, values: &[&str]) -> Vec<f32> {
+        match self {
+            Encoder::Ordinal(e) => e.transform(values),
+            Encoder::Target(e) => e.transform(values),
+        }
+    }
+}
+
+/// Fitted encoders keyed by feature name. Attach them with `Booster::set_encoders`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Encoders {
+    pub columns: BTreeMap<String, Encoder>,
+}
+
+impl Encoders {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(mut self, feature: &str, encoder: Encoder) -> Self {
+        self.columns.insert(feature.to_string(), encoder);
+        self
+    }
+
+    pub fn get(&self, feature: &str) -> Option<&Encoder> {
+        self.columns.get(feature)
+    }
+
+    /// Encodes the raw values of `feature`, or `None` if it has no encoder.
+    pub fn transform(&self, feature: &str, values: &[&str]) -> Option<Vec<f32>> {
+        self.get(feature).map(|e| e.transform(values))
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+/// Token counts of hashed buckets seen by a [`FeatureHasher`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CollisionStats {
+    /// Distinct tokens hashed so far.
+    pub tokens: usize,
+    /// Distinct tokens that landed in a bucket already used by another token.
+    pub collisions: usize,
+}
+
+impl CollisionStats {
+    pub fn collision_rate(&self) -> f64 {
+        if self.tokens == 0 {
+            0.0
+        } else {
+            self.collisions as f64 / self.tokens as f64
+        }
+    }
+}
+
+/// Hashes token streams into a fixed number of columns, producing CSR rows for
+/// `DMatrix::try_from_csr`. Uses 64-bit FNV-1a, which is stable across platforms and
+/// releases, so the same token always maps to the same column.
+#[derive(Debug, Clone)]
+pub struct FeatureHasher {
+    n_features: u32,
+    alternate_sign: bool,
+    buckets: HashMap<u32, Vec<u64>>,
+    stats: CollisionStats,
+}
+
+impl FeatureHasher {
+    pub fn new(n_features: u32) -> Self {
+        FeatureHasher {
+            n_features: n_features.max(1),
+            alternate_sign: false,
+            buckets: HashMap::new(),
+            stats: CollisionStats::default(),
+        }
+    }
+
+    /// Gives each token a sign from its hash, so collisions tend to cancel out instead of
+    /// adding up, as scikit-learn's `FeatureHasher` does.
+    pub fn alternate_sign(mut self, alternate_sign: bool) -> Self {
+        self.alternate_sign = alternate_sign;
+        self
+    }
+
+    pub fn n_features(&self) -> u32 {
+        self.n_features
+    }
+
+    pub fn stats(&self) -> CollisionStats {
+        self.stats
+    }
+
+    /// One CSR row per token iterator, each token counting 1 in its column.
+    pub fn transform<R, T>(&mut self, rows: R) -> Csr
+    where
+        R: IntoIterator,
+        R::Item: IntoIterator<Item = T>,
+        T: AsRef<str>,
+    {
+        let mut csr = Csr {
+            indptr: vec![0],
+            ..Default::default()
+        };
+        for tokens in rows {
+            let mut row: BTreeMap<u32, f32> = BTreeMap::new();
+            for token in tokens {
+                let (column, sign) = self.hash(token.as_ref());
+                *row.entry(column).or_default() += sign;
+            }
+            for (column, value) in row {
+                csr.indices.push(column);
+                csr.values.push(value);
+            }
+            csr.indptr.push(csr.indices.len() as u64);
+        }
+        csr
+    }
+
+    fn hash(&mut self, token: &str) -> (u32, f32) {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in token.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        let column = (hash % self.n_features as u64) as u32;
+        let seen = self.buckets.entry(column).or_default();
+        if !seen.contains(&hash) {
+            if !seen.is_empty() {
+                self.stats.collisions += 1;
+            }
+            seen.push(hash);
+            self.stats.tokens += 1;
+        }
+        let sign = if self.alternate_sign && hash >> 63 == 1 {
+            -1.0
+        } else {
+            1.0
+        };
+        (column, sign)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ordinal_encoder() {
+        let encoder = OrdinalEncoder::fit(&["fr", "de", "fr", "it"]);
+        let codes = encoder.transform(&["it", "de", "es"]);
+        assert_eq!(codes[..2], [2.0, 0.0]);
+        assert!(codes[2].is_nan());
+    }
+
+    #[test]
+    fn test_target_encoder() {
+        let encoder =
+            TargetEncoder::fit(&["a", "a", "b", "b"], &[1.0, 1.0, 0.0, 1.0], 2.0).unwrap();
+        assert_eq!(encoder.prior, 0.75);
+        assert_eq!(
+            encoder.transform(&["a", "c"]),
+            vec![(2.0 + 1.5) / 4.0, 0.75]
+        );
+        assert!(TargetEncoder::fit(&["a"], &[], 1.0).is_err());
+    }
+
+    #[test]
+    fn test_feature_hasher() {
+        let mut hasher = FeatureHasher::new(1 << 20);
+        let csr = hasher.transform([vec!["get", "/index", "get"], vec![]]);
+        assert_eq!(csr.indptr, vec![0, 2, 2]);
+        assert_eq!(csr.values.iter().sum::<f32>(), 3.0);
+        assert!(csr.indices.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(hasher.stats().tokens, 2);
+        let again = FeatureHasher::new(1 << 20).transform([["get", "/index", "get"]]);
+        assert_eq!(again.indices, csr.indices);
+
+        let mut tiny = FeatureHasher::new(1);
+        tiny.transform([["a", "b", "c"]]);
+        assert_eq!(tiny.stats().collisions, 2);
+    }
+
+    #[test]
+    fn test_encoders_roundtrip() {
+        let encoders = Encoders::new()
+            .insert(
+                "country",
+                Encoder::Ordinal(OrdinalEncoder::fit(&["fr", "de"])),
+            )
+            .insert(
+                "city",
+                Encoder::Target(TargetEncoder::fit(&["paris"], &[1.0], 1.0).unwrap()),
+            );
+        let json = encoders.to_json().unwrap();
+        assert!(json.contains("\"kind\":\"ordinal\""));
+        assert_eq!(Encoders::from_json(&json).unwrap(), encoders);
+        assert_eq!(encoders.transform("country", &["fr"]), Some(vec![1.0]));
+    }
+}
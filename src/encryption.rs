@@ -0,0 +1,93 @@
+//! Authenticated encryption of serialized models with AES-256-GCM, behind the
+//! `encryption` feature. A sealed model is a short header, a random 96-bit nonce and the
+//! ciphertext of the UBJSON model; the header is authenticated too, so any change to
+//! the file makes decryption fail instead of yielding a corrupted model.
+
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use rand::RngCore;
+use thiserror::Error;
+
+/// Marks sealed models and versions their layout.
+pub const MAGIC: &[u8; 8] = b"XGBENC01";
+const NONCE_LEN: usize = 12;
+
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("Not an encrypted model")]
+    Format,
+    #[error("Cannot decrypt model: wrong key or tampered data")]
+    Decrypt,
+    #[error("Cannot encrypt model")]
+    Encrypt,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+fn cipher(key: &[u8; 32]) -> Aes256Gcm {
+    Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key))
+}
+
+/// Encrypts `plaintext` under `key` with a fresh random nonce.
+pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::rngs::OsRng.fill_bytes(&mut nonce);
+    let ciphertext = cipher(key)
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: MAGIC,
+            },
+        )
+        .map_err(|_| EncryptionError::Encrypt)?;
+    let mut sealed = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    sealed.extend_from_slice(MAGIC);
+    sealed.extend_from_slice(&nonce);
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+/// Decrypts data produced by [`seal`], checking its integrity.
+pub fn open(key: &[u8; 32], sealed: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+    let body = sealed
+        .strip_prefix(MAGIC.as_slice())
+        .filter(|body| body.len() >= NONCE_LEN)
+        .ok_or(EncryptionError::Format)?;
+    let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+    cipher(key)
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad: MAGIC,
+            },
+        )
+        .map_err(|_| EncryptionError::Decrypt)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_open() {
+        let key = [7u8; 32];
+        let sealed = seal(&key, b"{\"learner\": {}}").unwrap();
+        assert!(sealed.starts_with(MAGIC));
+        assert_eq!(open(&key, &sealed).unwrap(), b"{\"learner\": {}}");
+        assert_ne!(seal(&key, b"x").unwrap(), seal(&key, b"x").unwrap());
+
+        assert!(matches!(
+            open(&[8u8; 32], &sealed),
+            Err(EncryptionError::Decrypt)
+        ));
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(matches!(
+            open(&key, &tampered),
+            Err(EncryptionError::Decrypt)
+        ));
+        assert!(matches!(open(&key, b"{}"), Err(EncryptionError::Format)));
+    }
+}
@@ -0,0 +1,199 @@
+//! Blending several boosters, e.g. trained on different folds or seeds.
+
+use std::thread;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::DMatrix;
+use crate::ffi_guard::panic_message;
+use crate::predict::{PredictOptions, Prediction};
+
+/// How member predictions are combined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Combine {
+    #[default]
+    Mean,
+    /// Mean weighted by the member weights.
+    Weighted,
+    /// Weighted mean of each member's normalized ranks in `[0, 1]`, per output column.
+    /// Robust to members with differently calibrated scores.
+    RankAverage,
+}
+
+pub struct Ensemble {
+    members: Vec<(Booster, f64)>,
+    combine: Combine,
+}
+
+impl Ensemble {
+    pub fn new(combine: Combine) -> Self {
+        Ensemble {
+            members: Vec::new(),
+            combine,
+        }
+    }
+
+    /// Adds a member with weight 1.
+    pub fn push(&mut self, booster: Booster) {
+        self.members.push((booster, 1.0));
+    }
+
+    pub fn push_weighted(&mut self, booster: Booster, weight: f64) {
+        self.members.push((booster, weight));
+    }
+
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    pub fn members(&self) -> impl Iterator<Item = &Booster> {
+        self.members.iter().map(|(b, _)| b)
+    }
+
+    /// Predicts `data` with every member and combines the outputs. A `DMatrix` cannot be
+    /// shared between threads, so the members take turns, each prediction being
+    /// multi-threaded by XGBoost itself. Use [`Ensemble::predict_dense`] to run the
+    /// members in parallel.
+    pub fn predict(&self, data: &DMatrix) -> Result<Vec<f32>, XGBoostError> {
+        if self.members.is_empty() {
+            return Err(XGBoostError::Predict);
+        }
+        let options = PredictOptions::default();
+        let predictions = self
+            .members
+            .iter()
+            .map(|(booster, _)| booster.predict_with_shape(data, &options))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.blend(predictions)
+    }
+
+    /// Predicts row-major dense `data`, NaN for missing, with every member in parallel and
+    /// combines the outputs.
+    pub fn predict_dense(
+        &self,
+        data: &[f32],
+        rows: usize,
+        cols: usize,
+    ) -> Result<Vec<f32>, XGBoostError> {
+        if self.members.is_empty() {
+            return Err(XGBoostError::Predict);
+        }
+        let options = PredictOptions::default();
+        let predictions = thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .members
+                .iter()
+                .map(|(booster, _)| {
+                    scope.spawn(|| booster.predict_dense(data, rows, cols, &options))
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| {
+                    h.join()
+                        .unwrap_or_else(|p| Err(XGBoostError::Panic(panic_message(p.as_ref()))))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        self.blend(predictions)
+    }
+
+    fn blend(&self, predictions: Vec<Prediction>) -> Result<Vec<f32>, XGBoostError> {
+        let weights: Vec<f64> = match self.combine {
+            Combine::Mean => vec![1.0; self.members.len()],
+            Combine::Weighted | Combine::RankAverage => {
+                self.members.iter().map(|(_, w)| *w).collect()
+            }
+        };
+        let row_len = predictions[0].shape.row_len();
+        let values: Vec<Vec<f32>> = predictions.into_iter().map(|p| p.values).collect();
+        combine(&values, &weights, self.combine, row_len.max(1))
+    }
+}
+
+fn combine(
+    predictions: &[Vec<f32>],
+    weights: &[f64],
+    method: Combine,
+    row_len: usize,
+) -> Result<Vec<f32>, XGBoostError> {
+    let len = predictions[0].len();
+    if predictions.iter().any(|p| p.len() != len) {
+        return Err(XGBoostError::Unsupported(
+            "members predict different output shapes".to_string(),
+        ));
+    }
+    let total: f64 = weights.iter().sum();
+    if total <= 0.0 {
+        return Err(XGBoostError::Unsupported(
+            "member weights must sum to a positive value".to_string(),
+        ));
+    }
+    let ranked: Vec<Vec<f32>>;
+    let inputs = if method == Combine::RankAverage {
+        ranked = predictions.iter().map(|p| ranks(p, row_len)).collect();
+        &ranked
+    } else {
+        predictions
+    };
+    Ok((0..len)
+        .map(|i| {
+            let sum: f64 = inputs
+                .iter()
+                .zip(weights)
+                .map(|(p, w)| p[i] as f64 * w)
+                .sum();
+            (sum / total) as f32
+        })
+        .collect())
+}
+
+/// Per output column, replaces values by their rank scaled to `[0, 1]`, ties sharing
+/// their average rank.
+fn ranks(values: &[f32], row_len: usize) -> Vec<f32> {
+    let mut out = vec![0.0; values.len()];
+    let rows = values.len() / row_len;
+    for column in 0..row_len {
+        let mut order: Vec<usize> = (0..rows).map(|r| r * row_len + column).collect();
+        order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+        let scale = rows.saturating_sub(1).max(1) as f32;
+        let mut start = 0;
+        while start < order.len() {
+            let mut end = start + 1;
+            while end < order.len() && values[order[end]] == values[order[start]] {
+                end += 1;
+            }
+            let rank = (start + end - 1) as f32 / 2.0 / scale;
+            for i in &order[start..end] {
+                out[*i] = rank;
+            }
+            start = end;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combine() {
+        let predictions = vec![vec![0.2, 0.4], vec![0.6, 0.8]];
+        let mean = combine(&predictions, &[1.0, 1.0], Combine::Mean, 1).unwrap();
+        assert!((mean[0] - 0.4).abs() < 1e-6 && (mean[1] - 0.6).abs() < 1e-6);
+        let weighted = combine(&predictions, &[3.0, 1.0], Combine::Weighted, 1).unwrap();
+        assert!((weighted[0] - 0.3).abs() < 1e-6);
+        assert!(combine(&predictions, &[0.0, 0.0], Combine::Weighted, 1).is_err());
+    }
+
+    #[test]
+    fn test_ranks() {
+        assert_eq!(ranks(&[0.9, 0.1, 0.5], 1), vec![1.0, 0.0, 0.5]);
+        assert_eq!(ranks(&[0.3, 0.3, 0.1], 1), vec![0.75, 0.75, 0.0]);
+        assert_eq!(ranks(&[0.1, 5.0, 0.2, 1.0], 2), vec![0.0, 1.0, 1.0, 0.0]);
+    }
+}
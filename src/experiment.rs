@@ -0,0 +1,262 @@
+//! One-stop training runs. An [`Experiment`] gathers everything a training script sets
+//! up by hand, parameters, data, evaluation sets, callbacks and where to write the
+//! results, and [`Experiment::run`] trains, records the metrics of every round and saves
+//! the artifacts.
+//!
+//! ```no_run
+//! # use xgb_rs::dmatrix::DMatrix;
+//! # use xgb_rs::experiment::Experiment;
+//! # use xgb_rs::params::TrainParams;
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! # let (dtrain, dvalid) = (DMatrix::try_new()?, DMatrix::try_new()?);
+//! let result = Experiment::new("churn")
+//!     .params(TrainParams::new().objective("binary:logistic"))
+//!     .num_boost_round(200)
+//!     .train(&dtrain)
+//!     .eval(&dvalid, "valid")
+//!     .output_dir("runs/churn")
+//!     .run()?;
+//! println!("{:?}", result.final_metrics("valid"));
+//! # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+use crate::artifacts::{self, Bundle};
+use crate::booster::{Booster, ModelFormat, XGBoostError};
+use crate::dmatrix::DMatrix;
+use crate::params::TrainParams;
+use crate::training::{StopReason, TrainControl, TrainingCallback};
+
+#[derive(Error, Debug)]
+pub enum ExperimentError {
+    #[error("Invalid experiment: {0}")]
+    Argument(String),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error("Cannot write artifacts: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// Metrics of every evaluation set after one round.
+pub type RoundMetrics = Vec<(String, HashMap<String, f64>)>;
+
+pub struct Experiment<'a> {
+    name: String,
+    params: TrainParams,
+    num_boost: usize,
+    dtrain: Option<&'a DMatrix>,
+    evals: Vec<(&'a DMatrix, String)>,
+    control: TrainControl,
+    callbacks: Vec<&'a mut dyn TrainingCallback>,
+    output_dir: Option<PathBuf>,
+    model_format: ModelFormat,
+}
+
+impl<'a> Experiment<'a> {
+    /// An experiment boosting 10 rounds with default parameters. `name` names the saved
+    /// model file.
+    pub fn new(name: &str) -> Self {
+        Experiment {
+            name: name.to_string(),
+            params: TrainParams::new(),
+            num_boost: 10,
+            dtrain: None,
+            evals: Vec::new(),
+            control: TrainControl::new(),
+            callbacks: Vec::new(),
+            output_dir: None,
+            model_format: ModelFormat::Ubj,
+        }
+    }
+
+    pub fn params(mut self, params: TrainParams) -> Self {
+        self.params = params;
+        self
+    }
+
+    pub fn num_boost_round(mut self, num_boost: usize) -> Self {
+        self.num_boost = num_boost;
+        self
+    }
+
+    pub fn train(mut self, dtrain: &'a DMatrix) -> Self {
+        self.dtrain = Some(dtrain);
+        self
+    }
+
+    /// Adds a set scored after every round under `name`.
+    pub fn eval(mut self, data: &'a DMatrix, name: &str) -> Self {
+        self.evals.push((data, name.to_string()));
+        self
+    }
+
+    pub fn control(mut self, control: TrainControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Adds a callback receiving the metrics after every round, e.g. a
+    /// [`MetricsLogger`](crate::training::MetricsLogger). Callbacks run in the order they
+    /// were added.
+    pub fn callback(mut self, callback: &'a mut dyn TrainingCallback) -> Self {
+        self.callbacks.push(callback);
+        self
+    }
+
+    /// Directory receiving the artifacts, written atomically with
+    /// [`artifacts::write_bundle`]: the model as `<name>.json` or `<name>.ubj`,
+    /// `params.json`, `metrics.json` and `feature_names.json`. A previous bundle there is
+    /// replaced.
+    pub fn output_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.output_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Format of the saved model, UBJSON by default.
+    pub fn model_format(mut self, format: ModelFormat) -> Self {
+        self.model_format = format;
+        self
+    }
+
+    /// Trains the model, then writes the artifacts if an output directory is set.
+    pub fn run(mut self) -> Result<ExperimentResult, ExperimentError> {
+        let dtrain = self
+            .dtrain
+            .ok_or_else(|| ExperimentError::Argument("no training data".to_string()))?;
+        if self.name.is_empty() || self.name.contains(['/', '\\']) {
+            return Err(ExperimentError::Argument(format!(
+                "{:?} is not a valid experiment name",
+                self.name
+            )));
+        }
+        let evals: Vec<(&DMatrix, &str)> = self
+            .evals
+            .iter()
+            .map(|(data, name)| (*data, name.as_str()))
+            .collect();
+        let mut recorder = Recorder {
+            history: Vec::new(),
+            callbacks: &mut self.callbacks,
+        };
+        let outcome = Booster::train_with_evals(
+            dtrain,
+            &evals,
+            &self.params,
+            self.num_boost,
+            &self.control,
+            &mut recorder,
+        )?;
+        let mut result = ExperimentResult {
+            booster: outcome.booster,
+            rounds: outcome.rounds,
+            stop: outcome.stop,
+            history: recorder.history,
+            artifacts: Vec::new(),
+        };
+        if let Some(dir) = &self.output_dir {
+            let bundle = Bundle {
+                name: &self.name,
+                booster: &result.booster,
+                format: self.model_format,
+                params: &self.params,
+                history: &result.history,
+            };
+            result.artifacts = artifacts::write_bundle(dir, &bundle)?;
+        }
+        Ok(result)
+    }
+}
+
+/// Keeps every round's metrics and forwards them to the user's callbacks.
+struct Recorder<'a, 'b> {
+    history: Vec<RoundMetrics>,
+    callbacks: &'b mut Vec<&'a mut dyn TrainingCallback>,
+}
+
+impl TrainingCallback for Recorder<'_, '_> {
+    fn after_round(
+        &mut self,
+        round: usize,
+        metrics: &[(String, HashMap<String, f64>)],
+    ) -> io::Result<()> {
+        self.history.push(metrics.to_vec());
+        for callback in self.callbacks.iter_mut() {
+            callback.after_round(round, metrics)?;
+        }
+        Ok(())
+    }
+}
+
+/// What [`Experiment::run`] produced.
+#[derive(Debug)]
+pub struct ExperimentResult {
+    pub booster: Booster,
+    pub rounds: usize,
+    pub stop: StopReason,
+    /// Metrics of every evaluation set, one entry per round.
+    pub history: Vec<RoundMetrics>,
+    /// Files written to the output directory: model, parameters, metrics and feature
+    /// names.
+    pub artifacts: Vec<PathBuf>,
+}
+
+impl ExperimentResult {
+    /// Metrics of the evaluation set `name` after the last round.
+    pub fn final_metrics(&self, name: &str) -> Option<&HashMap<String, f64>> {
+        self.history
+            .last()?
+            .iter()
+            .find(|(set, _)| set == name)
+            .map(|(_, metrics)| metrics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recorder_forwards_metrics() {
+        let mut seen = Vec::new();
+        struct Rounds<'a>(&'a mut Vec<usize>);
+        impl TrainingCallback for Rounds<'_> {
+            fn after_round(
+                &mut self,
+                round: usize,
+                _: &[(String, HashMap<String, f64>)],
+            ) -> io::Result<()> {
+                self.0.push(round);
+                Ok(())
+            }
+        }
+        let mut rounds = Rounds(&mut seen);
+        let mut callbacks: Vec<&mut dyn TrainingCallback> = vec![&mut rounds];
+        let mut recorder = Recorder {
+            history: Vec::new(),
+            callbacks: &mut callbacks,
+        };
+        let metrics = vec![(
+            "valid".to_string(),
+            HashMap::from([("rmse".to_string(), 0.5)]),
+        )];
+        recorder.after_round(0, &metrics).unwrap();
+        recorder.after_round(1, &metrics).unwrap();
+        assert_eq!(recorder.history.len(), 2);
+        drop(callbacks);
+        assert_eq!(seen, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_run_needs_training_data() {
+        assert!(matches!(
+            Experiment::new("empty").run(),
+            Err(ExperimentError::Argument(_))
+        ));
+    }
+}
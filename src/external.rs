@@ -0,0 +1,353 @@
+//! External-memory training data: a [`BatchSource`] is read batch by batch into an
+//! `ExtMemQuantileDMatrix`, which keeps only the quantized pages in a disk or host cache.
+//! With [`Device::Cuda`] this is the way to train with `hist` on datasets larger than GPU
+//! memory: batches are copied to the device one at a time and the cache stays on host.
+
+use std::ffi::CString;
+use std::io;
+use std::os::raw::{c_int, c_void};
+use std::path::PathBuf;
+
+use xgb_sys::{
+    XGDMatrixFree, XGDMatrixNumCol, XGDMatrixNumRow, XGDMatrixSetInfoFromInterface,
+    XGExtMemQuantileDMatrixCreateFromCallback, XGProxyDMatrixCreate, XGProxyDMatrixSetDataDense,
+};
+
+use crate::dmatrix::{array_interface, DMatrix, DMatrixError};
+use crate::ffi_guard::PanicSlot;
+use crate::predict::Device;
+
+/// Row-major dense rows with their labels and optional weights. NaN marks missing values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Batch {
+    pub data: Vec<f32>,
+    pub cols: usize,
+    pub labels: Vec<f32>,
+    pub weights: Option<Vec<f32>>,
+}
+
+impl Batch {
+    pub fn rows(&self) -> usize {
+        self.labels.len()
+    }
+}
+
+/// A dataset read in batches. XGBoost passes over it several times, calling
+/// [`BatchSource::reset`] before each pass, so every pass must yield the same batches.
+pub trait BatchSource {
+    fn reset(&mut self);
+    /// The next batch, or `None` after the last one.
+    fn next_batch(&mut self) -> io::Result<Option<Batch>>;
+}
+
+/// Settings of [`ext_mem_dmatrix`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtMemOptions {
+    cache_prefix: PathBuf,
+    device: Device,
+    on_host: bool,
+    max_bin: u32,
+    nthread: u32,
+}
+
+impl ExtMemOptions {
+    /// Cache pages are written to files starting with `cache_prefix`.
+    pub fn new(cache_prefix: impl Into<PathBuf>) -> Self {
+        ExtMemOptions {
+            cache_prefix: cache_prefix.into(),
+            device: Device::Cpu,
+            on_host: false,
+            max_bin: 256,
+            nthread: 0,
+        }
+    }
+
+    /// Device the matrix is built for, which must match the booster's `device`. CUDA
+    /// devices keep the cache in host memory, as recommended upstream.
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = device;
+        if matches!(device, Device::Cuda(_)) {
+            self.on_host = true;
+        }
+        self
+    }
+
+    /// Keep the cache in host memory instead of files.
+    pub fn on_host(mut self, on_host: bool) -> Self {
+        self.on_host = on_host;
+        self
+    }
+
+    /// Number of histogram bins; must match the booster's `max_bin`.
+    pub fn max_bin(mut self, max_bin: u32) -> Self {
+        self.max_bin = max_bin;
+        self
+    }
+
+    pub fn nthread(mut self, nthread: u32) -> Self {
+        self.nthread = nthread;
+        self
+    }
+
+    fn to_config(&self) -> String {
+        let prefix = serde_json::to_string(&self.cache_prefix.to_string_lossy()).unwrap();
+        format!(
+            "{{\"missing\": NaN, \"cache_prefix\": {}, \"on_host\": {}, \"max_bin\": {}, \"nthread\": {}}}",
+            prefix, self.on_host, self.max_bin, self.nthread
+        )
+    }
+}
+
+/// What the iterator callbacks work on; the current batch stays alive until the next
+/// call because the proxy only borrows it.
+struct IterState<'a> {
+    source: &'a mut dyn BatchSource,
+    proxy: *mut c_void,
+    current: Option<Batch>,
+    cols: Option<usize>,
+    error: Option<DMatrixError>,
+}
+
+struct IterContext<'a> {
+    state: IterState<'a>,
+    panic: PanicSlot,
+}
+
+impl IterState<'_> {
+    fn advance(&mut self) -> c_int {
+        let result = match self.source.next_batch() {
+            Ok(Some(batch)) => self.set(batch).map(|_| 1),
+            Ok(None) => Ok(0),
+            Err(e) => Err(e.into()),
+        };
+        result.unwrap_or_else(|e| {
+            self.error.get_or_insert(e);
+            0
+        })
+    }
+
+    fn set(&mut self, batch: Batch) -> Result<(), DMatrixError> {
+        check_batch(&batch, *self.cols.get_or_insert(batch.cols))?;
+        let batch = self.current.insert(batch);
+        let data = array_interface(
+            batch.data.as_ptr() as usize,
+            "<f4",
+            &[batch.rows(), batch.cols],
+        );
+        unsafe {
+            if XGProxyDMatrixSetDataDense(self.proxy, data.as_ptr()) != 0 {
+                return Err(DMatrixError::Convert("cannot set batch data".to_string()));
+            }
+        }
+        set_info(self.proxy, "label", &batch.labels)?;
+        if let Some(weights) = &batch.weights {
+            set_info(self.proxy, "weight", weights)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_batch(batch: &Batch, cols: usize) -> Result<(), DMatrixError> {
+    if batch.cols != cols {
+        return Err(DMatrixError::Argument(format!(
+            "batch has {} columns, previous batches had {}",
+            batch.cols, cols
+        )));
+    }
+    if batch.data.len() != batch.rows() * cols {
+        return Err(DMatrixError::Length {
+            field: "data".to_string(),
+            expected: (batch.rows() * cols) as u64,
+            got: batch.data.len(),
+        });
+    }
+    match &batch.weights {
+        Some(weights) if weights.len() != batch.rows() => Err(DMatrixError::Length {
+            field: "weight".to_string(),
+            expected: batch.rows() as u64,
+            got: weights.len(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+fn set_info(proxy: *mut c_void, field: &str, values: &[f32]) -> Result<(), DMatrixError> {
+    let name = CString::new(field).unwrap();
+    let data = array_interface(values.as_ptr() as usize, "<f4", &[values.len()]);
+    unsafe {
+        if XGDMatrixSetInfoFromInterface(proxy, name.as_ptr(), data.as_ptr()) == 0 {
+            Ok(())
+        } else {
+            Err(DMatrixError::SetInfo(field.to_string()))
+        }
+    }
+}
+
+unsafe extern "C" fn next_callback(handle: *mut c_void) -> c_int {
+    let IterContext { state, panic } = &mut *(handle as *mut IterContext);
+    panic.catch(0, || state.advance())
+}
+
+unsafe extern "C" fn reset_callback(handle: *mut c_void) {
+    let IterContext { state, panic } = &mut *(handle as *mut IterContext);
+    panic.catch((), || state.source.reset())
+}
+
+/// Builds an external-memory quantile matrix from `source`. Train on it with the `hist`
+/// tree method and the same `device` and `max_bin` as in `options`.
+pub fn ext_mem_dmatrix(
+    source: &mut dyn BatchSource,
+    options: &ExtMemOptions,
+) -> Result<DMatrix, DMatrixError> {
+    if matches!(options.device, Device::Cuda(_)) && !Device::cuda_available() {
+        return Err(DMatrixError::Argument(
+            "the linked XGBoost library was built without CUDA".to_string(),
+        ));
+    }
+    let config = CString::new(options.to_config()).unwrap();
+    let mut proxy: *mut c_void = std::ptr::null_mut();
+    unsafe {
+        if XGProxyDMatrixCreate(&mut proxy) != 0 {
+            return Err(DMatrixError::Create);
+        }
+    }
+    let mut context = IterContext {
+        state: IterState {
+            source,
+            proxy,
+            current: None,
+            cols: None,
+            error: None,
+        },
+        panic: PanicSlot::new(),
+    };
+    let mut handle: *mut c_void = std::ptr::null_mut();
+    let created = unsafe {
+        XGExtMemQuantileDMatrixCreateFromCallback(
+            &mut context as *mut IterContext as *mut c_void,
+            proxy,
+            std::ptr::null_mut(),
+            Some(reset_callback),
+            Some(next_callback),
+            config.as_ptr(),
+            &mut handle,
+        ) == 0
+    };
+    unsafe {
+        XGDMatrixFree(proxy);
+    }
+    let dmat = created.then(|| DMatrix::from_handle(handle, 0, 0));
+    if let Some(message) = context.panic.take() {
+        return Err(DMatrixError::CallbackPanic(message));
+    }
+    if let Some(error) = context.state.error {
+        return Err(error);
+    }
+    let mut dmat = dmat.ok_or(DMatrixError::Create)?;
+    unsafe {
+        if XGDMatrixNumRow(dmat.handle, &mut dmat.rows) != 0
+            || XGDMatrixNumCol(dmat.handle, &mut dmat.cols) != 0
+        {
+            return Err(DMatrixError::GetInfo("shape".to_string()));
+        }
+    }
+    dmat.within_tracked_limit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::booster::Booster;
+    use crate::params::TrainParams;
+
+    struct Chunks {
+        batches: Vec<Batch>,
+        next: usize,
+    }
+
+    impl BatchSource for Chunks {
+        fn reset(&mut self) {
+            self.next = 0;
+        }
+
+        fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+            self.next += 1;
+            Ok(self.batches.get(self.next - 1).cloned())
+        }
+    }
+
+    /// A fresh directory for one test's cache files, removed on drop.
+    struct CacheDir(std::path::PathBuf);
+
+    impl CacheDir {
+        fn new(test: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("xgb-rs-{}-{}", test, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            CacheDir(dir)
+        }
+    }
+
+    impl Drop for CacheDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn chunks() -> Chunks {
+        let batches = (0..3)
+            .map(|b| Batch {
+                data: (0..40).map(|i| (b * 40 + i) as f32).collect(),
+                cols: 2,
+                labels: (0..20).map(|i| (b * 20 + i) as f32).collect(),
+                weights: None,
+            })
+            .collect();
+        Chunks { batches, next: 0 }
+    }
+
+    #[test]
+    fn test_check_batch() {
+        let batch = &chunks().batches[0];
+        assert!(check_batch(batch, 2).is_ok());
+        assert!(check_batch(batch, 3).is_err());
+        let mut short = batch.clone();
+        short.weights = Some(vec![1.0; 3]);
+        assert!(matches!(
+            check_batch(&short, 2),
+            Err(DMatrixError::Length { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ext_mem_dmatrix() {
+        let dir = CacheDir::new("ext-mem");
+        let options = ExtMemOptions::new(dir.0.join("cache")).max_bin(64);
+        let dmat = ext_mem_dmatrix(&mut chunks(), &options).unwrap();
+        assert_eq!(dmat.num_rows(), 60);
+        assert_eq!(dmat.num_cols(), 2);
+        let params = TrainParams::new().max_bin(64).set("tree_method", "hist");
+        let booster = Booster::train_with_params(&dmat, &params, 3).unwrap();
+        assert_eq!(booster.boosted_rounds().unwrap(), 3);
+        // The cache files must outlive the matrix
+        drop(booster);
+        drop(dmat);
+    }
+
+    #[test]
+    fn test_ext_mem_source_error() {
+        struct Failing;
+        impl BatchSource for Failing {
+            fn reset(&mut self) {}
+            fn next_batch(&mut self) -> io::Result<Option<Batch>> {
+                Err(io::Error::other("cursor closed"))
+            }
+        }
+        let dir = CacheDir::new("ext-mem-err");
+        let options = ExtMemOptions::new(dir.0.join("cache"));
+        assert!(matches!(
+            ext_mem_dmatrix(&mut Failing, &options),
+            Err(DMatrixError::Source(_))
+        ));
+    }
+}
@@ -0,0 +1,348 @@
+//! Feature selection and screening by retraining on modified data.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::data::GroupKFold;
+use crate::dmatrix::{Csr, DMatrix, DMatrixError};
+use crate::importance::ImportanceType;
+use crate::interpret::mean_std;
+use crate::metrics::{Metric, MetricError};
+use crate::params::TrainParams;
+
+#[derive(Error, Debug)]
+pub enum SelectionError {
+    #[error("Invalid argument: {0}")]
+    Argument(String),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+    #[error(transparent)]
+    Metric(#[from] MetricError),
+}
+
+/// Settings of [`rfe`].
+#[derive(Debug, Clone)]
+pub struct RfeOptions {
+    pub params: TrainParams,
+    pub num_boost: usize,
+    /// Cross-validation folds scoring each feature set.
+    pub n_splits: usize,
+    /// Features removed per step.
+    pub step: usize,
+    /// Stop once this many features are left.
+    pub min_features: usize,
+    pub metric: Metric,
+    pub importance: ImportanceType,
+}
+
+impl RfeOptions {
+    pub fn new(params: TrainParams, num_boost: usize, metric: Metric) -> Self {
+        RfeOptions {
+            params,
+            num_boost,
+            n_splits: 5,
+            step: 1,
+            min_features: 1,
+            metric,
+            importance: ImportanceType::Gain,
+        }
+    }
+}
+
+/// One feature set evaluated by [`rfe`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RfeStep {
+    /// Column indices in `data`.
+    pub features: Vec<usize>,
+    pub cv_mean: f64,
+    pub cv_std: f64,
+    /// Features removed after this step, least important first.
+    pub dropped: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RfeReport {
+    pub steps: Vec<RfeStep>,
+    /// Index into `steps` of the best cross-validated score; ties go to fewer features.
+    pub best_step: usize,
+    /// Names of the selected features, in column order.
+    pub selected: Vec<String>,
+}
+
+impl RfeReport {
+    pub fn best(&self) -> &RfeStep {
+        &self.steps[self.best_step]
+    }
+}
+
+/// Recursive feature elimination: scores the current feature set by cross-validation,
+/// trains on all rows to rank the features by importance, drops the `step` least
+/// important ones and repeats down to `min_features`. Features the model never uses count
+/// as least important.
+///
+/// Rows are split into folds individually; matrices with query groups are rejected.
+pub fn rfe(data: &DMatrix, options: &RfeOptions) -> Result<RfeReport, SelectionError> {
+    let cols = data.num_cols() as usize;
+    if options.step == 0 || options.min_features == 0 || options.min_features > cols {
+        return Err(SelectionError::Argument(format!(
+            "step and min_features must be positive, min_features at most {}",
+            cols
+        )));
+    }
+    if data.get_group_ptr()?.len() > 1 {
+        return Err(SelectionError::Argument(
+            "cannot select features on a matrix with query groups".to_string(),
+        ));
+    }
+    let table = Table::new(data)?;
+    let row_ids: Vec<usize> = (0..data.num_rows() as usize).collect();
+    let folds = GroupKFold::new(options.n_splits).split(&row_ids)?;
+
+    let mut features: Vec<usize> = (0..cols).collect();
+    let mut steps = Vec::new();
+    loop {
+        let dmat = table.select(&features)?;
+        let mut scores = Vec::with_capacity(folds.len());
+        for fold in &folds {
+            let (train, test) = (dmat.slice(&fold.train)?, dmat.slice(&fold.test)?);
+            let booster = Booster::train_with_params(&train, &options.params, options.num_boost)?;
+            scores.push(options.metric.evaluate(&booster.predict(&test)?, &test)?);
+        }
+        let (cv_mean, cv_std) = mean_std(&scores);
+
+        let dropped = if features.len() > options.min_features {
+            let booster = Booster::train_with_params(&dmat, &options.params, options.num_boost)?;
+            let importance = booster.feature_importance(options.importance)?;
+            let mut ranked: Vec<(usize, f64)> = features
+                .iter()
+                .map(|f| (*f, importance.get(&table.names[*f]).copied().unwrap_or(0.0)))
+                .collect();
+            ranked.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let n_drop = options.step.min(features.len() - options.min_features);
+            ranked[..n_drop].iter().map(|(f, _)| *f).collect()
+        } else {
+            Vec::new()
+        };
+        steps.push(RfeStep {
+            features: features.clone(),
+            cv_mean,
+            cv_std,
+            dropped: dropped.clone(),
+        });
+        if dropped.is_empty() {
+            break;
+        }
+        features.retain(|f| !dropped.contains(f));
+    }
+
+    let best_step = best_step(&steps, options.metric.higher_is_better());
+    let selected = steps[best_step]
+        .features
+        .iter()
+        .map(|f| table.names[*f].clone())
+        .collect();
+    Ok(RfeReport {
+        steps,
+        best_step,
+        selected,
+    })
+}
+
+/// Settings of [`null_importance`].
+#[derive(Debug, Clone)]
+pub struct NullImportanceOptions {
+    pub params: TrainParams,
+    pub num_boost: usize,
+    /// Trainings on shuffled labels.
+    pub n_runs: usize,
+    /// A feature is flagged when its p-value is above this.
+    pub alpha: f64,
+    pub importance: ImportanceType,
+    pub seed: u64,
+}
+
+impl NullImportanceOptions {
+    pub fn new(params: TrainParams, num_boost: usize) -> Self {
+        NullImportanceOptions {
+            params,
+            num_boost,
+            n_runs: 20,
+            alpha: 0.05,
+            importance: ImportanceType::Gain,
+            seed: 0,
+        }
+    }
+}
+
+/// Importance of one feature against its distribution under shuffled labels.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NullImportance {
+    pub feature: String,
+    pub actual: f64,
+    pub null_mean: f64,
+    pub null_std: f64,
+    /// `(1 + runs with a null importance >= actual) / (1 + runs)`.
+    pub p_value: f64,
+    /// The feature does not beat the null distribution: likely noise, or a feature whose
+    /// importance comes from memorizing rows, as leaky identifiers do.
+    pub flagged: bool,
+}
+
+/// Null-importance screening: trains once on the real labels and `n_runs` times on
+/// shuffled labels, and compares each feature's importance with the importances it gets
+/// when it cannot carry any signal. Sorted by p-value, most significant first.
+///
+/// With `alpha = 0.05` at least 19 runs are needed for any feature to pass. Matrices with
+/// query groups are rejected.
+pub fn null_importance(
+    data: &DMatrix,
+    options: &NullImportanceOptions,
+) -> Result<Vec<NullImportance>, SelectionError> {
+    if options.n_runs == 0 {
+        return Err(SelectionError::Argument(
+            "n_runs must be positive".to_string(),
+        ));
+    }
+    if data.get_group_ptr()?.len() > 1 {
+        return Err(SelectionError::Argument(
+            "cannot shuffle labels of a matrix with query groups".to_string(),
+        ));
+    }
+    let table = Table::new(data)?;
+    let all: Vec<usize> = (0..table.cols).collect();
+    let importances = |dmat: &DMatrix| -> Result<Vec<f64>, SelectionError> {
+        let booster = Booster::train_with_params(dmat, &options.params, options.num_boost)?;
+        let scores = booster.feature_importance(options.importance)?;
+        Ok(table
+            .names
+            .iter()
+            .map(|name| scores.get(name).copied().unwrap_or(0.0))
+            .collect())
+    };
+    let actual = importances(&table.select(&all)?)?;
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let mut null: Vec<Vec<f64>> = vec![Vec::with_capacity(options.n_runs); table.cols];
+    for _ in 0..options.n_runs {
+        let shuffled = table.select(&all)?;
+        let mut labels = table.labels.clone();
+        labels.shuffle(&mut rng);
+        shuffled.try_add_label(&labels)?;
+        for (f, importance) in importances(&shuffled)?.into_iter().enumerate() {
+            null[f].push(importance);
+        }
+    }
+
+    let mut report: Vec<NullImportance> = table
+        .names
+        .iter()
+        .zip(actual)
+        .zip(null)
+        .map(|((feature, actual), null)| {
+            let (null_mean, null_std) = mean_std(&null);
+            let p_value = p_value(actual, &null);
+            NullImportance {
+                feature: feature.clone(),
+                actual,
+                null_mean,
+                null_std,
+                p_value,
+                flagged: p_value > options.alpha,
+            }
+        })
+        .collect();
+    report.sort_by(|a, b| a.p_value.total_cmp(&b.p_value));
+    Ok(report)
+}
+
+fn p_value(actual: f64, null: &[f64]) -> f64 {
+    let at_least = null.iter().filter(|v| **v >= actual).count();
+    (1 + at_least) as f64 / (1 + null.len()) as f64
+}
+
+/// A matrix with its data copied out once, to select column subsets from.
+pub(crate) struct Table<'a> {
+    data: &'a DMatrix,
+    csr: Csr,
+    pub(crate) rows: usize,
+    pub(crate) cols: usize,
+    pub(crate) labels: Vec<f32>,
+    pub(crate) names: Vec<String>,
+}
+
+impl<'a> Table<'a> {
+    pub(crate) fn new(data: &'a DMatrix) -> Result<Self, DMatrixError> {
+        let names = data.feature_names()?;
+        let cols = data.num_cols() as usize;
+        Ok(Table {
+            data,
+            csr: data.to_csr()?,
+            rows: data.num_rows() as usize,
+            cols,
+            labels: data.get_labels()?,
+            names: if names.is_empty() {
+                (0..cols).map(|i| format!("f{}", i)).collect()
+            } else {
+                names
+            },
+        })
+    }
+
+    /// Matrix of the given columns, in that order, see [`DMatrix::select_columns`]. Names
+    /// are set even if `data` has none, so importances can be matched to columns.
+    pub(crate) fn select(&self, features: &[usize]) -> Result<DMatrix, DMatrixError> {
+        let dmat = self.data.select_columns_of(&self.csr, features)?;
+        let names: Vec<&str> = features.iter().map(|f| self.names[*f].as_str()).collect();
+        dmat.set_feature_names(&names)?;
+        Ok(dmat)
+    }
+}
+
+fn best_step(steps: &[RfeStep], higher_is_better: bool) -> usize {
+    let sign = if higher_is_better { 1.0 } else { -1.0 };
+    // Later steps have fewer features, so `>=` prefers them on ties
+    (0..steps.len())
+        .reduce(|best, i| {
+            if sign * steps[i].cv_mean >= sign * steps[best].cv_mean {
+                i
+            } else {
+                best
+            }
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(features: usize, cv_mean: f64) -> RfeStep {
+        RfeStep {
+            features: (0..features).collect(),
+            cv_mean,
+            cv_std: 0.0,
+            dropped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_p_value() {
+        let null = [0.0, 1.0, 2.0, 3.0];
+        assert_eq!(p_value(5.0, &null), 0.2);
+        assert_eq!(p_value(2.0, &null), 0.6);
+        // An unused feature never beats the null
+        assert_eq!(p_value(0.0, &[0.0; 4]), 1.0);
+    }
+
+    #[test]
+    fn test_best_step() {
+        let steps = [step(4, 0.30), step(3, 0.25), step(2, 0.25), step(1, 0.40)];
+        assert_eq!(best_step(&steps, false), 2);
+        assert_eq!(best_step(&steps, true), 3);
+    }
+}
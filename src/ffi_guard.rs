@@ -0,0 +1,90 @@
+//! Panic containment for Rust code called back from XGBoost.
+//!
+//! Unwinding out of an `extern "C"` function aborts the process, and before Rust 1.81 it
+//! was undefined behavior. Every callback handed to the C API (data iterators, custom
+//! objectives, log handlers) must therefore run its body through [`PanicSlot::catch`]:
+//! a panic is caught at the boundary, its message is parked in the slot that travels
+//! with the callback's context pointer, and the callback returns its error code to
+//! XGBoost. Once the C call returns, the caller checks [`PanicSlot::take`] and turns the
+//! panic into an error (`CallbackPanic`), so the native library never sees an unwind.
+//!
+//! Calls from Rust into C need no guard: XGBoost reports failures through return codes
+//! and its C++ exceptions are caught inside the C API.
+
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+/// Where a callback records a caught panic for the Rust caller to pick up.
+#[derive(Debug, Default)]
+pub(crate) struct PanicSlot {
+    message: Mutex<Option<String>>,
+}
+
+impl PanicSlot {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Runs `f`, returning `on_panic` instead of unwinding if it panics. Only the first
+    /// panic is kept; after one, later calls return `on_panic` without running `f`, so
+    /// a callback invoked repeatedly stops doing work once it has failed.
+    pub(crate) fn catch<R>(&self, on_panic: R, f: impl FnOnce() -> R) -> R {
+        if self.is_poisoned() {
+            return on_panic;
+        }
+        match panic::catch_unwind(AssertUnwindSafe(f)) {
+            Ok(result) => result,
+            Err(payload) => {
+                let mut message = self.message.lock().unwrap_or_else(|e| e.into_inner());
+                message.get_or_insert_with(|| panic_message(payload.as_ref()));
+                on_panic
+            }
+        }
+    }
+
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.message
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// The message of the caught panic, if any, clearing the slot.
+    pub(crate) fn take(&self) -> Option<String> {
+        self.message
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+}
+
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_panic() {
+        let slot = PanicSlot::new();
+        assert_eq!(slot.catch(-1, || 0), 0);
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let code = slot.catch(-1, || panic!("bad batch {}", 3));
+        panic::set_hook(hook);
+        assert_eq!(code, -1);
+        assert!(slot.is_poisoned());
+        assert_eq!(slot.catch(-1, || 0), -1);
+        assert_eq!(slot.take().as_deref(), Some("bad batch 3"));
+        assert_eq!(slot.take(), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! Scoring Arrow record batches and Arrow Flight streams, behind the `flight` feature.
+//! Input columns are cast to `Float32`, nulls becoming missing values; results are
+//! batches with an optional `id` column and a `prediction` column, as written by
+//! [`ArrowIpcWriter`](crate::output::ArrowIpcWriter).
+//!
+//! [`score_flight_data`] turns the `FlightData` of a `DoPut`/`DoExchange` request, or of a
+//! `DoGet` response, into the `FlightData` of the scored batches, so a Flight service can
+//! return it directly.
+
+use std::sync::Arc;
+
+use arrow::array::{Array, Float32Array, StringArray};
+use arrow::compute::cast;
+use arrow::datatypes::DataType;
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::FlightData;
+use futures::{Stream, StreamExt, TryStreamExt};
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::output::prediction_batch;
+use crate::predict::PredictOptions;
+
+#[derive(Error, Debug)]
+pub enum FlightScoreError {
+    #[error("Invalid input: {0}")]
+    Argument(String),
+    #[error(transparent)]
+    Arrow(#[from] ArrowError),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+}
+
+/// How input batches map to the model's features.
+#[derive(Debug, Clone, Default)]
+pub struct FlightScoreOptions {
+    /// Column copied to the output `id` column instead of being used as a feature.
+    pub id_column: Option<String>,
+    /// Feature columns in model order. By default all other columns, in schema order.
+    pub features: Option<Vec<String>>,
+    pub predict: PredictOptions,
+}
+
+impl FlightScoreOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn id_column(mut self, name: &str) -> Self {
+        self.id_column = Some(name.to_string());
+        self
+    }
+
+    pub fn features(mut self, names: &[&str]) -> Self {
+        self.features = Some(names.iter().map(|n| n.to_string()).collect());
+        self
+    }
+
+    pub fn predict(mut self, predict: PredictOptions) -> Self {
+        self.predict = predict;
+        self
+    }
+}
+
+/// Row-major dense features of `batch`, NaN for nulls, with the ids if configured.
+fn to_dense(
+    batch: &RecordBatch,
+    options: &FlightScoreOptions,
+) -> Result<(Vec<Option<String>>, Vec<f32>, usize), FlightScoreError> {
+    let schema = batch.schema();
+    let id_index = match &options.id_column {
+        Some(name) => Some(schema.index_of(name)?),
+        None => None,
+    };
+    let feature_indices: Vec<usize> = match &options.features {
+        Some(names) => names
+            .iter()
+            .map(|name| schema.index_of(name))
+            .collect::<Result<_, _>>()?,
+        None => (0..schema.fields().len())
+            .filter(|i| Some(*i) != id_index)
+            .collect(),
+    };
+    let rows = batch.num_rows();
+    let cols = feature_indices.len();
+    let mut data = vec![f32::NAN; rows * cols];
+    for (col, index) in feature_indices.iter().enumerate() {
+        let values = cast(batch.column(*index), &DataType::Float32)?;
+        let values = values
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .ok_or_else(|| {
+                FlightScoreError::Argument(format!("column {} is not numeric", index))
+            })?;
+        for row in 0..rows {
+            if values.is_valid(row) {
+                data[row * cols + col] = values.value(row);
+            }
+        }
+    }
+    let ids = match id_index {
+        Some(index) => {
+            let ids = cast(batch.column(index), &DataType::Utf8)?;
+            let ids = ids
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .ok_or_else(|| FlightScoreError::Argument("invalid id column".to_string()))?;
+            ids.iter().map(|id| id.map(str::to_string)).collect()
+        }
+        None => Vec::new(),
+    };
+    Ok((ids, data, cols))
+}
+
+/// Scores one record batch.
+pub fn score_record_batch(
+    booster: &Booster,
+    batch: &RecordBatch,
+    options: &FlightScoreOptions,
+) -> Result<RecordBatch, FlightScoreError> {
+    let (ids, data, cols) = to_dense(batch, options)?;
+    let prediction = booster.predict_dense(&data, batch.num_rows(), cols, &options.predict)?;
+    Ok(prediction_batch(&ids, &prediction)?)
+}
+
+/// Decodes a Flight stream, scores each batch as it arrives and encodes the results as
+/// a Flight stream. Scoring runs on the polling task, so poll it from a runtime that
+/// tolerates CPU-bound work, e.g. with a dedicated worker.
+pub fn score_flight_data<S>(
+    booster: Arc<Booster>,
+    input: S,
+    options: FlightScoreOptions,
+) -> impl Stream<Item = Result<FlightData, FlightError>> + Send
+where
+    S: Stream<Item = Result<FlightData, FlightError>> + Send + 'static,
+{
+    let scored = FlightRecordBatchStream::new_from_flight_data(input).map(move |batch| {
+        let batch = batch?;
+        score_record_batch(&booster, &batch, &options)
+            .map_err(|e| FlightError::ExternalError(Box::new(e)))
+    });
+    FlightDataEncoderBuilder::new().build(scored)
+}
+
+/// Scores every batch of a Flight stream and collects the results.
+pub async fn score_flight_stream<S>(
+    booster: &Booster,
+    input: S,
+    options: &FlightScoreOptions,
+) -> Result<Vec<RecordBatch>, FlightError>
+where
+    S: Stream<Item = Result<FlightData, FlightError>> + Send + 'static,
+{
+    FlightRecordBatchStream::new_from_flight_data(input)
+        .and_then(|batch| async move {
+            score_record_batch(booster, &batch, options)
+                .map_err(|e| FlightError::ExternalError(Box::new(e)))
+        })
+        .try_collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int32Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+
+    #[test]
+    fn test_to_dense() {
+        let schema = Schema::new(vec![
+            Field::new("user", DataType::Utf8, false),
+            Field::new("age", DataType::Int32, true),
+            Field::new("score", DataType::Float32, false),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec!["a", "b"])),
+                Arc::new(Int32Array::from(vec![Some(30), None])),
+                Arc::new(Float32Array::from(vec![0.5, 1.5])),
+            ],
+        )
+        .unwrap();
+        let options = FlightScoreOptions::new().id_column("user");
+        let (ids, data, cols) = to_dense(&batch, &options).unwrap();
+        assert_eq!(ids, vec![Some("a".to_string()), Some("b".to_string())]);
+        assert_eq!(cols, 2);
+        assert_eq!(data[..2], [30.0, 0.5]);
+        assert!(data[2].is_nan());
+        assert_eq!(data[3], 1.5);
+
+        let options = FlightScoreOptions::new().features(&["score", "age"]);
+        let (ids, data, _) = to_dense(&batch, &options).unwrap();
+        assert!(ids.is_empty());
+        assert_eq!(data[..2], [0.5, 30.0]);
+        assert!(to_dense(&batch, &FlightScoreOptions::new().features(&["missing"])).is_err());
+    }
+}
@@ -0,0 +1,192 @@
+//! Registry of live [`Booster`](crate::booster::Booster) and
+//! [`DMatrix`](crate::dmatrix::DMatrix) handles, for finding leaks in tests. Handles are
+//! recorded when a wrapper takes ownership of one and forgotten when it frees or releases
+//! it with `into_raw_handle`.
+//!
+//! The registry only exists in debug builds. With `debug_assertions` off, nothing is
+//! recorded, [`live_handles`] reports zero and [`assert_no_leaks`] never panics.
+
+use std::os::raw::c_void;
+
+#[cfg(debug_assertions)]
+use std::collections::BTreeMap;
+#[cfg(debug_assertions)]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(debug_assertions)]
+use std::sync::{Mutex, MutexGuard};
+#[cfg(debug_assertions)]
+use std::thread::{self, ThreadId};
+
+/// Whether handles are being tracked, i.e. whether this is a debug build.
+pub const ENABLED: bool = cfg!(debug_assertions);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HandleKind {
+    Booster,
+    DMatrix,
+}
+
+/// Number of handles currently owned by wrappers, across all threads.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LiveHandles {
+    pub boosters: usize,
+    pub dmatrices: usize,
+}
+
+impl LiveHandles {
+    pub fn total(&self) -> usize {
+        self.boosters + self.dmatrices
+    }
+
+    fn add(&mut self, kind: HandleKind) {
+        match kind {
+            HandleKind::Booster => self.boosters += 1,
+            HandleKind::DMatrix => self.dmatrices += 1,
+        }
+    }
+}
+
+#[cfg(debug_assertions)]
+struct Entry {
+    kind: HandleKind,
+    thread: ThreadId,
+    serial: u64,
+}
+
+/// Live handles by address.
+#[cfg(debug_assertions)]
+static REGISTRY: Mutex<BTreeMap<usize, Entry>> = Mutex::new(BTreeMap::new());
+#[cfg(debug_assertions)]
+static SERIAL: AtomicU64 = AtomicU64::new(0);
+
+#[cfg(debug_assertions)]
+fn registry() -> MutexGuard<'static, BTreeMap<usize, Entry>> {
+    // A panic while holding the lock leaves the map consistent, so poisoning is ignored
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Records that a wrapper now owns `handle`.
+pub(crate) fn register(kind: HandleKind, handle: *mut c_void) {
+    #[cfg(debug_assertions)]
+    if !handle.is_null() {
+        let entry = Entry {
+            kind,
+            thread: thread::current().id(),
+            serial: SERIAL.fetch_add(1, Ordering::Relaxed) + 1,
+        };
+        registry().insert(handle as usize, entry);
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = (kind, handle);
+}
+
+/// Records that `handle` was freed or handed back to the caller.
+pub(crate) fn unregister(handle: *mut c_void) {
+    #[cfg(debug_assertions)]
+    registry().remove(&(handle as usize));
+    #[cfg(not(debug_assertions))]
+    let _ = handle;
+}
+
+/// Handles currently owned by a `Booster` or `DMatrix`.
+pub fn live_handles() -> LiveHandles {
+    let mut live = LiveHandles::default();
+    #[cfg(debug_assertions)]
+    for entry in registry().values() {
+        live.add(entry.kind);
+    }
+    live
+}
+
+/// Runs `f` and panics if a handle it created on this thread is still alive afterwards.
+/// Handles created on other threads are not checked, so tests running in parallel do not
+/// interfere.
+///
+/// ```no_run
+/// use xgb_rs::{dmatrix::DMatrix, handles};
+///
+/// handles::assert_no_leaks(|| {
+///     let dmat = DMatrix::try_from_data(&[1.0, 2.0], 2, 1).unwrap();
+///     dmat.close().unwrap();
+/// });
+/// ```
+pub fn assert_no_leaks(f: impl FnOnce()) {
+    #[cfg(debug_assertions)]
+    {
+        let since = SERIAL.load(Ordering::Relaxed);
+        f();
+        let leaked = leaked_since(since, thread::current().id());
+        if leaked.total() > 0 {
+            panic!(
+                "{} XGBoost handle(s) leaked: {} Booster, {} DMatrix",
+                leaked.total(),
+                leaked.boosters,
+                leaked.dmatrices
+            );
+        }
+    }
+    #[cfg(not(debug_assertions))]
+    f()
+}
+
+/// Handles registered by `thread` after serial `since` and still alive.
+#[cfg(debug_assertions)]
+fn leaked_since(since: u64, thread: ThreadId) -> LiveHandles {
+    let mut leaked = LiveHandles::default();
+    for entry in registry().values() {
+        if entry.serial > since && entry.thread == thread {
+            leaked.add(entry.kind);
+        }
+    }
+    leaked
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    fn fake(address: usize) -> *mut c_void {
+        address as *mut c_void
+    }
+
+    #[test]
+    fn test_register() {
+        let since = SERIAL.load(Ordering::Relaxed);
+        register(HandleKind::Booster, fake(0x1000));
+        register(HandleKind::DMatrix, fake(0x2000));
+        register(HandleKind::DMatrix, std::ptr::null_mut());
+        let leaked = leaked_since(since, thread::current().id());
+        assert_eq!(
+            leaked,
+            LiveHandles {
+                boosters: 1,
+                dmatrices: 1
+            }
+        );
+        assert!(live_handles().total() >= 2);
+        unregister(fake(0x1000));
+        unregister(fake(0x2000));
+        assert_eq!(leaked_since(since, thread::current().id()).total(), 0);
+    }
+
+    #[test]
+    fn test_assert_no_leaks() {
+        assert_no_leaks(|| {
+            register(HandleKind::Booster, fake(0x3000));
+            unregister(fake(0x3000));
+        });
+        // Handles made on other threads are theirs to check
+        assert_no_leaks(|| {
+            thread::spawn(|| register(HandleKind::DMatrix, fake(0x4000)))
+                .join()
+                .unwrap();
+        });
+        unregister(fake(0x4000));
+    }
+
+    #[test]
+    #[should_panic(expected = "1 XGBoost handle(s) leaked: 0 Booster, 1 DMatrix")]
+    fn test_assert_no_leaks_panics() {
+        assert_no_leaks(|| register(HandleKind::DMatrix, fake(0x5000)));
+    }
+}
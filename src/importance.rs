@@ -0,0 +1,93 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// Importance measures computed by `XGBoosterFeatureScore`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImportanceType {
+    /// Number of splits using the feature (the only type for `gblinear`, where it is the
+    /// coefficient).
+    Weight,
+    #[default]
+    Gain,
+    Cover,
+    TotalGain,
+    TotalCover,
+}
+
+impl ImportanceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImportanceType::Weight => "weight",
+            ImportanceType::Gain => "gain",
+            ImportanceType::Cover => "cover",
+            ImportanceType::TotalGain => "total_gain",
+            ImportanceType::TotalCover => "total_cover",
+        }
+    }
+}
+
+/// Joins raw scores against `feature_names` and sorts them by decreasing importance.
+///
+/// Scores keyed `f{i}` are renamed to `feature_names[i]`, and features that never appear
+/// in a split are listed with a score of 0. With `normalize`, scores sum to 1.
+pub fn rank(
+    scores: &HashMap<String, f64>,
+    feature_names: &[String],
+    normalize: bool,
+) -> Vec<(String, f64)> {
+    let mut ranked: Vec<(String, f64)> = if feature_names.is_empty() {
+        scores.iter().map(|(k, v)| (k.clone(), *v)).collect()
+    } else {
+        feature_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                let score = scores
+                    .get(name)
+                    .or_else(|| scores.get(&format!("f{}", i)))
+                    .copied()
+                    .unwrap_or(0.0);
+                (name.clone(), score)
+            })
+            .collect()
+    };
+    if normalize {
+        let total: f64 = ranked.iter().map(|(_, v)| v).sum();
+        if total > 0.0 {
+            ranked.iter_mut().for_each(|(_, v)| *v /= total);
+        }
+    }
+    ranked.sort_by(|a, b| {
+        b.1.partial_cmp(&a.1)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rank_with_names() {
+        let scores = HashMap::from([("f0".to_string(), 1.0), ("f2".to_string(), 3.0)]);
+        let names = vec!["age".to_string(), "city".to_string(), "income".to_string()];
+        let ranked = rank(&scores, &names, true);
+        assert_eq!(
+            ranked,
+            vec![
+                ("income".to_string(), 0.75),
+                ("age".to_string(), 0.25),
+                ("city".to_string(), 0.0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rank_without_names() {
+        let scores = HashMap::from([("f0".to_string(), 1.0), ("f1".to_string(), 2.0)]);
+        let ranked = rank(&scores, &[], false);
+        assert_eq!(ranked[0], ("f1".to_string(), 2.0));
+    }
+}
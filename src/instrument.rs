@@ -0,0 +1,74 @@
+//! Optional observability around FFI calls. Without the `metrics` and `tracing` features
+//! every hook compiles down to a plain call.
+//!
+//! With `metrics`, predictions are reported through the `metrics` facade, labelled by
+//! `path` (`dmatrix` or `dense`):
+//! - `xgb_predict_calls_total` and `xgb_predict_errors_total` counters,
+//! - `xgb_predict_rows_total` counter,
+//! - `xgb_predict_batch_rows` and `xgb_predict_latency_seconds` histograms.
+//!
+//! With `tracing`, training rounds, predictions and DMatrix construction run inside
+//! `xgb` debug spans with `op`, `rows` and, once done, `elapsed_ms` fields, and failures
+//! that cannot be returned are logged as warnings. Without it, those failures go to the
+//! handler set with [`set_log_handler`](crate::config::set_log_handler), if any.
+
+#[cfg(any(feature = "metrics", feature = "tracing"))]
+use std::time::Instant;
+
+/// Runs a prediction of `rows` rows and records it.
+pub(crate) fn observe_prediction<T, E>(
+    path: &'static str,
+    rows: u64,
+    predict: impl FnOnce() -> Result<T, E>,
+) -> Result<T, E> {
+    traced("predict", rows, || {
+        #[cfg(feature = "metrics")]
+        let started = Instant::now();
+        let result = predict();
+        #[cfg(feature = "metrics")]
+        record_prediction(path, rows, started, result.is_ok());
+        #[cfg(not(feature = "metrics"))]
+        let _ = path;
+        result
+    })
+}
+
+#[cfg(feature = "metrics")]
+fn record_prediction(path: &'static str, rows: u64, started: Instant, ok: bool) {
+    let elapsed = started.elapsed().as_secs_f64();
+    ::metrics::counter!("xgb_predict_calls_total", "path" => path).increment(1);
+    if ok {
+        ::metrics::counter!("xgb_predict_rows_total", "path" => path).increment(rows);
+        ::metrics::histogram!("xgb_predict_batch_rows", "path" => path).record(rows as f64);
+    } else {
+        ::metrics::counter!("xgb_predict_errors_total", "path" => path).increment(1);
+    }
+    ::metrics::histogram!("xgb_predict_latency_seconds", "path" => path).record(elapsed);
+}
+
+/// Runs `f` inside a span for operation `op` on `rows` rows.
+#[cfg(feature = "tracing")]
+pub(crate) fn traced<T>(op: &'static str, rows: u64, f: impl FnOnce() -> T) -> T {
+    let span = tracing::debug_span!("xgb", op, rows, elapsed_ms = tracing::field::Empty);
+    let _guard = span.enter();
+    let started = Instant::now();
+    let result = f();
+    span.record("elapsed_ms", started.elapsed().as_secs_f64() * 1e3);
+    result
+}
+
+/// Reports a failure that cannot be returned, e.g. from `Drop`: as a `tracing` warning
+/// with the `tracing` feature, to the log handler otherwise. Nothing is printed if neither
+/// is set up.
+pub(crate) fn warn(message: &str) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!("{}", message);
+    #[cfg(not(feature = "tracing"))]
+    crate::config::log(&format!("xgb-rs: {}", message));
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn traced<T>(_op: &'static str, _rows: u64, f: impl FnOnce() -> T) -> T {
+    f()
+}
@@ -0,0 +1,482 @@
+//! Importing models saved by the XGBoost Python and R packages, and exporting to the
+//! canonical JSON schema.
+//!
+//! Models are exchanged as the files `Booster.save_model` (Python) or `xgb.save` (R) write:
+//! JSON, or UBJSON for `.ubj` names and the default since XGBoost 2.1. Pickles, R `.rds`
+//! objects and the legacy binary format embed the model in a package-specific container
+//! and are rejected with an explanation of how to re-save them. [`import_model`] checks
+//! the document against the schema and the linked library's version before handing it to
+//! XGBoost, so unsupported models fail with an [`InterchangeError`] rather than inside
+//! the C library. Other libraries' formats, such as LightGBM's text models, are not
+//! supported.
+
+use std::os::raw::c_int;
+
+use serde_json::{Map, Value};
+use thiserror::Error;
+use xgb_sys::XGBoostVersion;
+
+use crate::booster::{Booster, XGBoostError};
+
+/// Tree and linear boosters XGBoost can load.
+pub const SUPPORTED_BOOSTERS: [&str; 3] = ["gbtree", "dart", "gblinear"];
+/// Oldest release writing the JSON schema.
+const FIRST_JSON_VERSION: [u32; 3] = [1, 0, 0];
+
+#[derive(Error, Debug)]
+pub enum InterchangeError {
+    #[error("Unsupported model file: {0}")]
+    Format(String),
+    #[error("Invalid model document: {0}")]
+    Schema(String),
+    #[error("Unsupported learner type {0:?}, expected one of gbtree, dart or gblinear")]
+    UnsupportedLearner(String),
+    #[error("Model written by XGBoost {}, which {reason} (linked library is {})", fmt_version(.model), fmt_version(.library))]
+    Version {
+        model: [u32; 3],
+        library: [u32; 3],
+        reason: String,
+    },
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+}
+
+fn fmt_version(version: &[u32; 3]) -> String {
+    format!("{}.{}.{}", version[0], version[1], version[2])
+}
+
+/// Version of the linked libxgboost as `[major, minor, patch]`.
+pub fn library_version() -> [u32; 3] {
+    let (mut major, mut minor, mut patch): (c_int, c_int, c_int) = (0, 0, 0);
+    unsafe { XGBoostVersion(&mut major, &mut minor, &mut patch) };
+    [major as u32, minor as u32, patch as u32]
+}
+
+/// Container a model file was saved in, detected from its first bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceFormat {
+    Json,
+    Ubj,
+    /// A Python pickle, e.g. of an `XGBClassifier`.
+    Pickle,
+    /// An R object saved with `saveRDS` or `save`.
+    Rds,
+    /// The binary format of XGBoost before 1.0, which 3.0 no longer reads.
+    LegacyBinary,
+    Unknown,
+}
+
+impl SourceFormat {
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes {
+            [b'{', rest @ ..] => match rest.iter().find(|b| !b.is_ascii_whitespace()) {
+                Some(b'"') | Some(b'}') => SourceFormat::Json,
+                Some(_) => SourceFormat::Ubj,
+                None => SourceFormat::Json,
+            },
+            [0x80, 2..=5, ..] => SourceFormat::Pickle,
+            [0x1f, 0x8b, ..] | [b'X', b'\n', ..] | [b'R', b'D', b'X', ..] => SourceFormat::Rds,
+            [b'b', b'i', b'n', b'f', ..] => SourceFormat::LegacyBinary,
+            _ => SourceFormat::Unknown,
+        }
+    }
+
+    /// Why this container cannot be imported, and how to re-save the model, if it cannot.
+    fn rejection(&self) -> Option<&'static str> {
+        match self {
+            SourceFormat::Json | SourceFormat::Ubj => None,
+            SourceFormat::Pickle => Some(
+                "Python pickle; save the model with `booster.save_model(\"model.json\")`, \
+                 or `get_booster().save_model` for scikit-learn estimators",
+            ),
+            SourceFormat::Rds => Some(
+                "R object; save the model with `xgb.save(model, \"model.json\")` instead of \
+                 `saveRDS`",
+            ),
+            SourceFormat::LegacyBinary => Some(
+                "legacy binary model; load it with XGBoost 1.x or 2.x and save it again as \
+                 JSON or UBJSON",
+            ),
+            SourceFormat::Unknown => Some("not a JSON or UBJSON XGBoost model"),
+        }
+    }
+}
+
+/// Which model versions are accepted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VersionPolicy {
+    /// Any release from 1.0 up to the linked library. Newer models may use parameters or
+    /// tree layouts the library does not know.
+    #[default]
+    UpToLibrary,
+    /// Exactly this `major.minor`, to pin the XGBoost release models are exchanged with.
+    Pinned(u32, u32),
+}
+
+impl VersionPolicy {
+    pub(crate) fn check(&self, model: [u32; 3], library: [u32; 3]) -> Result<(), InterchangeError> {
+        let reason = match self {
+            _ if model < FIRST_JSON_VERSION => Some("predates the JSON schema".to_string()),
+            VersionPolicy::UpToLibrary if model[..2] > library[..2] => {
+                Some("is newer than the linked library".to_string())
+            }
+            VersionPolicy::Pinned(major, minor) if model[..2] != [*major, *minor] => {
+                Some(format!("does not match the pinned {}.{}", major, minor))
+            }
+            _ => None,
+        };
+        match reason {
+            Some(reason) => Err(InterchangeError::Version {
+                model,
+                library,
+                reason,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// What [`validate`] found out about a model document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelHeader {
+    /// XGBoost release that wrote the model.
+    pub version: [u32; 3],
+    /// `gbtree`, `dart` or `gblinear`.
+    pub booster: String,
+    pub objective: String,
+}
+
+/// Checks that `model` follows the JSON model schema with a supported learner, and
+/// returns its header.
+pub fn validate(model: &Value) -> Result<ModelHeader, InterchangeError> {
+    let schema = |what: &str| InterchangeError::Schema(what.to_string());
+    let version: Vec<u32> = model["version"]
+        .as_array()
+        .ok_or_else(|| schema("missing version"))?
+        .iter()
+        .map(|v| v.as_u64().map(|v| v as u32))
+        .collect::<Option<_>>()
+        .ok_or_else(|| schema("invalid version"))?;
+    let version: [u32; 3] = version
+        .try_into()
+        .map_err(|_| schema("version is not [major, minor, patch]"))?;
+    let learner = model["learner"]
+        .as_object()
+        .ok_or_else(|| schema("missing learner"))?;
+    let booster = learner
+        .get("gradient_booster")
+        .and_then(|b| b["name"].as_str())
+        .ok_or_else(|| schema("missing learner.gradient_booster.name"))?;
+    if !SUPPORTED_BOOSTERS.contains(&booster) {
+        return Err(InterchangeError::UnsupportedLearner(booster.to_string()));
+    }
+    let objective = learner
+        .get("objective")
+        .and_then(|o| o["name"].as_str())
+        .ok_or_else(|| schema("missing learner.objective.name"))?;
+    if !learner
+        .get("learner_model_param")
+        .is_some_and(Value::is_object)
+    {
+        return Err(schema("missing learner.learner_model_param"));
+    }
+    Ok(ModelHeader {
+        version,
+        booster: booster.to_string(),
+        objective: objective.to_string(),
+    })
+}
+
+/// Parses a JSON or UBJSON model file into its JSON document.
+pub fn parse_model(bytes: &[u8]) -> Result<(SourceFormat, Value), InterchangeError> {
+    let format = SourceFormat::detect(bytes);
+    if let Some(reason) = format.rejection() {
+        return Err(InterchangeError::Format(reason.to_string()));
+    }
+    let value = match format {
+        SourceFormat::Json => {
+            serde_json::from_slice(bytes).map_err(|e| InterchangeError::Schema(e.to_string()))?
+        }
+        _ => ubjson_to_json(bytes).map_err(InterchangeError::Schema)?,
+    };
+    Ok((format, value))
+}
+
+/// Loads a model saved by the Python or R package after validating it and checking its
+/// version against `policy`.
+pub fn import_model(bytes: &[u8], policy: VersionPolicy) -> Result<Booster, InterchangeError> {
+    let (_, model) = parse_model(bytes)?;
+    let header = validate(&model)?;
+    policy.check(header.version, library_version())?;
+    let mut booster = Booster::new()?;
+    booster.load_model_from_buffer(bytes)?;
+    Ok(booster)
+}
+
+/// Like [`import_model`], reading the model from `path`.
+pub fn import_model_file(path: &str, policy: VersionPolicy) -> Result<Booster, InterchangeError> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| InterchangeError::Format(format!("cannot read {}: {}", path, e)))?;
+    import_model(&bytes, policy)
+}
+
+/// The model as a canonical JSON document, validated against the schema. With
+/// [`VersionPolicy::Pinned`], fails unless the linked library is that release, so
+/// consumers expecting a given schema version never receive another.
+pub fn export_json(booster: &Booster, policy: VersionPolicy) -> Result<Value, InterchangeError> {
+    let model = booster.model_json()?;
+    let header = validate(&model)?;
+    policy.check(header.version, library_version())?;
+    Ok(model)
+}
+
+/// Writes [`export_json`] to `path`.
+pub fn export_json_file(
+    booster: &Booster,
+    path: &str,
+    policy: VersionPolicy,
+) -> Result<(), InterchangeError> {
+    let model = export_json(booster, policy)?;
+    let bytes = serde_json::to_vec(&model).map_err(|e| InterchangeError::Schema(e.to_string()))?;
+    std::fs::write(path, bytes)
+        .map_err(|e| InterchangeError::Format(format!("cannot write {}: {}", path, e)))
+}
+
+/// Decodes a UBJSON document as written by XGBoost, including its strongly typed arrays.
+pub(crate) fn ubjson_to_json(bytes: &[u8]) -> Result<Value, String> {
+    let mut reader = UbjReader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    let marker = reader.byte()?;
+    reader.value(marker)
+}
+
+/// Deepest nesting of arrays and objects accepted, like `serde_json`. Models nest a few
+/// levels; a crafted file must not overflow the stack.
+const MAX_UBJ_DEPTH: usize = 128;
+
+struct UbjReader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Containers currently open.
+    depth: usize,
+}
+
+impl UbjReader<'_> {
+    fn take(&mut self, n: usize) -> Result<&[u8], String> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| format!("truncated UBJSON at byte {}", self.pos))?;
+        let out = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn int(&mut self, marker: u8) -> Result<i64, String> {
+        Ok(match marker {
+            b'i' => self.byte()? as i8 as i64,
+            b'U' => self.byte()? as i64,
+            b'I' => i16::from_be_bytes(self.take(2)?.try_into().unwrap()) as i64,
+            b'l' => i32::from_be_bytes(self.take(4)?.try_into().unwrap()) as i64,
+            b'L' => i64::from_be_bytes(self.take(8)?.try_into().unwrap()),
+            other => {
+                return Err(format!(
+                    "expected an integer, got marker {:?}",
+                    other as char
+                ))
+            }
+        })
+    }
+
+    fn length(&mut self) -> Result<usize, String> {
+        let marker = self.byte()?;
+        usize::try_from(self.int(marker)?).map_err(|_| "negative UBJSON length".to_string())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let len = self.length()?;
+        String::from_utf8(self.take(len)?.to_vec()).map_err(|e| e.to_string())
+    }
+
+    fn value(&mut self, marker: u8) -> Result<Value, String> {
+        Ok(match marker {
+            b'Z' => Value::Null,
+            b'T' => Value::Bool(true),
+            b'F' => Value::Bool(false),
+            b'i' | b'U' | b'I' | b'l' | b'L' => self.int(marker)?.into(),
+            b'd' => f32::from_be_bytes(self.take(4)?.try_into().unwrap()).into(),
+            b'D' => f64::from_be_bytes(self.take(8)?.try_into().unwrap()).into(),
+            b'C' => (self.byte()? as char).to_string().into(),
+            b'S' | b'H' => self.string()?.into(),
+            b'[' => Value::Array(self.container(false)?.into_iter().map(|(_, v)| v).collect()),
+            b'{' => Value::Object(self.container(true)?.into_iter().collect::<Map<_, _>>()),
+            other => return Err(format!("unknown UBJSON marker {:?}", other as char)),
+        })
+    }
+
+    /// Entries of an array or object, whose opening marker was read, with the optional
+    /// `$` type and `#` count headers. Array entries have empty keys.
+    fn container(&mut self, object: bool) -> Result<Vec<(String, Value)>, String> {
+        if self.depth == MAX_UBJ_DEPTH {
+            return Err(format!(
+                "UBJSON nested deeper than {} levels at byte {}",
+                MAX_UBJ_DEPTH, self.pos
+            ));
+        }
+        self.depth += 1;
+        let entries = self.entries(object);
+        self.depth -= 1;
+        entries
+    }
+
+    fn entries(&mut self, object: bool) -> Result<Vec<(String, Value)>, String> {
+        let mut element_type = None;
+        if self.peek() == Some(b'$') {
+            self.pos += 1;
+            element_type = Some(self.byte()?);
+        }
+        let count = if self.peek() == Some(b'#') {
+            self.pos += 1;
+            Some(self.length()?)
+        } else if element_type.is_some() {
+            return Err("UBJSON type marker without a count".to_string());
+        } else {
+            None
+        };
+        let end = if object { b'}' } else { b']' };
+        let mut entries = Vec::with_capacity(count.unwrap_or(0).min(1 << 20));
+        loop {
+            match count {
+                Some(count) if entries.len() == count => break,
+                None if self.peek() == Some(end) => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => {}
+            }
+            let key = if object {
+                self.string()?
+            } else {
+                String::new()
+            };
+            let marker = match element_type {
+                Some(marker) => marker,
+                None => self.byte()?,
+            };
+            entries.push((key, self.value(marker)?));
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn minimal_model(version: [u32; 3], booster: &str) -> Value {
+        json!({
+            "version": version,
+            "learner": {
+                "gradient_booster": {"name": booster},
+                "objective": {"name": "binary:logistic"},
+                "learner_model_param": {"num_feature": "2"}
+            }
+        })
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            SourceFormat::detect(b"{ \"learner\": {}}"),
+            SourceFormat::Json
+        );
+        assert_eq!(SourceFormat::detect(b"{L\x00"), SourceFormat::Ubj);
+        assert_eq!(SourceFormat::detect(&[0x80, 4, 0x95]), SourceFormat::Pickle);
+        assert_eq!(SourceFormat::detect(&[0x1f, 0x8b, 8]), SourceFormat::Rds);
+        assert_eq!(
+            SourceFormat::detect(b"binf\x00"),
+            SourceFormat::LegacyBinary
+        );
+        assert!(matches!(
+            parse_model(&[0x80, 4, 0x95]),
+            Err(InterchangeError::Format(reason)) if reason.contains("save_model")
+        ));
+    }
+
+    #[test]
+    fn test_validate() {
+        let header = validate(&minimal_model([2, 1, 4], "gbtree")).unwrap();
+        assert_eq!(header.version, [2, 1, 4]);
+        assert_eq!(header.objective, "binary:logistic");
+        assert!(matches!(
+            validate(&minimal_model([2, 1, 4], "gbforest")),
+            Err(InterchangeError::UnsupportedLearner(name)) if name == "gbforest"
+        ));
+        assert!(matches!(
+            validate(&json!({"learner": {}})),
+            Err(InterchangeError::Schema(_))
+        ));
+    }
+
+    #[test]
+    fn test_version_policy() {
+        let library = [3, 0, 2];
+        assert!(VersionPolicy::UpToLibrary.check([2, 1, 4], library).is_ok());
+        assert!(VersionPolicy::UpToLibrary.check([3, 0, 5], library).is_ok());
+        assert!(VersionPolicy::UpToLibrary
+            .check([3, 1, 0], library)
+            .is_err());
+        assert!(VersionPolicy::UpToLibrary
+            .check([0, 90, 0], library)
+            .is_err());
+        assert!(VersionPolicy::Pinned(2, 1)
+            .check([2, 1, 0], library)
+            .is_ok());
+        assert!(VersionPolicy::Pinned(2, 1)
+            .check([2, 0, 3], library)
+            .is_err());
+    }
+
+    #[test]
+    fn test_ubjson_to_json() {
+        let mut doc = b"{L\x00\x00\x00\x00\x00\x00\x00\x07version".to_vec();
+        doc.extend_from_slice(b"[$i#i\x03\x02\x01\x04");
+        doc.extend_from_slice(b"i\x04nameSi\x06gbtree");
+        doc.extend_from_slice(b"i\x01w[$d#i\x01\x3f\x00\x00\x00");
+        doc.extend_from_slice(b"i\x01x[TZ]}");
+        assert_eq!(
+            ubjson_to_json(&doc).unwrap(),
+            json!({"version": [2, 1, 4], "name": "gbtree", "w": [0.5], "x": [true, null]})
+        );
+        assert!(ubjson_to_json(b"{i\x07vers").is_err());
+
+        let nested = |depth: usize| {
+            let mut doc = vec![b'['; depth];
+            doc.extend(std::iter::repeat_n(b']', depth));
+            doc
+        };
+        assert!(ubjson_to_json(&nested(MAX_UBJ_DEPTH)).is_ok());
+        let err = ubjson_to_json(&nested(100_000)).unwrap_err();
+        assert!(err.contains("nested deeper"));
+        let mut doc = b"{i\x01a".to_vec();
+        doc.extend(nested(100_000));
+        doc.push(b'}');
+        assert!(matches!(
+            parse_model(&doc),
+            Err(InterchangeError::Schema(_))
+        ));
+    }
+}
@@ -0,0 +1,74 @@
+//! Conversions from other Rust numeric crates, each behind its own feature.
+
+#[cfg(feature = "nalgebra")]
+mod nalgebra_interop {
+    use crate::booster::{Booster, XGBoostError};
+    use crate::dmatrix::{DMatrix, DMatrixError};
+
+    /// nalgebra stores matrices column-major, XGBoost expects row-major: the data is
+    /// transposed on the way in.
+    impl TryFrom<&nalgebra::DMatrix<f32>> for DMatrix {
+        type Error = DMatrixError;
+
+        fn try_from(matrix: &nalgebra::DMatrix<f32>) -> Result<Self, Self::Error> {
+            let row_major = matrix.transpose();
+            DMatrix::try_from_data(
+                row_major.as_slice(),
+                matrix.nrows() as u64,
+                matrix.ncols() as u64,
+            )
+        }
+    }
+
+    impl Booster {
+        pub fn predict_dvector(
+            &self,
+            data: &DMatrix,
+        ) -> Result<nalgebra::DVector<f32>, XGBoostError> {
+            Ok(nalgebra::DVector::from_vec(self.predict(data)?))
+        }
+    }
+}
+
+#[cfg(feature = "candle")]
+mod candle_interop {
+    use crate::dmatrix::{DMatrix, DMatrixError};
+
+    /// Accepts a 2D tensor of any float dtype; it is copied to host memory and cast to f32.
+    impl TryFrom<&candle_core::Tensor> for DMatrix {
+        type Error = DMatrixError;
+
+        fn try_from(tensor: &candle_core::Tensor) -> Result<Self, Self::Error> {
+            let convert = |e: candle_core::Error| DMatrixError::Convert(e.to_string());
+            let (rows, cols) = tensor.dims2().map_err(convert)?;
+            let data = tensor
+                .to_dtype(candle_core::DType::F32)
+                .and_then(|t| t.flatten_all())
+                .and_then(|t| t.to_vec1::<f32>())
+                .map_err(convert)?;
+            DMatrix::try_from_data(&data, rows as u64, cols as u64)
+        }
+    }
+}
+
+#[cfg(feature = "burn")]
+mod burn_interop {
+    use burn::tensor::{backend::Backend, Tensor};
+
+    use crate::dmatrix::{DMatrix, DMatrixError};
+
+    /// Reads the tensor back from its backend and casts it to f32.
+    impl<B: Backend> TryFrom<Tensor<B, 2>> for DMatrix {
+        type Error = DMatrixError;
+
+        fn try_from(tensor: Tensor<B, 2>) -> Result<Self, Self::Error> {
+            let [rows, cols] = tensor.dims();
+            let data = tensor
+                .into_data()
+                .convert::<f32>()
+                .to_vec::<f32>()
+                .map_err(|e| DMatrixError::Convert(format!("{:?}", e)))?;
+            DMatrix::try_from_data(&data, rows as u64, cols as u64)
+        }
+    }
+}
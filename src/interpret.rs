@@ -0,0 +1,660 @@
+//! Model-agnostic interpretation tools built on repeated prediction.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::{DMatrix, DMatrixError};
+use crate::metrics::{Metric, MetricError};
+use crate::predict::{Device, PredictOptions, PredictionType};
+
+#[derive(Error, Debug)]
+pub enum InterpretError {
+    #[error("Invalid argument: {0}")]
+    Argument(String),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+    #[error(transparent)]
+    Metric(#[from] MetricError),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermutationImportance {
+    pub feature: usize,
+    pub name: String,
+    /// Mean metric degradation when the feature is shuffled; positive means the model
+    /// relies on it.
+    pub mean: f64,
+    pub std: f64,
+}
+
+/// Shuffles each column of `data` in turn, `n_repeats` times, and measures how much
+/// `metric` degrades against the labels of `data`. Sorted by decreasing importance.
+pub fn permutation_importance(
+    booster: &Booster,
+    data: &DMatrix,
+    metric: Metric,
+    n_repeats: usize,
+    seed: u64,
+) -> Result<Vec<PermutationImportance>, InterpretError> {
+    if n_repeats == 0 {
+        return Err(InterpretError::Argument(
+            "n_repeats must be positive".to_string(),
+        ));
+    }
+    let cols = data.num_cols() as usize;
+    let labels = data.get_labels()?;
+    let group_ptr = data.get_group_ptr()?;
+    let names = feature_names(data)?;
+    let dense = data.to_dense()?;
+    let baseline = metric.compute(&booster.predict(data)?, &labels, &group_ptr)?;
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut result = Vec::with_capacity(cols);
+    for feature in 0..cols {
+        let mut drops = Vec::with_capacity(n_repeats);
+        for _ in 0..n_repeats {
+            let mut column: Vec<f32> = dense.iter().skip(feature).step_by(cols).copied().collect();
+            column.shuffle(&mut rng);
+            let mut permuted = dense.clone();
+            for (row, value) in column.into_iter().enumerate() {
+                permuted[row * cols + feature] = value;
+            }
+            let dmat = data.with_values(&permuted)?;
+            let score = metric.compute(&booster.predict(&dmat)?, &labels, &group_ptr)?;
+            drops.push(if metric.higher_is_better() {
+                baseline - score
+            } else {
+                score - baseline
+            });
+        }
+        let (mean, std) = mean_std(&drops);
+        result.push(PermutationImportance {
+            feature,
+            name: names[feature].clone(),
+            mean,
+            std,
+        });
+    }
+    result.sort_by(|a, b| b.mean.total_cmp(&a.mean));
+    Ok(result)
+}
+
+/// Partial dependence of the prediction on one or two features.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartialDependence {
+    pub features: Vec<usize>,
+    /// Grid values for each feature in `features`.
+    pub grid: Vec<Vec<f32>>,
+    /// Mean prediction per grid point. With two features, point `(i, j)` is at
+    /// `i * grid[1].len() + j`.
+    pub average: Vec<f32>,
+    /// Per-row predictions per grid point (ICE curves), if requested.
+    pub individual: Option<Vec<Vec<f32>>>,
+}
+
+/// Sweeps `features` (one or two) over a grid and re-predicts every row of `data`.
+///
+/// Categorical features (feature type `"c"`) are swept over all observed categories.
+/// Numerical features use up to `grid_resolution` evenly spaced points between the 5th
+/// and 95th percentiles, or their distinct values when there are fewer.
+pub fn partial_dependence(
+    booster: &Booster,
+    data: &DMatrix,
+    features: &[usize],
+    grid_resolution: usize,
+    ice: bool,
+) -> Result<PartialDependence, InterpretError> {
+    let cols = data.num_cols() as usize;
+    if features.is_empty() || features.len() > 2 || features.iter().any(|f| *f >= cols) {
+        return Err(InterpretError::Argument(format!(
+            "expected one or two features below {}, got {:?}",
+            cols, features
+        )));
+    }
+    if grid_resolution < 2 {
+        return Err(InterpretError::Argument(
+            "grid_resolution must be at least 2".to_string(),
+        ));
+    }
+    let rows = data.num_rows() as usize;
+    let dense = data.to_dense()?;
+    let types = data.feature_types()?;
+    let grid: Vec<Vec<f32>> = features
+        .iter()
+        .map(|f| {
+            let column: Vec<f32> = dense.iter().skip(*f).step_by(cols).copied().collect();
+            let categorical = types.get(*f).is_some_and(|t| t == "c");
+            feature_grid(column, grid_resolution, categorical)
+        })
+        .collect();
+
+    let points: Vec<Vec<f32>> = match grid.as_slice() {
+        [g] => g.iter().map(|v| vec![*v]).collect(),
+        [g0, g1] => g0
+            .iter()
+            .flat_map(|a| g1.iter().map(move |b| vec![*a, *b]))
+            .collect(),
+        _ => unreachable!(),
+    };
+
+    let mut average = Vec::with_capacity(points.len());
+    let mut individual = vec![Vec::with_capacity(points.len()); if ice { rows } else { 0 }];
+    for point in &points {
+        let mut modified = dense.clone();
+        for row in 0..rows {
+            for (feature, value) in features.iter().zip(point) {
+                modified[row * cols + feature] = *value;
+            }
+        }
+        let dmat = data.with_values(&modified)?;
+        let preds = booster.predict(&dmat)?;
+        if preds.len() != rows {
+            return Err(InterpretError::Argument(
+                "partial dependence needs a single output per row".to_string(),
+            ));
+        }
+        average.push(preds.iter().sum::<f32>() / rows.max(1) as f32);
+        for (curve, pred) in individual.iter_mut().zip(&preds) {
+            curve.push(*pred);
+        }
+    }
+    Ok(PartialDependence {
+        features: features.to_vec(),
+        grid,
+        average,
+        individual: if ice { Some(individual) } else { None },
+    })
+}
+
+/// A step along a feature sweep where predictions move against the declared constraint.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonotoneViolation {
+    pub feature: usize,
+    pub name: String,
+    /// Probe row whose ICE curve violates the constraint.
+    pub row: usize,
+    pub from: f32,
+    pub to: f32,
+    /// Prediction change from `from` to `to`, of the wrong sign.
+    pub change: f32,
+}
+
+/// Checks declared monotone constraints empirically: every feature with a non-zero entry
+/// in `constraints` (`1` increasing, `-1` decreasing, as in XGBoost's
+/// `monotone_constraints`) is swept over a grid for each row of `data`, and every step
+/// where the prediction moves the wrong way by more than `tolerance` is reported.
+pub fn check_monotone(
+    booster: &Booster,
+    data: &DMatrix,
+    constraints: &[i8],
+    grid_resolution: usize,
+    tolerance: f32,
+) -> Result<Vec<MonotoneViolation>, InterpretError> {
+    if constraints.len() != data.num_cols() as usize {
+        return Err(InterpretError::Argument(format!(
+            "{} constraints for {} features",
+            constraints.len(),
+            data.num_cols()
+        )));
+    }
+    let names = feature_names(data)?;
+    let mut violations = Vec::new();
+    for (feature, direction) in constraints.iter().enumerate() {
+        if *direction == 0 {
+            continue;
+        }
+        let pd = partial_dependence(booster, data, &[feature], grid_resolution, true)?;
+        let grid = &pd.grid[0];
+        for (row, curve) in pd.individual.unwrap_or_default().iter().enumerate() {
+            for (step, change) in curve_violations(curve, *direction, tolerance) {
+                violations.push(MonotoneViolation {
+                    feature,
+                    name: names[feature].clone(),
+                    row,
+                    from: grid[step],
+                    to: grid[step + 1],
+                    change,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+/// Steps `i` of `curve` where `curve[i + 1] - curve[i]` goes against `direction`.
+fn curve_violations(curve: &[f32], direction: i8, tolerance: f32) -> Vec<(usize, f32)> {
+    curve
+        .windows(2)
+        .enumerate()
+        .map(|(i, w)| (i, w[1] - w[0]))
+        .filter(|(_, change)| change * (direction.signum() as f32) < -tolerance)
+        .collect()
+}
+
+/// One feature's share of a prediction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Reason {
+    pub feature: String,
+    /// Input value, NaN when missing (serialized as `null`).
+    pub value: f32,
+    /// SHAP contribution to the margin.
+    pub contribution: f32,
+}
+
+/// SHAP explanation of one row's margin for one output group, ready to be returned by a
+/// serving endpoint: `margin == base_value + sum of contributions`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Explanation {
+    pub row: usize,
+    pub group: usize,
+    pub margin: f32,
+    pub base_value: f32,
+    /// In feature order.
+    pub reasons: Vec<Reason>,
+}
+
+impl Explanation {
+    /// The `k` reasons with the largest absolute contribution, largest first.
+    pub fn top_k(&self, k: usize) -> Vec<&Reason> {
+        let mut reasons: Vec<&Reason> = self.reasons.iter().collect();
+        reasons.sort_by(|a, b| b.contribution.abs().total_cmp(&a.contribution.abs()));
+        reasons.truncate(k);
+        reasons
+    }
+}
+
+/// How SHAP contributions are computed by [`explain_with`] and
+/// [`global_shap_summary_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShapOptions {
+    approximate: bool,
+    device: Option<Device>,
+}
+
+impl ShapOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use Saabas-style approximate contributions, much faster on deep ensembles but not
+    /// consistent like exact TreeSHAP.
+    pub fn approximate(mut self, approximate: bool) -> Self {
+        self.approximate = approximate;
+        self
+    }
+
+    /// Device computing the contributions. `Device::Cuda` runs TreeSHAP on the GPU and
+    /// falls back to the CPU when the library was built without CUDA. Like
+    /// [`PredictOptions::device`] it only applies to this call; the booster's own device
+    /// is restored afterwards.
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    fn predict_options(&self) -> PredictOptions {
+        let options = PredictOptions::new()
+            .kind(PredictionType::Contributions)
+            .approx_contribs(self.approximate)
+            .strict_shape(true);
+        match self.device {
+            Some(device) => options.device(device),
+            None => options,
+        }
+    }
+}
+
+/// Computes SHAP contributions for every row of `data` and joins them with feature names
+/// and input values. Multi-output models get one explanation per row and group, row by
+/// row.
+pub fn explain(booster: &Booster, data: &DMatrix) -> Result<Vec<Explanation>, InterpretError> {
+    explain_with(booster, data, &ShapOptions::new())
+}
+
+/// [`explain`] with approximate or GPU contributions.
+pub fn explain_with(
+    booster: &Booster,
+    data: &DMatrix,
+    options: &ShapOptions,
+) -> Result<Vec<Explanation>, InterpretError> {
+    let prediction = booster.predict_with_shape(data, &options.predict_options())?;
+    let values = data.to_dense()?;
+    let names = feature_names(data)?;
+    Ok(build_explanations(
+        &prediction.values,
+        prediction.shape.0[1] as usize,
+        &values,
+        &names,
+    ))
+}
+
+/// `contributions` laid out as `(rows, groups, features + 1)`, bias last.
+fn build_explanations(
+    contributions: &[f32],
+    groups: usize,
+    values: &[f32],
+    names: &[String],
+) -> Vec<Explanation> {
+    let cols = names.len();
+    contributions
+        .chunks(cols + 1)
+        .enumerate()
+        .map(|(i, contribs)| {
+            let (row, group) = (i / groups.max(1), i % groups.max(1));
+            let base_value = contribs[cols];
+            Explanation {
+                row,
+                group,
+                margin: contribs.iter().sum(),
+                base_value,
+                reasons: names
+                    .iter()
+                    .zip(&values[row * cols..(row + 1) * cols])
+                    .zip(contribs)
+                    .map(|((name, value), contribution)| Reason {
+                        feature: name.clone(),
+                        value: *value,
+                        contribution: *contribution,
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
+
+/// Rows scored per batch by [`global_shap_summary`].
+const SHAP_CHUNK_ROWS: usize = 4096;
+
+/// Aggregated SHAP contributions of one feature over a dataset.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeatureShap {
+    pub feature: String,
+    /// Mean absolute contribution, the usual global importance.
+    pub mean_abs: f64,
+    /// Mean signed contribution.
+    pub mean: f64,
+    /// Share of contributions above zero.
+    pub positive_fraction: f64,
+    /// Pearson correlation between the feature's value and its contribution over rows
+    /// where it is present: positive when larger values push predictions up. `None` when
+    /// either side is constant.
+    pub value_correlation: Option<f64>,
+}
+
+/// Dataset-wide SHAP summary. Multi-output models are summarized over all rows and
+/// groups together.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShapSummary {
+    pub rows: u64,
+    pub groups: usize,
+    /// Mean bias term.
+    pub base_value: f64,
+    /// Sorted by decreasing `mean_abs`.
+    pub features: Vec<FeatureShap>,
+}
+
+/// Computes SHAP contributions for `data` in batches of rows and aggregates them per
+/// feature, so memory stays bounded by one batch regardless of the dataset size.
+pub fn global_shap_summary(
+    booster: &Booster,
+    data: &DMatrix,
+) -> Result<ShapSummary, InterpretError> {
+    global_shap_summary_with(booster, data, &ShapOptions::new())
+}
+
+/// [`global_shap_summary`] with approximate or GPU contributions.
+pub fn global_shap_summary_with(
+    booster: &Booster,
+    data: &DMatrix,
+    options: &ShapOptions,
+) -> Result<ShapSummary, InterpretError> {
+    let options = options.predict_options();
+    let names = feature_names(data)?;
+    let mut acc = ShapAccumulator::new(names.len());
+    let num_rows = data.num_rows() as usize;
+    let mut groups = 1;
+    for start in (0..num_rows).step_by(SHAP_CHUNK_ROWS) {
+        let rows: Vec<i32> = (start..num_rows.min(start + SHAP_CHUNK_ROWS))
+            .map(|r| r as i32)
+            .collect();
+        let chunk = data.slice(&rows)?;
+        let prediction = booster.predict_with_shape(&chunk, &options)?;
+        groups = prediction.shape.0[1] as usize;
+        acc.add(&prediction.values, groups, &chunk.to_dense()?);
+    }
+    Ok(acc.finish(names, data.num_rows(), groups))
+}
+
+/// Running sums per feature of `(rows, groups, features + 1)` contributions.
+struct ShapAccumulator {
+    cols: usize,
+    count: f64,
+    bias: f64,
+    abs: Vec<f64>,
+    sum: Vec<f64>,
+    positive: Vec<f64>,
+    // Sums over rows where the feature is present, for the value correlation
+    present: Vec<f64>,
+    x: Vec<f64>,
+    xx: Vec<f64>,
+    y: Vec<f64>,
+    yy: Vec<f64>,
+    xy: Vec<f64>,
+}
+
+impl ShapAccumulator {
+    fn new(cols: usize) -> Self {
+        ShapAccumulator {
+            cols,
+            count: 0.0,
+            bias: 0.0,
+            abs: vec![0.0; cols],
+            sum: vec![0.0; cols],
+            positive: vec![0.0; cols],
+            present: vec![0.0; cols],
+            x: vec![0.0; cols],
+            xx: vec![0.0; cols],
+            y: vec![0.0; cols],
+            yy: vec![0.0; cols],
+            xy: vec![0.0; cols],
+        }
+    }
+
+    fn add(&mut self, contributions: &[f32], groups: usize, values: &[f32]) {
+        let cols = self.cols;
+        for (i, contribs) in contributions.chunks(cols + 1).enumerate() {
+            let row = i / groups.max(1);
+            self.count += 1.0;
+            self.bias += contribs[cols] as f64;
+            for (f, (c, v)) in contribs[..cols]
+                .iter()
+                .zip(&values[row * cols..(row + 1) * cols])
+                .enumerate()
+            {
+                let (c, v) = (*c as f64, *v as f64);
+                self.abs[f] += c.abs();
+                self.sum[f] += c;
+                if c > 0.0 {
+                    self.positive[f] += 1.0;
+                }
+                if !v.is_nan() {
+                    self.present[f] += 1.0;
+                    self.x[f] += v;
+                    self.xx[f] += v * v;
+                    self.y[f] += c;
+                    self.yy[f] += c * c;
+                    self.xy[f] += v * c;
+                }
+            }
+        }
+    }
+
+    fn finish(self, names: Vec<String>, rows: u64, groups: usize) -> ShapSummary {
+        let n = self.count.max(1.0);
+        let mut features: Vec<FeatureShap> = names
+            .into_iter()
+            .enumerate()
+            .map(|(f, feature)| {
+                let m = self.present[f];
+                let cov = m * self.xy[f] - self.x[f] * self.y[f];
+                let var_x = m * self.xx[f] - self.x[f] * self.x[f];
+                let var_y = m * self.yy[f] - self.y[f] * self.y[f];
+                let value_correlation = (var_x > 1e-12 && var_y > 1e-12)
+                    .then(|| (cov / (var_x.sqrt() * var_y.sqrt())).clamp(-1.0, 1.0));
+                FeatureShap {
+                    feature,
+                    mean_abs: self.abs[f] / n,
+                    mean: self.sum[f] / n,
+                    positive_fraction: self.positive[f] / n,
+                    value_correlation,
+                }
+            })
+            .collect();
+        features.sort_by(|a, b| b.mean_abs.total_cmp(&a.mean_abs));
+        ShapSummary {
+            rows,
+            groups,
+            base_value: self.bias / n,
+            features,
+        }
+    }
+}
+
+fn feature_grid(mut column: Vec<f32>, resolution: usize, categorical: bool) -> Vec<f32> {
+    column.retain(|v| !v.is_nan());
+    column.sort_by(|a, b| a.total_cmp(b));
+    let mut distinct = column.clone();
+    distinct.dedup();
+    if categorical || distinct.len() <= resolution {
+        return distinct;
+    }
+    let percentile = |p: f64| column[((column.len() - 1) as f64 * p).round() as usize];
+    let (lo, hi) = (percentile(0.05), percentile(0.95));
+    (0..resolution)
+        .map(|i| lo + (hi - lo) * i as f32 / (resolution - 1) as f32)
+        .collect()
+}
+
+/// DMatrix feature names, or `f{index}` when none are set.
+fn feature_names(data: &DMatrix) -> Result<Vec<String>, DMatrixError> {
+    let names = data.feature_names()?;
+    if names.is_empty() {
+        Ok((0..data.num_cols()).map(|i| format!("f{}", i)).collect())
+    } else {
+        Ok(names)
+    }
+}
+
+pub(crate) fn mean_std(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n;
+    (mean, var.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_grid() {
+        let column: Vec<f32> = (0..=100).map(|v| v as f32).collect();
+        let grid = feature_grid(column, 3, false);
+        assert_eq!(grid, vec![5.0, 50.0, 95.0]);
+        let grid = feature_grid(vec![2.0, f32::NAN, 1.0, 2.0], 10, false);
+        assert_eq!(grid, vec![1.0, 2.0]);
+        let grid = feature_grid(vec![3.0, 1.0, 2.0, 1.0], 2, true);
+        assert_eq!(grid, vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_curve_violations() {
+        let curve = [0.1, 0.2, 0.15, 0.3, 0.29];
+        assert_eq!(curve_violations(&curve, 1, 0.02).len(), 1);
+        assert_eq!(curve_violations(&curve, 1, 0.0).len(), 2);
+        assert_eq!(curve_violations(&curve, -1, 0.0).len(), 2);
+        assert_eq!(curve_violations(&curve, -1, 0.0)[0].0, 0);
+    }
+
+    #[test]
+    fn test_explanations() {
+        let names = vec!["age".to_string(), "income".to_string()];
+        let explanations = build_explanations(
+            &[0.1, -0.5, 0.2, 0.3, 0.0, 0.2],
+            1,
+            &[30.0, 1.0, 40.0, f32::NAN],
+            &names,
+        );
+        assert_eq!(explanations.len(), 2);
+        let first = &explanations[0];
+        assert!((first.margin - -0.2).abs() < 1e-6);
+        assert_eq!(first.base_value, 0.2);
+        let top = first.top_k(1);
+        assert_eq!(top[0].feature, "income");
+        let json = serde_json::to_string(&explanations[1]).unwrap();
+        assert!(json.contains("\"value\":null"));
+    }
+
+    #[test]
+    fn test_shap_accumulator() {
+        let mut acc = ShapAccumulator::new(2);
+        // Feature "a" rises with its value, "b" is missing on the second row
+        acc.add(
+            &[-0.2, 0.1, 0.5, 0.4, -0.1, 0.5],
+            1,
+            &[1.0, 5.0, 3.0, f32::NAN],
+        );
+        acc.add(&[1.0, 0.0, 0.5], 1, &[5.0, 1.0]);
+        let summary = acc.finish(vec!["a".to_string(), "b".to_string()], 3, 1);
+        assert_eq!(summary.base_value, 0.5);
+        let a = &summary.features[0];
+        assert_eq!(a.feature, "a");
+        assert!((a.mean_abs - 1.6 / 3.0).abs() < 1e-6);
+        assert!((a.mean - 1.2 / 3.0).abs() < 1e-6);
+        assert!((a.positive_fraction - 2.0 / 3.0).abs() < 1e-6);
+        assert!((a.value_correlation.unwrap() - 1.0).abs() < 1e-6);
+        let b = &summary.features[1];
+        assert!((b.value_correlation.unwrap() - 1.0).abs() < 1e-6);
+        let json = serde_json::to_string(&summary).unwrap();
+        assert!(json.contains("\"feature\":\"b\""));
+    }
+
+    #[test]
+    fn test_shap_options() {
+        let json = ShapOptions::new().predict_options().to_json();
+        assert!(json.starts_with("{\"type\": 2,"));
+        assert!(json.contains("\"strict_shape\": true"));
+        let options = ShapOptions::new()
+            .approximate(true)
+            .device(Device::Cuda(0))
+            .predict_options();
+        assert!(options.to_json().starts_with("{\"type\": 3,"));
+        assert_eq!(options.device, Some(Device::Cuda(0)));
+    }
+
+    #[test]
+    fn test_shap_device_restored() {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[0., 1.]).unwrap();
+        let booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let device =
+            |b: &Booster| b.config().unwrap()["learner"]["generic_param"]["device"].clone();
+        let before = device(&booster);
+        let options = ShapOptions::new().device(Device::Cuda(0));
+        assert_eq!(explain_with(&booster, &dtrain, &options).unwrap().len(), 2);
+        assert_eq!(device(&booster), before);
+    }
+
+    #[test]
+    fn test_mean_std() {
+        let (mean, std) = mean_std(&[1.0, 3.0]);
+        assert_eq!(mean, 2.0);
+        assert_eq!(std, 1.0);
+    }
+}
@@ -0,0 +1,295 @@
+//! Streaming scoring from Kafka, behind the `kafka` feature. Feature records are
+//! consumed from an input topic, scored in batches with the current model of a
+//! [`ReloadableBooster`], and produced to an output topic under the same key.
+//!
+//! Records are JSON, `{"id": "u42", "features": [1.0, null, 3.0]}` with `id` optional and
+//! `null` for missing values; results are `{"id": "u42", "prediction": [0.7],
+//! "model_version": 0}`. Offsets are committed only once a batch's results are
+//! acknowledged, so delivery is at least once. Consumption waits while a batch is scored
+//! and produced, which bounds memory and passes backpressure from the output topic and
+//! the scorer to the input topic. Malformed records are skipped and counted.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use rdkafka::consumer::{CommitMode, Consumer, StreamConsumer};
+use rdkafka::error::KafkaError;
+use rdkafka::message::{Message, OwnedMessage};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{ClientConfig, Offset, TopicPartitionList};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::booster::XGBoostError;
+use crate::predict::PredictOptions;
+use crate::reload::ReloadableBooster;
+use crate::training::CancellationToken;
+
+#[derive(Error, Debug)]
+pub enum ScoringError {
+    #[error(transparent)]
+    Kafka(#[from] KafkaError),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[error("Scoring task failed: {0}")]
+    Task(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct KafkaScoringConfig {
+    pub brokers: String,
+    pub group_id: String,
+    pub input_topic: String,
+    pub output_topic: String,
+    /// Records scored at once.
+    pub batch_size: usize,
+    /// Longest wait for a batch to fill before scoring what arrived.
+    pub max_batch_delay: Duration,
+    /// How long a produced result may wait in the producer queue.
+    pub produce_timeout: Duration,
+    /// Extra librdkafka settings applied to both clients, e.g. security options.
+    pub client_options: Vec<(String, String)>,
+}
+
+impl KafkaScoringConfig {
+    pub fn new(brokers: &str, group_id: &str, input_topic: &str, output_topic: &str) -> Self {
+        KafkaScoringConfig {
+            brokers: brokers.to_string(),
+            group_id: group_id.to_string(),
+            input_topic: input_topic.to_string(),
+            output_topic: output_topic.to_string(),
+            batch_size: 1024,
+            max_batch_delay: Duration::from_millis(100),
+            produce_timeout: Duration::from_secs(30),
+            client_options: Vec::new(),
+        }
+    }
+
+    fn client_config(&self) -> ClientConfig {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", &self.brokers);
+        for (key, value) in &self.client_options {
+            config.set(key, value);
+        }
+        config
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FeatureRecord {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub features: Vec<Option<f32>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScoredRecord<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<&'a str>,
+    pub prediction: &'a [f32],
+    pub model_version: u64,
+}
+
+/// Counters of a scoring run.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScoringStats {
+    pub consumed: u64,
+    pub produced: u64,
+    pub skipped: u64,
+}
+
+/// Parses a record payload with `cols` features.
+fn parse_record(payload: &[u8], cols: usize) -> Result<FeatureRecord, String> {
+    let record: FeatureRecord = serde_json::from_slice(payload).map_err(|e| e.to_string())?;
+    if record.features.len() != cols {
+        return Err(format!(
+            "expected {} features, got {}",
+            cols,
+            record.features.len()
+        ));
+    }
+    Ok(record)
+}
+
+/// Consumes, scores and produces until `shutdown` is cancelled, then commits what was
+/// produced and returns. Commits are asynchronous while running; the last one is
+/// repeated synchronously before returning, so a restart resumes after it.
+pub async fn run(
+    model: Arc<ReloadableBooster>,
+    config: &KafkaScoringConfig,
+    shutdown: CancellationToken,
+) -> Result<ScoringStats, ScoringError> {
+    let consumer: StreamConsumer = config
+        .client_config()
+        .set("group.id", &config.group_id)
+        .set("enable.auto.commit", "false")
+        .create()?;
+    consumer.subscribe(&[&config.input_topic])?;
+    let producer: FutureProducer = config.client_config().create()?;
+    let mut stats = ScoringStats::default();
+    let batch_size = config.batch_size.max(1);
+    let mut batch: Vec<OwnedMessage> = Vec::with_capacity(batch_size);
+    let mut committed = None;
+    while !shutdown.is_cancelled() {
+        let deadline = Instant::now() + config.max_batch_delay;
+        while batch.len() < batch_size {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match tokio::time::timeout(remaining, consumer.recv()).await {
+                Ok(message) => batch.push(message?.detach()),
+                Err(_) => break,
+            }
+        }
+        if batch.is_empty() {
+            continue;
+        }
+        stats.consumed += batch.len() as u64;
+        score_batch(&model, &producer, config, &batch, &mut stats).await?;
+        let offsets = next_offsets(&batch)?;
+        consumer.commit(&offsets, CommitMode::Async)?;
+        committed = Some(offsets);
+        batch.clear();
+    }
+    if let Some(offsets) = committed {
+        consumer.commit(&offsets, CommitMode::Sync)?;
+    }
+    Ok(stats)
+}
+
+async fn score_batch(
+    model: &ReloadableBooster,
+    producer: &FutureProducer,
+    config: &KafkaScoringConfig,
+    batch: &[OwnedMessage],
+    stats: &mut ScoringStats,
+) -> Result<(), ScoringError> {
+    let (booster, version) = model.snapshot();
+    let cols = booster.get_number_of_features()?;
+    let mut records = Vec::with_capacity(batch.len());
+    for message in batch {
+        match parse_record(message.payload().unwrap_or_default(), cols) {
+            Ok(record) => records.push((message, record)),
+            Err(reason) => {
+                stats.skipped += 1;
+                crate::instrument::warn(&format!(
+                    "skipping record at {}/{}@{}: {}",
+                    message.topic(),
+                    message.partition(),
+                    message.offset(),
+                    reason
+                ));
+            }
+        }
+    }
+    if records.is_empty() {
+        return Ok(());
+    }
+    let data: Vec<f32> = records
+        .iter()
+        .flat_map(|(_, r)| r.features.iter().map(|v| v.unwrap_or(f32::NAN)))
+        .collect();
+    let rows = records.len();
+    // Scoring is CPU bound, keep it off the async workers
+    let prediction = tokio::task::spawn_blocking(move || {
+        booster.predict_dense(&data, rows, cols, &PredictOptions::default())
+    })
+    .await
+    .map_err(|e| ScoringError::Task(e.to_string()))??;
+
+    let payloads: Vec<Vec<u8>> = records
+        .iter()
+        .enumerate()
+        .map(|(row, (_, record))| {
+            serde_json::to_vec(&ScoredRecord {
+                id: record.id.as_deref(),
+                prediction: prediction.row(row),
+                model_version: version,
+            })
+            .expect("scored records serialize")
+        })
+        .collect();
+    let deliveries = records
+        .iter()
+        .zip(&payloads)
+        .map(|((message, _), payload)| {
+            let mut record = FutureRecord::to(&config.output_topic).payload(payload);
+            if let Some(key) = message.key() {
+                record = record.key(key);
+            }
+            producer.send(record, config.produce_timeout)
+        });
+    for delivery in futures::future::join_all(deliveries).await {
+        delivery.map_err(|(e, _)| e)?;
+        stats.produced += 1;
+    }
+    Ok(())
+}
+
+/// The offsets following the last message of each partition in `batch`, to commit.
+fn next_offsets(batch: &[OwnedMessage]) -> Result<TopicPartitionList, KafkaError> {
+    let mut next: BTreeMap<(&str, i32), i64> = BTreeMap::new();
+    for message in batch {
+        let offset = next
+            .entry((message.topic(), message.partition()))
+            .or_default();
+        *offset = (*offset).max(message.offset() + 1);
+    }
+    let mut offsets = TopicPartitionList::new();
+    for ((topic, partition), offset) in next {
+        offsets.add_partition_offset(topic, partition, Offset::Offset(offset))?;
+    }
+    Ok(offsets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_record() {
+        let record = parse_record(br#"{"id": "u1", "features": [1.5, null]}"#, 2).unwrap();
+        assert_eq!(record.id.as_deref(), Some("u1"));
+        assert_eq!(record.features, vec![Some(1.5), None]);
+        assert!(parse_record(br#"{"features": [1.5]}"#, 2).is_err());
+        assert!(parse_record(b"not json", 2).is_err());
+    }
+
+    #[test]
+    fn test_next_offsets() {
+        use rdkafka::Timestamp;
+        let message = |partition, offset| {
+            OwnedMessage::new(
+                None,
+                None,
+                "features".to_string(),
+                Timestamp::NotAvailable,
+                partition,
+                offset,
+                None,
+            )
+        };
+        let batch = vec![message(0, 7), message(1, 3), message(0, 5)];
+        let offsets = next_offsets(&batch).unwrap();
+        assert_eq!(offsets.count(), 2);
+        let next = |partition| {
+            offsets
+                .find_partition("features", partition)
+                .map(|elem| elem.offset())
+        };
+        assert_eq!(next(0), Some(Offset::Offset(8)));
+        assert_eq!(next(1), Some(Offset::Offset(4)));
+    }
+
+    #[test]
+    fn test_scored_record_json() {
+        let record = ScoredRecord {
+            id: None,
+            prediction: &[0.25],
+            model_version: 3,
+        };
+        assert_eq!(
+            serde_json::to_string(&record).unwrap(),
+            r#"{"prediction":[0.25],"model_version":3}"#
+        );
+    }
+}
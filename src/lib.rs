@@ -1,2 +1,56 @@
+pub mod artifacts;
+pub mod audit;
 pub mod booster;
+pub mod cache;
+pub mod calibration;
+pub mod collective;
+pub mod compat;
+pub mod config;
+pub mod data;
+pub mod diagnostics;
 pub mod dmatrix;
+pub mod drift;
+pub mod encoding;
+#[cfg(feature = "encryption")]
+pub mod encryption;
+pub mod ensemble;
+pub mod experiment;
+pub mod external;
+pub mod feature_selection;
+mod ffi_guard;
+#[cfg(feature = "flight")]
+pub mod flight;
+pub mod handles;
+pub mod importance;
+mod instrument;
+pub mod interchange;
+pub mod interop;
+pub mod interpret;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+pub mod memory;
+pub mod metrics;
+#[cfg(feature = "mlflow")]
+pub mod mlflow;
+pub mod model;
+pub mod model_card;
+pub mod output;
+pub mod params;
+pub mod predict;
+pub mod ranking;
+pub mod reload;
+pub mod schema;
+pub mod score;
+#[cfg(feature = "server")]
+pub mod server;
+pub mod shadow;
+pub mod stats;
+#[cfg(feature = "object-store")]
+pub mod storage;
+pub mod training;
+#[cfg(feature = "wandb")]
+pub mod wandb;
+pub mod weights;
+pub mod whatif;
+
+pub use memory::memory_stats;
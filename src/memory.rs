@@ -0,0 +1,233 @@
+//! Memory footprint of training and of live XGBoost objects.
+//!
+//! The C API reports no allocator statistics, so [`memory_stats`] adds up sizes recorded
+//! by the wrappers instead: each `DMatrix` is sized when created, each `Booster` whenever
+//! its model is loaded or trained.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::params::TrainParams;
+
+static DMATRICES: AtomicU64 = AtomicU64::new(0);
+static DMATRIX_BYTES: AtomicU64 = AtomicU64::new(0);
+static BOOSTERS: AtomicU64 = AtomicU64::new(0);
+static MODEL_BYTES: AtomicU64 = AtomicU64::new(0);
+/// Limit on the tracked total, 0 for none. See [`GlobalConfig::tracked_memory_limit`].
+///
+/// [`GlobalConfig::tracked_memory_limit`]: crate::config::GlobalConfig::tracked_memory_limit
+static LIMIT: AtomicU64 = AtomicU64::new(0);
+
+/// Memory held by the live `Booster` and `DMatrix` objects of the process, for reporting
+/// to an orchestrator. See [`memory_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryStats {
+    pub dmatrices: u64,
+    /// Estimated size of XGBoost's copy of the data: 8 bytes per stored value plus row
+    /// offsets. Labels, weights and external-memory pages on disk are not counted
+    /// separately.
+    pub dmatrix_bytes: u64,
+    pub boosters: u64,
+    /// Size of the models serialized as UBJSON, taken after each load and boosting round.
+    /// Saving a model in another format does not change it.
+    pub model_bytes: u64,
+}
+
+impl MemoryStats {
+    pub fn total_bytes(&self) -> u64 {
+        self.dmatrix_bytes + self.model_bytes
+    }
+}
+
+/// Current totals over all live boosters and matrices, including those created through
+/// `from_raw_handle`. Handles released with `into_raw_handle` are no longer counted.
+pub fn memory_stats() -> MemoryStats {
+    MemoryStats {
+        dmatrices: DMATRICES.load(Ordering::Relaxed),
+        dmatrix_bytes: DMATRIX_BYTES.load(Ordering::Relaxed),
+        boosters: BOOSTERS.load(Ordering::Relaxed),
+        model_bytes: MODEL_BYTES.load(Ordering::Relaxed),
+    }
+}
+
+pub(crate) fn set_limit(bytes: u64) {
+    LIMIT.store(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn limit() -> Option<u64> {
+    Some(LIMIT.load(Ordering::Relaxed)).filter(|&limit| limit > 0)
+}
+
+/// The limit and the tracked total, if the total is above the limit.
+pub(crate) fn over_limit() -> Option<(u64, u64)> {
+    let limit = limit()?;
+    let used = memory_stats().total_bytes();
+    (used > limit).then_some((limit, used))
+}
+
+/// Size of a matrix with `rows` rows and `nnz` stored values in XGBoost's CSR pages.
+pub(crate) fn dmatrix_bytes(rows: u64, nnz: u64) -> u64 {
+    nnz * 8 + (rows + 1) * 8
+}
+
+pub(crate) fn track_dmatrix(bytes: u64) {
+    DMATRICES.fetch_add(1, Ordering::Relaxed);
+    DMATRIX_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn untrack_dmatrix(bytes: u64) {
+    DMATRICES.fetch_sub(1, Ordering::Relaxed);
+    DMATRIX_BYTES.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+pub(crate) fn track_booster() {
+    BOOSTERS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// A booster whose model was last sized at `model_bytes` went away.
+pub(crate) fn untrack_booster(model_bytes: u64) {
+    BOOSTERS.fetch_sub(1, Ordering::Relaxed);
+    MODEL_BYTES.fetch_sub(model_bytes, Ordering::Relaxed);
+}
+
+/// Records a new size for a model, replacing `old`.
+pub(crate) fn resize_model(old: u64, new: u64) {
+    MODEL_BYTES.fetch_add(new, Ordering::Relaxed);
+    MODEL_BYTES.fetch_sub(old, Ordering::Relaxed);
+}
+
+/// Approximate peak memory of a training run, in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MemoryEstimate {
+    pub host: u64,
+    pub device: u64,
+}
+
+impl MemoryEstimate {
+    pub fn total(&self) -> u64 {
+        self.host + self.device
+    }
+}
+
+/// Rough peak memory of training on a dense `rows` x `cols` matrix with `params`, for
+/// sizing machines up front. It counts the input, XGBoost's copy of it for the tree method
+/// (quantized bins for `hist`/`approx`, a sorted column copy for `exact`), gradients,
+/// prediction caches and node histograms. Allocator overhead and the model itself are
+/// left out, so keep some headroom.
+///
+/// With `device=cuda` the quantized matrix, gradients and histograms are counted on the
+/// device, and the host only holds the input.
+pub fn estimate_training_memory(rows: u64, cols: u64, params: &TrainParams) -> MemoryEstimate {
+    let max_bin: u64 = parsed(params, "max_bin").unwrap_or(256);
+    let max_depth: u32 = parsed(params, "max_depth").unwrap_or(6);
+    let max_leaves: u64 = parsed(params, "max_leaves").unwrap_or(0);
+    let groups = params.get_num_class().unwrap_or(1).max(1) as u64;
+    let exact = params.get("tree_method").as_deref() == Some("exact");
+    let on_device = params
+        .get("device")
+        .is_some_and(|d| d.starts_with("cuda") || d.starts_with("gpu"));
+
+    let cells = rows * cols;
+    let input = cells * 4;
+    // Labels and weights, float gradient pairs and the prediction cache per output group
+    let per_row = 8 + groups * 8 + groups * 4;
+    let rows_state = rows * per_row;
+
+    let method = if exact {
+        // Column-major copy sorted by value, 8 bytes per entry, plus the row positions
+        cells * 8 + rows * 4
+    } else {
+        let bin_bytes = match max_bin {
+            0..=256 => 1,
+            257..=65536 => 2,
+            _ => 4,
+        };
+        // Quantized index, row pointers, and one histogram of double gradient pairs per
+        // node of the widest level that is built at the same time
+        let nodes = match max_leaves {
+            0 => 1u64 << max_depth.min(16),
+            leaves => leaves.min(1u64 << max_depth.clamp(1, 16)),
+        };
+        cells * bin_bytes + rows * 8 + nodes * groups * cols * max_bin * 16
+    };
+
+    if on_device && !exact {
+        MemoryEstimate {
+            host: input,
+            device: method + rows_state,
+        }
+    } else {
+        MemoryEstimate {
+            host: input + method + rows_state,
+            device: 0,
+        }
+    }
+}
+
+fn parsed<T: std::str::FromStr>(params: &TrainParams, key: &str) -> Option<T> {
+    params.get(key).and_then(|v| v.parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_stats() {
+        // Other tests run concurrently, so only this test's own changes are checked
+        let bytes = dmatrix_bytes(3, 6);
+        assert_eq!(bytes, 6 * 8 + 4 * 8);
+        track_dmatrix(1 << 40);
+        track_booster();
+        resize_model(0, 1 << 41);
+        let stats = memory_stats();
+        assert!(stats.dmatrices >= 1 && stats.boosters >= 1);
+        assert!(stats.total_bytes() >= 3 << 40);
+        untrack_dmatrix(1 << 40);
+        untrack_booster(1 << 41);
+        assert!(memory_stats().total_bytes() < 1 << 40);
+    }
+
+    #[test]
+    fn test_tracked_limit() {
+        // A lower limit would fail matrices created by concurrent tests
+        set_limit(u64::MAX);
+        assert_eq!(limit(), Some(u64::MAX));
+        assert_eq!(over_limit(), None);
+        set_limit(0);
+        assert_eq!(limit(), None);
+        assert_eq!(over_limit(), None);
+    }
+
+    #[test]
+    fn test_estimate_hist() {
+        let params = TrainParams::new().max_depth(2).max_bin(16);
+        let estimate = estimate_training_memory(100, 10, &params);
+        let input = 100 * 10 * 4;
+        let method = 100 * 10 + 100 * 8 + 4 * 10 * 16 * 16;
+        assert_eq!(estimate.host, input + method + 100 * 20);
+        assert_eq!(estimate.device, 0);
+
+        let params = params.set("device", "cuda:0");
+        let estimate = estimate_training_memory(100, 10, &params);
+        assert_eq!(estimate.host, input);
+        assert_eq!(estimate.device, method + 100 * 20);
+    }
+
+    #[test]
+    fn test_estimate_grows_with_settings() {
+        let base = estimate_training_memory(1000, 50, &TrainParams::new());
+        let exact =
+            estimate_training_memory(1000, 50, &TrainParams::new().set("tree_method", "exact"));
+        assert!(exact.host < base.host);
+        let wide = estimate_training_memory(1000, 50, &TrainParams::new().max_bin(1024));
+        assert!(wide.host > base.host);
+        let leaves = estimate_training_memory(1000, 50, &TrainParams::new().max_leaves(4));
+        assert!(leaves.host < base.host);
+        let multi = estimate_training_memory(
+            1000,
+            50,
+            &TrainParams::new().objective("multi:softprob").num_class(3),
+        );
+        assert!(multi.total() > base.total());
+    }
+}
@@ -0,0 +1,797 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::{DMatrix, DMatrixError};
+
+const EPS: f64 = 1e-16;
+
+#[derive(Error, Debug)]
+pub enum MetricError {
+    #[error("Got {0} predictions for {1} labels")]
+    Length(usize, usize),
+    #[error("Cannot evaluate a metric on empty data")]
+    Empty,
+    #[error("Invalid group boundaries for {0} rows")]
+    Groups(usize),
+    #[error("Prediction {value} at row {row} is not finite")]
+    NonFinite { row: usize, value: f32 },
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+}
+
+/// Metrics mirroring XGBoost's `eval_metric` definitions (unweighted).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Rmse,
+    Mae,
+    LogLoss,
+    Error,
+    Auc,
+    /// NDCG, optionally truncated at the given position.
+    Ndcg(Option<usize>),
+    /// Mean average precision, optionally truncated at the given position.
+    Map(Option<usize>),
+}
+
+impl Metric {
+    /// Name as used by XGBoost's `eval_metric`, e.g. `ndcg@5`.
+    pub fn name(&self) -> String {
+        match self {
+            Metric::Rmse => "rmse".to_string(),
+            Metric::Mae => "mae".to_string(),
+            Metric::LogLoss => "logloss".to_string(),
+            Metric::Error => "error".to_string(),
+            Metric::Auc => "auc".to_string(),
+            Metric::Ndcg(None) => "ndcg".to_string(),
+            Metric::Ndcg(Some(k)) => format!("ndcg@{}", k),
+            Metric::Map(None) => "map".to_string(),
+            Metric::Map(Some(k)) => format!("map@{}", k),
+        }
+    }
+
+    pub fn higher_is_better(&self) -> bool {
+        matches!(self, Metric::Auc | Metric::Ndcg(_) | Metric::Map(_))
+    }
+
+    /// Computes the metric from raw slices. `group_ptr` follows XGBoost's layout
+    /// (`[0, end_0, end_1, ...]`) and is only used by ranking metrics; when empty
+    /// all rows form a single query.
+    pub fn compute(
+        &self,
+        preds: &[f32],
+        labels: &[f32],
+        group_ptr: &[u32],
+    ) -> Result<f64, MetricError> {
+        match self {
+            Metric::Rmse => rmse(preds, labels),
+            Metric::Mae => mae(preds, labels),
+            Metric::LogLoss => logloss(preds, labels),
+            Metric::Error => error(preds, labels),
+            Metric::Auc => auc(preds, labels),
+            Metric::Ndcg(k) => ndcg(preds, labels, group_ptr, *k),
+            Metric::Map(k) => map(preds, labels, group_ptr, *k),
+        }
+    }
+
+    /// Computes the metric against the labels and groups stored in `data`.
+    pub fn evaluate(&self, preds: &[f32], data: &DMatrix) -> Result<f64, MetricError> {
+        let labels = data.get_labels()?;
+        let group_ptr = match self {
+            Metric::Ndcg(_) | Metric::Map(_) => data.get_group_ptr()?,
+            _ => Vec::new(),
+        };
+        self.compute(preds, &labels, &group_ptr)
+    }
+}
+
+/// Metrics of the rows of one protected group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupMetrics {
+    pub count: usize,
+    pub mean_prediction: f64,
+    /// Keyed by [`Metric::name`].
+    pub metrics: BTreeMap<String, f64>,
+}
+
+/// Per-group metrics with, for every metric and for `mean_prediction`, the disparity
+/// ratio: smallest group value over largest, 1 meaning parity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupEvaluation {
+    pub groups: BTreeMap<String, GroupMetrics>,
+    pub disparity: BTreeMap<String, f64>,
+}
+
+/// Predicts `data` and computes `metrics` separately for each protected group, given as
+/// one group label per row. Ranking metrics treat each group as a single query.
+pub fn evaluate_by_group(
+    booster: &Booster,
+    data: &DMatrix,
+    group_labels: &[&str],
+    metrics: &[Metric],
+) -> Result<GroupEvaluation, MetricError> {
+    let preds = booster.predict(data)?;
+    evaluate_groups(&preds, &data.get_labels()?, group_labels, metrics)
+}
+
+fn evaluate_groups(
+    preds: &[f32],
+    labels: &[f32],
+    group_labels: &[&str],
+    metrics: &[Metric],
+) -> Result<GroupEvaluation, MetricError> {
+    check(preds, labels)?;
+    if group_labels.len() != labels.len() {
+        return Err(MetricError::Length(group_labels.len(), labels.len()));
+    }
+    let mut rows: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (row, group) in group_labels.iter().enumerate() {
+        rows.entry(group).or_default().push(row);
+    }
+    let mut groups = BTreeMap::new();
+    for (group, rows) in rows {
+        let group_preds: Vec<f32> = rows.iter().map(|r| preds[*r]).collect();
+        let group_labels: Vec<f32> = rows.iter().map(|r| labels[*r]).collect();
+        let metrics = metrics
+            .iter()
+            .map(|m| Ok((m.name(), m.compute(&group_preds, &group_labels, &[])?)))
+            .collect::<Result<BTreeMap<_, _>, MetricError>>()?;
+        let mean_prediction =
+            group_preds.iter().map(|p| *p as f64).sum::<f64>() / rows.len() as f64;
+        groups.insert(
+            group.to_string(),
+            GroupMetrics {
+                count: rows.len(),
+                mean_prediction,
+                metrics,
+            },
+        );
+    }
+
+    let mut disparity = BTreeMap::new();
+    let names = metrics
+        .iter()
+        .map(Metric::name)
+        .chain(["mean_prediction".to_string()]);
+    for name in names {
+        let values: Vec<f64> = groups
+            .values()
+            .map(|g| match name.as_str() {
+                "mean_prediction" => g.mean_prediction,
+                _ => g.metrics[&name],
+            })
+            .filter(|v| v.is_finite())
+            .collect();
+        let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let ratio = if values.is_empty() || max <= 0.0 {
+            f64::NAN
+        } else {
+            min / max
+        };
+        disparity.insert(name, ratio);
+    }
+    Ok(GroupEvaluation { groups, disparity })
+}
+
+fn check(preds: &[f32], labels: &[f32]) -> Result<(), MetricError> {
+    if preds.len() != labels.len() {
+        return Err(MetricError::Length(preds.len(), labels.len()));
+    }
+    if preds.is_empty() {
+        return Err(MetricError::Empty);
+    }
+    if let Some(row) = preds.iter().position(|p| !p.is_finite()) {
+        return Err(MetricError::NonFinite {
+            row,
+            value: preds[row],
+        });
+    }
+    Ok(())
+}
+
+pub fn rmse(preds: &[f32], labels: &[f32]) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let sum: f64 = preds
+        .iter()
+        .zip(labels)
+        .map(|(p, y)| (*p as f64 - *y as f64).powi(2))
+        .sum();
+    Ok((sum / preds.len() as f64).sqrt())
+}
+
+pub fn mae(preds: &[f32], labels: &[f32]) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let sum: f64 = preds
+        .iter()
+        .zip(labels)
+        .map(|(p, y)| (*p as f64 - *y as f64).abs())
+        .sum();
+    Ok(sum / preds.len() as f64)
+}
+
+/// Binary log loss, with probabilities clipped to `[1e-16, 1 - 1e-16]` as XGBoost does.
+pub fn logloss(preds: &[f32], labels: &[f32]) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let sum: f64 = preds
+        .iter()
+        .zip(labels)
+        .map(|(p, y)| {
+            let p = (*p as f64).clamp(EPS, 1.0 - EPS);
+            let y = *y as f64;
+            -(y * p.ln() + (1.0 - y) * (1.0 - p).ln())
+        })
+        .sum();
+    Ok(sum / preds.len() as f64)
+}
+
+/// Binary classification error rate, counting predictions above 0.5 as positive.
+pub fn error(preds: &[f32], labels: &[f32]) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let wrong = preds
+        .iter()
+        .zip(labels)
+        .filter(|(p, y)| (**p > 0.5) != (**y > 0.5))
+        .count();
+    Ok(wrong as f64 / preds.len() as f64)
+}
+
+/// Area under the ROC curve for binary labels. Tied predictions contribute a
+/// trapezoid. Returns NaN when only one class is present, like XGBoost. Like every
+/// metric here, it rejects NaN and infinite predictions.
+pub fn auc(preds: &[f32], labels: &[f32]) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let order = sorted_desc(preds);
+    let (mut tp, mut fp) = (0.0f64, 0.0f64);
+    let mut area = 0.0;
+    let mut i = 0;
+    while i < order.len() {
+        let (prev_tp, prev_fp) = (tp, fp);
+        let score = preds[order[i]].to_bits();
+        while i < order.len() && preds[order[i]].to_bits() == score {
+            if labels[order[i]] > 0.5 {
+                tp += 1.0;
+            } else {
+                fp += 1.0;
+            }
+            i += 1;
+        }
+        area += (fp - prev_fp) * (tp + prev_tp) / 2.0;
+    }
+    if tp == 0.0 || fp == 0.0 {
+        return Ok(f64::NAN);
+    }
+    Ok(area / (tp * fp))
+}
+
+/// NDCG with exponential gain (`2^rel - 1`), averaged over queries. Queries
+/// without any relevant document score 1, as in XGBoost's default `ndcg`.
+pub fn ndcg(
+    preds: &[f32],
+    labels: &[f32],
+    group_ptr: &[u32],
+    k: Option<usize>,
+) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let groups = groups(group_ptr, preds.len())?;
+    let dcg = |rels: &mut dyn Iterator<Item = f32>, k: usize| -> f64 {
+        rels.take(k)
+            .enumerate()
+            .map(|(i, rel)| (2f64.powf(rel as f64) - 1.0) / ((i + 2) as f64).log2())
+            .sum()
+    };
+    let mut total = 0.0;
+    for (begin, end) in &groups {
+        let (p, y) = (&preds[*begin..*end], &labels[*begin..*end]);
+        let k = k.unwrap_or(p.len());
+        let mut ideal = y.to_vec();
+        ideal.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        let idcg = dcg(&mut ideal.into_iter(), k);
+        if idcg == 0.0 {
+            total += 1.0;
+            continue;
+        }
+        let ranked = sorted_desc(p);
+        total += dcg(&mut ranked.into_iter().map(|i| y[i]), k) / idcg;
+    }
+    Ok(total / groups.len() as f64)
+}
+
+/// Mean average precision over queries, with labels above zero treated as
+/// relevant. Queries without relevant documents score 1, as in XGBoost's `map`.
+pub fn map(
+    preds: &[f32],
+    labels: &[f32],
+    group_ptr: &[u32],
+    k: Option<usize>,
+) -> Result<f64, MetricError> {
+    check(preds, labels)?;
+    let groups = groups(group_ptr, preds.len())?;
+    let mut total = 0.0;
+    for (begin, end) in &groups {
+        let (p, y) = (&preds[*begin..*end], &labels[*begin..*end]);
+        let k = k.unwrap_or(p.len());
+        let (mut hits, mut sum_ap) = (0usize, 0.0f64);
+        for (i, idx) in sorted_desc(p).into_iter().enumerate() {
+            if y[idx] > 0.0 {
+                hits += 1;
+                if i < k {
+                    sum_ap += hits as f64 / (i + 1) as f64;
+                }
+            }
+        }
+        total += if hits == 0 { 1.0 } else { sum_ap / hits as f64 };
+    }
+    Ok(total / groups.len() as f64)
+}
+
+/// Quantiles of the residuals reported by [`regression_report`].
+pub const RESIDUAL_QUANTILES: [f64; 5] = [0.05, 0.25, 0.5, 0.75, 0.95];
+
+/// Error summary of a regressor. Residuals are `label - prediction`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RegressionReport {
+    pub count: usize,
+    pub rmse: f64,
+    pub mae: f64,
+    /// Mean absolute percentage error over rows with a non-zero label, NaN if there are
+    /// none.
+    pub mape: f64,
+    /// Coefficient of determination, NaN when the labels are constant.
+    pub r2: f64,
+    /// Residuals at [`RESIDUAL_QUANTILES`], linearly interpolated.
+    pub residual_quantiles: Vec<(f64, f64)>,
+}
+
+pub fn regression_report(preds: &[f32], labels: &[f32]) -> Result<RegressionReport, MetricError> {
+    check(preds, labels)?;
+    let n = preds.len() as f64;
+    let mut residuals: Vec<f64> = preds
+        .iter()
+        .zip(labels)
+        .map(|(p, y)| *y as f64 - *p as f64)
+        .collect();
+    let ss_res: f64 = residuals.iter().map(|r| r * r).sum();
+    let mean_label = labels.iter().map(|y| *y as f64).sum::<f64>() / n;
+    let ss_tot: f64 = labels
+        .iter()
+        .map(|y| (*y as f64 - mean_label).powi(2))
+        .sum();
+    let percentage: Vec<f64> = residuals
+        .iter()
+        .zip(labels)
+        .filter(|(_, y)| **y != 0.0)
+        .map(|(r, y)| (r / *y as f64).abs())
+        .collect();
+    let mape = if percentage.is_empty() {
+        f64::NAN
+    } else {
+        percentage.iter().sum::<f64>() / percentage.len() as f64
+    };
+    residuals.sort_by(f64::total_cmp);
+    let residual_quantiles = RESIDUAL_QUANTILES
+        .iter()
+        .map(|q| {
+            let pos = q * (residuals.len() - 1) as f64;
+            let (lo, hi) = (pos.floor() as usize, pos.ceil() as usize);
+            (
+                *q,
+                residuals[lo] + (residuals[hi] - residuals[lo]) * (pos - lo as f64),
+            )
+        })
+        .collect();
+    Ok(RegressionReport {
+        count: preds.len(),
+        rmse: (ss_res / n).sqrt(),
+        mae: residuals.iter().map(|r| r.abs()).sum::<f64>() / n,
+        mape,
+        r2: if ss_tot == 0.0 {
+            f64::NAN
+        } else {
+            1.0 - ss_res / ss_tot
+        },
+        residual_quantiles,
+    })
+}
+
+/// [`regression_report`] for each segment, given as one segment label per row.
+pub fn regression_report_by_segment(
+    preds: &[f32],
+    labels: &[f32],
+    segments: &[&str],
+) -> Result<BTreeMap<String, RegressionReport>, MetricError> {
+    check(preds, labels)?;
+    if segments.len() != labels.len() {
+        return Err(MetricError::Length(segments.len(), labels.len()));
+    }
+    let mut rows: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (row, segment) in segments.iter().enumerate() {
+        rows.entry(segment).or_default().push(row);
+    }
+    rows.into_iter()
+        .map(|(segment, rows)| {
+            let segment_preds: Vec<f32> = rows.iter().map(|r| preds[*r]).collect();
+            let segment_labels: Vec<f32> = rows.iter().map(|r| labels[*r]).collect();
+            let report = regression_report(&segment_preds, &segment_labels)?;
+            Ok((segment.to_string(), report))
+        })
+        .collect()
+}
+
+/// Binary confusion matrix, positives being labels above 0.5.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConfusionMatrix {
+    pub true_positives: usize,
+    pub false_positives: usize,
+    pub true_negatives: usize,
+    pub false_negatives: usize,
+}
+
+/// Threshold metrics of a binary classifier. Ratios with an empty denominator are 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassificationReport {
+    pub threshold: f32,
+    pub confusion: ConfusionMatrix,
+    pub accuracy: f64,
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    /// Number of positive labels.
+    pub support: usize,
+}
+
+/// Counts predictions at or above `threshold` as positive and compares them with the
+/// binary labels.
+pub fn classification_report(
+    preds: &[f32],
+    labels: &[f32],
+    threshold: f32,
+) -> Result<ClassificationReport, MetricError> {
+    check(preds, labels)?;
+    let mut confusion = ConfusionMatrix::default();
+    for (p, y) in preds.iter().zip(labels) {
+        match (*p >= threshold, *y > 0.5) {
+            (true, true) => confusion.true_positives += 1,
+            (true, false) => confusion.false_positives += 1,
+            (false, false) => confusion.true_negatives += 1,
+            (false, true) => confusion.false_negatives += 1,
+        }
+    }
+    let ratio = |num: usize, den: usize| {
+        if den == 0 {
+            0.0
+        } else {
+            num as f64 / den as f64
+        }
+    };
+    let tp = confusion.true_positives;
+    let precision = ratio(tp, tp + confusion.false_positives);
+    let recall = ratio(tp, tp + confusion.false_negatives);
+    let f1 = if precision + recall == 0.0 {
+        0.0
+    } else {
+        2.0 * precision * recall / (precision + recall)
+    };
+    Ok(ClassificationReport {
+        threshold,
+        confusion,
+        accuracy: ratio(tp + confusion.true_negatives, preds.len()),
+        precision,
+        recall,
+        f1,
+        support: tp + confusion.false_negatives,
+    })
+}
+
+/// What [`optimal_threshold`] optimizes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThresholdCriterion {
+    /// Maximize Youden's J, `tpr - fpr`.
+    YoudenJ,
+    /// Maximize F1.
+    F1,
+    /// Minimize the total cost of errors, given the cost of one false positive and of one
+    /// false negative.
+    Cost {
+        false_positive: f64,
+        false_negative: f64,
+    },
+}
+
+/// Threshold picked by [`optimal_threshold`] with the metrics it achieves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThresholdChoice {
+    pub threshold: f32,
+    /// Value of the criterion: J, F1, or total cost.
+    pub score: f64,
+    pub report: ClassificationReport,
+}
+
+/// Tries every distinct prediction as threshold, plus an infinite one predicting no
+/// positive, and returns the best according to `criterion`. Ties go to the highest
+/// threshold.
+pub fn optimal_threshold(
+    preds: &[f32],
+    labels: &[f32],
+    criterion: ThresholdCriterion,
+) -> Result<ThresholdChoice, MetricError> {
+    let counts = cumulative_counts(preds, labels)?;
+    let (positives, negatives) = counts.last().map(|c| (c.1, c.2)).unwrap_or_default();
+    let ratio = |num: f64, den: f64| if den == 0.0 { 0.0 } else { num / den };
+    // Higher is better, costs are negated
+    let score = |tp: f64, fp: f64| match criterion {
+        ThresholdCriterion::YoudenJ => ratio(tp, positives) - ratio(fp, negatives),
+        ThresholdCriterion::F1 => ratio(2.0 * tp, tp + fp + positives),
+        ThresholdCriterion::Cost {
+            false_positive,
+            false_negative,
+        } => -(fp * false_positive + (positives - tp) * false_negative),
+    };
+    let (threshold, best) = std::iter::once((f32::INFINITY, 0.0, 0.0))
+        .chain(counts)
+        .map(|(threshold, tp, fp)| (threshold, score(tp, fp)))
+        .fold((f32::INFINITY, f64::NEG_INFINITY), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        });
+    let score = match criterion {
+        ThresholdCriterion::Cost { .. } => -best,
+        _ => best,
+    };
+    Ok(ThresholdChoice {
+        threshold,
+        score,
+        report: classification_report(preds, labels, threshold)?,
+    })
+}
+
+/// One operating point of a classifier: rows scoring at or above `threshold` are positive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CurvePoint {
+    pub threshold: f32,
+    /// False positive rate for ROC curves, recall for precision-recall curves.
+    pub x: f64,
+    /// True positive rate for ROC curves, precision for precision-recall curves.
+    pub y: f64,
+}
+
+/// ROC curve with one point per distinct prediction, from the highest threshold down,
+/// starting at `(0, 0)` with an infinite threshold. Empty when only one class is present.
+pub fn roc_curve(preds: &[f32], labels: &[f32]) -> Result<Vec<CurvePoint>, MetricError> {
+    let counts = cumulative_counts(preds, labels)?;
+    let (positives, negatives) = counts.last().map(|c| (c.1, c.2)).unwrap_or_default();
+    if positives == 0.0 || negatives == 0.0 {
+        return Ok(Vec::new());
+    }
+    let start = CurvePoint {
+        threshold: f32::INFINITY,
+        x: 0.0,
+        y: 0.0,
+    };
+    Ok(std::iter::once(start)
+        .chain(counts.iter().map(|(threshold, tp, fp)| CurvePoint {
+            threshold: *threshold,
+            x: fp / negatives,
+            y: tp / positives,
+        }))
+        .collect())
+}
+
+/// Precision-recall curve with one point per distinct prediction, from the highest
+/// threshold down. Empty when there are no positive labels.
+pub fn pr_curve(preds: &[f32], labels: &[f32]) -> Result<Vec<CurvePoint>, MetricError> {
+    let counts = cumulative_counts(preds, labels)?;
+    let positives = counts.last().map(|c| c.1).unwrap_or_default();
+    if positives == 0.0 {
+        return Ok(Vec::new());
+    }
+    Ok(counts
+        .iter()
+        .map(|(threshold, tp, fp)| CurvePoint {
+            threshold: *threshold,
+            x: tp / positives,
+            y: tp / (tp + fp),
+        })
+        .collect())
+}
+
+/// `(threshold, true positives, false positives)` after each distinct prediction, from
+/// the highest down.
+fn cumulative_counts(preds: &[f32], labels: &[f32]) -> Result<Vec<(f32, f64, f64)>, MetricError> {
+    check(preds, labels)?;
+    let order = sorted_desc(preds);
+    let (mut tp, mut fp) = (0.0, 0.0);
+    let mut counts = Vec::new();
+    let mut i = 0;
+    while i < order.len() {
+        let score = preds[order[i]];
+        // Compared bitwise so every group consumes at least its first row
+        while i < order.len() && preds[order[i]].to_bits() == score.to_bits() {
+            if labels[order[i]] > 0.5 {
+                tp += 1.0;
+            } else {
+                fp += 1.0;
+            }
+            i += 1;
+        }
+        counts.push((score, tp, fp));
+    }
+    Ok(counts)
+}
+
+fn sorted_desc(preds: &[f32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..preds.len()).collect();
+    order.sort_by(|a, b| preds[*b].total_cmp(&preds[*a]));
+    order
+}
+
+fn groups(group_ptr: &[u32], rows: usize) -> Result<Vec<(usize, usize)>, MetricError> {
+    if group_ptr.is_empty() {
+        return Ok(vec![(0, rows)]);
+    }
+    if group_ptr[0] != 0 || *group_ptr.last().unwrap() as usize != rows {
+        return Err(MetricError::Groups(rows));
+    }
+    group_ptr
+        .windows(2)
+        .map(|w| {
+            if w[0] < w[1] {
+                Ok((w[0] as usize, w[1] as usize))
+            } else {
+                Err(MetricError::Groups(rows))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classification_report() {
+        let preds = [0.9, 0.8, 0.4, 0.3, 0.6];
+        let labels = [1.0, 0.0, 1.0, 0.0, 1.0];
+        let report = classification_report(&preds, &labels, 0.5).unwrap();
+        assert_eq!(
+            report.confusion,
+            ConfusionMatrix {
+                true_positives: 2,
+                false_positives: 1,
+                true_negatives: 1,
+                false_negatives: 1,
+            }
+        );
+        assert_eq!(report.support, 3);
+        assert!((report.precision - 2.0 / 3.0).abs() < 1e-12);
+        assert!((report.recall - 2.0 / 3.0).abs() < 1e-12);
+        assert!((report.f1 - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(report.accuracy, 0.6);
+        let none = classification_report(&preds, &labels, 1.0).unwrap();
+        assert_eq!((none.precision, none.f1), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_regression_report() {
+        let preds = [1.0, 2.0, 3.0, 5.0];
+        let labels = [1.0, 3.0, 3.0, 0.0];
+        let report = regression_report(&preds, &labels).unwrap();
+        assert_eq!(report.count, 4);
+        assert_eq!(report.mae, 1.5);
+        assert!((report.rmse - (26.0f64 / 4.0).sqrt()).abs() < 1e-12);
+        // The zero label is left out of MAPE
+        assert!((report.mape - (1.0 / 3.0) / 3.0).abs() < 1e-12);
+        assert!((report.r2 - (1.0 - 26.0 / 6.75)).abs() < 1e-12);
+        assert_eq!(report.residual_quantiles[2], (0.5, 0.0));
+        assert_eq!(report.residual_quantiles[0].1, -5.0 + 5.0 * 0.15);
+        assert!(regression_report(&[1.0], &[2.0]).unwrap().r2.is_nan());
+
+        let by_segment =
+            regression_report_by_segment(&preds, &labels, &["a", "b", "a", "b"]).unwrap();
+        assert_eq!(by_segment["a"].mae, 0.0);
+        assert_eq!(by_segment["b"].count, 2);
+    }
+
+    #[test]
+    fn test_optimal_threshold() {
+        let preds = [0.9, 0.7, 0.6, 0.4, 0.2];
+        let labels = [1.0, 1.0, 0.0, 1.0, 0.0];
+        let j = optimal_threshold(&preds, &labels, ThresholdCriterion::YoudenJ).unwrap();
+        assert_eq!(j.threshold, 0.7);
+        assert!((j.score - 2.0 / 3.0).abs() < 1e-12);
+        let f1 = optimal_threshold(&preds, &labels, ThresholdCriterion::F1).unwrap();
+        assert_eq!(f1.threshold, 0.4);
+        assert!((f1.score - f1.report.f1).abs() < 1e-12);
+        let costly_fp = ThresholdCriterion::Cost {
+            false_positive: 10.0,
+            false_negative: 1.0,
+        };
+        let cost = optimal_threshold(&preds, &labels, costly_fp).unwrap();
+        assert_eq!((cost.threshold, cost.score), (0.7, 1.0));
+        let costly_fn = ThresholdCriterion::Cost {
+            false_positive: 1.0,
+            false_negative: 10.0,
+        };
+        let cost = optimal_threshold(&preds, &labels, costly_fn).unwrap();
+        assert_eq!((cost.threshold, cost.score), (0.4, 1.0));
+    }
+
+    #[test]
+    fn test_curves() {
+        let preds = [0.9, 0.8, 0.8, 0.3];
+        let labels = [1.0, 1.0, 0.0, 0.0];
+        let roc = roc_curve(&preds, &labels).unwrap();
+        let points: Vec<(f64, f64)> = roc.iter().map(|p| (p.x, p.y)).collect();
+        assert_eq!(points, vec![(0.0, 0.0), (0.0, 0.5), (0.5, 1.0), (1.0, 1.0)]);
+        assert_eq!(roc[2].threshold, 0.8);
+        let pr = pr_curve(&preds, &labels).unwrap();
+        assert_eq!(pr[0].y, 1.0);
+        assert!((pr[1].y - 2.0 / 3.0).abs() < 1e-12);
+        assert_eq!(pr[1].x, 1.0);
+        assert!(roc_curve(&[0.1], &[1.0]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_non_finite_predictions() {
+        let preds = [0.9, f32::NAN, 0.3];
+        let labels = [1.0, 0.0, 0.0];
+        for result in [roc_curve(&preds, &labels), pr_curve(&preds, &labels)] {
+            assert!(matches!(result, Err(MetricError::NonFinite { row: 1, .. })));
+        }
+        assert!(matches!(
+            optimal_threshold(&[0.9, 0.2, f32::INFINITY], &labels, ThresholdCriterion::F1),
+            Err(MetricError::NonFinite { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_evaluate_groups() {
+        let preds = [0.9, 0.2, 0.6, 0.4];
+        let labels = [1.0, 0.0, 0.0, 1.0];
+        let report =
+            evaluate_groups(&preds, &labels, &["a", "a", "b", "b"], &[Metric::Error]).unwrap();
+        assert_eq!(report.groups["a"].count, 2);
+        assert_eq!(report.groups["a"].metrics["error"], 0.0);
+        assert_eq!(report.groups["b"].metrics["error"], 1.0);
+        assert_eq!(report.disparity["error"], 0.0);
+        assert!((report.disparity["mean_prediction"] - 0.5 / 0.55).abs() < 1e-6);
+        assert!(evaluate_groups(&preds, &labels, &["a"], &[Metric::Error]).is_err());
+    }
+
+    #[test]
+    fn test_regression_metrics() {
+        let preds = [1.0, 2.0, 4.0];
+        let labels = [1.0, 3.0, 2.0];
+        assert!((rmse(&preds, &labels).unwrap() - (5.0f64 / 3.0).sqrt()).abs() < 1e-9);
+        assert!((mae(&preds, &labels).unwrap() - 1.0).abs() < 1e-9);
+        assert!(rmse(&preds, &labels[..2]).is_err());
+    }
+
+    #[test]
+    fn test_auc() {
+        assert_eq!(auc(&[0.9, 0.8, 0.2, 0.1], &[1., 1., 0., 0.]).unwrap(), 1.0);
+        assert_eq!(auc(&[0.5, 0.5], &[1., 0.]).unwrap(), 0.5);
+        assert!(auc(&[0.1, 0.2], &[1., 1.]).unwrap().is_nan());
+        assert!(matches!(
+            auc(&[0.9, f32::NAN, 0.1], &[1., 0., 0.]),
+            Err(MetricError::NonFinite { row: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_ranking_metrics() {
+        let preds = [0.9, 0.1, 0.5, 0.2, 0.8];
+        let labels = [1., 0., 0., 1., 0.];
+        let group_ptr = [0, 2, 5];
+        assert!((ndcg(&preds, &labels, &group_ptr, None).unwrap() - 0.75).abs() < 1e-9);
+        assert!((ndcg(&preds, &labels, &group_ptr, Some(2)).unwrap() - 0.5).abs() < 1e-9);
+        assert!((map(&preds, &labels, &group_ptr, None).unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert!(ndcg(&preds, &labels, &[0, 3], None).is_err());
+    }
+}
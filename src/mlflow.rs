@@ -0,0 +1,311 @@
+//! Experiment tracking with MLflow, behind the `mlflow` feature. An [`MlflowLogger`]
+//! writes to an existing run of a tracking server through its REST API: parameters with
+//! [`MlflowLogger::log_params`], per-round metrics as a [`TrainingCallback`], and the
+//! trained model with [`MlflowLogger::log_model`].
+//!
+//! Metrics are named `<dataset>-<metric>`, e.g. `valid-rmse`, stepped by round, and sent in
+//! batches. Model uploads go through the server's artifact proxy, so the server must run
+//! with artifact serving enabled (`mlflow server --serve-artifacts`, the default since
+//! MLflow 2.0).
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::booster::{Booster, ModelFormat, XGBoostError};
+use crate::params::TrainParams;
+use crate::training::TrainingCallback;
+
+/// Largest number of metrics MLflow accepts in one `log-batch` request.
+const MAX_BATCH_METRICS: usize = 1000;
+/// Largest number of parameters MLflow accepts in one `log-batch` request.
+const MAX_BATCH_PARAMS: usize = 100;
+const ARTIFACT_SCHEME: &str = "mlflow-artifacts:/";
+
+#[derive(Error, Debug)]
+pub enum MlflowError {
+    #[error("MLflow request to {endpoint} failed: {message}")]
+    Request { endpoint: String, message: String },
+    #[error("Unexpected MLflow response: {0}")]
+    Response(String),
+    #[error("Unsupported artifact location {0}, only mlflow-artifacts:/ URIs can be uploaded to")]
+    ArtifactLocation(String),
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+}
+
+/// Logs to one MLflow run. Metrics are buffered and sent every `flush_every` rounds and
+/// by [`MlflowLogger::finish`].
+pub struct MlflowLogger {
+    tracking_uri: String,
+    run_id: String,
+    token: Option<String>,
+    flush_every: usize,
+    pending: Vec<Value>,
+    rounds_buffered: usize,
+}
+
+impl MlflowLogger {
+    /// Logs to run `run_id` of the server at `tracking_uri`, e.g. `http://localhost:5000`.
+    pub fn new(tracking_uri: &str, run_id: &str) -> Self {
+        MlflowLogger {
+            tracking_uri: tracking_uri.trim_end_matches('/').to_string(),
+            run_id: run_id.to_string(),
+            token: None,
+            flush_every: 1,
+            pending: Vec::new(),
+            rounds_buffered: 0,
+        }
+    }
+
+    /// Like [`MlflowLogger::new`], with the server and token taken from the standard
+    /// `MLFLOW_TRACKING_URI` and `MLFLOW_TRACKING_TOKEN` variables.
+    pub fn from_env(run_id: &str) -> Option<Self> {
+        let uri = std::env::var("MLFLOW_TRACKING_URI").ok()?;
+        let mut logger = MlflowLogger::new(&uri, run_id);
+        logger.token = std::env::var("MLFLOW_TRACKING_TOKEN").ok();
+        Some(logger)
+    }
+
+    /// Bearer token sent with every request.
+    pub fn token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    /// Send metrics every `rounds` rounds rather than after each one.
+    pub fn flush_every(mut self, rounds: usize) -> Self {
+        self.flush_every = rounds.max(1);
+        self
+    }
+
+    pub fn run_id(&self) -> &str {
+        &self.run_id
+    }
+
+    /// Logs every parameter of `params` as an MLflow run parameter.
+    pub fn log_params(&self, params: &TrainParams) -> Result<(), MlflowError> {
+        let params: Vec<Value> = params
+            .to_pairs()
+            .into_iter()
+            .map(|(key, value)| json!({"key": key, "value": value}))
+            .collect();
+        for chunk in params.chunks(MAX_BATCH_PARAMS) {
+            self.post(
+                "runs/log-batch",
+                &json!({"run_id": self.run_id, "params": chunk}),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Uploads the model as UBJSON to `<artifact_path>/model.ubj` in the run's
+    /// artifacts.
+    pub fn log_model(&self, booster: &Booster, artifact_path: &str) -> Result<(), MlflowError> {
+        let run = self.get(&format!("runs/get?run_id={}", self.run_id))?;
+        let root = run["run"]["info"]["artifact_uri"]
+            .as_str()
+            .ok_or_else(|| MlflowError::Response("run without artifact_uri".to_string()))?;
+        let url = format!(
+            "{}/{}",
+            self.artifact_url(root)?,
+            artifact_file(artifact_path, "model.ubj")
+        );
+        let model = booster.save_model_to_buffer(ModelFormat::Ubj)?;
+        self.authorized(ureq::put(&url))
+            .set("Content-Type", "application/octet-stream")
+            .send_bytes(&model)
+            .map_err(|e| request_error(&url, e))?;
+        Ok(())
+    }
+
+    /// Sends buffered metrics and marks the run as finished.
+    pub fn finish(&mut self) -> Result<(), MlflowError> {
+        self.flush()?;
+        self.post(
+            "runs/update",
+            &json!({"run_id": self.run_id, "status": "FINISHED", "end_time": now_millis()}),
+        )?;
+        Ok(())
+    }
+
+    /// Sends the buffered metrics.
+    pub fn flush(&mut self) -> Result<(), MlflowError> {
+        let pending = std::mem::take(&mut self.pending);
+        for chunk in pending.chunks(MAX_BATCH_METRICS) {
+            self.post(
+                "runs/log-batch",
+                &json!({"run_id": self.run_id, "metrics": chunk}),
+            )?;
+        }
+        self.rounds_buffered = 0;
+        Ok(())
+    }
+
+    /// HTTP location of the artifact root `root`, which must be proxied by the server.
+    fn artifact_url(&self, root: &str) -> Result<String, MlflowError> {
+        let path = root
+            .strip_prefix(ARTIFACT_SCHEME)
+            .ok_or_else(|| MlflowError::ArtifactLocation(root.to_string()))?;
+        Ok(format!(
+            "{}/api/2.0/mlflow-artifacts/artifacts/{}",
+            self.tracking_uri,
+            path.trim_matches('/')
+        ))
+    }
+
+    fn post(&self, endpoint: &str, body: &Value) -> Result<Value, MlflowError> {
+        let url = format!("{}/api/2.0/mlflow/{}", self.tracking_uri, endpoint);
+        let response = self
+            .authorized(ureq::post(&url))
+            .send_json(body)
+            .map_err(|e| request_error(endpoint, e))?;
+        response
+            .into_json()
+            .map_err(|e| MlflowError::Response(e.to_string()))
+    }
+
+    fn get(&self, endpoint: &str) -> Result<Value, MlflowError> {
+        let url = format!("{}/api/2.0/mlflow/{}", self.tracking_uri, endpoint);
+        let response = self
+            .authorized(ureq::get(&url))
+            .call()
+            .map_err(|e| request_error(endpoint, e))?;
+        response
+            .into_json()
+            .map_err(|e| MlflowError::Response(e.to_string()))
+    }
+
+    fn authorized(&self, request: ureq::Request) -> ureq::Request {
+        match &self.token {
+            Some(token) => request.set("Authorization", &format!("Bearer {}", token)),
+            None => request,
+        }
+    }
+}
+
+impl TrainingCallback for MlflowLogger {
+    fn after_round(
+        &mut self,
+        round: usize,
+        metrics: &[(String, HashMap<String, f64>)],
+    ) -> io::Result<()> {
+        self.pending
+            .extend(round_metrics(round, metrics, now_millis()));
+        self.rounds_buffered += 1;
+        if self.rounds_buffered >= self.flush_every {
+            self.flush().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+fn request_error(endpoint: &str, error: ureq::Error) -> MlflowError {
+    let message = match error {
+        ureq::Error::Status(code, response) => format!(
+            "status {}: {}",
+            code,
+            response.into_string().unwrap_or_default()
+        ),
+        ureq::Error::Transport(transport) => transport.to_string(),
+    };
+    MlflowError::Request {
+        endpoint: endpoint.to_string(),
+        message,
+    }
+}
+
+/// `log-batch` metric entries for one round, in a stable order.
+fn round_metrics(
+    round: usize,
+    metrics: &[(String, HashMap<String, f64>)],
+    timestamp: u64,
+) -> Vec<Value> {
+    metrics
+        .iter()
+        .flat_map(|(dataset, values)| {
+            values
+                .iter()
+                .collect::<BTreeMap<_, _>>()
+                .into_iter()
+                .map(move |(metric, value)| {
+                    json!({
+                        "key": metric_key(dataset, metric),
+                        "value": value,
+                        "timestamp": timestamp,
+                        "step": round,
+                    })
+                })
+        })
+        .collect()
+}
+
+/// `<dataset>-<metric>`, with characters MLflow rejects in keys, like the `@` of
+/// `ndcg@10`, replaced by `_`.
+fn metric_key(dataset: &str, metric: &str) -> String {
+    format!("{}-{}", dataset, metric)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "_-. /".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn artifact_file(artifact_path: &str, file: &str) -> String {
+    match artifact_path.trim_matches('/') {
+        "" => file.to_string(),
+        path => format!("{}/{}", path, file),
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_key() {
+        assert_eq!(metric_key("valid", "rmse"), "valid-rmse");
+        assert_eq!(metric_key("valid", "ndcg@10"), "valid-ndcg_10");
+    }
+
+    #[test]
+    fn test_round_metrics() {
+        let metrics = vec![(
+            "train".to_string(),
+            HashMap::from([("rmse".to_string(), 0.5), ("mae".to_string(), 0.25)]),
+        )];
+        let entries = round_metrics(3, &metrics, 1000);
+        assert_eq!(
+            entries[0],
+            json!({"key": "train-mae", "value": 0.25, "timestamp": 1000, "step": 3})
+        );
+        assert_eq!(entries[1]["key"], "train-rmse");
+    }
+
+    #[test]
+    fn test_artifact_url() {
+        let logger = MlflowLogger::new("http://mlflow:5000/", "abc");
+        assert_eq!(
+            logger
+                .artifact_url("mlflow-artifacts:/1/abc/artifacts")
+                .unwrap(),
+            "http://mlflow:5000/api/2.0/mlflow-artifacts/artifacts/1/abc/artifacts"
+        );
+        assert!(logger.artifact_url("s3://bucket/1/abc/artifacts").is_err());
+        assert_eq!(artifact_file("/model/", "model.ubj"), "model/model.ubj");
+        assert_eq!(artifact_file("", "model.ubj"), "model.ubj");
+    }
+}
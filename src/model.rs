@@ -0,0 +1,744 @@
+//! Introspection of the JSON model representation.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::dmatrix::Csr;
+
+/// Coefficients of a `gblinear` model. The base score is not included in `bias`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearWeights {
+    /// Feature-major: the weight of feature `f` for output group `g` is at `f * num_groups + g`.
+    pub coefficients: Vec<f32>,
+    pub bias: Vec<f32>,
+    pub num_features: usize,
+    pub num_groups: usize,
+}
+
+impl LinearWeights {
+    pub fn coefficient(&self, feature: usize, group: usize) -> f32 {
+        self.coefficients[feature * self.num_groups + group]
+    }
+
+    pub(crate) fn from_model_json(model: &Value) -> Result<Self, String> {
+        let booster = &model["learner"]["gradient_booster"];
+        let name = booster["name"].as_str().unwrap_or_default();
+        if name != "gblinear" {
+            return Err(format!("expected a gblinear booster, got {:?}", name));
+        }
+        let weights: Vec<f32> = booster["model"]["weights"]
+            .as_array()
+            .ok_or("missing linear weights")?
+            .iter()
+            .map(|w| w.as_f64().map(|w| w as f32).ok_or("invalid linear weight"))
+            .collect::<Result<_, _>>()?;
+        let num_features = parse_usize(&model["learner"]["learner_model_param"]["num_feature"])?;
+        if weights.is_empty() || !weights.len().is_multiple_of(num_features + 1) {
+            return Err("linear weights do not match the number of features".to_string());
+        }
+        let num_groups = weights.len() / (num_features + 1);
+        let split = num_features * num_groups;
+        Ok(LinearWeights {
+            coefficients: weights[..split].to_vec(),
+            bias: weights[split..].to_vec(),
+            num_features,
+            num_groups,
+        })
+    }
+}
+
+/// One regression tree, in the array-of-nodes layout of XGBoost's JSON schema. Node 0 is
+/// the root; leaves have `left_children[i] == -1` and store their value in
+/// `split_conditions[i]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tree {
+    pub left_children: Vec<i32>,
+    pub right_children: Vec<i32>,
+    pub parents: Vec<i32>,
+    pub split_indices: Vec<u32>,
+    pub split_conditions: Vec<f32>,
+    pub default_left: Vec<bool>,
+    pub base_weights: Vec<f32>,
+    pub loss_changes: Vec<f32>,
+    pub sum_hessian: Vec<f32>,
+}
+
+impl Tree {
+    pub fn num_nodes(&self) -> usize {
+        self.left_children.len()
+    }
+
+    pub fn is_leaf(&self, node: usize) -> bool {
+        self.left_children[node] == -1
+    }
+
+    pub fn leaf_value(&self, node: usize) -> f32 {
+        self.split_conditions[node]
+    }
+
+    pub fn num_leaves(&self) -> usize {
+        (0..self.num_nodes()).filter(|n| self.is_leaf(*n)).count()
+    }
+
+    /// Depth of the deepest leaf, a single-leaf tree having depth 0.
+    pub fn max_depth(&self) -> usize {
+        self.leaf_depths().into_iter().max().unwrap_or(0)
+    }
+
+    /// Depth of every leaf, in node order.
+    pub fn leaf_depths(&self) -> Vec<usize> {
+        let mut depths = vec![0usize; self.num_nodes()];
+        let mut stack = vec![0usize];
+        let mut leaves = Vec::new();
+        while let Some(node) = stack.pop() {
+            if node >= self.num_nodes() {
+                continue;
+            }
+            if self.is_leaf(node) {
+                leaves.push((node, depths[node]));
+                continue;
+            }
+            for child in [self.left_children[node], self.right_children[node]] {
+                depths[child as usize] = depths[node] + 1;
+                stack.push(child as usize);
+            }
+        }
+        leaves.sort_unstable();
+        leaves.into_iter().map(|(_, d)| d).collect()
+    }
+
+    pub(crate) fn from_json(tree: &Value) -> Result<Self, String> {
+        let leaf_size = parse_usize(&tree["tree_param"]["size_leaf_vector"]).unwrap_or(1);
+        if leaf_size > 1 {
+            return Err("multi-target trees are not supported".to_string());
+        }
+        Ok(Tree {
+            left_children: parse_array(&tree["left_children"], |v| v.as_i64().map(|v| v as i32))?,
+            right_children: parse_array(&tree["right_children"], |v| v.as_i64().map(|v| v as i32))?,
+            parents: parse_array(&tree["parents"], |v| v.as_i64().map(|v| v as i32))?,
+            split_indices: parse_array(&tree["split_indices"], |v| v.as_u64().map(|v| v as u32))?,
+            split_conditions: parse_array(&tree["split_conditions"], as_f32)?,
+            default_left: parse_array(&tree["default_left"], |v| {
+                v.as_bool().or_else(|| v.as_u64().map(|v| v != 0))
+            })?,
+            base_weights: parse_array(&tree["base_weights"], as_f32)?,
+            loss_changes: parse_array(&tree["loss_changes"], as_f32)?,
+            sum_hessian: parse_array(&tree["sum_hessian"], as_f32)?,
+        })
+    }
+}
+
+/// The trees of a `gbtree` or `dart` model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TreeEnsemble {
+    pub trees: Vec<Tree>,
+    /// Output group of each tree.
+    pub tree_info: Vec<usize>,
+    /// Trees of boosting round `r` are `iteration_indptr[r]..iteration_indptr[r + 1]`.
+    pub iteration_indptr: Vec<usize>,
+    /// Per-tree scaling, all 1 except for DART.
+    pub tree_weights: Vec<f32>,
+    pub num_groups: usize,
+}
+
+impl TreeEnsemble {
+    pub fn num_rounds(&self) -> usize {
+        self.iteration_indptr.len().saturating_sub(1)
+    }
+
+    /// Total number of leaves, the width of [`TreeEnsemble::leaf_one_hot`].
+    pub fn num_leaves(&self) -> usize {
+        self.trees.iter().map(Tree::num_leaves).sum()
+    }
+
+    /// One-hot encodes leaf membership: given leaf indices laid out as `(rows, trees)`,
+    /// row `i` gets a 1 in the column of each leaf it falls into. Columns are numbered tree
+    /// by tree, leaves in node order.
+    pub fn leaf_one_hot(&self, leaves: &[f32]) -> Csr {
+        let mut offset = 0;
+        let columns: Vec<Vec<u32>> = self
+            .trees
+            .iter()
+            .map(|tree| {
+                (0..tree.num_nodes())
+                    .map(|node| {
+                        let column = offset;
+                        if tree.is_leaf(node) {
+                            offset += 1;
+                        }
+                        column
+                    })
+                    .collect()
+            })
+            .collect();
+        let n_trees = self.trees.len().max(1);
+        let mut csr = Csr {
+            indptr: vec![0],
+            ..Default::default()
+        };
+        for row in leaves.chunks(n_trees) {
+            for (t, leaf) in row.iter().enumerate() {
+                csr.indices.push(columns[t][*leaf as usize]);
+                csr.values.push(1.0);
+            }
+            csr.indptr.push(csr.indices.len() as u64);
+        }
+        csr
+    }
+
+    /// Margins laid out as `(rows, groups)` from leaf indices, summing the leaf values in
+    /// double precision in tree order. Rows start from `base_margin` when it is set, from
+    /// the per-group `intercept` otherwise.
+    pub(crate) fn margins_f64(
+        &self,
+        leaves: &[f32],
+        intercept: &[f64],
+        base_margin: &[f32],
+    ) -> Vec<f64> {
+        let n_trees = self.trees.len();
+        let groups = self.num_groups;
+        let rows = leaves.len().checked_div(n_trees).unwrap_or(0);
+        let mut margins: Vec<f64> = (0..rows * groups)
+            .map(|i| match base_margin.get(i) {
+                Some(margin) => *margin as f64,
+                None => intercept
+                    .get(i % groups)
+                    .or(intercept.first())
+                    .copied()
+                    .unwrap_or(0.0),
+            })
+            .collect();
+        for (row, row_leaves) in leaves.chunks(n_trees.max(1)).enumerate().take(rows) {
+            for (t, leaf) in row_leaves.iter().enumerate() {
+                let value = self.trees[t].leaf_value(*leaf as usize) as f64;
+                margins[row * groups + self.tree_info[t]] += self.tree_weights[t] as f64 * value;
+            }
+        }
+        margins
+    }
+
+    pub(crate) fn from_model_json(model: &Value) -> Result<Self, String> {
+        let booster = &model["learner"]["gradient_booster"];
+        let (gbtree, tree_weights) = match booster["name"].as_str().unwrap_or_default() {
+            "gbtree" => (booster, None),
+            "dart" => (
+                &booster["gbtree"],
+                Some(parse_array(&booster["weight_drop"], as_f32)?),
+            ),
+            name => return Err(format!("expected a tree booster, got {:?}", name)),
+        };
+        let trees = gbtree["model"]["trees"]
+            .as_array()
+            .ok_or("missing trees")?
+            .iter()
+            .map(Tree::from_json)
+            .collect::<Result<Vec<_>, _>>()?;
+        let tree_info = parse_array(&gbtree["model"]["tree_info"], |v| {
+            v.as_u64().map(|v| v as usize)
+        })?;
+        let iteration_indptr = parse_array(&gbtree["model"]["iteration_indptr"], |v| {
+            v.as_u64().map(|v| v as usize)
+        })?;
+        let num_class =
+            parse_usize(&model["learner"]["learner_model_param"]["num_class"]).unwrap_or(0);
+        let tree_weights = tree_weights.unwrap_or_else(|| vec![1.0; trees.len()]);
+        if tree_info.len() != trees.len() || tree_weights.len() != trees.len() {
+            return Err("inconsistent tree metadata".to_string());
+        }
+        Ok(TreeEnsemble {
+            trees,
+            tree_info,
+            iteration_indptr,
+            tree_weights,
+            num_groups: num_class.max(1),
+        })
+    }
+}
+
+/// The model's `base_score` mapped to margin space through the objective's inverse link,
+/// one value per output group when the model stores a vector.
+pub(crate) fn margin_intercept(model: &Value) -> Result<Vec<f64>, String> {
+    let learner = &model["learner"];
+    let raw = learner["learner_model_param"]["base_score"]
+        .as_str()
+        .ok_or("missing base_score")?;
+    let scores = raw
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .map(|v| v.trim().parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid base_score {:?}", raw))?;
+    let link: fn(f64) -> f64 = match learner["objective"]["name"].as_str().unwrap_or_default() {
+        "binary:logistic" | "binary:logitraw" | "reg:logistic" => |p| (p / (1.0 - p)).ln(),
+        "count:poisson" | "reg:gamma" | "reg:tweedie" | "survival:cox" | "survival:aft" => f64::ln,
+        _ => |p| p,
+    };
+    Ok(scores.into_iter().map(link).collect())
+}
+
+/// Size figures of a tree model, e.g. to enforce deployment budgets in CI.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Complexity {
+    pub num_trees: usize,
+    pub num_leaves: usize,
+    /// Deepest tree, a stump has depth 1.
+    pub max_depth: usize,
+    /// Mean of the per-tree depths.
+    pub mean_depth: f64,
+    /// Size in bytes of the model saved as UBJSON.
+    pub serialized_size: usize,
+}
+
+impl Complexity {
+    pub(crate) fn new(ensemble: &TreeEnsemble, serialized_size: usize) -> Self {
+        let depths: Vec<usize> = ensemble.trees.iter().map(Tree::max_depth).collect();
+        Complexity {
+            num_trees: ensemble.trees.len(),
+            num_leaves: ensemble.trees.iter().map(Tree::num_leaves).sum(),
+            max_depth: depths.iter().copied().max().unwrap_or(0),
+            mean_depth: if depths.is_empty() {
+                0.0
+            } else {
+                depths.iter().sum::<usize>() as f64 / depths.len() as f64
+            },
+            serialized_size,
+        }
+    }
+}
+
+/// Headline facts about a model, as shown by the `Debug` and `Display` output of a
+/// [`Booster`](crate::booster::Booster).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelSummary {
+    /// `gbtree`, `dart` or `gblinear`.
+    pub booster: String,
+    pub objective: String,
+    pub num_features: usize,
+    /// 0 unless the objective is multi-class.
+    pub num_class: usize,
+    /// `None` for linear models.
+    pub num_trees: Option<usize>,
+    /// Number of feature names stored in the model, 0 if trained without names.
+    pub num_feature_names: usize,
+}
+
+impl ModelSummary {
+    pub(crate) fn from_model_json(model: &Value) -> Result<Self, String> {
+        let learner = &model["learner"];
+        let booster = &learner["gradient_booster"];
+        let name = booster["name"].as_str().ok_or("missing booster name")?;
+        let num_trees = match name {
+            "gbtree" => Some(&booster["model"]),
+            "dart" => Some(&booster["gbtree"]["model"]),
+            _ => None,
+        }
+        .map(|model| parse_usize(&model["gbtree_model_param"]["num_trees"]))
+        .transpose()?;
+        Ok(ModelSummary {
+            booster: name.to_string(),
+            objective: learner["objective"]["name"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            num_features: parse_usize(&learner["learner_model_param"]["num_feature"])?,
+            num_class: parse_usize(&learner["learner_model_param"]["num_class"]).unwrap_or(0),
+            num_trees,
+            num_feature_names: learner["feature_names"].as_array().map_or(0, Vec::len),
+        })
+    }
+}
+
+impl fmt::Display for ModelSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.booster)?;
+        if !self.objective.is_empty() {
+            write!(f, " {}", self.objective)?;
+        }
+        if let Some(num_trees) = self.num_trees {
+            write!(f, ", {} trees", num_trees)?;
+        }
+        write!(f, ", {} features", self.num_features)?;
+        if self.num_feature_names > 0 {
+            write!(f, " ({} named)", self.num_feature_names)?;
+        }
+        if self.num_class > 0 {
+            write!(f, ", {} classes", self.num_class)?;
+        }
+        Ok(())
+    }
+}
+
+/// Output of each boosting round for each row, excluding the base score.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundContributions {
+    /// Laid out as `(rows, rounds, groups)`.
+    pub values: Vec<f32>,
+    pub rows: usize,
+    pub rounds: usize,
+    pub groups: usize,
+}
+
+impl RoundContributions {
+    pub fn get(&self, row: usize, round: usize, group: usize) -> f32 {
+        self.values[(row * self.rounds + round) * self.groups + group]
+    }
+
+    /// Running sum over rounds for one row and group: the margin after each round,
+    /// minus the base score.
+    pub fn cumulative(&self, row: usize, group: usize) -> Vec<f32> {
+        (0..self.rounds)
+            .scan(0.0, |acc, round| {
+                *acc += self.get(row, round, group);
+                Some(*acc)
+            })
+            .collect()
+    }
+
+    /// Builds contributions from leaf indices as returned by leaf prediction, one per
+    /// tree for each row.
+    pub(crate) fn from_leaves(ensemble: &TreeEnsemble, leaves: &[f32]) -> Self {
+        let n_trees = ensemble.trees.len();
+        let rows = leaves.len().checked_div(n_trees).unwrap_or(0);
+        let (rounds, groups) = (ensemble.num_rounds(), ensemble.num_groups);
+        let mut values = vec![0.0; rows * rounds * groups];
+        for (row, row_leaves) in leaves.chunks(n_trees.max(1)).enumerate().take(rows) {
+            for round in 0..rounds {
+                let trees = ensemble.iteration_indptr[round]..ensemble.iteration_indptr[round + 1];
+                for t in trees {
+                    let leaf = ensemble.trees[t].leaf_value(row_leaves[t] as usize);
+                    let group = ensemble.tree_info[t];
+                    values[(row * rounds + round) * groups + group] +=
+                        ensemble.tree_weights[t] * leaf;
+                }
+            }
+        }
+        RoundContributions {
+            values,
+            rows,
+            rounds,
+            groups,
+        }
+    }
+}
+
+/// Per-node arrays of a JSON tree, reindexed when nodes are removed.
+const NODE_ARRAYS: [&str; 10] = [
+    "left_children",
+    "right_children",
+    "parents",
+    "split_indices",
+    "split_conditions",
+    "split_type",
+    "default_left",
+    "base_weights",
+    "loss_changes",
+    "sum_hessian",
+];
+
+/// Collapses every split whose children are leaves and whose loss change is below
+/// `min_gain` into a leaf, bottom-up, like XGBoost's `prune` updater. The new leaf value is
+/// the node weight scaled by the learning rate, which is recovered from existing leaves.
+/// Returns the number of removed nodes.
+pub(crate) fn prune_model_json(model: &mut Value, min_gain: f32) -> Result<usize, String> {
+    let booster = &mut model["learner"]["gradient_booster"];
+    let gbtree = match booster["name"].as_str().unwrap_or_default() {
+        "gbtree" => booster,
+        "dart" => &mut booster["gbtree"],
+        name => return Err(format!("expected a tree booster, got {:?}", name)),
+    };
+    let trees = gbtree["model"]["trees"]
+        .as_array_mut()
+        .ok_or("missing trees")?;
+    let mut removed = 0;
+    for tree in trees.iter_mut() {
+        removed += prune_tree(tree, min_gain)?;
+    }
+    Ok(removed)
+}
+
+fn prune_tree(json: &mut Value, min_gain: f32) -> Result<usize, String> {
+    let mut tree = Tree::from_json(json)?;
+    let eta = (0..tree.num_nodes())
+        .find(|n| tree.is_leaf(*n) && tree.base_weights[*n] != 0.0)
+        .map(|n| tree.leaf_value(n) / tree.base_weights[n])
+        .unwrap_or(1.0);
+
+    // Children always have larger ids than their parent, so a reverse scan is bottom-up.
+    let mut collapsed = false;
+    for node in (0..tree.num_nodes()).rev() {
+        if tree.is_leaf(node) {
+            continue;
+        }
+        let (left, right) = (
+            tree.left_children[node] as usize,
+            tree.right_children[node] as usize,
+        );
+        if tree.is_leaf(left) && tree.is_leaf(right) && tree.loss_changes[node] < min_gain {
+            tree.left_children[node] = -1;
+            tree.right_children[node] = -1;
+            tree.split_conditions[node] = tree.base_weights[node] * eta;
+            tree.loss_changes[node] = 0.0;
+            collapsed = true;
+        }
+    }
+    if !collapsed {
+        return Ok(0);
+    }
+
+    // Renumber reachable nodes in breadth-first order
+    let mut order = vec![0usize];
+    let mut i = 0;
+    while i < order.len() {
+        let node = order[i];
+        if !tree.is_leaf(node) {
+            order.push(tree.left_children[node] as usize);
+            order.push(tree.right_children[node] as usize);
+        }
+        i += 1;
+    }
+    let mut new_id = vec![-1i64; tree.num_nodes()];
+    for (new, old) in order.iter().enumerate() {
+        new_id[*old] = new as i64;
+    }
+    let remap = |id: i32| -> i64 {
+        if id < 0 || id as usize >= new_id.len() {
+            id as i64
+        } else {
+            new_id[id as usize]
+        }
+    };
+
+    let obj = json.as_object_mut().ok_or("tree is not an object")?;
+    for name in NODE_ARRAYS {
+        let Some(Value::Array(old)) = obj.get(name) else {
+            continue;
+        };
+        let values: Vec<Value> = order
+            .iter()
+            .map(|n| match name {
+                "left_children" => Value::from(remap(tree.left_children[*n])),
+                "right_children" => Value::from(remap(tree.right_children[*n])),
+                "parents" => Value::from(remap(tree.parents[*n])),
+                "split_conditions" => Value::from(tree.split_conditions[*n]),
+                "loss_changes" => Value::from(tree.loss_changes[*n]),
+                "split_indices" | "split_type" | "default_left" if tree.is_leaf(*n) => {
+                    Value::from(0)
+                }
+                _ => old.get(*n).cloned().unwrap_or(Value::from(0)),
+            })
+            .collect();
+        obj.insert(name.to_string(), Value::Array(values));
+    }
+    remap_categories(obj, &new_id)?;
+    let num_nodes = order.len();
+    obj["tree_param"]["num_nodes"] = Value::from(num_nodes.to_string());
+    obj["tree_param"]["num_deleted"] = Value::from("0");
+    Ok(tree.num_nodes() - num_nodes)
+}
+
+/// Drops categorical split sets of removed nodes and renumbers the remaining ones.
+fn remap_categories(
+    obj: &mut serde_json::Map<String, Value>,
+    new_id: &[i64],
+) -> Result<(), String> {
+    let nodes = match obj.get("categories_nodes") {
+        Some(nodes) => parse_array(nodes, |v| v.as_u64().map(|v| v as usize))?,
+        None => return Ok(()),
+    };
+    let categories = parse_array(&obj["categories"], |v| v.as_i64())?;
+    let segments = parse_array(&obj["categories_segments"], |v| {
+        v.as_u64().map(|v| v as usize)
+    })?;
+    let sizes = parse_array(&obj["categories_sizes"], |v| v.as_u64().map(|v| v as usize))?;
+    let (mut new_nodes, mut new_categories, mut new_segments, mut new_sizes) =
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for (i, node) in nodes.iter().enumerate() {
+        // Collapsed splits are leaves now; their category sets must go too
+        let id = new_id.get(*node).copied().unwrap_or(-1);
+        let is_split = obj["left_children"]
+            .get(id.max(0) as usize)
+            .and_then(|v| v.as_i64())
+            .is_some_and(|c| c != -1);
+        if id < 0 || !is_split {
+            continue;
+        }
+        new_nodes.push(id);
+        new_segments.push(new_categories.len());
+        new_sizes.push(sizes[i]);
+        new_categories.extend_from_slice(&categories[segments[i]..segments[i] + sizes[i]]);
+    }
+    obj.insert("categories_nodes".to_string(), Value::from(new_nodes));
+    obj.insert("categories".to_string(), Value::from(new_categories));
+    obj.insert("categories_segments".to_string(), Value::from(new_segments));
+    obj.insert("categories_sizes".to_string(), Value::from(new_sizes));
+    Ok(())
+}
+
+fn as_f32(value: &Value) -> Option<f32> {
+    value.as_f64().map(|v| v as f32)
+}
+
+fn parse_array<T>(value: &Value, parse: impl Fn(&Value) -> Option<T>) -> Result<Vec<T>, String> {
+    value
+        .as_array()
+        .ok_or_else(|| format!("expected an array, got {}", value))?
+        .iter()
+        .map(|v| parse(v).ok_or_else(|| format!("invalid array value {}", v)))
+        .collect()
+}
+
+/// Model parameters are serialized as strings, e.g. `"num_feature": "3"`.
+pub(crate) fn parse_usize(value: &Value) -> Result<usize, String> {
+    match value {
+        Value::String(s) => s.parse().map_err(|_| format!("invalid integer {:?}", s)),
+        Value::Number(n) => n
+            .as_u64()
+            .map(|n| n as usize)
+            .ok_or_else(|| format!("invalid integer {}", n)),
+        _ => Err(format!("expected an integer, got {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_weights() {
+        let model: Value = serde_json::from_str(
+            r#"{"learner": {
+                "learner_model_param": {"num_feature": "2", "num_class": "0"},
+                "gradient_booster": {"name": "gblinear", "model": {"weights": [0.5, -1.0, 0.25]}}
+            }}"#,
+        )
+        .unwrap();
+        let weights = LinearWeights::from_model_json(&model).unwrap();
+        assert_eq!(weights.coefficients, vec![0.5, -1.0]);
+        assert_eq!(weights.bias, vec![0.25]);
+        assert_eq!(weights.coefficient(1, 0), -1.0);
+    }
+
+    pub(crate) fn stump_model() -> Value {
+        serde_json::from_str(
+            r#"{"learner": {
+                "learner_model_param": {"num_feature": "1", "num_class": "0"},
+                "gradient_booster": {"name": "gbtree", "model": {
+                    "gbtree_model_param": {"num_trees": "2", "num_parallel_tree": "1"},
+                    "tree_info": [0, 0],
+                    "iteration_indptr": [0, 1, 2],
+                    "trees": [
+                        {"tree_param": {"num_nodes": "3", "size_leaf_vector": "1"},
+                         "left_children": [1, -1, -1], "right_children": [2, -1, -1],
+                         "parents": [2147483647, 0, 0], "split_indices": [0, 0, 0],
+                         "split_conditions": [0.5, -0.2, 0.3], "default_left": [1, 0, 0],
+                         "base_weights": [0.0, -0.2, 0.3], "loss_changes": [2.0, 0.0, 0.0],
+                         "sum_hessian": [4.0, 2.0, 2.0]},
+                        {"tree_param": {"num_nodes": "1", "size_leaf_vector": "1"},
+                         "left_children": [-1], "right_children": [-1], "parents": [2147483647],
+                         "split_indices": [0], "split_conditions": [0.1], "default_left": [0],
+                         "base_weights": [0.1], "loss_changes": [0.0], "sum_hessian": [4.0]}
+                    ]
+                }}
+            }}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_model_summary() {
+        let mut model = stump_model();
+        model["learner"]["objective"] = serde_json::json!({"name": "reg:squarederror"});
+        model["learner"]["feature_names"] = serde_json::json!(["age"]);
+        let summary = ModelSummary::from_model_json(&model).unwrap();
+        assert_eq!(summary.num_trees, Some(2));
+        assert_eq!(summary.num_feature_names, 1);
+        assert_eq!(
+            summary.to_string(),
+            "gbtree reg:squarederror, 2 trees, 1 features (1 named)"
+        );
+    }
+
+    #[test]
+    fn test_round_contributions() {
+        let ensemble = TreeEnsemble::from_model_json(&stump_model()).unwrap();
+        assert_eq!(ensemble.num_rounds(), 2);
+        assert_eq!(ensemble.trees[0].num_leaves(), 2);
+        assert_eq!(ensemble.trees[0].max_depth(), 1);
+        // Row 0 lands in leaf 1 then 0, row 1 in leaf 2 then 0
+        let contributions = RoundContributions::from_leaves(&ensemble, &[1.0, 0.0, 2.0, 0.0]);
+        assert_eq!(contributions.get(0, 0, 0), -0.2);
+        assert_eq!(contributions.get(1, 0, 0), 0.3);
+        assert_eq!(contributions.cumulative(1, 0), vec![0.3, 0.4]);
+    }
+
+    #[test]
+    fn test_margins_f64() {
+        let mut model = stump_model();
+        model["learner"]["learner_model_param"]["base_score"] = "5E-1".into();
+        model["learner"]["objective"] = serde_json::json!({"name": "binary:logistic"});
+        let intercept = margin_intercept(&model).unwrap();
+        assert_eq!(intercept, vec![0.0]);
+        let ensemble = TreeEnsemble::from_model_json(&model).unwrap();
+        let margins = ensemble.margins_f64(&[1.0, 0.0, 2.0, 0.0], &intercept, &[]);
+        assert_eq!(
+            margins,
+            vec![
+                -0.2f32 as f64 + 0.1f32 as f64,
+                0.3f32 as f64 + 0.1f32 as f64
+            ]
+        );
+        let margins = ensemble.margins_f64(&[1.0, 0.0], &intercept, &[1.0]);
+        assert_eq!(margins, vec![1.0 + -0.2f32 as f64 + 0.1f32 as f64]);
+
+        model["learner"]["learner_model_param"]["base_score"] = "[2E0]".into();
+        model["learner"]["objective"] = serde_json::json!({"name": "reg:squarederror"});
+        assert_eq!(margin_intercept(&model).unwrap(), vec![2.0]);
+    }
+
+    #[test]
+    fn test_leaf_one_hot() {
+        let ensemble = TreeEnsemble::from_model_json(&stump_model()).unwrap();
+        assert_eq!(ensemble.num_leaves(), 3);
+        let csr = ensemble.leaf_one_hot(&[1.0, 0.0, 2.0, 0.0]);
+        assert_eq!(csr.indptr, vec![0, 2, 4]);
+        assert_eq!(csr.indices, vec![0, 2, 1, 2]);
+        assert_eq!(csr.values, vec![1.0; 4]);
+    }
+
+    #[test]
+    fn test_complexity() {
+        let ensemble = TreeEnsemble::from_model_json(&stump_model()).unwrap();
+        let complexity = Complexity::new(&ensemble, 100);
+        assert_eq!(complexity.num_trees, 2);
+        assert_eq!(complexity.num_leaves, 3);
+        assert_eq!(complexity.max_depth, 1);
+        assert_eq!(complexity.mean_depth, 0.5);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut model = stump_model();
+        model["learner"]["gradient_booster"]["model"]["trees"][0]["base_weights"][0] =
+            Value::from(0.05);
+        assert_eq!(prune_model_json(&mut model, 1.0).unwrap(), 0);
+        assert_eq!(prune_model_json(&mut model, 3.0).unwrap(), 2);
+        let ensemble = TreeEnsemble::from_model_json(&model).unwrap();
+        let tree = &ensemble.trees[0];
+        assert_eq!(tree.num_nodes(), 1);
+        assert!(tree.is_leaf(0));
+        assert_eq!(tree.leaf_value(0), 0.05);
+        assert_eq!(
+            model["learner"]["gradient_booster"]["model"]["trees"][0]["tree_param"]["num_nodes"],
+            "1"
+        );
+    }
+
+    #[test]
+    fn test_tree_model_rejected() {
+        let model: Value =
+            serde_json::from_str(r#"{"learner": {"gradient_booster": {"name": "gbtree"}}}"#)
+                .unwrap();
+        assert!(LinearWeights::from_model_json(&model).is_err());
+    }
+}
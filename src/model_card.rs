@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::schema::FeatureSchema;
+
+/// Booster attribute under which the card is stored.
+pub const MODEL_CARD_ATTR: &str = "model_card";
+
+/// Lightweight governance metadata stored inside the model file as a booster attribute,
+/// so it travels with the model through `save_model`/`load_model`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelCard {
+    pub name: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub training_data_hash: Option<String>,
+    #[serde(default)]
+    pub metrics: BTreeMap<String, f64>,
+    /// Names, types and expected ranges of the model's inputs, empty if not recorded.
+    #[serde(default, skip_serializing_if = "FeatureSchema::is_empty")]
+    pub feature_schema: FeatureSchema,
+}
+
+impl ModelCard {
+    pub fn new(name: &str, version: &str) -> Self {
+        ModelCard {
+            name: name.to_string(),
+            version: version.to_string(),
+            training_data_hash: None,
+            metrics: BTreeMap::new(),
+            feature_schema: FeatureSchema::new(),
+        }
+    }
+
+    pub fn training_data_hash(mut self, hash: &str) -> Self {
+        self.training_data_hash = Some(hash.to_string());
+        self
+    }
+
+    pub fn metric(mut self, name: &str, value: f64) -> Self {
+        self.metrics.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn feature_schema(mut self, schema: FeatureSchema) -> Self {
+        self.feature_schema = schema;
+        self
+    }
+
+    /// Stable 64-bit FNV-1a hash of the raw training values, hex encoded. Unlike
+    /// `DefaultHasher` it does not change between Rust releases.
+    pub fn hash_data(data: &[f32]) -> String {
+        let mut hash: u64 = 0xcbf29ce484222325;
+        for byte in data.iter().flat_map(|v| v.to_le_bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Checks the card is complete and consistent with a model using `num_features` inputs.
+    pub fn validate(&self, num_features: usize) -> Result<(), String> {
+        if self.name.is_empty() {
+            return Err("name is empty".to_string());
+        }
+        if self.version.is_empty() {
+            return Err("version is empty".to_string());
+        }
+        let schema = &self.feature_schema;
+        if !schema.is_empty() && schema.len() != num_features {
+            return Err(format!(
+                "{} features in the schema of a model with {} features",
+                schema.len(),
+                num_features
+            ));
+        }
+        for feature in &schema.features {
+            let min = feature.min.unwrap_or(f32::NEG_INFINITY);
+            let max = feature.max.unwrap_or(f32::INFINITY);
+            if min.is_nan() || max.is_nan() || min > max {
+                return Err(format!(
+                    "feature {} has an empty range [{}, {}]",
+                    feature.name, min, max
+                ));
+            }
+        }
+        if let Some((name, _)) = self.metrics.iter().find(|(_, v)| !v.is_finite()) {
+            return Err(format!("metric {} is not finite", name));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    pub(crate) fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::FeatureType;
+
+    #[test]
+    fn test_card_roundtrip() {
+        let card = ModelCard::new("churn", "1.2.0")
+            .training_data_hash(&ModelCard::hash_data(&[0.1, 0.2]))
+            .metric("auc", 0.81)
+            .feature_schema(
+                FeatureSchema::new()
+                    .bounded_feature("age", FeatureType::Int, 0.0, 120.0)
+                    .feature("tenure", FeatureType::Float),
+            );
+        let parsed = ModelCard::from_json(&card.to_json().unwrap()).unwrap();
+        assert_eq!(card, parsed);
+        assert_eq!(parsed.feature_schema.features[0].max, Some(120.0));
+        assert!(parsed.validate(2).is_ok());
+        assert!(parsed.validate(3).is_err());
+        let inverted = ModelCard::new("churn", "1.2.0").feature_schema(
+            FeatureSchema::new().bounded_feature("age", FeatureType::Int, 5.0, 1.0),
+        );
+        assert!(inverted.validate(1).is_err());
+        // Without a schema any feature count is accepted
+        assert!(ModelCard::new("churn", "1.2.0").validate(7).is_ok());
+    }
+
+    #[test]
+    fn test_hash_is_stable() {
+        assert_eq!(ModelCard::hash_data(&[]), "cbf29ce484222325");
+        assert_ne!(ModelCard::hash_data(&[1.0]), ModelCard::hash_data(&[2.0]));
+    }
+}
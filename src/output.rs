@@ -0,0 +1,433 @@
+//! Incremental prediction output. Batch scoring hands each scored batch to a
+//! [`PredictionWriter`], which encodes it right away, so predictions never accumulate in
+//! memory. Rows can carry an id copied from the input.
+
+use std::io::{self, Write};
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::DMatrix;
+use crate::predict::{PredictOptions, Prediction};
+
+/// A sink for batches of predictions.
+pub trait PredictionWriter {
+    /// Writes one batch. `ids` is empty, or holds one optional id per row.
+    fn write_batch(&mut self, ids: &[Option<String>], prediction: &Prediction) -> io::Result<()>;
+
+    /// Completes the output, e.g. writes a footer, and flushes it.
+    fn finish(&mut self) -> io::Result<()>;
+}
+
+fn check_ids(ids: &[Option<String>], prediction: &Prediction) -> io::Result<()> {
+    if ids.is_empty() || ids.len() as u64 == prediction.shape.rows() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} ids for {} rows", ids.len(), prediction.shape.rows()),
+        ))
+    }
+}
+
+/// Comma-separated lines: the id, if any, then the row's predictions.
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    header: bool,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        CsvWriter {
+            writer,
+            header: false,
+        }
+    }
+
+    /// Starts the output with a header line, `id` and `prediction` or `prediction_<i>`
+    /// columns.
+    pub fn with_header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> PredictionWriter for CsvWriter<W> {
+    fn write_batch(&mut self, ids: &[Option<String>], prediction: &Prediction) -> io::Result<()> {
+        check_ids(ids, prediction)?;
+        let row_len = prediction.shape.row_len();
+        if std::mem::take(&mut self.header) {
+            let mut columns: Vec<String> = prediction_columns(row_len);
+            if !ids.is_empty() {
+                columns.insert(0, "id".to_string());
+            }
+            writeln!(self.writer, "{}", columns.join(","))?;
+        }
+        for row in 0..prediction.shape.rows() as usize {
+            let preds: Vec<String> = prediction.row(row).iter().map(|p| p.to_string()).collect();
+            match ids.get(row) {
+                Some(Some(id)) => writeln!(self.writer, "{},{}", csv_field(id), preds.join(","))?,
+                Some(None) => writeln!(self.writer, ",{}", preds.join(","))?,
+                None => writeln!(self.writer, "{}", preds.join(","))?,
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Quotes `field` if it holds a comma, a quote or a line break, doubling inner quotes.
+fn csv_field(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+fn prediction_columns(row_len: usize) -> Vec<String> {
+    if row_len == 1 {
+        vec!["prediction".to_string()]
+    } else {
+        (0..row_len).map(|i| format!("prediction_{}", i)).collect()
+    }
+}
+
+/// One JSON object per line, `{"id": ..., "prediction": ...}`, with a number for a
+/// single output and an array otherwise. The id is omitted when there are none.
+pub struct JsonLinesWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(writer: W) -> Self {
+        JsonLinesWriter { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> PredictionWriter for JsonLinesWriter<W> {
+    fn write_batch(&mut self, ids: &[Option<String>], prediction: &Prediction) -> io::Result<()> {
+        check_ids(ids, prediction)?;
+        for row in 0..prediction.shape.rows() as usize {
+            let values = prediction.row(row);
+            let mut record = serde_json::Map::new();
+            if let Some(id) = ids.get(row) {
+                record.insert("id".to_string(), serde_json::json!(id));
+            }
+            let value = match values {
+                [value] => serde_json::json!(value),
+                values => serde_json::json!(values),
+            };
+            record.insert("prediction".to_string(), value);
+            serde_json::to_writer(&mut self.writer, &record)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Arrow IPC stream with a nullable `id` column, when there are ids, and a `prediction`
+/// column of `Float32`, or of fixed-size lists of them for several outputs. Requires the
+/// `arrow` feature.
+#[cfg(feature = "arrow")]
+pub struct ArrowIpcWriter<W: Write> {
+    output: Option<W>,
+    stream: Option<arrow::ipc::writer::StreamWriter<W>>,
+}
+
+#[cfg(feature = "arrow")]
+impl<W: Write> ArrowIpcWriter<W> {
+    pub fn new(writer: W) -> Self {
+        ArrowIpcWriter {
+            output: Some(writer),
+            stream: None,
+        }
+    }
+
+    /// The underlying writer, finishing the stream if that was not done yet.
+    pub fn into_inner(self) -> io::Result<W> {
+        match self.stream {
+            Some(stream) => stream.into_inner().map_err(io::Error::other),
+            None => self
+                .output
+                .ok_or_else(|| io::Error::other("the Arrow stream was already finished")),
+        }
+    }
+}
+
+#[cfg(feature = "arrow")]
+pub(crate) fn prediction_batch(
+    ids: &[Option<String>],
+    prediction: &Prediction,
+) -> Result<arrow::record_batch::RecordBatch, arrow::error::ArrowError> {
+    use std::sync::Arc;
+
+    use arrow::array::{ArrayRef, FixedSizeListArray, Float32Array, StringArray};
+    use arrow::datatypes::{DataType, Field, Schema};
+
+    let mut fields = Vec::new();
+    let mut columns: Vec<ArrayRef> = Vec::new();
+    if !ids.is_empty() {
+        fields.push(Field::new("id", DataType::Utf8, true));
+        columns.push(Arc::new(StringArray::from_iter(
+            ids.iter().map(Option::as_deref),
+        )));
+    }
+    let values = Arc::new(Float32Array::from(prediction.values.clone()));
+    let row_len = prediction.shape.row_len();
+    if row_len == 1 {
+        fields.push(Field::new("prediction", DataType::Float32, false));
+        columns.push(values);
+    } else {
+        let item = Arc::new(Field::new("item", DataType::Float32, false));
+        let list = FixedSizeListArray::try_new(item.clone(), row_len as i32, values, None)?;
+        fields.push(Field::new(
+            "prediction",
+            DataType::FixedSizeList(item, row_len as i32),
+            false,
+        ));
+        columns.push(Arc::new(list));
+    }
+    arrow::record_batch::RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)
+}
+
+#[cfg(feature = "arrow")]
+impl<W: Write> PredictionWriter for ArrowIpcWriter<W> {
+    fn write_batch(&mut self, ids: &[Option<String>], prediction: &Prediction) -> io::Result<()> {
+        check_ids(ids, prediction)?;
+        let batch = prediction_batch(ids, prediction).map_err(io::Error::other)?;
+        if self.stream.is_none() {
+            let output = self
+                .output
+                .take()
+                .ok_or_else(|| io::Error::other("the Arrow stream was already finished"))?;
+            let stream = arrow::ipc::writer::StreamWriter::try_new(output, &batch.schema())
+                .map_err(io::Error::other)?;
+            self.stream = Some(stream);
+        }
+        let stream = self.stream.as_mut().unwrap();
+        stream.write(&batch).map_err(io::Error::other)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        match self.stream.as_mut() {
+            Some(stream) => stream.finish().map_err(io::Error::other),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Predicts `data` in chunks of `chunk_rows` rows with
+/// [`Booster::predict_chunked_with`] and writes each chunk to `writer` as soon as it is
+/// scored. `ids`, if given, holds one id per row of `data`. Returns the number of rows.
+pub fn write_chunked(
+    booster: &Booster,
+    data: &DMatrix,
+    chunk_rows: usize,
+    options: &PredictOptions,
+    ids: Option<&[String]>,
+    writer: &mut dyn PredictionWriter,
+) -> Result<u64, XGBoostError> {
+    if ids.is_some_and(|ids| ids.len() as u64 != data.num_rows()) {
+        return Err(XGBoostError::Callback(format!(
+            "{} ids for {} rows",
+            ids.unwrap_or_default().len(),
+            data.num_rows()
+        )));
+    }
+    let mut batch_ids = Vec::new();
+    booster.predict_chunked_with(data, chunk_rows, options, |start, chunk| {
+        batch_ids.clear();
+        if let Some(ids) = ids {
+            let rows = chunk.shape.rows() as usize;
+            batch_ids.extend(ids[start..start + rows].iter().cloned().map(Some));
+        }
+        writer
+            .write_batch(&batch_ids, chunk)
+            .map_err(|e| XGBoostError::Callback(e.to_string()))
+    })?;
+    writer
+        .finish()
+        .map_err(|e| XGBoostError::Callback(e.to_string()))?;
+    Ok(data.num_rows())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::predict::Shape;
+
+    fn prediction(values: Vec<f32>, rows: u64) -> Prediction {
+        let row_len = values.len() as u64 / rows;
+        Prediction {
+            values,
+            shape: Shape::from_dims(&[rows, row_len]),
+        }
+    }
+
+    #[test]
+    fn test_csv_writer() {
+        let mut writer = CsvWriter::new(Vec::new()).with_header(true);
+        let ids = vec![Some("a".to_string()), None];
+        writer
+            .write_batch(&ids, &prediction(vec![0.1, 0.9, 0.4, 0.6], 2))
+            .unwrap();
+        writer
+            .write_batch(
+                &[Some("c,\"d\"".to_string())],
+                &prediction(vec![0.5, 0.5], 1),
+            )
+            .unwrap();
+        writer.finish().unwrap();
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            "id,prediction_0,prediction_1\na,0.1,0.9\n,0.4,0.6\n\"c,\"\"d\"\"\",0.5,0.5\n"
+        );
+        assert!(CsvWriter::new(Vec::new())
+            .write_batch(&ids, &prediction(vec![0.1], 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_json_lines_writer() {
+        let mut writer = JsonLinesWriter::new(Vec::new());
+        writer
+            .write_batch(&[], &prediction(vec![0.25, 0.75], 2))
+            .unwrap();
+        writer
+            .write_batch(&[Some("x".to_string())], &prediction(vec![1.0, 2.0], 1))
+            .unwrap();
+        let out = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            "{\"prediction\":0.25}\n{\"prediction\":0.75}\n{\"id\":\"x\",\"prediction\":[1.0,2.0]}\n"
+        );
+    }
+
+    #[cfg(feature = "arrow")]
+    fn read_ipc(bytes: &[u8]) -> Vec<arrow::record_batch::RecordBatch> {
+        arrow::ipc::reader::StreamReader::try_new(std::io::Cursor::new(bytes), None)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_arrow_ipc_writer() {
+        use arrow::array::{Array, FixedSizeListArray, Float32Array, StringArray};
+        use arrow::datatypes::DataType;
+
+        let mut writer = ArrowIpcWriter::new(Vec::new());
+        let ids = vec![Some("a".to_string()), None];
+        writer
+            .write_batch(&ids, &prediction(vec![0.1, 0.9, 0.4, 0.6], 2))
+            .unwrap();
+        writer
+            .write_batch(&[Some("c".to_string())], &prediction(vec![0.5, 0.5], 1))
+            .unwrap();
+        writer.finish().unwrap();
+        let batches = read_ipc(&writer.into_inner().unwrap());
+        assert_eq!(batches.len(), 2);
+        assert_eq!((batches[0].num_rows(), batches[1].num_rows()), (2, 1));
+        let ids = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(ids.value(0), "a");
+        assert!(ids.is_null(1));
+        let predictions = batches[0]
+            .column(1)
+            .as_any()
+            .downcast_ref::<FixedSizeListArray>()
+            .unwrap();
+        assert_eq!(predictions.value_length(), 2);
+        let values = predictions
+            .values()
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .unwrap();
+        assert_eq!(&values.values()[..], &[0.1, 0.9, 0.4, 0.6]);
+
+        // One output and no ids: a plain Float32 column
+        let mut writer = ArrowIpcWriter::new(Vec::new());
+        writer
+            .write_batch(&[], &prediction(vec![0.25, 0.75], 2))
+            .unwrap();
+        let batches = read_ipc(&writer.into_inner().unwrap());
+        let schema = batches[0].schema();
+        assert_eq!(schema.fields().len(), 1);
+        assert_eq!(schema.field(0).data_type(), &DataType::Float32);
+        let values = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .unwrap();
+        assert_eq!(&values.values()[..], &[0.25, 0.75]);
+        // Nothing written yet, so there is no stream to read
+        assert!(ArrowIpcWriter::new(Vec::new())
+            .into_inner()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[cfg(feature = "arrow")]
+    #[test]
+    fn test_write_chunked_arrow() {
+        use arrow::array::{Array, Float32Array, StringArray};
+
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4, 0.5, 0.6], 3, 2).unwrap();
+        dtrain.try_add_label(&[1., 2., 3.]).unwrap();
+        let booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let expected = booster.predict(&dtrain).unwrap();
+        let ids: Vec<String> = ["r0", "r1", "r2"].map(String::from).to_vec();
+        let options = PredictOptions::default();
+        let mut writer = ArrowIpcWriter::new(Vec::new());
+        let rows =
+            write_chunked(&booster, &dtrain, 2, &options, Some(&ids[..]), &mut writer).unwrap();
+        assert_eq!(rows, 3);
+        let batches = read_ipc(&writer.into_inner().unwrap());
+        // Chunks of 2 and 1 rows, one record batch each
+        assert_eq!(
+            batches.iter().map(|b| b.num_rows()).collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+        let mut got_ids = Vec::new();
+        let mut got = Vec::new();
+        for batch in &batches {
+            let ids = batch
+                .column(0)
+                .as_any()
+                .downcast_ref::<StringArray>()
+                .unwrap();
+            got_ids.extend((0..ids.len()).map(|i| ids.value(i).to_string()));
+            let values = batch
+                .column(1)
+                .as_any()
+                .downcast_ref::<Float32Array>()
+                .unwrap();
+            got.extend_from_slice(&values.values()[..]);
+        }
+        assert_eq!(got_ids, ids);
+        assert_eq!(got, expected);
+        let mut writer = ArrowIpcWriter::new(Vec::new());
+        assert!(
+            write_chunked(&booster, &dtrain, 2, &options, Some(&ids[..2]), &mut writer).is_err()
+        );
+    }
+}
@@ -0,0 +1,758 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ParamError {
+    #[error("Invalid value {value} for {name}: {reason}")]
+    Invalid {
+        name: String,
+        value: String,
+        reason: String,
+    },
+}
+
+impl ParamError {
+    fn invalid(name: &str, value: impl ToString, reason: &str) -> Self {
+        ParamError::Invalid {
+            name: name.to_string(),
+            value: value.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoosterType {
+    GbTree,
+    GbLinear,
+    Dart,
+}
+
+impl BoosterType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BoosterType::GbTree => "gbtree",
+            BoosterType::GbLinear => "gblinear",
+            BoosterType::Dart => "dart",
+        }
+    }
+}
+
+/// How DART picks the trees to drop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SampleType {
+    #[default]
+    Uniform,
+    Weighted,
+}
+
+/// How DART weights new trees against the dropped ones.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NormalizeType {
+    #[default]
+    Tree,
+    Forest,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DartParams {
+    pub rate_drop: f64,
+    pub skip_drop: f64,
+    pub sample_type: SampleType,
+    pub normalize_type: NormalizeType,
+    pub one_drop: bool,
+}
+
+impl Default for DartParams {
+    fn default() -> Self {
+        DartParams {
+            rate_drop: 0.0,
+            skip_drop: 0.0,
+            sample_type: SampleType::default(),
+            normalize_type: NormalizeType::default(),
+            one_drop: false,
+        }
+    }
+}
+
+impl DartParams {
+    fn validate(&self) -> Result<(), ParamError> {
+        if !(0.0..=1.0).contains(&self.rate_drop) {
+            return Err(ParamError::invalid(
+                "rate_drop",
+                self.rate_drop,
+                "must be in [0, 1]",
+            ));
+        }
+        if !(0.0..=1.0).contains(&self.skip_drop) {
+            return Err(ParamError::invalid(
+                "skip_drop",
+                self.skip_drop,
+                "must be in [0, 1]",
+            ));
+        }
+        Ok(())
+    }
+
+    fn pairs(&self) -> Vec<(String, String)> {
+        let sample_type = match self.sample_type {
+            SampleType::Uniform => "uniform",
+            SampleType::Weighted => "weighted",
+        };
+        let normalize_type = match self.normalize_type {
+            NormalizeType::Tree => "tree",
+            NormalizeType::Forest => "forest",
+        };
+        vec![
+            ("rate_drop".to_string(), self.rate_drop.to_string()),
+            ("skip_drop".to_string(), self.skip_drop.to_string()),
+            ("sample_type".to_string(), sample_type.to_string()),
+            ("normalize_type".to_string(), normalize_type.to_string()),
+            ("one_drop".to_string(), (self.one_drop as u8).to_string()),
+        ]
+    }
+}
+
+/// How lambdarank builds document pairs within a query.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PairMethod {
+    /// Sample `num_pair_per_sample` pairs for each document.
+    #[default]
+    Mean,
+    /// Pair each of the top `num_pair_per_sample` documents with all others, which
+    /// matches truncated metrics like `ndcg@k`.
+    TopK,
+}
+
+/// Parameters of the `rank:*` objectives.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RankingParams {
+    pub pair_method: Option<PairMethod>,
+    pub num_pair_per_sample: Option<u32>,
+    /// Use `2^rel - 1` gains for NDCG instead of the relevance itself. Relevance degrees
+    /// must then stay below 32.
+    pub ndcg_exp_gain: Option<bool>,
+    /// Evaluate with `ndcg@k`.
+    pub ndcg_at: Option<u32>,
+}
+
+impl RankingParams {
+    fn validate(&self) -> Result<(), ParamError> {
+        if self.num_pair_per_sample == Some(0) {
+            return Err(ParamError::invalid(
+                "lambdarank_num_pair_per_sample",
+                0,
+                "must be positive",
+            ));
+        }
+        if self.ndcg_at == Some(0) {
+            return Err(ParamError::invalid(
+                "eval_metric",
+                "ndcg@0",
+                "k must be positive",
+            ));
+        }
+        Ok(())
+    }
+
+    fn pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        if let Some(method) = self.pair_method {
+            let method = match method {
+                PairMethod::Mean => "mean",
+                PairMethod::TopK => "topk",
+            };
+            pairs.push(("lambdarank_pair_method".to_string(), method.to_string()));
+        }
+        if let Some(n) = self.num_pair_per_sample {
+            pairs.push(("lambdarank_num_pair_per_sample".to_string(), n.to_string()));
+        }
+        if let Some(exp_gain) = self.ndcg_exp_gain {
+            pairs.push(("ndcg_exp_gain".to_string(), (exp_gain as u8).to_string()));
+        }
+        if let Some(k) = self.ndcg_at {
+            pairs.push(("eval_metric".to_string(), format!("ndcg@{}", k)));
+        }
+        pairs
+    }
+}
+
+/// How the tree methods based on histograms grow a tree.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GrowPolicy {
+    /// Split the nodes closest to the root first.
+    #[default]
+    DepthWise,
+    /// Split the nodes with the highest loss change first, bounded by `max_leaves`.
+    LossGuide,
+}
+
+impl GrowPolicy {
+    fn as_str(&self) -> &'static str {
+        match self {
+            GrowPolicy::DepthWise => "depthwise",
+            GrowPolicy::LossGuide => "lossguide",
+        }
+    }
+}
+
+/// Fractions of columns sampled per tree, per depth level and per split. They compound:
+/// each level samples from the tree's columns and each split from the level's.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ColumnSampling {
+    pub bytree: Option<f64>,
+    pub bylevel: Option<f64>,
+    pub bynode: Option<f64>,
+}
+
+impl ColumnSampling {
+    fn pairs(&self) -> Vec<(String, String)> {
+        [
+            ("colsample_bytree", self.bytree),
+            ("colsample_bylevel", self.bylevel),
+            ("colsample_bynode", self.bynode),
+        ]
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|v| (k.to_string(), v.to_string())))
+        .collect()
+    }
+}
+
+/// Typed training parameters. Anything not covered can be passed through [`TrainParams::set`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrainParams {
+    booster: Option<BoosterType>,
+    objective: Option<String>,
+    eta: Option<f64>,
+    tweedie_variance_power: Option<f64>,
+    max_depth: Option<u32>,
+    max_leaves: Option<u32>,
+    max_bin: Option<u32>,
+    grow_policy: Option<GrowPolicy>,
+    min_child_weight: Option<f64>,
+    num_class: Option<u32>,
+    nthread: Option<u32>,
+    subsample: Option<f64>,
+    colsample: ColumnSampling,
+    seed: Option<u64>,
+    seed_per_iteration: bool,
+    dart: Option<DartParams>,
+    ranking: Option<RankingParams>,
+    eval_metrics: Vec<String>,
+    extra: Vec<(String, String)>,
+}
+
+impl TrainParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn booster(mut self, booster: BoosterType) -> Self {
+        self.booster = Some(booster);
+        self
+    }
+
+    pub fn objective(mut self, objective: &str) -> Self {
+        self.objective = Some(objective.to_string());
+        self
+    }
+
+    /// `count:poisson`, for counts. Combine with
+    /// [`DMatrix::set_exposure`](crate::dmatrix::DMatrix::set_exposure) to model rates.
+    pub fn poisson(self) -> Self {
+        self.objective("count:poisson")
+    }
+
+    /// `reg:tweedie` with the given variance power in `[1, 2)`: 1 is Poisson-like and
+    /// values towards 2 approach Gamma. Suits claim amounts with many exact zeros.
+    pub fn tweedie(mut self, variance_power: f64) -> Self {
+        self.tweedie_variance_power = Some(variance_power);
+        self.objective("reg:tweedie")
+    }
+
+    /// `reg:gamma`, for positive, right-skewed severities.
+    pub fn gamma(self) -> Self {
+        self.objective("reg:gamma")
+    }
+
+    pub fn eta(mut self, eta: f64) -> Self {
+        self.eta = Some(eta);
+        self
+    }
+
+    pub fn max_depth(mut self, max_depth: u32) -> Self {
+        self.max_depth = Some(max_depth);
+        self
+    }
+
+    /// Maximum number of leaves per tree, `0` for no limit.
+    pub fn max_leaves(mut self, max_leaves: u32) -> Self {
+        self.max_leaves = Some(max_leaves);
+        self
+    }
+
+    /// Maximum number of histogram bins per feature for the `hist` and `approx` methods.
+    pub fn max_bin(mut self, max_bin: u32) -> Self {
+        self.max_bin = Some(max_bin);
+        self
+    }
+
+    pub fn grow_policy(mut self, grow_policy: GrowPolicy) -> Self {
+        self.grow_policy = Some(grow_policy);
+        self
+    }
+
+    /// Minimum sum of hessians in a child for a split to be kept.
+    pub fn min_child_weight(mut self, min_child_weight: f64) -> Self {
+        self.min_child_weight = Some(min_child_weight);
+        self
+    }
+
+    /// Number of classes for `multi:*` objectives. Inferred from the labels by
+    /// [`Booster::train_with_params`](crate::booster::Booster::train_with_params) if unset.
+    pub fn num_class(mut self, num_class: u32) -> Self {
+        self.num_class = Some(num_class);
+        self
+    }
+
+    /// Number of OpenMP threads for training, `0` for all cores.
+    pub fn nthread(mut self, nthread: u32) -> Self {
+        self.nthread = Some(nthread);
+        self
+    }
+
+    /// Fraction of rows sampled for each tree.
+    pub fn subsample(mut self, subsample: f64) -> Self {
+        self.subsample = Some(subsample);
+        self
+    }
+
+    pub fn colsample(mut self, colsample: ColumnSampling) -> Self {
+        self.colsample = colsample;
+        self
+    }
+
+    /// Seed of the random number generator used for row and column sampling.
+    ///
+    /// In distributed training every worker must use the same seed: XGBoost draws the
+    /// column samples from it on each worker and they have to agree for the histograms to
+    /// be merged. Randomness of your own that should differ per worker can be seeded with
+    /// [`WorkerId::seed`](crate::collective::WorkerId::seed).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Reseeds the generator from the iteration number at every round, so the samples of
+    /// round `i` do not depend on the rounds before it. Training that is resumed from a
+    /// saved model then grows the same trees as an uninterrupted run.
+    pub fn seed_per_iteration(mut self, enabled: bool) -> Self {
+        self.seed_per_iteration = enabled;
+        self
+    }
+
+    /// Switches to the DART booster with the given dropout settings.
+    pub fn dart(mut self, dart: DartParams) -> Self {
+        self.booster = Some(BoosterType::Dart);
+        self.dart = Some(dart);
+        self
+    }
+
+    /// Lambdarank settings, only valid with a `rank:*` objective.
+    pub fn ranking(mut self, ranking: RankingParams) -> Self {
+        self.ranking = Some(ranking);
+        self
+    }
+
+    /// Adds an evaluation metric, e.g. `"auc"` or `"ndcg@10"`. Can be called repeatedly.
+    pub fn eval_metric(mut self, metric: &str) -> Self {
+        self.eval_metrics.push(metric.to_string());
+        self
+    }
+
+    /// Raw parameter, applied after the typed ones.
+    pub fn set(mut self, key: &str, value: &str) -> Self {
+        self.extra.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn get_objective(&self) -> Option<&str> {
+        self.objective.as_deref()
+    }
+
+    pub fn get_booster(&self) -> Option<BoosterType> {
+        self.booster
+    }
+
+    /// The typed `num_class`, or one passed through [`TrainParams::set`].
+    pub fn get_num_class(&self) -> Option<u32> {
+        self.num_class.or_else(|| {
+            self.extra
+                .iter()
+                .rev()
+                .find(|(k, _)| k == "num_class")
+                .and_then(|(_, v)| v.parse().ok())
+        })
+    }
+
+    /// Value of `key` as it will be handed to XGBoost, typed or raw, if it is set.
+    pub fn get(&self, key: &str) -> Option<String> {
+        self.to_pairs()
+            .into_iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    pub fn is_multiclass(&self) -> bool {
+        self.objective
+            .as_deref()
+            .is_some_and(|o| o.starts_with("multi:"))
+    }
+
+    /// For `multi:*` objectives, checks that `labels` are class indices below `num_class`,
+    /// or infers `num_class` from the largest label when it is unset.
+    pub fn resolve_num_class(&self, labels: &[f32]) -> Result<Self, ParamError> {
+        if !self.is_multiclass() {
+            return Ok(self.clone());
+        }
+        if let Some(label) = labels.iter().find(|l| !(**l >= 0.0 && l.fract() == 0.0)) {
+            return Err(ParamError::invalid(
+                "label",
+                label,
+                "multiclass labels must be non-negative integers",
+            ));
+        }
+        let needed = labels.iter().fold(0.0f32, |m, l| m.max(*l)) as u32 + 1;
+        match self.get_num_class() {
+            Some(num_class) if num_class < needed => Err(ParamError::invalid(
+                "num_class",
+                num_class,
+                &format!("labels go up to {}", needed - 1),
+            )),
+            Some(_) => Ok(self.clone()),
+            None => Ok(self.clone().num_class(needed)),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ParamError> {
+        if let Some(eta) = self.eta {
+            if eta <= 0.0 {
+                return Err(ParamError::invalid("eta", eta, "must be positive"));
+            }
+        }
+        if let Some(power) = self.tweedie_variance_power {
+            if !(1.0..2.0).contains(&power) {
+                return Err(ParamError::invalid(
+                    "tweedie_variance_power",
+                    power,
+                    "must be in [1, 2)",
+                ));
+            }
+            if self.objective.as_deref() != Some("reg:tweedie") {
+                return Err(ParamError::invalid(
+                    "objective",
+                    self.objective.as_deref().unwrap_or("unset"),
+                    "tweedie_variance_power needs reg:tweedie",
+                ));
+            }
+        }
+        if let Some(max_bin) = self.max_bin {
+            if max_bin < 2 {
+                return Err(ParamError::invalid(
+                    "max_bin",
+                    max_bin,
+                    "must be at least 2",
+                ));
+            }
+        }
+        if let Some(weight) = self.min_child_weight {
+            if !(weight >= 0.0 && weight.is_finite()) {
+                return Err(ParamError::invalid(
+                    "min_child_weight",
+                    weight,
+                    "must be finite and non-negative",
+                ));
+            }
+        }
+        if self.grow_policy == Some(GrowPolicy::LossGuide)
+            && self.max_depth == Some(0)
+            && self.max_leaves.unwrap_or(0) == 0
+        {
+            return Err(ParamError::invalid(
+                "max_leaves",
+                0,
+                "lossguide without max_depth needs a leaf limit",
+            ));
+        }
+        let fractions = [
+            ("subsample", self.subsample),
+            ("colsample_bytree", self.colsample.bytree),
+            ("colsample_bylevel", self.colsample.bylevel),
+            ("colsample_bynode", self.colsample.bynode),
+        ];
+        for (name, fraction) in fractions {
+            if let Some(fraction) = fraction {
+                if !(fraction > 0.0 && fraction <= 1.0) {
+                    return Err(ParamError::invalid(name, fraction, "must be in (0, 1]"));
+                }
+            }
+        }
+        if self.num_class == Some(0) {
+            return Err(ParamError::invalid("num_class", 0, "must be positive"));
+        }
+        if let Some(ranking) = &self.ranking {
+            if !self
+                .objective
+                .as_deref()
+                .is_some_and(|o| o.starts_with("rank:"))
+            {
+                return Err(ParamError::invalid(
+                    "objective",
+                    self.objective.as_deref().unwrap_or("unset"),
+                    "lambdarank parameters need a rank:* objective",
+                ));
+            }
+            ranking.validate()?;
+        }
+        if let Some(metric) = self.eval_metrics.iter().find(|m| m.is_empty()) {
+            return Err(ParamError::invalid(
+                "eval_metric",
+                metric,
+                "must not be empty",
+            ));
+        }
+        if let Some(dart) = &self.dart {
+            if self.booster != Some(BoosterType::Dart) {
+                return Err(ParamError::invalid(
+                    "booster",
+                    self.booster.map(|b| b.as_str()).unwrap_or("gbtree"),
+                    "DART parameters need booster=dart",
+                ));
+            }
+            dart.validate()?;
+        }
+        Ok(())
+    }
+
+    /// Key/value pairs in the order they should be handed to `XGBoosterSetParam`.
+    pub fn to_pairs(&self) -> Vec<(String, String)> {
+        let mut pairs = Vec::new();
+        if let Some(booster) = self.booster {
+            pairs.push(("booster".to_string(), booster.as_str().to_string()));
+        }
+        if let Some(objective) = &self.objective {
+            pairs.push(("objective".to_string(), objective.clone()));
+        }
+        if let Some(num_class) = self.num_class {
+            pairs.push(("num_class".to_string(), num_class.to_string()));
+        }
+        if let Some(power) = self.tweedie_variance_power {
+            pairs.push(("tweedie_variance_power".to_string(), power.to_string()));
+        }
+        if let Some(eta) = self.eta {
+            pairs.push(("eta".to_string(), eta.to_string()));
+        }
+        if let Some(max_depth) = self.max_depth {
+            pairs.push(("max_depth".to_string(), max_depth.to_string()));
+        }
+        if let Some(max_leaves) = self.max_leaves {
+            pairs.push(("max_leaves".to_string(), max_leaves.to_string()));
+        }
+        if let Some(grow_policy) = self.grow_policy {
+            pairs.push(("grow_policy".to_string(), grow_policy.as_str().to_string()));
+        }
+        if let Some(max_bin) = self.max_bin {
+            pairs.push(("max_bin".to_string(), max_bin.to_string()));
+        }
+        if let Some(weight) = self.min_child_weight {
+            pairs.push(("min_child_weight".to_string(), weight.to_string()));
+        }
+        if let Some(nthread) = self.nthread {
+            pairs.push(("nthread".to_string(), nthread.to_string()));
+        }
+        if let Some(subsample) = self.subsample {
+            pairs.push(("subsample".to_string(), subsample.to_string()));
+        }
+        pairs.extend(self.colsample.pairs());
+        if let Some(seed) = self.seed {
+            pairs.push(("seed".to_string(), seed.to_string()));
+        }
+        if self.seed_per_iteration {
+            pairs.push(("seed_per_iteration".to_string(), "1".to_string()));
+        }
+        if let Some(dart) = &self.dart {
+            pairs.extend(dart.pairs());
+        }
+        if let Some(ranking) = &self.ranking {
+            pairs.extend(ranking.pairs());
+        }
+        for metric in &self.eval_metrics {
+            pairs.push(("eval_metric".to_string(), metric.clone()));
+        }
+        pairs.extend(self.extra.iter().cloned());
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dart_pairs() {
+        let params = TrainParams::new().eta(0.1).dart(DartParams {
+            rate_drop: 0.1,
+            sample_type: SampleType::Weighted,
+            ..Default::default()
+        });
+        assert!(params.validate().is_ok());
+        let pairs = params.to_pairs();
+        assert_eq!(pairs[0], ("booster".to_string(), "dart".to_string()));
+        assert!(pairs.contains(&("sample_type".to_string(), "weighted".to_string())));
+        assert!(pairs.contains(&("rate_drop".to_string(), "0.1".to_string())));
+        let pairs = TrainParams::new().nthread(4).to_pairs();
+        assert_eq!(pairs, vec![("nthread".to_string(), "4".to_string())]);
+    }
+
+    #[test]
+    fn test_resolve_num_class() {
+        let params = TrainParams::new().objective("multi:softprob");
+        let resolved = params.resolve_num_class(&[0.0, 2.0, 1.0]).unwrap();
+        assert_eq!(resolved.get_num_class(), Some(3));
+        assert!(resolved
+            .to_pairs()
+            .contains(&("num_class".to_string(), "3".to_string())));
+        assert!(params.resolve_num_class(&[0.0, 1.5]).is_err());
+        let params = params.set("num_class", "2");
+        assert!(params.resolve_num_class(&[0.0, 2.0]).is_err());
+        let binary = TrainParams::new().objective("binary:logistic");
+        assert_eq!(
+            binary
+                .resolve_num_class(&[0.0, 1.0])
+                .unwrap()
+                .get_num_class(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sampling_params() {
+        let params = TrainParams::new()
+            .subsample(0.8)
+            .colsample(ColumnSampling {
+                bytree: Some(0.5),
+                bynode: Some(0.9),
+                ..Default::default()
+            })
+            .seed(7)
+            .seed_per_iteration(true);
+        assert!(params.validate().is_ok());
+        let pairs = params.to_pairs();
+        assert!(pairs.contains(&("colsample_bytree".to_string(), "0.5".to_string())));
+        assert!(pairs.contains(&("colsample_bynode".to_string(), "0.9".to_string())));
+        assert!(!pairs.iter().any(|(k, _)| k == "colsample_bylevel"));
+        assert_eq!(
+            pairs.last(),
+            Some(&("seed_per_iteration".to_string(), "1".to_string()))
+        );
+        let params = params.colsample(ColumnSampling {
+            bylevel: Some(0.0),
+            ..Default::default()
+        });
+        assert!(params.validate().is_err());
+        assert!(TrainParams::new().subsample(1.5).validate().is_err());
+    }
+
+    #[test]
+    fn test_tree_growth_params() {
+        let params = TrainParams::new()
+            .max_bin(64)
+            .grow_policy(GrowPolicy::LossGuide)
+            .max_leaves(8)
+            .min_child_weight(2.5);
+        assert!(params.validate().is_ok());
+        let pairs = params.to_pairs();
+        assert!(pairs.contains(&("grow_policy".to_string(), "lossguide".to_string())));
+        assert!(pairs.contains(&("max_bin".to_string(), "64".to_string())));
+        assert!(pairs.contains(&("min_child_weight".to_string(), "2.5".to_string())));
+        assert!(TrainParams::new().max_bin(1).validate().is_err());
+        assert!(TrainParams::new()
+            .min_child_weight(-1.0)
+            .validate()
+            .is_err());
+        assert!(TrainParams::new()
+            .grow_policy(GrowPolicy::LossGuide)
+            .max_depth(0)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_ranking_params() {
+        let ranking = RankingParams {
+            pair_method: Some(PairMethod::TopK),
+            num_pair_per_sample: Some(8),
+            ndcg_exp_gain: Some(false),
+            ndcg_at: Some(10),
+        };
+        let params = TrainParams::new()
+            .objective("rank:ndcg")
+            .ranking(ranking.clone())
+            .eval_metric("map@5");
+        assert!(params.validate().is_ok());
+        let pairs = params.to_pairs();
+        assert!(pairs.contains(&("lambdarank_pair_method".to_string(), "topk".to_string())));
+        assert!(pairs.contains(&("ndcg_exp_gain".to_string(), "0".to_string())));
+        let metrics: Vec<&str> = pairs
+            .iter()
+            .filter(|(k, _)| k == "eval_metric")
+            .map(|(_, v)| v.as_str())
+            .collect();
+        assert_eq!(metrics, vec!["ndcg@10", "map@5"]);
+
+        let unranked = TrainParams::new().ranking(ranking.clone());
+        assert!(unranked.validate().is_err());
+        let zero = RankingParams {
+            num_pair_per_sample: Some(0),
+            ..ranking
+        };
+        assert!(TrainParams::new()
+            .objective("rank:pairwise")
+            .ranking(zero)
+            .validate()
+            .is_err());
+    }
+
+    #[test]
+    fn test_count_objectives() {
+        assert_eq!(
+            TrainParams::new().poisson().get_objective(),
+            Some("count:poisson")
+        );
+        assert_eq!(
+            TrainParams::new().gamma().get_objective(),
+            Some("reg:gamma")
+        );
+        let tweedie = TrainParams::new().tweedie(1.5);
+        assert!(tweedie.validate().is_ok());
+        assert_eq!(
+            tweedie.get("tweedie_variance_power").as_deref(),
+            Some("1.5")
+        );
+        assert!(TrainParams::new().tweedie(2.0).validate().is_err());
+        assert!(tweedie.objective("reg:gamma").validate().is_err());
+    }
+
+    #[test]
+    fn test_invalid_dart() {
+        let params = TrainParams::new().dart(DartParams {
+            rate_drop: 1.5,
+            ..Default::default()
+        });
+        assert!(params.validate().is_err());
+        let params = params
+            .dart(DartParams::default())
+            .booster(BoosterType::GbTree);
+        assert!(params.validate().is_err());
+    }
+}
@@ -0,0 +1,316 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::sync::Arc;
+use xgb_sys::XGBuildInfo;
+
+use crate::dmatrix::InvalidValuePolicy;
+
+/// What `XGBoosterPredictFromDMatrix` should compute, matching the C API `type` field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PredictionType {
+    #[default]
+    Value,
+    Margin,
+    Contributions,
+    ApproxContributions,
+    Interactions,
+    ApproxInteractions,
+    Leaf,
+}
+
+impl PredictionType {
+    fn code(&self) -> u8 {
+        match self {
+            PredictionType::Value => 0,
+            PredictionType::Margin => 1,
+            PredictionType::Contributions => 2,
+            PredictionType::ApproxContributions => 3,
+            PredictionType::Interactions => 4,
+            PredictionType::ApproxInteractions => 5,
+            PredictionType::Leaf => 6,
+        }
+    }
+}
+
+/// Where XGBoost runs, as its `device` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Device {
+    Cpu,
+    /// CUDA device ordinal.
+    Cuda(u32),
+}
+
+impl Device {
+    pub fn as_param(&self) -> String {
+        match self {
+            Device::Cpu => "cpu".to_string(),
+            Device::Cuda(ordinal) => format!("cuda:{}", ordinal),
+        }
+    }
+
+    /// Whether the linked XGBoost library was built with CUDA support.
+    pub fn cuda_available() -> bool {
+        let mut out: *const c_char = std::ptr::null();
+        unsafe {
+            if XGBuildInfo(&mut out) != 0 || out.is_null() {
+                return false;
+            }
+            let info = CStr::from_ptr(out).to_string_lossy();
+            serde_json::from_str::<serde_json::Value>(&info)
+                .map(|v| v["USE_CUDA"].as_bool().unwrap_or(false))
+                .unwrap_or(false)
+        }
+    }
+
+    /// This device, or the CPU if it needs CUDA and the library was built without it.
+    pub(crate) fn or_cpu(self) -> Device {
+        match self {
+            Device::Cuda(_) if !Device::cuda_available() => Device::Cpu,
+            device => device,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PredictOptions {
+    pub(crate) kind: PredictionType,
+    pub(crate) training: bool,
+    pub(crate) iteration_begin: u32,
+    pub(crate) iteration_end: u32,
+    pub(crate) strict_shape: bool,
+    pub(crate) approx_contribs: bool,
+    pub(crate) device: Option<Device>,
+    pub(crate) nthread: Option<u32>,
+    pub(crate) invalid_values: Option<InvalidValuePolicy>,
+    pub(crate) audit_ids: Option<Arc<[String]>>,
+}
+
+impl PredictOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Untransformed margins, e.g. log-odds for `binary:logistic`.
+    pub fn margin() -> Self {
+        Self::new().kind(PredictionType::Margin)
+    }
+
+    /// Single-threaded prediction on the CPU, so every row is accumulated tree by tree in
+    /// model order and results are bit-identical from run to run and across machines
+    /// with the same XGBoost build. GPU prediction sums in a different order, and a
+    /// library compiled with other floating point flags (e.g. FMA) may still differ in the
+    /// last bits. Setting [`PredictOptions::device`] or [`PredictOptions::nthread`]
+    /// afterwards overrides it.
+    pub fn deterministic() -> Self {
+        Self::new().device(Device::Cpu).nthread(1)
+    }
+
+    pub fn kind(mut self, kind: PredictionType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    /// Predict as during training. For DART this applies dropout, so it should only be
+    /// used by custom training loops.
+    pub fn training(mut self, training: bool) -> Self {
+        self.training = training;
+        self
+    }
+
+    /// Only use trees from boosting rounds in `[begin, end)`. `end == 0` means all rounds.
+    /// For DART the remaining trees keep their normalized weights.
+    pub fn iteration_range(mut self, begin: u32, end: u32) -> Self {
+        self.iteration_begin = begin;
+        self.iteration_end = end;
+        self
+    }
+
+    /// Ask XGBoost for a fixed-rank output, e.g. `(rows, groups)` for values even with a
+    /// single group. See [`Shape`].
+    pub fn strict_shape(mut self, strict_shape: bool) -> Self {
+        self.strict_shape = strict_shape;
+        self
+    }
+
+    /// Compute contributions and interactions with the Saabas approximation instead of
+    /// exact TreeSHAP. Much faster on deep ensembles, at the cost of consistency. Other
+    /// prediction types ignore it.
+    pub fn approx_contribs(mut self, approx_contribs: bool) -> Self {
+        self.approx_contribs = approx_contribs;
+        self
+    }
+
+    /// The prediction type sent to XGBoost, with `approx_contribs` applied.
+    fn effective_kind(&self) -> PredictionType {
+        match (self.kind, self.approx_contribs) {
+            (PredictionType::Contributions, true) => PredictionType::ApproxContributions,
+            (PredictionType::Interactions, true) => PredictionType::ApproxInteractions,
+            (kind, _) => kind,
+        }
+    }
+
+    /// Device to predict on, for this call only. XGBoost has no per-call device setting,
+    /// so the booster is reconfigured for the call and restored afterwards, while other
+    /// calls on it wait. CUDA falls back to the CPU when the library was built without it
+    /// or the prediction fails on the device, e.g. because no GPU is visible.
+    pub fn device(mut self, device: Device) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Number of OpenMP threads used to predict, `0` for all cores. Like
+    /// [`PredictOptions::device`] it only applies to this call; the booster's own
+    /// `nthread` is restored afterwards.
+    pub fn nthread(mut self, nthread: u32) -> Self {
+        self.nthread = Some(nthread);
+        self
+    }
+
+    /// Check in-place inputs for infinite and subnormal values and handle them with
+    /// `policy`, see [`sanitize`](crate::dmatrix::sanitize). Off by default; inputs
+    /// already in a [`DMatrix`](crate::dmatrix::DMatrix) are not checked.
+    pub fn invalid_values(mut self, policy: InvalidValuePolicy) -> Self {
+        self.invalid_values = Some(policy);
+        self
+    }
+
+    /// The caller's ids of the rows, one per row, copied into their
+    /// [`AuditRecord`](crate::audit::AuditRecord)s when the booster has an audit log.
+    pub fn audit_ids(mut self, ids: impl Into<Arc<[String]>>) -> Self {
+        self.audit_ids = Some(ids.into());
+        self
+    }
+
+    pub(crate) fn to_json(&self) -> String {
+        format!(
+            "{{\"type\": {}, \"training\": {}, \"iteration_begin\": {}, \"iteration_end\": {}, \"strict_shape\": {}}}",
+            self.effective_kind().code(),
+            self.training,
+            self.iteration_begin,
+            self.iteration_end,
+            self.strict_shape
+        )
+    }
+
+    /// Configuration for in-place prediction, which also needs the missing value marker.
+    pub(crate) fn to_inplace_json(&self) -> String {
+        let json = self.to_json();
+        format!(
+            "{}, \"missing\": NaN, \"cache_id\": 0}}",
+            &json[..json.len() - 1]
+        )
+    }
+}
+
+/// Output dimensions padded to rank 4 with trailing ones. With `strict_shape` they are:
+///
+/// - values and margins: `(rows, groups, 1, 1)`
+/// - contributions: `(rows, groups, features + 1, 1)`
+/// - interactions: `(rows, groups, features + 1, features + 1)`
+/// - leaf indices: `(rows, rounds, groups, trees per group)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shape(pub [u64; 4]);
+
+impl Shape {
+    pub(crate) fn from_dims(dims: &[u64]) -> Self {
+        let mut shape = [1; 4];
+        shape[..dims.len().min(4)].copy_from_slice(&dims[..dims.len().min(4)]);
+        Shape(shape)
+    }
+
+    pub fn rows(&self) -> u64 {
+        self.0[0]
+    }
+
+    /// Number of values per row.
+    pub fn row_len(&self) -> usize {
+        self.0[1..].iter().product::<u64>() as usize
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().product::<u64>() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Flat row-major prediction output with its dimensions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    pub values: Vec<f32>,
+    pub shape: Shape,
+}
+
+impl Prediction {
+    pub fn row(&self, row: usize) -> &[f32] {
+        let n = self.shape.row_len();
+        &self.values[row * n..(row + 1) * n]
+    }
+
+    /// The values widened to `f64`. This is exact, but XGBoost already rounded them to
+    /// single precision; see [`Booster::predict_margin_f64`](crate::booster::Booster::predict_margin_f64)
+    /// for margins accumulated in double precision.
+    pub fn to_f64(&self) -> Vec<f64> {
+        self.values.iter().map(|v| *v as f64).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_padding() {
+        let shape = Shape::from_dims(&[3, 2, 5]);
+        assert_eq!(shape, Shape([3, 2, 5, 1]));
+        assert_eq!(shape.row_len(), 10);
+        assert_eq!(shape.len(), 30);
+    }
+
+    #[test]
+    fn test_inplace_json() {
+        let json = PredictOptions::margin().to_inplace_json();
+        assert!(json.starts_with("{\"type\": 1,"));
+        assert!(json.ends_with("\"strict_shape\": false, \"missing\": NaN, \"cache_id\": 0}"));
+    }
+
+    #[test]
+    fn test_approx_contribs() {
+        let json = |options: PredictOptions| options.approx_contribs(true).to_json();
+        assert!(
+            json(PredictOptions::new().kind(PredictionType::Contributions))
+                .starts_with("{\"type\": 3,")
+        );
+        assert!(
+            json(PredictOptions::new().kind(PredictionType::Interactions))
+                .starts_with("{\"type\": 5,")
+        );
+        assert!(json(PredictOptions::margin()).starts_with("{\"type\": 1,"));
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let options = PredictOptions::deterministic().kind(PredictionType::Margin);
+        assert_eq!(options.device, Some(Device::Cpu));
+        assert_eq!(options.nthread, Some(1));
+        assert!(options.to_json().starts_with("{\"type\": 1,"));
+    }
+
+    #[test]
+    fn test_device_param() {
+        assert_eq!(Device::Cpu.as_param(), "cpu");
+        assert_eq!(Device::Cuda(1).as_param(), "cuda:1");
+        assert_eq!(Device::Cpu.or_cpu(), Device::Cpu);
+    }
+
+    #[test]
+    fn test_prediction_row() {
+        let prediction = Prediction {
+            values: vec![0.1, 0.9, 0.3, 0.7],
+            shape: Shape::from_dims(&[2, 2]),
+        };
+        assert_eq!(prediction.row(1), &[0.3, 0.7]);
+    }
+}
@@ -0,0 +1,117 @@
+//! Helpers to lay out learning-to-rank data the way XGBoost expects it: rows of a query
+//! contiguous, and one group size per query.
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RankingError {
+    #[error("Query {qid} at row {row} was already seen before another query; sort by qid first")]
+    NotContiguous { qid: u32, row: usize },
+    #[error("Group sizes add up to {got} rows, expected {expected}")]
+    GroupSum { expected: u64, got: u64 },
+    #[error("Group {0} is empty")]
+    EmptyGroup(usize),
+    #[error("Expected {expected} values, got {got}")]
+    Length { expected: usize, got: usize },
+}
+
+/// Row order that makes every query contiguous, queries in increasing qid order. The sort
+/// is stable, so rows keep their relative order within a query.
+pub fn sort_by_qid(qids: &[u32]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..qids.len()).collect();
+    order.sort_by_key(|i| qids[*i]);
+    order
+}
+
+/// Reorders `values`, holding `width` values per row, into the row `order` given by
+/// [`sort_by_qid`]. Use it for the feature matrix (`width` = columns) and for labels or
+/// weights (`width` = 1).
+pub fn permute_rows<T: Copy>(
+    values: &[T],
+    width: usize,
+    order: &[usize],
+) -> Result<Vec<T>, RankingError> {
+    if values.len() != order.len() * width {
+        return Err(RankingError::Length {
+            expected: order.len() * width,
+            got: values.len(),
+        });
+    }
+    Ok(order
+        .iter()
+        .flat_map(|row| &values[row * width..(row + 1) * width])
+        .copied()
+        .collect())
+}
+
+/// Size of each query group from a per-row qid column, in row order. Rows of a query must
+/// be contiguous; they do not have to be sorted by qid.
+pub fn group_sizes_from_qid(qids: &[u32]) -> Result<Vec<u32>, RankingError> {
+    let mut sizes = Vec::new();
+    let mut seen = HashSet::new();
+    for (row, qid) in qids.iter().enumerate() {
+        if row > 0 && qids[row - 1] == *qid {
+            *sizes.last_mut().unwrap() += 1;
+        } else if seen.insert(*qid) {
+            sizes.push(1);
+        } else {
+            return Err(RankingError::NotContiguous { qid: *qid, row });
+        }
+    }
+    Ok(sizes)
+}
+
+/// Checks that `group_sizes` has no empty group and covers exactly `num_rows` rows.
+pub fn validate_groups(group_sizes: &[u32], num_rows: u64) -> Result<(), RankingError> {
+    if let Some(group) = group_sizes.iter().position(|s| *s == 0) {
+        return Err(RankingError::EmptyGroup(group));
+    }
+    let total: u64 = group_sizes.iter().map(|s| *s as u64).sum();
+    if total != num_rows {
+        return Err(RankingError::GroupSum {
+            expected: num_rows,
+            got: total,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sort_and_group() {
+        let qids = [7, 3, 7, 3, 5];
+        let order = sort_by_qid(&qids);
+        assert_eq!(order, vec![1, 3, 4, 0, 2]);
+        let sorted = permute_rows(&qids, 1, &order).unwrap();
+        assert_eq!(group_sizes_from_qid(&sorted).unwrap(), vec![2, 1, 2]);
+        let rows = permute_rows(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9], 2, &order).unwrap();
+        assert_eq!(&rows[..4], &[2, 3, 6, 7]);
+        assert!(permute_rows(&[1.0], 2, &order).is_err());
+    }
+
+    #[test]
+    fn test_group_errors() {
+        assert_eq!(group_sizes_from_qid(&[2, 2, 1]).unwrap(), vec![2, 1]);
+        assert_eq!(
+            group_sizes_from_qid(&[1, 2, 1]),
+            Err(RankingError::NotContiguous { qid: 1, row: 2 })
+        );
+        assert!(validate_groups(&[2, 1], 3).is_ok());
+        assert_eq!(
+            validate_groups(&[2, 2], 3),
+            Err(RankingError::GroupSum {
+                expected: 3,
+                got: 4
+            })
+        );
+        assert_eq!(
+            validate_groups(&[2, 0, 1], 3),
+            Err(RankingError::EmptyGroup(1))
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Zero-downtime model replacement for serving.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+use crate::booster::{Booster, XGBoostError};
+use crate::dmatrix::DMatrix;
+
+/// A booster that can be replaced while serving. Callers take a snapshot with
+/// [`ReloadableBooster::current`]; a swap only affects later snapshots, so in-flight
+/// predictions finish on the model they started with, which is freed once the last
+/// snapshot is dropped.
+pub struct ReloadableBooster {
+    current: RwLock<Arc<Booster>>,
+    path: Option<PathBuf>,
+    modified: Mutex<Option<SystemTime>>,
+    version: AtomicU64,
+}
+
+impl ReloadableBooster {
+    pub fn new(booster: Booster) -> Self {
+        ReloadableBooster {
+            current: RwLock::new(Arc::new(booster)),
+            path: None,
+            modified: Mutex::new(None),
+            version: AtomicU64::new(0),
+        }
+    }
+
+    /// Loads the model at `path`, which [`ReloadableBooster::reload_if_changed`] and
+    /// [`ReloadableBooster::watch`] then keep an eye on.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, XGBoostError> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modified_time(&path);
+        let mut reloadable = Self::new(load(&path)?);
+        reloadable.path = Some(path);
+        reloadable.modified = Mutex::new(modified);
+        Ok(reloadable)
+    }
+
+    /// Snapshot of the model currently served.
+    pub fn current(&self) -> Arc<Booster> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Number of swaps so far.
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    /// Snapshot of the model currently served together with its version. Unlike calling
+    /// [`ReloadableBooster::current`] and [`ReloadableBooster::version`] in turn, the
+    /// version always belongs to the returned model.
+    pub fn snapshot(&self) -> (Arc<Booster>, u64) {
+        let current = self.current.read().unwrap_or_else(|e| e.into_inner());
+        (current.clone(), self.version.load(Ordering::Acquire))
+    }
+
+    pub fn predict(&self, data: &DMatrix) -> Result<Vec<f32>, XGBoostError> {
+        self.current().predict(data)
+    }
+
+    /// Serves `booster` from now on.
+    pub fn swap_booster(&self, booster: Booster) {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        *current = Arc::new(booster);
+        // Bumped under the lock so that snapshots see the model and version together
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Loads a serialized model and serves it from now on. On error the current model
+    /// stays in place.
+    pub fn swap(&self, bytes: &[u8]) -> Result<(), XGBoostError> {
+        let mut booster = Booster::new()?;
+        booster.load_model_from_buffer(bytes)?;
+        self.swap_booster(booster);
+        Ok(())
+    }
+
+    /// Reloads the watched file if its modification time changed. Returns whether a new
+    /// model is served; on error the current model stays in place.
+    pub fn reload_if_changed(&self) -> Result<bool, XGBoostError> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        let mut modified = self.modified.lock().unwrap_or_else(|e| e.into_inner());
+        let latest = modified_time(path);
+        if latest.is_none() || latest == *modified {
+            return Ok(false);
+        }
+        self.swap_booster(load(path)?);
+        *modified = latest;
+        Ok(true)
+    }
+
+    /// Polls the watched file every `interval` on a background thread. Failed reloads,
+    /// e.g. of a partially written file, are retried at the next poll.
+    pub fn watch(self: &Arc<Self>, interval: Duration) -> Watcher {
+        let stop = Arc::new(AtomicBool::new(false));
+        let reloadable = Arc::clone(self);
+        let stopped = Arc::clone(&stop);
+        let handle = thread::spawn(move || {
+            while !stopped.load(Ordering::Acquire) {
+                let _ = reloadable.reload_if_changed();
+                thread::park_timeout(interval);
+            }
+        });
+        Watcher {
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+/// Background reload thread started by [`ReloadableBooster::watch`], stopped on drop.
+pub struct Watcher {
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Watcher {
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.stop.store(true, Ordering::Release);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn load(path: &Path) -> Result<Booster, XGBoostError> {
+    let mut booster = Booster::new()?;
+    booster.load_model(&path.to_string_lossy())?;
+    Ok(booster)
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::booster::ModelFormat;
+    use std::fs::File;
+
+    fn model_bytes(label: f32) -> Vec<u8> {
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[label, label]).unwrap();
+        let booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        booster.save_model_to_buffer(ModelFormat::Json).unwrap()
+    }
+
+    #[test]
+    fn test_swap() {
+        let reloadable = ReloadableBooster::new(Booster::new().unwrap());
+        let (before, version) = reloadable.snapshot();
+        assert_eq!(version, 0);
+        reloadable.swap_booster(Booster::new().unwrap());
+        // A snapshot taken before the swap keeps its model
+        let (after, version) = reloadable.snapshot();
+        assert!(!Arc::ptr_eq(&before, &after));
+        assert!(Arc::ptr_eq(&after, &reloadable.current()));
+        assert_eq!(version, 1);
+        assert_eq!(reloadable.version(), 1);
+        reloadable.swap(&model_bytes(1.0)).unwrap();
+        assert_eq!(reloadable.version(), 2);
+        assert_eq!(reloadable.current().boosted_rounds().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_swap_keeps_feature_schema() {
+        use crate::schema::{FeatureSchema, FeatureType};
+
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1.0, 1.0]).unwrap();
+        let mut booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        let schema = FeatureSchema::new()
+            .feature("a", FeatureType::Float)
+            .feature("b", FeatureType::Float);
+        booster.set_feature_schema(schema.clone()).unwrap();
+        let reloadable = ReloadableBooster::new(Booster::new().unwrap());
+        reloadable
+            .swap(&booster.save_model_to_buffer(ModelFormat::Ubj).unwrap())
+            .unwrap();
+        assert_eq!(reloadable.current().feature_schema(), Some(&schema));
+    }
+
+    #[test]
+    fn test_failed_swap_keeps_model() {
+        let reloadable = ReloadableBooster::new(Booster::new().unwrap());
+        let before = reloadable.current();
+        assert!(reloadable.swap(b"not a model").is_err());
+        assert!(Arc::ptr_eq(&before, &reloadable.current()));
+        assert_eq!(reloadable.version(), 0);
+    }
+
+    #[test]
+    fn test_reload_if_changed() {
+        let dir = std::env::temp_dir().join(format!("xgb-rs-reload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.json");
+        let touch = |contents: &[u8], secs: u64| {
+            std::fs::write(&path, contents).unwrap();
+            let time = SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        };
+        touch(&model_bytes(0.0), 1_000);
+        let reloadable = ReloadableBooster::from_path(&path).unwrap();
+        let original = reloadable.current();
+        assert!(!reloadable.reload_if_changed().unwrap());
+
+        // A partially written file fails to load and leaves the model in place
+        touch(b"{\"learner\":", 2_000);
+        assert!(reloadable.reload_if_changed().is_err());
+        assert!(Arc::ptr_eq(&original, &reloadable.current()));
+        assert_eq!(reloadable.version(), 0);
+
+        touch(&model_bytes(1.0), 3_000);
+        assert!(reloadable.reload_if_changed().unwrap());
+        assert!(!Arc::ptr_eq(&original, &reloadable.current()));
+        assert_eq!(reloadable.version(), 1);
+        assert!(!reloadable.reload_if_changed().unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::dmatrix::{DMatrix, DMatrixError};
+
+/// Booster attribute under which the schema is stored.
+pub const FEATURE_SCHEMA_ATTR: &str = "feature_schema";
+
+#[derive(Error, Debug)]
+pub enum SchemaError {
+    #[error("Expected {expected} features, got {got}")]
+    ColumnCount { expected: usize, got: usize },
+    #[error("Feature {position} should be {expected:?}, got {got:?}")]
+    Name {
+        position: usize,
+        expected: String,
+        got: String,
+    },
+    #[error("Feature {feature:?} has value {value} at row {row}, outside [{min}, {max}]")]
+    Range {
+        feature: String,
+        row: usize,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+    #[error("Invalid feature schema: {0}")]
+    Invalid(String),
+    #[error(transparent)]
+    DMatrix(#[from] DMatrixError),
+}
+
+/// Feature types as understood by XGBoost's `feature_types`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeatureType {
+    #[serde(rename = "float")]
+    Float,
+    #[serde(rename = "int")]
+    Int,
+    #[serde(rename = "i")]
+    Indicator,
+    #[serde(rename = "c")]
+    Categorical,
+}
+
+impl FeatureType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeatureType::Float => "float",
+            FeatureType::Int => "int",
+            FeatureType::Indicator => "i",
+            FeatureType::Categorical => "c",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Feature {
+    pub name: String,
+    pub kind: FeatureType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f32>,
+}
+
+/// Expected model inputs, in column order. Attach it with `Booster::set_feature_schema`
+/// and use `Booster::predict_checked` to reject mismatching matrices.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FeatureSchema {
+    pub features: Vec<Feature>,
+}
+
+impl FeatureSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn feature(mut self, name: &str, kind: FeatureType) -> Self {
+        self.features.push(Feature {
+            name: name.to_string(),
+            kind,
+            min: None,
+            max: None,
+        });
+        self
+    }
+
+    /// Same as [`FeatureSchema::feature`], with an inclusive range of allowed values.
+    pub fn bounded_feature(mut self, name: &str, kind: FeatureType, min: f32, max: f32) -> Self {
+        self.features.push(Feature {
+            name: name.to_string(),
+            kind,
+            min: Some(min),
+            max: Some(max),
+        });
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.features.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.features.is_empty()
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.features.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    pub fn types(&self) -> Vec<&str> {
+        self.features.iter().map(|f| f.kind.as_str()).collect()
+    }
+
+    /// Checks column count and, if the matrix carries feature names, their order.
+    pub fn validate_matrix(&self, data: &DMatrix) -> Result<(), SchemaError> {
+        if data.num_cols() as usize != self.len() {
+            return Err(SchemaError::ColumnCount {
+                expected: self.len(),
+                got: data.num_cols() as usize,
+            });
+        }
+        let names = data.feature_names()?;
+        if names.is_empty() {
+            return Ok(());
+        }
+        for (position, (feature, got)) in self.features.iter().zip(names).enumerate() {
+            if feature.name != got {
+                return Err(SchemaError::Name {
+                    position,
+                    expected: feature.name.clone(),
+                    got,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks row-major dense values against the declared ranges. Missing values (NaN)
+    /// are always accepted.
+    pub fn validate_values(&self, data: &[f32]) -> Result<(), SchemaError> {
+        if self.is_empty() || !data.len().is_multiple_of(self.len()) {
+            return Err(SchemaError::ColumnCount {
+                expected: self.len(),
+                got: data.len(),
+            });
+        }
+        for (row, values) in data.chunks(self.len()).enumerate() {
+            for (feature, value) in self.features.iter().zip(values) {
+                let min = feature.min.unwrap_or(f32::NEG_INFINITY);
+                let max = feature.max.unwrap_or(f32::INFINITY);
+                if !value.is_nan() && (*value < min || *value > max) {
+                    return Err(SchemaError::Range {
+                        feature: feature.name.clone(),
+                        row,
+                        value: *value,
+                        min,
+                        max,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, SchemaError> {
+        serde_json::to_string(self).map_err(|e| SchemaError::Invalid(e.to_string()))
+    }
+
+    pub(crate) fn from_json(json: &str) -> Result<Self, SchemaError> {
+        serde_json::from_str(json).map_err(|e| SchemaError::Invalid(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_values() {
+        let schema = FeatureSchema::new()
+            .bounded_feature("age", FeatureType::Int, 0.0, 120.0)
+            .feature("income", FeatureType::Float);
+        assert!(schema.validate_values(&[30.0, 1e6, f32::NAN, -3.0]).is_ok());
+        let err = schema
+            .validate_values(&[30.0, 1.0, 130.0, 2.0])
+            .unwrap_err();
+        assert!(matches!(err, SchemaError::Range { row: 1, .. }));
+        assert!(schema.validate_values(&[1.0, 2.0, 3.0]).is_err());
+    }
+
+    #[test]
+    fn test_schema_roundtrip() {
+        let schema = FeatureSchema::new().feature("country", FeatureType::Categorical);
+        let json = schema.to_json().unwrap();
+        assert!(json.contains("\"c\""));
+        assert_eq!(FeatureSchema::from_json(&json).unwrap(), schema);
+    }
+}
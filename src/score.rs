@@ -0,0 +1,280 @@
+//! Batch scoring from file to file without materializing the whole input.
+//!
+//! Inputs are CSV or LibSVM text, read from a local path or, with the `object-store`
+//! feature, an `s3://` or `gs://` URI. Parquet is not read here; with the `arrow`
+//! feature, decode it into record batches and predict those instead.
+
+use std::fs::File;
+#[cfg(feature = "object-store")]
+use std::io::Cursor;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::output::{CsvWriter, PredictionWriter};
+use crate::predict::PredictOptions;
+#[cfg(feature = "object-store")]
+use crate::storage::{self, StorageError};
+
+#[derive(Error, Debug)]
+pub enum ScoreError {
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Cannot parse line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+    #[cfg(feature = "object-store")]
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+}
+
+/// Layout of the input file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputFormat {
+    /// Delimited dense values, empty fields are missing.
+    Csv { delimiter: u8, has_header: bool },
+    /// `label index:value ... [# comment]` lines with zero-based indices. With
+    /// `comment_ids`, the trimmed comment is passed through as the row's id.
+    LibSvm { comment_ids: bool },
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoreOptions {
+    pub format: InputFormat,
+    /// Rows predicted at once.
+    pub batch_rows: usize,
+    /// CSV column copied as the first output column instead of being used as a feature.
+    /// LibSVM ids come from comments instead, see [`InputFormat::LibSvm`].
+    pub id_column: Option<usize>,
+    /// CSV columns skipped, e.g. the label.
+    pub skip_columns: Vec<usize>,
+    pub predict: PredictOptions,
+}
+
+impl Default for ScoreOptions {
+    fn default() -> Self {
+        ScoreOptions {
+            format: InputFormat::Csv {
+                delimiter: b',',
+                has_header: false,
+            },
+            batch_rows: 65536,
+            id_column: None,
+            skip_columns: Vec::new(),
+            predict: PredictOptions::default(),
+        }
+    }
+}
+
+/// Scores the input at `input_uri` with `booster` and writes one CSV line per row to
+/// `output`: the id, if any, followed by the row's predictions.
+///
+/// `input_uri` is a local path, or an `s3://` or `gs://` URI with the `object-store`
+/// feature. A remote object is downloaded in one request and parsed from memory; rows are
+/// still parsed and predicted batch by batch.
+pub fn score_file(
+    booster: &Booster,
+    input_uri: &str,
+    output: impl AsRef<Path>,
+    options: &ScoreOptions,
+) -> Result<u64, ScoreError> {
+    let mut writer = BufWriter::new(File::create(output)?);
+    #[cfg(feature = "object-store")]
+    let rows = if storage::is_remote(input_uri) {
+        let reader = Cursor::new(storage::get(input_uri)?);
+        score_reader(booster, reader, &mut writer, options)?
+    } else {
+        let reader = BufReader::new(File::open(input_uri)?);
+        score_reader(booster, reader, &mut writer, options)?
+    };
+    #[cfg(not(feature = "object-store"))]
+    let rows = score_reader(
+        booster,
+        BufReader::new(File::open(input_uri)?),
+        &mut writer,
+        options,
+    )?;
+    writer.flush()?;
+    Ok(rows)
+}
+
+/// Like [`score_file`] over any reader and writer. Returns the number of scored rows.
+pub fn score_reader<R: BufRead, W: Write>(
+    booster: &Booster,
+    reader: R,
+    writer: W,
+    options: &ScoreOptions,
+) -> Result<u64, ScoreError> {
+    score_into(booster, reader, &mut CsvWriter::new(writer), options)
+}
+
+/// Scores `reader` batch by batch into `writer`, passing the ids through, and finishes
+/// the writer. Returns the number of scored rows.
+pub fn score_into<R: BufRead>(
+    booster: &Booster,
+    reader: R,
+    writer: &mut dyn PredictionWriter,
+    options: &ScoreOptions,
+) -> Result<u64, ScoreError> {
+    let cols = booster.get_number_of_features()?;
+    let batch_rows = options.batch_rows.max(1);
+    let mut values = Vec::with_capacity(batch_rows * cols);
+    let mut ids = Vec::with_capacity(batch_rows);
+    let mut scored = 0;
+    let mut lines = reader.lines().enumerate();
+    if let InputFormat::Csv {
+        has_header: true, ..
+    } = options.format
+    {
+        lines.next();
+    }
+    for (line_no, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, row) = parse_line(&line, cols, options).map_err(|reason| ScoreError::Parse {
+            line: line_no + 1,
+            reason,
+        })?;
+        values.extend(row);
+        ids.push(id);
+        if ids.len() == batch_rows {
+            scored += write_batch(booster, &values, &ids, cols, writer, options)?;
+            values.clear();
+            ids.clear();
+        }
+    }
+    if !ids.is_empty() {
+        scored += write_batch(booster, &values, &ids, cols, writer, options)?;
+    }
+    writer.finish()?;
+    Ok(scored)
+}
+
+fn write_batch(
+    booster: &Booster,
+    values: &[f32],
+    ids: &[Option<String>],
+    cols: usize,
+    writer: &mut dyn PredictionWriter,
+    options: &ScoreOptions,
+) -> Result<u64, ScoreError> {
+    let prediction = booster.predict_dense(values, ids.len(), cols, &options.predict)?;
+    // Without ids every id is None; an empty slice keeps the output id-free.
+    let ids = if has_ids(options) { ids } else { &[] };
+    writer.write_batch(ids, &prediction)?;
+    Ok(prediction.shape.rows())
+}
+
+/// Whether rows carry an id to pass through.
+fn has_ids(options: &ScoreOptions) -> bool {
+    match options.format {
+        InputFormat::Csv { .. } => options.id_column.is_some(),
+        InputFormat::LibSvm { comment_ids } => comment_ids,
+    }
+}
+
+/// Parses one input line into its id and `cols` dense values, NaN for missing.
+fn parse_line(
+    line: &str,
+    cols: usize,
+    options: &ScoreOptions,
+) -> Result<(Option<String>, Vec<f32>), String> {
+    let mut row = vec![f32::NAN; cols];
+    match options.format {
+        InputFormat::Csv { delimiter, .. } => {
+            let mut id = None;
+            let mut feature = 0;
+            for (column, field) in line.split(delimiter as char).enumerate() {
+                if options.id_column == Some(column) {
+                    id = Some(field.to_string());
+                    continue;
+                }
+                if options.skip_columns.contains(&column) {
+                    continue;
+                }
+                if feature >= cols {
+                    return Err(format!("more than {} feature columns", cols));
+                }
+                let field = field.trim();
+                if !field.is_empty() {
+                    row[feature] = field
+                        .parse()
+                        .map_err(|_| format!("invalid value {:?}", field))?;
+                }
+                feature += 1;
+            }
+            Ok((id, row))
+        }
+        InputFormat::LibSvm { comment_ids } => {
+            let (line, comment) = match line.split_once('#') {
+                Some((line, comment)) => (line, Some(comment.trim())),
+                None => (line, None),
+            };
+            for entry in line.split_whitespace().skip(1) {
+                let (index, value) = entry
+                    .split_once(':')
+                    .ok_or_else(|| format!("invalid entry {:?}", entry))?;
+                let index: usize = index
+                    .parse()
+                    .map_err(|_| format!("invalid index {:?}", index))?;
+                if index >= cols {
+                    return Err(format!(
+                        "index {} out of range for {} features",
+                        index, cols
+                    ));
+                }
+                row[index] = value
+                    .parse()
+                    .map_err(|_| format!("invalid value {:?}", value))?;
+            }
+            let id = comment.filter(|_| comment_ids).map(str::to_string);
+            Ok((id, row))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_csv_line() {
+        let options = ScoreOptions {
+            id_column: Some(0),
+            skip_columns: vec![1],
+            ..Default::default()
+        };
+        let (id, row) = parse_line("u42,1,0.5,,2", 3, &options).unwrap();
+        assert_eq!(id.as_deref(), Some("u42"));
+        assert_eq!(row[0], 0.5);
+        assert!(row[1].is_nan());
+        assert_eq!(row[2], 2.0);
+        assert!(parse_line("u42,1,0.5,x,2", 3, &options).is_err());
+        assert!(parse_line("u42,1,0.5,1,2,3", 3, &options).is_err());
+    }
+
+    #[test]
+    fn test_parse_libsvm_line() {
+        let mut options = ScoreOptions {
+            format: InputFormat::LibSvm { comment_ids: false },
+            ..Default::default()
+        };
+        let (id, row) = parse_line("1 0:1.5 2:-1 # u42", 3, &options).unwrap();
+        assert_eq!(id, None);
+        assert_eq!(row[0], 1.5);
+        assert!(row[1].is_nan());
+        assert_eq!(row[2], -1.0);
+        assert!(parse_line("1 3:1", 3, &options).is_err());
+        options.format = InputFormat::LibSvm { comment_ids: true };
+        let (id, row) = parse_line("1 0:1.5 2:-1 # u42", 3, &options).unwrap();
+        assert_eq!(id.as_deref(), Some("u42"));
+        assert_eq!(row[2], -1.0);
+        let (id, _) = parse_line("1 0:1.5", 3, &options).unwrap();
+        assert_eq!(id, None);
+    }
+}
@@ -0,0 +1,213 @@
+//! Minimal HTTP scoring service built on the crate's serving pieces: a
+//! [`ReloadableBooster`] for model loading and rollout, and the model's
+//! [`FeatureSchema`](crate::schema::FeatureSchema) for input validation.
+//!
+//! Endpoints:
+//! - `GET /health`: liveness, always `200`.
+//! - `GET /ready`: `200` with the model version once a model is loaded.
+//! - `POST /predict`: `{"rows": [[1.0, null, 3.0], ...]}` to
+//!   `{"predictions": [[0.7], ...], "model_version": 0}`; `null` marks missing values.
+//!   Input that does not match the model is a `400`, a failure while scoring a `500`.
+//!   `model_version` is the version of the model that scored the request.
+
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+use crate::booster::XGBoostError;
+use crate::predict::PredictOptions;
+use crate::reload::ReloadableBooster;
+use crate::schema::SchemaError;
+
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub addr: SocketAddr,
+    pub model_path: PathBuf,
+    /// Poll the model file for changes at this interval.
+    pub reload_interval: Option<Duration>,
+    /// Largest accepted batch.
+    pub max_rows: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PredictRequest {
+    pub rows: Vec<Vec<Option<f32>>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PredictResponse {
+    pub predictions: Vec<Vec<f32>>,
+    pub model_version: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+}
+
+type ApiError = (StatusCode, Json<ErrorResponse>);
+
+#[derive(Clone)]
+struct AppState {
+    model: Arc<ReloadableBooster>,
+    max_rows: usize,
+}
+
+/// Routes of the service, to be nested in a larger application if needed.
+pub fn router(model: Arc<ReloadableBooster>, max_rows: usize) -> Router {
+    Router::new()
+        .route("/health", get(|| async { StatusCode::OK }))
+        .route("/ready", get(ready))
+        .route("/predict", post(predict))
+        .with_state(AppState { model, max_rows })
+}
+
+/// Loads the model and serves until the process is stopped.
+pub async fn serve(config: ServerConfig) -> std::io::Result<()> {
+    let model = Arc::new(
+        ReloadableBooster::from_path(&config.model_path)
+            .map_err(|e| std::io::Error::other(e.to_string()))?,
+    );
+    let _watcher = config.reload_interval.map(|i| model.watch(i));
+    let listener = tokio::net::TcpListener::bind(config.addr).await?;
+    axum::serve(listener, router(model, config.max_rows)).await
+}
+
+async fn ready(State(state): State<AppState>) -> (StatusCode, String) {
+    (StatusCode::OK, state.model.version().to_string())
+}
+
+async fn predict(
+    State(state): State<AppState>,
+    Json(request): Json<PredictRequest>,
+) -> Result<Json<PredictResponse>, ApiError> {
+    let rows = request.rows.len();
+    if rows == 0 || rows > state.max_rows {
+        return Err(bad_request(format!(
+            "expected between 1 and {} rows, got {}",
+            state.max_rows, rows
+        )));
+    }
+    let cols = request.rows[0].len();
+    if request.rows.iter().any(|r| r.len() != cols) {
+        return Err(bad_request("rows have different lengths".to_string()));
+    }
+    let data: Vec<f32> = request
+        .rows
+        .iter()
+        .flatten()
+        .map(|v| v.unwrap_or(f32::NAN))
+        .collect();
+
+    // Scoring is CPU bound, keep it off the async workers
+    let (model, version) = state.model.snapshot();
+    let result = tokio::task::spawn_blocking(move || {
+        let schema = model.feature_schema();
+        let expected = match schema {
+            Some(schema) => schema.len(),
+            None => model.get_number_of_features()?,
+        };
+        // Checked first: value checks alone would read rows of the wrong width as others
+        if cols != expected {
+            return Err(SchemaError::ColumnCount {
+                expected,
+                got: cols,
+            }
+            .into());
+        }
+        if let Some(schema) = schema {
+            schema.validate_values(&data)?;
+        }
+        model.predict_dense(&data, rows, cols, &PredictOptions::default())
+    })
+    .await
+    .map_err(|e| internal(e.to_string()))?;
+
+    // Only invalid input is the client's fault, failures inside XGBoost are ours
+    let prediction = match result {
+        Ok(prediction) => prediction,
+        Err(e @ XGBoostError::Schema(_)) => return Err(bad_request(e.to_string())),
+        Err(e) => return Err(internal(e.to_string())),
+    };
+    Ok(Json(PredictResponse {
+        predictions: (0..rows).map(|r| prediction.row(r).to_vec()).collect(),
+        model_version: version,
+    }))
+}
+
+fn bad_request(error: String) -> ApiError {
+    (StatusCode::BAD_REQUEST, Json(ErrorResponse { error }))
+}
+
+fn internal(error: String) -> ApiError {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse { error }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_json() {
+        let request: PredictRequest =
+            serde_json::from_str(r#"{"rows": [[1.0, null], [2.5, 3.0]]}"#).unwrap();
+        assert_eq!(request.rows[0], vec![Some(1.0), None]);
+        let response = PredictResponse {
+            predictions: vec![vec![0.5]],
+            model_version: 2,
+        };
+        assert_eq!(
+            serde_json::to_string(&response).unwrap(),
+            r#"{"predictions":[[0.5]],"model_version":2}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_predict_checks_row_width() {
+        use crate::booster::Booster;
+        use crate::dmatrix::DMatrix;
+        use crate::schema::{FeatureSchema, FeatureType};
+
+        let dtrain = DMatrix::try_from_data(&[0.1, 0.2, 0.3, 0.4], 2, 2).unwrap();
+        dtrain.try_add_label(&[1., 2.]).unwrap();
+        let mut booster = Booster::train(&dtrain, &dtrain, 2).unwrap();
+        booster
+            .set_feature_schema(
+                FeatureSchema::new()
+                    .bounded_feature("a", FeatureType::Float, 0.0, 1.0)
+                    .feature("b", FeatureType::Float),
+            )
+            .unwrap();
+        let state = AppState {
+            model: Arc::new(ReloadableBooster::new(booster)),
+            max_rows: 10,
+        };
+        let request = |rows: Vec<Vec<Option<f32>>>| Json(PredictRequest { rows });
+
+        // Two one-column rows hold as many values as one valid row
+        let narrow = request(vec![vec![Some(0.5)], vec![Some(5.0)]]);
+        let (status, _) = predict(State(state.clone()), narrow).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let wide = request(vec![vec![Some(0.5), Some(1.0), Some(2.0)]]);
+        let (status, _) = predict(State(state.clone()), wide).await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let out_of_range = request(vec![vec![Some(5.0), Some(1.0)]]);
+        let (status, _) = predict(State(state.clone()), out_of_range)
+            .await
+            .unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let valid = request(vec![vec![Some(0.5), None]]);
+        let Json(response) = predict(State(state), valid).await.unwrap();
+        assert_eq!(response.predictions.len(), 1);
+    }
+}
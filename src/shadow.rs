@@ -0,0 +1,222 @@
+//! Shadow scoring: serve a primary model while a challenger scores the same requests.
+
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use crate::booster::{Booster, XGBoostError};
+use crate::predict::PredictOptions;
+
+/// Paired predictions of one request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowRecord {
+    pub rows: usize,
+    pub primary: Vec<f32>,
+    /// The challenger's predictions, or why it failed.
+    pub challenger: Result<Vec<f32>, String>,
+}
+
+struct Job {
+    data: Vec<f32>,
+    rows: usize,
+    cols: usize,
+    primary: Vec<f32>,
+}
+
+/// Scores requests with the primary booster and returns its result, while a background
+/// thread scores a copy with the challenger and hands both to a recorder callback.
+///
+/// The challenger never affects the primary path: its errors and panics, as well as
+/// panics in the recorder, are caught on the worker thread, and when the bounded queue
+/// is full the request is not shadowed and counted in [`ShadowPredictor::dropped`].
+pub struct ShadowPredictor {
+    primary: Arc<Booster>,
+    queue: ShadowQueue,
+}
+
+impl ShadowPredictor {
+    pub fn new<F>(primary: Booster, challenger: Booster, queue_size: usize, recorder: F) -> Self
+    where
+        F: FnMut(ShadowRecord) + Send + 'static,
+    {
+        let score = move |job: &Job| {
+            challenger
+                .predict_dense(&job.data, job.rows, job.cols, &PredictOptions::default())
+                .map(|p| p.values)
+                .map_err(|e| e.to_string())
+        };
+        ShadowPredictor {
+            primary: Arc::new(primary),
+            queue: ShadowQueue::new(queue_size, score, recorder),
+        }
+    }
+
+    /// Predicts row-major dense `data` with the primary booster and queues it for the
+    /// challenger.
+    pub fn predict(
+        &self,
+        data: &[f32],
+        rows: usize,
+        cols: usize,
+    ) -> Result<Vec<f32>, XGBoostError> {
+        let primary = self
+            .primary
+            .predict_dense(data, rows, cols, &PredictOptions::default())?
+            .values;
+        self.queue.submit(Job {
+            data: data.to_vec(),
+            rows,
+            cols,
+            primary: primary.clone(),
+        });
+        Ok(primary)
+    }
+
+    pub fn primary(&self) -> &Booster {
+        &self.primary
+    }
+
+    /// Requests that were not shadowed because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// The bounded queue and worker thread behind [`ShadowPredictor`].
+struct ShadowQueue {
+    sender: Option<SyncSender<Job>>,
+    worker: Option<JoinHandle<()>>,
+    dropped: AtomicU64,
+}
+
+impl ShadowQueue {
+    fn new<S, F>(queue_size: usize, mut score: S, mut recorder: F) -> Self
+    where
+        S: FnMut(&Job) -> Result<Vec<f32>, String> + Send + 'static,
+        F: FnMut(ShadowRecord) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(queue_size);
+        let worker = thread::spawn(move || {
+            for job in receiver {
+                let challenger = panic::catch_unwind(AssertUnwindSafe(|| score(&job)))
+                    .unwrap_or_else(|_| Err("challenger panicked".to_string()));
+                let record = ShadowRecord {
+                    rows: job.rows,
+                    primary: job.primary,
+                    challenger,
+                };
+                let _ = panic::catch_unwind(AssertUnwindSafe(|| recorder(record)));
+            }
+        });
+        ShadowQueue {
+            sender: Some(sender),
+            worker: Some(worker),
+            dropped: AtomicU64::new(0),
+        }
+    }
+
+    /// Queues `job` for the worker, or counts it as dropped if the queue is full.
+    fn submit(&self, job: Job) {
+        if let Some(sender) = &self.sender {
+            if let Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) = sender.try_send(job)
+            {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl Drop for ShadowQueue {
+    /// Waits for the queued requests to be scored and recorded.
+    fn drop(&mut self) {
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn job(value: f32) -> Job {
+        Job {
+            data: vec![value],
+            rows: 1,
+            cols: 1,
+            primary: vec![value],
+        }
+    }
+
+    #[test]
+    fn test_full_queue_drops() {
+        // The worker blocks on the first job until released, so the queue fills up
+        let (release, blocked) = mpsc::channel::<()>();
+        let (started, running) = mpsc::channel::<()>();
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&records);
+        let queue = ShadowQueue::new(
+            1,
+            move |job: &Job| {
+                if job.data[0] == 0.0 {
+                    started.send(()).unwrap();
+                    blocked.recv().unwrap();
+                }
+                Ok(job.data.clone())
+            },
+            move |record| recorded.lock().unwrap().push(record),
+        );
+        queue.submit(job(0.0));
+        running.recv().unwrap();
+        queue.submit(job(1.0));
+        queue.submit(job(2.0));
+        queue.submit(job(3.0));
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 2);
+        release.send(()).unwrap();
+        drop(queue);
+        let records = records.lock().unwrap();
+        let scored: Vec<f32> = records.iter().map(|r| r.primary[0]).collect();
+        assert_eq!(scored, vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn test_panics_are_isolated() {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let records = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&records);
+        let queue = ShadowQueue::new(
+            4,
+            |job: &Job| {
+                if job.data[0] == 1.0 {
+                    panic!("challenger failed");
+                }
+                Ok(job.data.clone())
+            },
+            move |record: ShadowRecord| {
+                if record.primary[0] == 2.0 {
+                    panic!("recorder failed");
+                }
+                recorded.lock().unwrap().push(record);
+            },
+        );
+        for value in [0.0, 1.0, 2.0, 3.0] {
+            queue.submit(job(value));
+        }
+        drop(queue);
+        panic::set_hook(hook);
+        let records = records.lock().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].challenger, Ok(vec![0.0]));
+        assert_eq!(
+            records[1].challenger,
+            Err("challenger panicked".to_string())
+        );
+        // The worker survived both panics
+        assert_eq!(records[2].challenger, Ok(vec![3.0]));
+    }
+}
@@ -0,0 +1,96 @@
+/// Summary of one column. Missing values (NaN) are excluded from `min`/`max`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnStats {
+    pub missing: u64,
+    pub min: f32,
+    pub max: f32,
+}
+
+impl ColumnStats {
+    pub fn all_missing(&self, rows: u64) -> bool {
+        self.missing == rows
+    }
+
+    /// True if every present value is the same (or none is present).
+    pub fn is_constant(&self) -> bool {
+        self.min >= self.max
+    }
+}
+
+/// Per-column diagnostics, to catch unusable features before training.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataStats {
+    pub rows: u64,
+    pub columns: Vec<ColumnStats>,
+}
+
+impl DataStats {
+    /// Computes stats over a row-major dense slice.
+    pub fn compute(data: &[f32], rows: u64, cols: u64) -> Self {
+        let mut columns = vec![
+            ColumnStats {
+                missing: 0,
+                min: f32::INFINITY,
+                max: f32::NEG_INFINITY,
+            };
+            cols as usize
+        ];
+        if cols > 0 {
+            for row in data.chunks(cols as usize).take(rows as usize) {
+                for (stats, value) in columns.iter_mut().zip(row) {
+                    if value.is_nan() {
+                        stats.missing += 1;
+                    } else {
+                        stats.min = stats.min.min(*value);
+                        stats.max = stats.max.max(*value);
+                    }
+                }
+            }
+        }
+        DataStats { rows, columns }
+    }
+
+    pub fn all_missing_columns(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.all_missing(self.rows))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Columns carrying no information: all missing or a single distinct value.
+    pub fn constant_columns(&self) -> Vec<usize> {
+        self.columns
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_constant())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn missing_ratio(&self, column: usize) -> f64 {
+        if self.rows == 0 {
+            0.0
+        } else {
+            self.columns[column].missing as f64 / self.rows as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let nan = f32::NAN;
+        let stats = DataStats::compute(&[1.0, nan, 5.0, 2.0, nan, 5.0, 3.0, nan, 5.0], 3, 3);
+        assert_eq!(stats.columns[0].min, 1.0);
+        assert_eq!(stats.columns[0].max, 3.0);
+        assert_eq!(stats.columns[1].missing, 3);
+        assert_eq!(stats.all_missing_columns(), vec![1]);
+        assert_eq!(stats.constant_columns(), vec![1, 2]);
+        assert_eq!(stats.missing_ratio(1), 1.0);
+    }
+}
@@ -0,0 +1,85 @@
+//! Model artifacts in object storage. With the `object-store` feature,
+//! [`Booster::save_model`](crate::booster::Booster::save_model) and
+//! [`Booster::load_model`](crate::booster::Booster::load_model) accept `s3://` and `gs://`
+//! URIs: the model goes through the in-memory buffer APIs and is uploaded or downloaded
+//! in one request, so no local file is written.
+//!
+//! Credentials, regions and endpoints are read from the usual `AWS_*` and `GOOGLE_*`
+//! environment variables. The calls block on a private runtime and must not be made from
+//! inside an async runtime; use `spawn_blocking` there.
+
+use std::future::Future;
+
+use object_store::path::Path;
+use object_store::{parse_url_opts, ObjectStore, PutPayload};
+use thiserror::Error;
+use url::Url;
+
+#[derive(Error, Debug)]
+pub enum StorageError {
+    #[error("Invalid object URI: {0}")]
+    Uri(String),
+    #[error(transparent)]
+    ObjectStore(#[from] object_store::Error),
+    #[error("Cannot start I/O runtime: {0}")]
+    Runtime(#[from] std::io::Error),
+}
+
+/// URI schemes routed to object storage.
+pub const SCHEMES: [&str; 2] = ["s3", "gs"];
+
+/// Whether `uri` names an object rather than a local path.
+pub fn is_remote(uri: &str) -> bool {
+    SCHEMES.iter().any(|scheme| {
+        uri.strip_prefix(scheme)
+            .is_some_and(|rest| rest.starts_with("://"))
+    })
+}
+
+fn open(uri: &str) -> Result<(Box<dyn ObjectStore>, Path), StorageError> {
+    let url = Url::parse(uri).map_err(|e| StorageError::Uri(format!("{}: {}", uri, e)))?;
+    let options = std::env::vars().map(|(key, value)| (key.to_ascii_lowercase(), value));
+    Ok(parse_url_opts(&url, options)?)
+}
+
+fn block_on<F: Future>(future: F) -> Result<F::Output, StorageError> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+/// Uploads `bytes` to `uri`, replacing any existing object.
+pub fn put(uri: &str, bytes: Vec<u8>) -> Result<(), StorageError> {
+    let (store, path) = open(uri)?;
+    block_on(store.put(&path, PutPayload::from(bytes)))??;
+    Ok(())
+}
+
+/// Downloads the object at `uri`.
+pub fn get(uri: &str) -> Result<Vec<u8>, StorageError> {
+    let (store, path) = open(uri)?;
+    let bytes = block_on(async { store.get(&path).await?.bytes().await })??;
+    Ok(bytes.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_remote() {
+        assert!(is_remote("s3://models/churn/model.ubj"));
+        assert!(is_remote("gs://models/model.json"));
+        assert!(!is_remote("s3_models/model.json"));
+        assert!(!is_remote("/tmp/model.json"));
+    }
+
+    #[test]
+    fn test_invalid_uri() {
+        assert!(matches!(
+            get("s3://my bucket/model.json"),
+            Err(StorageError::Uri(_))
+        ));
+    }
+}
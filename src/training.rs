@@ -0,0 +1,311 @@
+//! Controls for long-running training loops.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::booster::Booster;
+
+/// Cooperative cancellation flag, shared by cloning. Training checks it between boosting
+/// rounds, so a round in progress always completes.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// When to stop [`Booster::train_with_control`] before all rounds are done.
+#[derive(Debug, Clone, Default)]
+pub struct TrainControl {
+    cancel: Option<CancellationToken>,
+    time_budget: Option<Duration>,
+}
+
+impl TrainControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel_token(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Wall-clock budget for the whole run. No new round starts once it is spent.
+    pub fn time_budget(mut self, budget: Duration) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Why training started at `started` should stop now, if it should.
+    pub(crate) fn check(&self, started: Instant) -> Option<StopReason> {
+        if self.cancel.as_ref().is_some_and(|t| t.is_cancelled()) {
+            return Some(StopReason::Cancelled);
+        }
+        if self.time_budget.is_some_and(|b| started.elapsed() >= b) {
+            return Some(StopReason::TimedOut);
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopReason {
+    Completed,
+    Cancelled,
+    TimedOut,
+}
+
+/// The model trained so far and why training ended.
+#[derive(Debug)]
+pub struct TrainOutcome {
+    /// Truncated to the best round if training was stopped early.
+    pub booster: Booster,
+    /// Rounds in `booster`.
+    pub rounds: usize,
+    pub stop: StopReason,
+    /// Score of the best round, if there were evaluation sets.
+    pub best_score: Option<f64>,
+}
+
+/// Tracks the best round by one metric of the last evaluation set, as XGBoost's early
+/// stopping does.
+#[derive(Debug, Default)]
+pub(crate) struct BestRound {
+    metric: Option<String>,
+    best: Option<(usize, f64)>,
+}
+
+impl BestRound {
+    /// Tracks `metric`, or the only metric reported if `None`.
+    pub(crate) fn new(metric: Option<String>) -> Self {
+        BestRound { metric, best: None }
+    }
+
+    pub(crate) fn observe(&mut self, round: usize, metrics: &[(String, HashMap<String, f64>)]) {
+        let Some((_, values)) = metrics.last() else {
+            return;
+        };
+        let score = match &self.metric {
+            Some(metric) => values.get(metric),
+            None if values.len() == 1 => values.values().next(),
+            None => None,
+        };
+        let Some(&score) = score.filter(|s| !s.is_nan()) else {
+            return;
+        };
+        let maximize = self.metric.as_deref().map_or_else(
+            || values.keys().next().is_some_and(|m| maximizes(m)),
+            maximizes,
+        );
+        let better = match self.best {
+            None => true,
+            Some((_, best)) if maximize => score > best,
+            Some((_, best)) => score < best,
+        };
+        if better {
+            self.best = Some((round, score));
+        }
+    }
+
+    pub(crate) fn round(&self) -> Option<usize> {
+        self.best.map(|(round, _)| round)
+    }
+
+    pub(crate) fn score(&self) -> Option<f64> {
+        self.best.map(|(_, score)| score)
+    }
+}
+
+/// Whether higher is better for an XGBoost metric, e.g. `auc` or `ndcg@5`.
+fn maximizes(metric: &str) -> bool {
+    let name = metric.split('@').next().unwrap_or(metric);
+    matches!(
+        name,
+        "auc" | "aucpr" | "map" | "ndcg" | "pre" | "interval-regression-accuracy"
+    )
+}
+
+/// Hook called by [`Booster::train_with_evals`] after every round. An error aborts training.
+pub trait TrainingCallback {
+    /// `metrics` holds each evaluation set's name with its metrics for this round.
+    fn after_round(
+        &mut self,
+        round: usize,
+        metrics: &[(String, HashMap<String, f64>)],
+    ) -> io::Result<()>;
+}
+
+impl TrainingCallback for () {
+    fn after_round(&mut self, _: usize, _: &[(String, HashMap<String, f64>)]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// One `round,dataset,metric,value` line per metric, after a header line.
+    Csv,
+    /// One JSON object per round, e.g. `{"round":0,"valid":{"rmse":0.5}}`.
+    JsonLines,
+}
+
+/// Streams per-round metrics to `writer` so training curves can be followed live.
+pub struct MetricsLogger<W: Write> {
+    writer: W,
+    format: LogFormat,
+    flush_every: usize,
+    rounds_written: usize,
+}
+
+impl<W: Write> MetricsLogger<W> {
+    /// Flushes after every round. See [`MetricsLogger::flush_every`].
+    pub fn new(writer: W, format: LogFormat) -> Self {
+        MetricsLogger {
+            writer,
+            format,
+            flush_every: 1,
+            rounds_written: 0,
+        }
+    }
+
+    /// Flush every `rounds` rounds, `0` to leave flushing to the writer.
+    pub fn flush_every(mut self, rounds: usize) -> Self {
+        self.flush_every = rounds;
+        self
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> TrainingCallback for MetricsLogger<W> {
+    fn after_round(
+        &mut self,
+        round: usize,
+        metrics: &[(String, HashMap<String, f64>)],
+    ) -> io::Result<()> {
+        match self.format {
+            LogFormat::Csv => {
+                if self.rounds_written == 0 {
+                    writeln!(self.writer, "round,dataset,metric,value")?;
+                }
+                for (dataset, values) in metrics {
+                    for (metric, value) in values.iter().collect::<BTreeMap<_, _>>() {
+                        writeln!(self.writer, "{},{},{},{}", round, dataset, metric, value)?;
+                    }
+                }
+            }
+            LogFormat::JsonLines => {
+                let mut line = serde_json::Map::new();
+                line.insert("round".to_string(), round.into());
+                for (dataset, values) in metrics {
+                    let values: BTreeMap<_, _> = values.iter().collect();
+                    line.insert(dataset.clone(), serde_json::json!(values));
+                }
+                serde_json::to_writer(&mut self.writer, &line)?;
+                writeln!(self.writer)?;
+            }
+        }
+        self.rounds_written += 1;
+        if self.flush_every > 0 && self.rounds_written.is_multiple_of(self.flush_every) {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_metrics(rmse: f64) -> Vec<(String, HashMap<String, f64>)> {
+        let metrics = HashMap::from([("rmse".to_string(), rmse), ("mae".to_string(), 0.5)]);
+        vec![("valid".to_string(), metrics)]
+    }
+
+    #[test]
+    fn test_metrics_logger_csv() {
+        let mut logger = MetricsLogger::new(Vec::new(), LogFormat::Csv);
+        logger.after_round(0, &round_metrics(1.5)).unwrap();
+        logger.after_round(1, &round_metrics(1.25)).unwrap();
+        let out = String::from_utf8(logger.into_inner()).unwrap();
+        assert_eq!(
+            out,
+            "round,dataset,metric,value\n0,valid,mae,0.5\n0,valid,rmse,1.5\n\
+             1,valid,mae,0.5\n1,valid,rmse,1.25\n"
+        );
+    }
+
+    #[test]
+    fn test_metrics_logger_jsonl() {
+        let mut logger = MetricsLogger::new(Vec::new(), LogFormat::JsonLines).flush_every(0);
+        logger.after_round(3, &round_metrics(1.5)).unwrap();
+        let out = String::from_utf8(logger.into_inner()).unwrap();
+        assert_eq!(out, "{\"round\":3,\"valid\":{\"mae\":0.5,\"rmse\":1.5}}\n");
+    }
+
+    #[test]
+    fn test_best_round() {
+        let mut best = BestRound::new(None);
+        best.observe(0, &round_metrics(1.5)[..0]);
+        assert_eq!(best.round(), None);
+        let single = |rmse: f64| {
+            vec![(
+                "valid".to_string(),
+                HashMap::from([("rmse".to_string(), rmse)]),
+            )]
+        };
+        for (round, rmse) in [1.5, 1.0, 1.25, 1.0].into_iter().enumerate() {
+            best.observe(round, &single(rmse));
+        }
+        assert_eq!((best.round(), best.score()), (Some(1), Some(1.0)));
+
+        // Named metric on the last set, higher is better
+        let mut best = BestRound::new(Some("auc".to_string()));
+        for (round, auc) in [0.7, 0.9, 0.8].into_iter().enumerate() {
+            let mut metrics = round_metrics(1.0);
+            metrics.push((
+                "test".to_string(),
+                HashMap::from([("auc".to_string(), auc), ("rmse".to_string(), 0.1)]),
+            ));
+            best.observe(round, &metrics);
+        }
+        assert_eq!((best.round(), best.score()), (Some(1), Some(0.9)));
+
+        // Several metrics and none named, so nothing to pick by
+        let mut best = BestRound::new(None);
+        best.observe(0, &round_metrics(1.0));
+        assert_eq!(best.round(), None);
+        assert!(maximizes("ndcg@5") && !maximizes("logloss"));
+    }
+
+    #[test]
+    fn test_check() {
+        let started = Instant::now();
+        let token = CancellationToken::new();
+        let control = TrainControl::new().cancel_token(token.clone());
+        assert_eq!(control.check(started), None);
+        token.cancel();
+        assert_eq!(control.check(started), Some(StopReason::Cancelled));
+
+        let control = TrainControl::new().time_budget(Duration::ZERO);
+        assert_eq!(control.check(started), Some(StopReason::TimedOut));
+        let control = TrainControl::new().time_budget(Duration::from_secs(3600));
+        assert_eq!(control.check(started), None);
+    }
+}
@@ -0,0 +1,267 @@
+//! Experiment tracking with Weights & Biases, behind the `wandb` feature. A [`WandbLogger`]
+//! creates or resumes a run and, as a [`TrainingCallback`], streams each round's metrics
+//! to its history, so training curves appear in the same dashboards as runs logged from
+//! Python. [`WandbLogger::finish`] writes the summary and marks the run as finished.
+//!
+//! It speaks the same HTTP endpoints as the official clients: GraphQL to upsert the run
+//! and the file stream API for history and summary. Metrics are named
+//! `<dataset>-<metric>` like `xgboost.callback.WandbCallback` in Python; the round is the
+//! `_step`.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+
+use crate::params::TrainParams;
+use crate::training::TrainingCallback;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.wandb.ai";
+
+const UPSERT_RUN: &str = "mutation UpsertBucket($id: String, $name: String, $project: String, \
+    $entity: String, $config: JSONString) { upsertBucket(input: {id: $id, name: $name, \
+    modelName: $project, entityName: $entity, config: $config}) { bucket { id name \
+    historyLineCount } } }";
+
+#[derive(Error, Debug)]
+pub enum WandbError {
+    #[error("W&B request to {endpoint} failed: {message}")]
+    Request { endpoint: String, message: String },
+    #[error("Unexpected W&B response: {0}")]
+    Response(String),
+    #[error("Missing W&B setting: {0}")]
+    Config(String),
+}
+
+/// Where a run lives and how to reach it.
+#[derive(Debug, Clone)]
+pub struct WandbConfig {
+    pub api_key: String,
+    pub entity: String,
+    pub project: String,
+    /// Run id, 8 lowercase alphanumerics by W&B convention. Reusing an id resumes the run.
+    pub run_id: String,
+    pub base_url: String,
+}
+
+impl WandbConfig {
+    pub fn new(api_key: &str, entity: &str, project: &str, run_id: &str) -> Self {
+        WandbConfig {
+            api_key: api_key.to_string(),
+            entity: entity.to_string(),
+            project: project.to_string(),
+            run_id: run_id.to_string(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+        }
+    }
+
+    /// From the standard `WANDB_API_KEY`, `WANDB_ENTITY`, `WANDB_PROJECT`, `WANDB_RUN_ID`
+    /// and, optionally, `WANDB_BASE_URL` variables.
+    pub fn from_env() -> Result<Self, WandbError> {
+        let var =
+            |name: &str| std::env::var(name).map_err(|_| WandbError::Config(name.to_string()));
+        let mut config = WandbConfig::new(
+            &var("WANDB_API_KEY")?,
+            &var("WANDB_ENTITY")?,
+            &var("WANDB_PROJECT")?,
+            &var("WANDB_RUN_ID")?,
+        );
+        if let Ok(base_url) = var("WANDB_BASE_URL") {
+            config.base_url = base_url;
+        }
+        Ok(config)
+    }
+}
+
+pub struct WandbLogger {
+    config: WandbConfig,
+    started: Instant,
+    /// Lines of `wandb-history.jsonl` sent so far, the offset of the next ones.
+    history_lines: usize,
+    summary: Map<String, Value>,
+}
+
+impl WandbLogger {
+    /// Creates the run, or resumes it if `config.run_id` exists, recording `params` as
+    /// its config. A resumed run's history is appended to, after the lines it already has.
+    pub fn start(config: WandbConfig, params: &TrainParams) -> Result<Self, WandbError> {
+        let mut logger = WandbLogger {
+            config,
+            started: Instant::now(),
+            history_lines: 0,
+            summary: Map::new(),
+        };
+        let variables = json!({
+            "id": logger.config.run_id,
+            "name": logger.config.run_id,
+            "project": logger.config.project,
+            "entity": logger.config.entity,
+            "config": run_config(params).to_string(),
+        });
+        let response = logger.post(
+            "graphql",
+            &json!({"query": UPSERT_RUN, "variables": variables}),
+        )?;
+        logger.history_lines = history_line_count(&response)?;
+        Ok(logger)
+    }
+
+    /// Writes the last value of every metric as the run summary and marks it finished.
+    pub fn finish(self) -> Result<(), WandbError> {
+        let summary = Value::Object(self.summary.clone()).to_string();
+        self.stream(json!({
+            "files": {"wandb-summary.json": {"offset": 0, "content": [summary]}},
+        }))?;
+        self.stream(json!({"complete": true, "exitcode": 0}))?;
+        Ok(())
+    }
+
+    fn stream(&self, body: Value) -> Result<Value, WandbError> {
+        let endpoint = format!(
+            "files/{}/{}/{}/file_stream",
+            self.config.entity, self.config.project, self.config.run_id
+        );
+        self.post(&endpoint, &body)
+    }
+
+    fn post(&self, endpoint: &str, body: &Value) -> Result<Value, WandbError> {
+        let url = format!(
+            "{}/{}",
+            self.config.base_url.trim_end_matches('/'),
+            endpoint
+        );
+        let credentials = BASE64.encode(format!("api:{}", self.config.api_key));
+        let response = ureq::post(&url)
+            .set("Authorization", &format!("Basic {}", credentials))
+            .send_json(body)
+            .map_err(|e| request_error(endpoint, e))?;
+        response
+            .into_json()
+            .map_err(|e| WandbError::Response(e.to_string()))
+    }
+}
+
+impl TrainingCallback for WandbLogger {
+    fn after_round(
+        &mut self,
+        round: usize,
+        metrics: &[(String, HashMap<String, f64>)],
+    ) -> io::Result<()> {
+        let runtime = self.started.elapsed().as_secs_f64();
+        let row = history_row(round, metrics, runtime, now_secs());
+        for (key, value) in &row {
+            if !key.starts_with('_') {
+                self.summary.insert(key.clone(), value.clone());
+            }
+        }
+        let line = Value::Object(row).to_string();
+        self.stream(json!({
+            "files": {"wandb-history.jsonl": {"offset": self.history_lines, "content": [line]}},
+        }))
+        .map_err(io::Error::other)?;
+        self.history_lines += 1;
+        Ok(())
+    }
+}
+
+fn request_error(endpoint: &str, error: ureq::Error) -> WandbError {
+    let message = match error {
+        ureq::Error::Status(code, response) => format!(
+            "status {}: {}",
+            code,
+            response.into_string().unwrap_or_default()
+        ),
+        ureq::Error::Transport(transport) => transport.to_string(),
+    };
+    WandbError::Request {
+        endpoint: endpoint.to_string(),
+        message,
+    }
+}
+
+/// Lines already in the history of the run upserted by `response`, 0 for a new run.
+fn history_line_count(response: &Value) -> Result<usize, WandbError> {
+    if let Some(errors) = response.get("errors") {
+        return Err(WandbError::Response(errors.to_string()));
+    }
+    let bucket = response
+        .pointer("/data/upsertBucket/bucket")
+        .ok_or_else(|| WandbError::Response(response.to_string()))?;
+    Ok(bucket
+        .get("historyLineCount")
+        .and_then(Value::as_u64)
+        .unwrap_or(0) as usize)
+}
+
+/// W&B run config, `{"key": {"value": ...}}` for every parameter.
+fn run_config(params: &TrainParams) -> Value {
+    params
+        .to_pairs()
+        .into_iter()
+        .map(|(key, value)| (key, json!({"value": value})))
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+/// One line of `wandb-history.jsonl`.
+fn history_row(
+    round: usize,
+    metrics: &[(String, HashMap<String, f64>)],
+    runtime: f64,
+    timestamp: f64,
+) -> Map<String, Value> {
+    let mut row = Map::new();
+    row.insert("_step".to_string(), round.into());
+    row.insert("_runtime".to_string(), runtime.into());
+    row.insert("_timestamp".to_string(), timestamp.into());
+    for (dataset, values) in metrics {
+        for (metric, value) in values.iter().collect::<BTreeMap<_, _>>() {
+            row.insert(format!("{}-{}", dataset, metric), (*value).into());
+        }
+    }
+    row
+}
+
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0.0, |d| d.as_secs_f64())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_line_count() {
+        let resumed = json!({"data": {"upsertBucket": {"bucket": {"historyLineCount": 12}}}});
+        assert_eq!(history_line_count(&resumed).unwrap(), 12);
+        let created = json!({"data": {"upsertBucket": {"bucket": {"id": "abc"}}}});
+        assert_eq!(history_line_count(&created).unwrap(), 0);
+        assert!(history_line_count(&json!({"errors": [{"message": "denied"}]})).is_err());
+        assert!(history_line_count(&json!({"data": null})).is_err());
+    }
+
+    #[test]
+    fn test_history_row() {
+        let metrics = vec![(
+            "valid".to_string(),
+            HashMap::from([("auc".to_string(), 0.75)]),
+        )];
+        let row = history_row(4, &metrics, 1.5, 100.0);
+        assert_eq!(row["_step"], 4);
+        assert_eq!(row["_runtime"], 1.5);
+        assert_eq!(row["valid-auc"], 0.75);
+        assert_eq!(row.len(), 4);
+    }
+
+    #[test]
+    fn test_run_config() {
+        let config = run_config(&TrainParams::new().objective("binary:logistic"));
+        assert_eq!(config["objective"], json!({"value": "binary:logistic"}));
+    }
+}
@@ -0,0 +1,38 @@
+//! Per-row sample weights.
+
+use std::collections::BTreeMap;
+
+/// Weights each row by `n_rows / (n_classes * n_rows_of_its_class)`, like scikit-learn's
+/// `class_weight="balanced"`, so every class contributes the same total weight. Labels are
+/// compared by value, NaN labels get weight 0.
+pub fn balanced_from_labels(labels: &[f32]) -> Vec<f32> {
+    let mut counts: BTreeMap<u32, usize> = BTreeMap::new();
+    for label in labels.iter().filter(|l| !l.is_nan()) {
+        *counts.entry(label.to_bits()).or_default() += 1;
+    }
+    let total: usize = counts.values().sum();
+    labels
+        .iter()
+        .map(|label| match counts.get(&label.to_bits()) {
+            Some(count) if !label.is_nan() => total as f32 / (counts.len() * count) as f32,
+            _ => 0.0,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_from_labels() {
+        let weights = balanced_from_labels(&[0.0, 0.0, 0.0, 1.0, 2.0, 2.0]);
+        assert_eq!(
+            weights,
+            vec![2.0 / 3.0, 2.0 / 3.0, 2.0 / 3.0, 2.0, 1.0, 1.0]
+        );
+        let per_class: f32 = weights[..3].iter().sum();
+        assert!((per_class - weights[3]).abs() < 1e-6);
+        assert_eq!(balanced_from_labels(&[1.0, f32::NAN])[1], 0.0);
+    }
+}
@@ -0,0 +1,101 @@
+//! Counterfactual scoring: how predictions move when some features of a row change.
+
+use thiserror::Error;
+
+use crate::booster::{Booster, XGBoostError};
+use crate::predict::PredictOptions;
+
+#[derive(Error, Debug)]
+pub enum WhatIfError {
+    #[error("Feature {feature} is out of range for a row of {cols} features")]
+    Feature { feature: usize, cols: usize },
+    #[error(transparent)]
+    XGBoost(#[from] XGBoostError),
+}
+
+/// Prediction of one scenario and its difference to the unmodified row, one value per
+/// output group.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScenarioScore {
+    pub prediction: Vec<f32>,
+    pub delta: Vec<f32>,
+}
+
+/// Outcome of [`score_with_overrides`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct WhatIf {
+    pub base: Vec<f32>,
+    /// In the order of the scenarios.
+    pub scenarios: Vec<ScenarioScore>,
+}
+
+/// Scores `base_row` as is and once per scenario, where a scenario is a list of
+/// `(feature index, value)` overrides applied to a copy of the row. Use NaN to make a
+/// feature missing. All rows are predicted in a single batch.
+pub fn score_with_overrides(
+    booster: &Booster,
+    base_row: &[f32],
+    scenarios: &[Vec<(usize, f32)>],
+) -> Result<WhatIf, WhatIfError> {
+    let rows = scenario_rows(base_row, scenarios)?;
+    let cols = base_row.len();
+    let prediction =
+        booster.predict_dense(&rows, scenarios.len() + 1, cols, &PredictOptions::new())?;
+    let base = prediction.row(0).to_vec();
+    let scenarios = (1..=scenarios.len())
+        .map(|i| {
+            let prediction = prediction.row(i).to_vec();
+            let delta = prediction.iter().zip(&base).map(|(p, b)| p - b).collect();
+            ScenarioScore { prediction, delta }
+        })
+        .collect();
+    Ok(WhatIf { base, scenarios })
+}
+
+/// `base_row` followed by one modified copy per scenario, row-major.
+fn scenario_rows(
+    base_row: &[f32],
+    scenarios: &[Vec<(usize, f32)>],
+) -> Result<Vec<f32>, WhatIfError> {
+    let cols = base_row.len();
+    let mut rows = Vec::with_capacity(cols * (scenarios.len() + 1));
+    rows.extend_from_slice(base_row);
+    for overrides in scenarios {
+        let start = rows.len();
+        rows.extend_from_slice(base_row);
+        for (feature, value) in overrides {
+            if *feature >= cols {
+                return Err(WhatIfError::Feature {
+                    feature: *feature,
+                    cols,
+                });
+            }
+            rows[start + feature] = *value;
+        }
+    }
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scenario_rows() {
+        let rows = scenario_rows(
+            &[1.0, 2.0],
+            &[vec![(0, 5.0)], vec![(1, f32::NAN), (0, 0.0)]],
+        )
+        .unwrap();
+        assert_eq!(&rows[..4], &[1.0, 2.0, 5.0, 2.0]);
+        assert_eq!(rows[4], 0.0);
+        assert!(rows[5].is_nan());
+        assert!(matches!(
+            scenario_rows(&[1.0], &[vec![(1, 0.0)]]),
+            Err(WhatIfError::Feature {
+                feature: 1,
+                cols: 1
+            })
+        ));
+    }
+}
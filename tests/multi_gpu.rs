@@ -0,0 +1,33 @@
+//! Multi-GPU training on one machine. Needs XGBoost built with CUDA and NCCL and at least
+//! two visible GPUs; run with `cargo test --features cuda-tests --test multi_gpu`.
+#![cfg(feature = "cuda-tests")]
+
+use rand::{Rng, SeedableRng};
+use xgb_rs::collective::train_multi_gpu;
+use xgb_rs::dmatrix::DMatrix;
+use xgb_rs::params::TrainParams;
+
+const N_ROWS: usize = 20000;
+const N_COLS: usize = 10;
+
+fn shard(rank: u32, world_size: u32) -> DMatrix {
+    let rows = N_ROWS / world_size as usize;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(rank as u64);
+    let data: Vec<f32> = (0..rows * N_COLS).map(|_| rng.gen()).collect();
+    let labels: Vec<f32> = data.chunks(N_COLS).map(|row| row[0] + row[1]).collect();
+    let dmat = DMatrix::try_from_data(&data, rows as u64, N_COLS as u64).expect("Failed dmat");
+    dmat.try_add_label(&labels).expect("Could not set target");
+    dmat
+}
+
+#[test]
+fn test_train_two_gpus() {
+    let params = TrainParams::new().max_depth(4);
+    let booster = train_multi_gpu(&[0, 1], &params, 20, |rank, world_size| {
+        Ok(shard(rank, world_size))
+    })
+    .expect("Could not train");
+    assert_eq!(booster.boosted_rounds().unwrap(), 20);
+    let preds = booster.predict(&shard(0, 2)).unwrap();
+    assert_eq!(preds.len(), N_ROWS / 2);
+}